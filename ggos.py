@@ -54,6 +54,26 @@ parser.add_argument(
 
 args = parser.parse_args()
 
+# commands of the coreutils app, it is installed once under each name
+COREUTILS = [
+    "cat",
+    "cp",
+    "echo",
+    "free",
+    "grep",
+    "head",
+    "hexdump",
+    "kill",
+    "ls",
+    "mkdir",
+    "mv",
+    "ps",
+    "rm",
+    "tail",
+    "uptime",
+    "wc",
+]
+
 
 def info(step: str, content: str):
     print(f"\033[1;32m[+] {step}:\033[0m \033[1m{content}\033[0m")
@@ -158,6 +178,39 @@ def copy_to_esp(src: str, dst: str):
         raise Exception(f"{src} is not a file")
 
 
+def cpio_entry(name: str, mode: int, data: bytes = b"") -> bytes:
+    """An entry of a cpio archive in the "newc" format"""
+    name_bytes = name.encode() + b"\0"
+    fields = [0, mode, 0, 0, 1, 0, len(data), 0, 0, 0, 0, len(name_bytes), 0]
+    entry = b"070701" + b"".join(f"{field:08X}".encode() for field in fields)
+    entry += name_bytes
+    entry += b"\0" * (-len(entry) % 4)
+    entry += data
+    entry += b"\0" * (-len(entry) % 4)
+    return entry
+
+
+def build_initramfs(files: dict[str, str]):
+    """Pack `files`, archive paths to host paths, into the initramfs"""
+    dst = os.path.join(os.getcwd(), args.boot, "INITRD.CPIO")
+
+    if args.dry_run:
+        debug("Would pack", f"{len(files)} files -> {dst}")
+        return
+
+    dirs = sorted({os.path.dirname(name) for name in files if os.path.dirname(name)})
+    archive = b"".join(cpio_entry(d, 0o040755) for d in dirs)
+
+    for name, src in files.items():
+        with open(src, "rb") as f:
+            archive += cpio_entry(name, 0o100755, f.read())
+
+    archive += cpio_entry("TRAILER!!!", 0)
+
+    with open(dst, "wb") as f:
+        f.write(archive)
+
+
 def build():
     cargo_exe = shutil.which("cargo")
 
@@ -178,6 +231,13 @@ def build():
     if os.path.exists(config_path):
         copy_to_esp(config_path, os.path.join("EFI", "BOOT", "boot.conf"))
 
+    # startup script of the shell
+    initramfs = {}
+    rc_path = os.path.join(os.getcwd(), "pkg", "kernel", "config", "rc")
+    if os.path.exists(rc_path):
+        copy_to_esp(rc_path, os.path.join("ETC", "RC"))
+        initramfs["ETC/RC"] = rc_path
+
     # build kernel
     kernel = os.path.join(os.getcwd(), "pkg", "kernel")
     info("Building", "kernel...")
@@ -208,7 +268,13 @@ def build():
         compile_output = os.path.join(
             os.getcwd(), "target", "x86_64-unknown-ggos", profile_dir, app_name
         )
-        copy_to_esp(compile_output, os.path.join("APP", app))
+        names = [app] + (COREUTILS if app == "coreutils" else [])
+        for name in names:
+            copy_to_esp(compile_output, os.path.join("APP", name))
+            initramfs[f"APP/{name.upper()}"] = compile_output
+
+    info("Packing", "initramfs...")
+    build_initramfs(initramfs)
 
 
 def clippy():
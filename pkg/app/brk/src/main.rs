@@ -8,7 +8,7 @@ use lib::*;
 
 extern crate lib;
 
-fn main() -> isize {
+fn main(_args: &[&str]) -> isize {
     println!("Welcome to Brk Test!");
 
     pre_test();
@@ -46,7 +46,7 @@ fn main() -> isize {
                     None => println!("Brk to {:#x} failed", addr),
                 }
 
-                sys_stat();
+                sys_list_process();
             }
             "cur" => match sys_brk(None) {
                 Some(brk) => println!("Current brk addr: {:#x}", brk),
@@ -89,7 +89,7 @@ fn pre_test() {
                 brk + 0x1000,
                 new_brk
             );
-            sys_stat();
+            sys_list_process();
         }
         None => {
             println!("Brk to {:#x} failed", brk + 0x1000);
@@ -102,7 +102,7 @@ fn pre_test() {
     match new_brk {
         Some(new_brk) => {
             println!("Brk to {:#x} success, new brk addr: {:#x}", brk, new_brk);
-            sys_stat();
+            sys_list_process();
         }
         None => {
             println!("Brk to {:#x} failed", brk);
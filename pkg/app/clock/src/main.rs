@@ -4,13 +4,13 @@
 #![allow(clippy::diverging_sub_expression)]
 
 use embedded_graphics::pixelcolor::Rgb888;
-use lib::*;
+use lib::{time::Duration, *};
 
 mod display;
 
 extern crate lib;
 
-fn main() -> ! {
+fn main(_args: &[&str]) -> ! {
     clock();
 }
 
@@ -26,12 +26,7 @@ fn clock() -> ! {
     use micromath::F32Ext;
 
     loop {
-        let start = sys_time();
-        let mut current = start;
-
-        while (current - start) < Duration::try_seconds(1).unwrap() {
-            current = sys_time();
-        }
+        sleep(Duration::from_secs(1));
 
         angle += ANGLE_INCR;
         if angle >= 360.0 {
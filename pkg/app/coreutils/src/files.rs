@@ -0,0 +1,528 @@
+//! Files and directories
+
+use alloc::{format, string::String, vec, vec::Vec};
+use lib::fs::{BufWriter, File, Input, Read, Write};
+use lib::getopt::Opt;
+use lib::*;
+
+use crate::{inputs, options, usage};
+
+/// Columns of the terminal, which `ls` fits the names into
+const TERMINAL_WIDTH: usize = 80;
+
+/// Bytes a line of `hexdump` shows
+const HEX_LINE: usize = 16;
+
+/// `dir/name`, without doubling the `/`
+fn join(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+/// The last component of `path`
+fn basename(path: &str) -> &str {
+    let path = path.trim_end_matches('/');
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// `path` made absolute against the working directory, without `.`, `..`
+/// or empty components, as the kernel sees it
+fn absolute(path: &str) -> String {
+    let cwd = match path.starts_with('/') {
+        true => String::new(),
+        false => sys_getcwd(),
+    };
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in cwd.split('/').chain(path.split('/')) {
+        match part {
+            "" | "." => (),
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+
+    format!("/{}", parts.join("/"))
+}
+
+/// Whether `a` and `b` name the same file, FAT names ignore case
+fn same_file(a: &str, b: &str) -> bool {
+    absolute(a).eq_ignore_ascii_case(&absolute(b))
+}
+
+/// Copy all of `input` to `output`
+fn copy(input: &mut impl Read, output: &mut impl Write) -> Result<(), Errno> {
+    let mut buf = vec![0; 4096];
+
+    loop {
+        match input.read(&mut buf)? {
+            0 => return Ok(()),
+            count => output.write_all(&buf[..count])?,
+        }
+    }
+}
+
+/// `size` in bytes, e.g. `12.3K`
+fn humanized_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+
+    let mut size = size as f32;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1}{}", size, UNITS[unit])
+}
+
+/// How `ls` shows the entries
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Listing {
+    Long,
+    Lines,
+    Columns,
+}
+
+/// The name `ls` shows for `entry`, with a `/` after directories
+fn display_name(entry: &Metadata) -> String {
+    match entry.is_dir() {
+        true => format!("{}/", entry.name),
+        false => entry.name.clone(),
+    }
+}
+
+fn list(out: &mut impl Write, entries: &[Metadata], listing: Listing) -> Result<(), Errno> {
+    let names: Vec<String> = entries.iter().map(display_name).collect();
+
+    match listing {
+        Listing::Long => {
+            writeln!(out, "  Size | Last Modified       | Name")?;
+
+            for (entry, name) in entries.iter().zip(&names) {
+                let time = DateTime::from_timestamp_millis(entry.stat.modified).unwrap_or_default();
+                writeln!(
+                    out,
+                    "{:>6} | {:04}/{:02}/{:02} {:02}:{:02}:{:02} | {}",
+                    humanized_size(entry.stat.len),
+                    time.year(),
+                    time.month(),
+                    time.day(),
+                    time.hour(),
+                    time.minute(),
+                    time.second(),
+                    name
+                )?;
+            }
+        }
+        Listing::Lines => {
+            for name in &names {
+                writeln!(out, "{}", name)?;
+            }
+        }
+        Listing::Columns if names.is_empty() => (),
+        Listing::Columns => {
+            // down the columns first, as wide as the longest name
+            let width = names
+                .iter()
+                .map(|name| name.chars().count())
+                .max()
+                .unwrap_or(0)
+                + 2;
+            let columns = (TERMINAL_WIDTH / width).max(1);
+            let rows = names.len().div_ceil(columns);
+
+            for row in 0..rows {
+                let mut line = String::new();
+                for name in names.iter().skip(row).step_by(rows) {
+                    line.push_str(&format!("{:<width$}", name, width = width));
+                }
+                writeln!(out, "{}", line.trim_end())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+const LS_USAGE: &str = "[-l1] [path...]";
+
+/// `ls`, the entries of directories, or the files themselves
+pub fn ls(args: &[&str]) -> isize {
+    let mut listing = None;
+    let Some(paths) = options("ls", LS_USAGE, args, "l1", |opt| {
+        listing = match opt {
+            Opt::Flag('l') => Some(Listing::Long),
+            _ => Some(Listing::Lines),
+        };
+        true
+    }) else {
+        return 2;
+    };
+
+    let paths = if paths.is_empty() { &["."][..] } else { paths };
+    let mut out = BufWriter::stdout();
+    let listing = listing.unwrap_or(match out.get_ref().is_console() {
+        true => Listing::Columns,
+        false => Listing::Lines,
+    });
+
+    let mut status = 0;
+    for (index, path) in paths.iter().enumerate() {
+        let entries = match sys_stat(path) {
+            Ok(stat) if stat.is_dir() => sys_read_dir(path),
+            Ok(stat) => Ok(vec![Metadata {
+                name: String::from(*path),
+                stat,
+            }]),
+            Err(errno) => Err(errno),
+        };
+
+        let mut entries = match entries {
+            Ok(entries) => entries,
+            Err(errno) => {
+                errln!("ls: {}: {}", path, errno);
+                status = 1;
+                continue;
+            }
+        };
+        entries.sort_by_key(|entry| entry.name.to_ascii_lowercase());
+
+        let result = match paths.len() {
+            1 => list(&mut out, &entries, listing),
+            _ => {
+                let gap = if index > 0 { "\n" } else { "" };
+                writeln!(out, "{}{}:", gap, path).and_then(|_| list(&mut out, &entries, listing))
+            }
+        };
+
+        if result.is_err() {
+            return 1;
+        }
+    }
+
+    status
+}
+
+/// `cat`, the files one after another
+pub fn cat(args: &[&str]) -> isize {
+    let Some(paths) = options("cat", "[file...]", args, "", |_| true) else {
+        return 2;
+    };
+
+    let mut out = File::stdout();
+    let mut status = 0;
+
+    for path in inputs(paths) {
+        let result = Input::open(path).and_then(|mut input| copy(&mut input, &mut out));
+        if let Err(errno) = result {
+            errln!("cat: {}: {}", path, errno);
+            status = 1;
+        }
+    }
+
+    status
+}
+
+/// Lines of `hexdump`, the offset, the bytes in hex and as text
+struct HexDump<W> {
+    out: W,
+    offset: usize,
+    line: Vec<u8>,
+}
+
+impl<W: Write> HexDump<W> {
+    fn push(&mut self, mut data: &[u8]) -> Result<(), Errno> {
+        while !data.is_empty() {
+            let count = (HEX_LINE - self.line.len()).min(data.len());
+            self.line.extend_from_slice(&data[..count]);
+            data = &data[count..];
+
+            if self.line.len() == HEX_LINE {
+                self.show_line()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn show_line(&mut self) -> Result<(), Errno> {
+        write!(self.out, "{:08x} ", self.offset)?;
+
+        for index in 0..HEX_LINE {
+            if index % 8 == 0 {
+                write!(self.out, " ")?;
+            }
+            match self.line.get(index) {
+                Some(byte) => write!(self.out, "{:02x} ", byte)?,
+                None => write!(self.out, "   ")?,
+            }
+        }
+
+        let text: String = self
+            .line
+            .iter()
+            .map(|&byte| match byte.is_ascii_graphic() || byte == b' ' {
+                true => byte as char,
+                false => '.',
+            })
+            .collect();
+        writeln!(self.out, " |{}|", text)?;
+
+        self.offset += self.line.len();
+        self.line.clear();
+
+        Ok(())
+    }
+
+    /// Show the last line, and the offset of the end
+    fn finish(&mut self) -> Result<(), Errno> {
+        if !self.line.is_empty() {
+            self.show_line()?;
+        }
+        writeln!(self.out, "{:08x}", self.offset)
+    }
+}
+
+const HEXDUMP_USAGE: &str = "[-n length] [file...]";
+
+/// `hexdump`, the bytes of the files one after another
+pub fn hexdump(args: &[&str]) -> isize {
+    let mut limit = None;
+    let Some(paths) = options("hexdump", HEXDUMP_USAGE, args, "n:", |opt| match opt {
+        Opt::Value(_, value) => value.parse().map(|n: usize| limit = Some(n)).is_ok(),
+        _ => false,
+    }) else {
+        return 2;
+    };
+
+    let mut dump = HexDump {
+        out: BufWriter::stdout(),
+        offset: 0,
+        line: Vec::new(),
+    };
+    let mut buf = vec![0; 512];
+    let mut status = 0;
+
+    for path in inputs(paths) {
+        let mut input = match Input::open(path) {
+            Ok(input) => input,
+            Err(errno) => {
+                errln!("hexdump: {}: {}", path, errno);
+                status = 1;
+                continue;
+            }
+        };
+
+        loop {
+            let wanted = limit.map_or(buf.len(), |limit: usize| {
+                buf.len().min(limit - dump.offset - dump.line.len())
+            });
+            if wanted == 0 {
+                break;
+            }
+
+            let count = match input.read(&mut buf[..wanted]) {
+                Ok(0) => break,
+                Ok(count) => count,
+                Err(errno) => {
+                    errln!("hexdump: {}: {}", path, errno);
+                    status = 1;
+                    break;
+                }
+            };
+
+            if dump.push(&buf[..count]).is_err() {
+                return 1;
+            }
+        }
+    }
+
+    if dump.finish().is_err() {
+        return 1;
+    }
+
+    status
+}
+
+/// Where `source` goes for `cp` and `mv`, into `dest` if it is a directory
+fn destination(source: &str, dest: &str, into_dir: bool) -> String {
+    match into_dir {
+        true => join(dest, basename(source)),
+        false => String::from(dest),
+    }
+}
+
+fn copy_file(source: &str, dest: &str) -> Result<(), Errno> {
+    let mut input = File::open(source)?;
+    let mut output = File::create(dest)?;
+    copy(&mut input, &mut output)
+}
+
+/// There is no rename, the file is copied then removed
+fn move_file(source: &str, dest: &str) -> Result<(), Errno> {
+    copy_file(source, dest)?;
+    sys_unlink(source)
+}
+
+const TRANSFER_USAGE: &str = "<source> <dest>, or <source>... <dir>";
+
+/// Copy or move the files of `args`, with `op`, as `cp` and `mv` do
+fn transfer(name: &str, args: &[&str], op: fn(&str, &str) -> Result<(), Errno>) -> isize {
+    let Some(operands) = options(name, TRANSFER_USAGE, args, "", |_| true) else {
+        return 2;
+    };
+
+    let [sources @ .., dest] = operands else {
+        return usage(name, TRANSFER_USAGE, "missing file operand");
+    };
+    if sources.is_empty() {
+        return usage(name, TRANSFER_USAGE, "missing destination file operand");
+    }
+
+    let into_dir = sys_stat(dest).is_ok_and(|stat| stat.is_dir());
+    if sources.len() > 1 && !into_dir {
+        errln!("{}: {}: {}", name, dest, Errno::ENOTDIR);
+        return 1;
+    }
+
+    let mut status = 0;
+    for source in sources {
+        let target = destination(source, dest, into_dir);
+
+        let result = match sys_stat(source) {
+            Ok(stat) if stat.is_dir() => Err(Errno::EISDIR),
+            Ok(_) if same_file(source, &target) => {
+                errln!("{}: '{}' and '{}' are the same file", name, source, target);
+                status = 1;
+                continue;
+            }
+            Ok(_) => op(source, &target),
+            Err(errno) => Err(errno),
+        };
+
+        if let Err(errno) = result {
+            errln!("{}: {}: {}", name, source, errno);
+            status = 1;
+        }
+    }
+
+    status
+}
+
+/// `cp`, copy files
+pub fn cp(args: &[&str]) -> isize {
+    transfer("cp", args, copy_file)
+}
+
+/// `mv`, move files
+pub fn mv(args: &[&str]) -> isize {
+    transfer("mv", args, move_file)
+}
+
+/// Remove the file at `path`, or the directory and all in it
+fn remove(path: &str, recursive: bool) -> Result<(), Errno> {
+    if !sys_stat(path)?.is_dir() {
+        return sys_unlink(path);
+    }
+
+    if !recursive {
+        return Err(Errno::EISDIR);
+    }
+
+    for entry in sys_read_dir(path)? {
+        remove(&join(path, &entry.name), true)?;
+    }
+
+    sys_rmdir(path)
+}
+
+const RM_USAGE: &str = "[-rf] <path>...";
+
+/// `rm`, remove files, and directories with `-r`
+pub fn rm(args: &[&str]) -> isize {
+    let (mut recursive, mut force) = (false, false);
+    let Some(paths) = options("rm", RM_USAGE, args, "rf", |opt| {
+        match opt {
+            Opt::Flag('r') => recursive = true,
+            _ => force = true,
+        }
+        true
+    }) else {
+        return 2;
+    };
+
+    if paths.is_empty() && !force {
+        return usage("rm", RM_USAGE, "missing operand");
+    }
+
+    let mut status = 0;
+    for path in paths {
+        if absolute(path) == "/" {
+            errln!("rm: refusing to remove '/'");
+            status = 1;
+            continue;
+        }
+
+        match remove(path, recursive) {
+            Ok(()) => (),
+            Err(Errno::ENOENT) if force => (),
+            Err(errno) => {
+                errln!("rm: {}: {}", path, errno);
+                status = 1;
+            }
+        }
+    }
+
+    status
+}
+
+/// Create the directory at `path` and those above it, if they are missing
+fn make_parents(path: &str) -> Result<(), Errno> {
+    let ends = path.match_indices('/').map(|(index, _)| index);
+
+    for end in ends.chain([path.len()]).filter(|&end| end > 0) {
+        match sys_mkdir(&path[..end]) {
+            Ok(()) | Err(Errno::EEXIST) => (),
+            Err(errno) => return Err(errno),
+        }
+    }
+
+    match sys_stat(path)?.is_dir() {
+        true => Ok(()),
+        false => Err(Errno::EEXIST),
+    }
+}
+
+const MKDIR_USAGE: &str = "[-p] <dir>...";
+
+/// `mkdir`, create directories, and those above them with `-p`
+pub fn mkdir(args: &[&str]) -> isize {
+    let mut parents = false;
+    let Some(paths) = options("mkdir", MKDIR_USAGE, args, "p", |_| {
+        parents = true;
+        true
+    }) else {
+        return 2;
+    };
+
+    if paths.is_empty() {
+        return usage("mkdir", MKDIR_USAGE, "missing operand");
+    }
+
+    let mut status = 0;
+    for path in paths {
+        let result = match parents {
+            true => make_parents(path),
+            false => sys_mkdir(path),
+        };
+
+        if let Err(errno) = result {
+            errln!("mkdir: {}: {}", path, errno);
+            status = 1;
+        }
+    }
+
+    status
+}
@@ -0,0 +1,127 @@
+//! The common commands in one program
+//!
+//! It runs the command it is called as, so it is installed once under
+//! the name of each: `/APP/LS` runs `ls`. `coreutils <command> [args...]`
+//! runs any of them as well.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+mod files;
+mod procs;
+mod text;
+
+use core::fmt::Display;
+use lib::getopt::{GetOpt, Opt, OptError};
+use lib::*;
+
+extern crate lib;
+
+/// A command, given the arguments after its name and returning its status
+type Command = fn(&[&str]) -> isize;
+
+const COMMANDS: &[(&str, Command)] = &[
+    ("cat", files::cat),
+    ("cp", files::cp),
+    ("echo", text::echo),
+    ("free", procs::free),
+    ("grep", text::grep),
+    ("head", text::head),
+    ("hexdump", files::hexdump),
+    ("kill", procs::kill),
+    ("ls", files::ls),
+    ("mkdir", files::mkdir),
+    ("mv", files::mv),
+    ("ps", procs::ps),
+    ("rm", files::rm),
+    ("tail", text::tail),
+    ("uptime", procs::uptime),
+    ("wc", text::wc),
+];
+
+/// The command called `name`, FAT keeps the names in upper case
+fn command(name: &str) -> Option<Command> {
+    COMMANDS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, command)| *command)
+}
+
+/// Tell of a wrong use of `name`, returning the status for it
+pub fn usage(name: &str, usage: &str, error: impl Display) -> isize {
+    errln!("{}: {}", name, error);
+    errln!("Usage: {} {}", name, usage);
+    2
+}
+
+/// The files a command reads, standard input if there are none
+pub fn inputs<'a>(paths: &'a [&'a str]) -> &'a [&'a str] {
+    match paths {
+        [] => &["-"],
+        paths => paths,
+    }
+}
+
+/// Read the options of `args`, giving each one to `option`, which returns
+/// false for a value it cannot take. The operands, or None once the usage
+/// is told of.
+pub fn options<'a>(
+    name: &str,
+    usage_text: &str,
+    args: &'a [&'a str],
+    spec: &'a str,
+    mut option: impl FnMut(Opt<'a>) -> bool,
+) -> Option<&'a [&'a str]> {
+    let mut opts = GetOpt::new(args, spec);
+
+    for opt in opts.by_ref() {
+        match opt {
+            Ok(opt) if option(opt) => (),
+            Ok(Opt::Value(c, value)) => {
+                usage(
+                    name,
+                    usage_text,
+                    format_args!("-{}: invalid value '{}'", c, value),
+                );
+                return None;
+            }
+            Ok(Opt::Flag(c)) => {
+                usage(name, usage_text, OptError::Unknown(c));
+                return None;
+            }
+            Err(e) => {
+                usage(name, usage_text, e);
+                return None;
+            }
+        }
+    }
+
+    Some(opts.operands())
+}
+
+fn main(args: &[&str]) -> isize {
+    let name = args
+        .first()
+        .map_or("", |path| path.rsplit('/').next().unwrap_or(path));
+
+    if let Some(command) = command(name) {
+        return command(&args[1..]);
+    }
+
+    match args.get(1).and_then(|name| command(name)) {
+        Some(command) => command(&args[2..]),
+        None => {
+            println!("Usage: coreutils <command> [args...]");
+            println!();
+            println!("Commands:");
+            for (name, _) in COMMANDS {
+                println!("    {}", name);
+            }
+            2
+        }
+    }
+}
+
+entry!(main);
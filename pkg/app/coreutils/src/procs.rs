@@ -0,0 +1,243 @@
+//! Processes and the system, as `/proc` tells of them
+
+use alloc::{format, string::String, vec::Vec};
+use lib::fs::{BufWriter, Write};
+use lib::signal::Signal;
+use lib::*;
+
+use crate::{options, usage};
+
+/// The `Key:\tvalue` lines of a `/proc` file
+struct Fields(String);
+
+impl Fields {
+    fn read(path: &str) -> Result<Self, Errno> {
+        lib::fs::read_to_string(path).map(Self)
+    }
+
+    fn get(&self, key: &str) -> &str {
+        self.0
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(k, _)| *k == key)
+            .map_or("", |(_, value)| value.trim())
+    }
+
+    /// A value in kB, 0 if it is missing
+    fn kb(&self, key: &str) -> usize {
+        self.get(key)
+            .trim_end_matches("kB")
+            .trim()
+            .parse()
+            .unwrap_or(0)
+    }
+}
+
+/// Pids of the live processes, in order
+fn pids() -> Result<Vec<u16>, Errno> {
+    let mut pids: Vec<u16> = sys_read_dir("/proc")?
+        .iter()
+        .filter_map(|entry| entry.name.parse().ok())
+        .collect();
+    pids.sort();
+    Ok(pids)
+}
+
+/// `ps`, the processes
+pub fn ps(args: &[&str]) -> isize {
+    if options("ps", "", args, "", |_| true).is_none() {
+        return 2;
+    }
+
+    let pids = match pids() {
+        Ok(pids) => pids,
+        Err(errno) => {
+            errln!("ps: /proc: {}", errno);
+            return 1;
+        }
+    };
+
+    let mut out = BufWriter::stdout();
+    let header = writeln!(
+        out,
+        "{:>5} {:>5} {:>5} {:>4} {:<8} {:>8} {:>8}  NAME",
+        "PID", "PPID", "PGID", "NI", "STATE", "TICKS", "MEM"
+    );
+    if header.is_err() {
+        return 1;
+    }
+
+    for pid in pids {
+        // it may have exited since
+        let Ok(status) = Fields::read(&format!("/proc/{}/status", pid)) else {
+            continue;
+        };
+
+        let pgid = sys_get_pgid(pid).map_or(String::from("-"), |pgid| format!("{}", pgid));
+        let result = writeln!(
+            out,
+            "{:>5} {:>5} {:>5} {:>4} {:<8} {:>8} {:>6}kB  {}",
+            pid,
+            status.get("PPid"),
+            pgid,
+            status.get("Nice"),
+            status.get("State"),
+            status.get("Ticks"),
+            status.kb("Memory"),
+            status.get("Name")
+        );
+
+        if result.is_err() {
+            return 1;
+        }
+    }
+
+    0
+}
+
+/// The signal `-name` or `-number` of `kill`, as in `-9`, `-KILL` or
+/// `-SIGKILL`
+fn parse_signal(text: &str) -> Option<Signal> {
+    if let Ok(number) = text.parse::<u8>() {
+        return Signal::try_from(number).ok();
+    }
+
+    let name = text.to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    signals().find(|signal| format!("{:?}", signal)[3..] == *name)
+}
+
+fn signals() -> impl Iterator<Item = Signal> {
+    (1..64).filter_map(|number| Signal::try_from(number).ok())
+}
+
+const KILL_USAGE: &str = "[-<signal>] <pid | -pgid>..., or -l";
+
+/// `kill`, send a signal to processes, `SIGTERM` unless another is given
+pub fn kill(args: &[&str]) -> isize {
+    let (signal, targets) = match args {
+        ["-l"] => {
+            for signal in signals() {
+                println!("{:>2} {:?}", signal as u8, signal);
+            }
+            return 0;
+        }
+        ["--", targets @ ..] => (Some(Signal::SIGTERM), targets),
+        [first, targets @ ..] if first.starts_with('-') => (parse_signal(&first[1..]), targets),
+        targets => (Some(Signal::SIGTERM), targets),
+    };
+
+    let Some(signal) = signal else {
+        return usage(
+            "kill",
+            KILL_USAGE,
+            format_args!("{}: invalid signal", args[0]),
+        );
+    };
+    if targets.is_empty() {
+        return usage("kill", KILL_USAGE, "missing pid");
+    }
+
+    let mut status = 0;
+    for target in targets {
+        // a process group is given negated
+        let sent = match target.strip_prefix('-') {
+            Some(pgid) => pgid.parse().map(|pgid| sys_kill_group(pgid, signal)),
+            None => target.parse().map(|pid| sys_kill(pid, signal)),
+        };
+
+        match sent {
+            Ok(true) => (),
+            Ok(false) => {
+                errln!("kill: {}: no such process", target);
+                status = 1;
+            }
+            Err(_) => {
+                errln!("kill: {}: not a pid", target);
+                status = 1;
+            }
+        }
+    }
+
+    status
+}
+
+/// `free`, the memory used and left, in kB
+pub fn free(args: &[&str]) -> isize {
+    if options("free", "", args, "", |_| true).is_none() {
+        return 2;
+    }
+
+    let info = match Fields::read("/proc/meminfo") {
+        Ok(info) => info,
+        Err(errno) => {
+            errln!("free: /proc/meminfo: {}", errno);
+            return 1;
+        }
+    };
+
+    println!("{:<12} {:>10} {:>10} {:>10}", "", "total", "used", "free");
+
+    for (name, total, used) in [
+        ("Mem:", "MemTotal", "MemUsed"),
+        ("KernelHeap:", "KernelHeapTotal", "KernelHeapUsed"),
+        ("UserHeap:", "UserHeapTotal", "UserHeapUsed"),
+    ] {
+        let (total, used) = (info.kb(total), info.kb(used));
+        println!(
+            "{:<12} {:>10} {:>10} {:>10}",
+            name,
+            total,
+            used,
+            total.saturating_sub(used)
+        );
+    }
+
+    println!("Shared: {} kB", info.kb("MemShared"));
+
+    0
+}
+
+/// `uptime`, the time, how long since boot and how many processes
+pub fn uptime(args: &[&str]) -> isize {
+    if options("uptime", "", args, "", |_| true).is_none() {
+        return 2;
+    }
+
+    let seconds = match lib::fs::read_to_string("/proc/uptime") {
+        Ok(text) => text
+            .split('.')
+            .next()
+            .and_then(|seconds| seconds.trim().parse::<u64>().ok())
+            .unwrap_or(0),
+        Err(errno) => {
+            errln!("uptime: /proc/uptime: {}", errno);
+            return 1;
+        }
+    };
+
+    let now = sys_time();
+    let processes = pids().map_or(0, |pids| pids.len());
+
+    let mut up = String::new();
+    if seconds >= 86400 {
+        up.push_str(&format!("{} days, ", seconds / 86400));
+    }
+    up.push_str(&format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60
+    ));
+
+    println!(
+        " {:02}:{:02}:{:02} up {}, {} processes",
+        now.hour(),
+        now.minute(),
+        now.second(),
+        up,
+        processes
+    );
+
+    0
+}
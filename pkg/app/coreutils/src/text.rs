@@ -0,0 +1,327 @@
+//! Text, counted, cut and searched a line at a time
+
+use alloc::{collections::VecDeque, format, string::String, vec::Vec};
+use core::ops::AddAssign;
+use lib::fs::{BufReader, BufWriter, File, Input, Read, Write};
+use lib::getopt::Opt;
+use lib::*;
+
+use crate::{inputs, options, usage};
+
+/// Lines `head` and `tail` show by default
+const DEFAULT_LINES: usize = 10;
+
+/// `echo`, the arguments on a line, without the newline after `-n`
+pub fn echo(args: &[&str]) -> isize {
+    let (words, end) = match args {
+        ["-n", words @ ..] => (words, ""),
+        words => (words, "\n"),
+    };
+
+    match write!(File::stdout(), "{}{}", words.join(" "), end) {
+        Ok(()) => 0,
+        Err(_) => 1,
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct Counts {
+    lines: usize,
+    words: usize,
+    bytes: usize,
+}
+
+impl Counts {
+    fn of(input: &mut impl Read) -> Result<Self, Errno> {
+        let mut counts = Self::default();
+        let mut in_word = false;
+        let mut buf = [0; 512];
+
+        loop {
+            let count = input.read(&mut buf)?;
+            if count == 0 {
+                return Ok(counts);
+            }
+
+            for &byte in &buf[..count] {
+                if byte == b'\n' {
+                    counts.lines += 1;
+                }
+                if byte.is_ascii_whitespace() {
+                    in_word = false;
+                } else if !in_word {
+                    in_word = true;
+                    counts.words += 1;
+                }
+            }
+            counts.bytes += count;
+        }
+    }
+}
+
+impl AddAssign for Counts {
+    fn add_assign(&mut self, other: Self) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.bytes += other.bytes;
+    }
+}
+
+const WC_USAGE: &str = "[-lwc] [file...]";
+
+/// `wc`, the lines, words and bytes of the files
+pub fn wc(args: &[&str]) -> isize {
+    // lines, words and bytes, all of them unless some are asked for
+    let mut shown = [false; 3];
+    let Some(paths) = options("wc", WC_USAGE, args, "lwc", |opt| {
+        match opt {
+            Opt::Flag('l') => shown[0] = true,
+            Opt::Flag('w') => shown[1] = true,
+            _ => shown[2] = true,
+        }
+        true
+    }) else {
+        return 2;
+    };
+
+    if shown == [false; 3] {
+        shown = [true; 3];
+    }
+
+    let mut out = BufWriter::stdout();
+    let mut show = |counts: Counts, name: &str| {
+        let mut line = String::new();
+        let values = [counts.lines, counts.words, counts.bytes];
+        for (value, _) in values.iter().zip(shown).filter(|(_, shown)| *shown) {
+            line.push_str(&format!("{:>8}", value));
+        }
+        if !name.is_empty() {
+            line.push(' ');
+            line.push_str(name);
+        }
+        writeln!(out, "{}", line)
+    };
+
+    let mut total = Counts::default();
+    let mut status = 0;
+
+    for path in inputs(paths) {
+        let counts = match Input::open(path).and_then(|mut input| Counts::of(&mut input)) {
+            Ok(counts) => counts,
+            Err(errno) => {
+                errln!("wc: {}: {}", path, errno);
+                status = 1;
+                continue;
+            }
+        };
+
+        total += counts;
+        if show(counts, if paths.is_empty() { "" } else { path }).is_err() {
+            return 1;
+        }
+    }
+
+    if paths.len() > 1 && show(total, "total").is_err() {
+        return 1;
+    }
+
+    status
+}
+
+/// The count of `-n` and the operands, for `head` and `tail`
+fn line_count<'a>(
+    name: &str,
+    usage_text: &str,
+    args: &'a [&'a str],
+) -> Option<(usize, &'a [&'a str])> {
+    let mut lines = DEFAULT_LINES;
+    let paths = options(name, usage_text, args, "n:", |opt| match opt {
+        Opt::Value(_, value) => value.parse().map(|n| lines = n).is_ok(),
+        _ => false,
+    })?;
+
+    Some((lines, paths))
+}
+
+/// Show the lines of each file that `select` picks, after a header with
+/// its name if there are several
+fn each_input(
+    name: &str,
+    paths: &[&str],
+    mut select: impl FnMut(BufReader<Input>, &mut BufWriter<File>) -> Result<(), Errno>,
+) -> isize {
+    let mut out = BufWriter::stdout();
+    let mut status = 0;
+
+    for (index, path) in inputs(paths).iter().enumerate() {
+        let input = match Input::open(path) {
+            Ok(input) => input,
+            Err(errno) => {
+                errln!("{}: {}: {}", name, path, errno);
+                status = 1;
+                continue;
+            }
+        };
+
+        if paths.len() > 1 {
+            let gap = if index > 0 { "\n" } else { "" };
+            if writeln!(out, "{}==> {} <==", gap, path).is_err() {
+                return 1;
+            }
+        }
+
+        if let Err(errno) = select(BufReader::new(input), &mut out) {
+            errln!("{}: {}: {}", name, path, errno);
+            status = 1;
+        }
+    }
+
+    status
+}
+
+const HEAD_USAGE: &str = "[-n lines] [file...]";
+
+/// `head`, the first lines of the files
+pub fn head(args: &[&str]) -> isize {
+    let Some((count, paths)) = line_count("head", HEAD_USAGE, args) else {
+        return 2;
+    };
+
+    each_input("head", paths, |mut input, out| {
+        let mut line = Vec::new();
+        for _ in 0..count {
+            line.clear();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            out.write_all(&line)?;
+        }
+        Ok(())
+    })
+}
+
+const TAIL_USAGE: &str = "[-n lines] [file...]";
+
+/// `tail`, the last lines of the files
+pub fn tail(args: &[&str]) -> isize {
+    let Some((count, paths)) = line_count("tail", TAIL_USAGE, args) else {
+        return 2;
+    };
+
+    each_input("tail", paths, |mut input, out| {
+        let mut lines = VecDeque::with_capacity(count + 1);
+        loop {
+            let mut line = Vec::new();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            lines.push_back(line);
+            if lines.len() > count {
+                lines.pop_front();
+            }
+        }
+
+        for line in lines {
+            out.write_all(&line)?;
+        }
+        Ok(())
+    })
+}
+
+const GREP_USAGE: &str = "[-civn] <pattern> [file...]";
+
+/// `grep`, the lines the pattern is in. It is plain text, not a regular
+/// expression. The status is 0 if a line is found, 1 if none and 2 on
+/// errors.
+pub fn grep(args: &[&str]) -> isize {
+    let (mut count_only, mut ignore_case, mut invert, mut number) = (false, false, false, false);
+    let Some(operands) = options("grep", GREP_USAGE, args, "civn", |opt| {
+        match opt {
+            Opt::Flag('c') => count_only = true,
+            Opt::Flag('i') => ignore_case = true,
+            Opt::Flag('v') => invert = true,
+            _ => number = true,
+        }
+        true
+    }) else {
+        return 2;
+    };
+
+    let [pattern, paths @ ..] = operands else {
+        return usage("grep", GREP_USAGE, "missing pattern");
+    };
+
+    let pattern = match ignore_case {
+        true => pattern.to_lowercase(),
+        false => String::from(*pattern),
+    };
+    let prefix = paths.len() > 1;
+
+    let mut out = BufWriter::stdout();
+    let mut found = false;
+    let mut failed = false;
+
+    for path in inputs(paths) {
+        let input = match Input::open(path) {
+            Ok(input) => input,
+            Err(errno) => {
+                errln!("grep: {}: {}", path, errno);
+                failed = true;
+                continue;
+            }
+        };
+
+        let mut matches = 0;
+        for (index, line) in BufReader::new(input).lines().enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(errno) => {
+                    errln!("grep: {}: {}", path, errno);
+                    failed = true;
+                    break;
+                }
+            };
+
+            let matched = match ignore_case {
+                true => line.to_lowercase().contains(&pattern),
+                false => line.contains(&pattern),
+            };
+            if matched == invert {
+                continue;
+            }
+
+            matches += 1;
+            if count_only {
+                continue;
+            }
+
+            let result = match (prefix, number) {
+                (true, true) => writeln!(out, "{}:{}:{}", path, index + 1, line),
+                (true, false) => writeln!(out, "{}:{}", path, line),
+                (false, true) => writeln!(out, "{}:{}", index + 1, line),
+                (false, false) => writeln!(out, "{}", line),
+            };
+            if result.is_err() {
+                return 2;
+            }
+        }
+
+        if count_only {
+            let result = match prefix {
+                true => writeln!(out, "{}:{}", path, matches),
+                false => writeln!(out, "{}", matches),
+            };
+            if result.is_err() {
+                return 2;
+            }
+        }
+
+        found |= matches > 0;
+    }
+
+    match (failed, found) {
+        (true, _) => 2,
+        (false, true) => 0,
+        (false, false) => 1,
+    }
+}
@@ -1,16 +1,14 @@
 #![no_std]
 #![no_main]
 
-use lib::*;
+use lib::{vec::Vec, *};
 
 extern crate lib;
 
 static CHOPSTICK: [Semaphore; 5] = semaphore_array![0, 1, 2, 3, 4];
 static WAITER: Semaphore = Semaphore::new(64);
 
-fn main() -> isize {
-    let mut pids = [0u16; 5];
-
+fn main(_args: &[&str]) -> isize {
     // allow 4 philosophers to eat at the same time
     WAITER.init(4);
 
@@ -18,30 +16,26 @@ fn main() -> isize {
         chop.init(1);
     }
 
-    for (i, item) in pids.iter_mut().enumerate() {
-        let pid = sys_fork();
-        if pid == 0 {
-            philosopher(i);
-        } else {
-            *item = pid;
-        }
-    }
+    let threads: Vec<_> = (0..5)
+        .map(|i| thread::spawn(move || philosopher(i)))
+        .collect();
 
     let cpid = sys_get_pid();
+    let tids: Vec<_> = threads.iter().map(|t| t.id()).collect();
 
-    println!("#{} holds threads: {:?}", cpid, &pids);
+    println!("#{} holds threads: {:?}", cpid, &tids);
 
-    sys_stat();
+    sys_list_process();
 
-    for pid in pids {
-        println!("#{} Waiting for #{}...", cpid, pid);
-        sys_wait_pid(pid);
+    for thread in threads {
+        println!("#{} Waiting for #{}...", cpid, thread.id());
+        thread.join();
     }
 
     0
 }
 
-fn philosopher(id: usize) -> ! {
+fn philosopher(id: usize) {
     let pid = sys_get_pid();
 
     for _ in 0..100 {
@@ -58,7 +52,6 @@ fn philosopher(id: usize) -> ! {
         CHOPSTICK[id].signal();
         WAITER.signal();
     }
-    sys_exit(0);
 }
 
 #[inline(never)]
@@ -15,7 +15,7 @@ fn factorial(n: u64) -> u64 {
     }
 }
 
-fn main() -> isize {
+fn main(_args: &[&str]) -> isize {
     print!("Input n: ");
 
     let input = lib::stdin().read_line();
@@ -32,7 +32,7 @@ fn main() -> isize {
     let result = factorial(n);
 
     // print system status
-    sys_stat();
+    sys_list_process();
 
     // print result
     println!("The factorial of {} under modulo {} is {}.", n, MOD, result);
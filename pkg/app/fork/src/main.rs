@@ -8,12 +8,11 @@ use lib::*;
 
 static mut M: u64 = 0xdeadbeef;
 
-fn main() -> isize {
+fn main(_args: &[&str]) -> isize {
     let mut c = 32;
     let m_ptr = &raw mut M;
 
-    // do not alloc heap before `fork`
-    // which may cause unexpected behavior since we won't copy the heap in `fork`
+    // the child gets a copy of the memory, its changes stay its own
     let pid = sys_fork();
 
     if pid == 0 {
@@ -31,7 +30,7 @@ fn main() -> isize {
     } else {
         println!("I am the parent process");
 
-        sys_stat();
+        sys_list_process();
 
         assert_eq!(c, 32);
 
@@ -45,7 +44,7 @@ fn main() -> isize {
 
         unsafe {
             println!("parent read value of M: {:#x}", *m_ptr);
-            assert_eq!(*m_ptr, 0x2333);
+            assert_eq!(*m_ptr, 0xdeadbeef);
         }
 
         c += 1024;
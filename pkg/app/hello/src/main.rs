@@ -5,8 +5,13 @@ use lib::*;
 
 extern crate lib;
 
-fn main() -> isize {
+fn main(args: &[&str]) -> isize {
     println!("Hello, world!!!");
+    println!("Arguments: {:?}", args);
+
+    if let Some(user) = lib::env::var("USER") {
+        println!("Hello, {}!", user);
+    }
 
     let time = lib::sys_time();
     println!("Now at: {}", time);
@@ -1,7 +1,7 @@
 #![no_std]
 #![no_main]
 
-use lib::*;
+use lib::{vec::Vec, *};
 
 extern crate lib;
 
@@ -12,37 +12,35 @@ static IS_NOT_FULL: Semaphore = Semaphore::new(0x1000);
 static IS_NOT_EMPTY: Semaphore = Semaphore::new(0x2000);
 static MUTEX: Semaphore = Semaphore::new(0x6666);
 
-fn main() -> isize {
+fn main(_args: &[&str]) -> isize {
     IS_NOT_EMPTY.init(0);
     IS_NOT_FULL.init(QUEUE_COUNT);
     MUTEX.init(1);
 
-    let mut pids = [0u16; QUEUE_COUNT];
-
-    for (i, item) in pids.iter_mut().enumerate() {
-        let pid = sys_fork();
-        if pid == 0 {
+    let threads: Vec<_> = (0..QUEUE_COUNT)
+        .map(|i| {
             if i % 2 == 0 {
-                producer(i);
+                thread::spawn(move || producer(i))
             } else {
-                consumer(i);
+                thread::spawn(move || consumer(i))
             }
-        } else {
-            *item = pid;
-        }
-    }
+        })
+        .collect();
 
     let cpid = sys_get_pid();
+    let tids: Vec<_> = threads.iter().map(|t| t.id()).collect();
 
-    println!("#{} holds threads: {:?}", cpid, &pids);
+    println!("#{} holds threads: {:?}", cpid, &tids);
 
-    sys_stat();
+    sys_list_process();
 
-    for pid in pids {
-        println!("#{} Waiting for #{}...", cpid, pid);
-        sys_wait_pid(pid);
+    for thread in threads {
+        println!("#{} Waiting for #{}...", cpid, thread.id());
+        thread.join();
     }
 
+    println!("#{} Final count: {}", cpid, unsafe { COUNT });
+
     MUTEX.free();
     IS_NOT_EMPTY.free();
     IS_NOT_FULL.free();
@@ -50,7 +48,7 @@ fn main() -> isize {
     0
 }
 
-fn producer(id: usize) -> ! {
+fn producer(id: usize) {
     let pid = sys_get_pid();
     println!("New producer #{}({})", id, pid);
     for _ in 0..10 {
@@ -67,10 +65,9 @@ fn producer(id: usize) -> ! {
         MUTEX.signal();
         IS_NOT_EMPTY.signal();
     }
-    sys_exit(0);
 }
 
-fn consumer(id: usize) -> ! {
+fn consumer(id: usize) {
     let pid = sys_get_pid();
     println!("New consumer #{}({})", id, pid);
     for _ in 0..10 {
@@ -87,7 +84,6 @@ fn consumer(id: usize) -> ! {
         MUTEX.signal();
         IS_NOT_FULL.signal();
     }
-    sys_exit(0);
 }
 
 #[inline(never)]
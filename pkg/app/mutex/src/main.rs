@@ -1,7 +1,7 @@
 #![no_std]
 #![no_main]
 
-use lib::*;
+use lib::{time::Duration, *};
 
 extern crate lib;
 
@@ -10,28 +10,20 @@ static LOCK: SpinLock = SpinLock::new();
 static mut BURGER: isize = 0;
 static mut BURGER_SEM: isize = 0;
 
-fn main() -> isize {
-    let pid = sys_fork();
+fn main(_args: &[&str]) -> isize {
+    let semaphore = thread::spawn(try_semaphore);
 
-    if pid == 0 {
-        try_semaphore();
-    } else {
-        try_spin();
-        sys_wait_pid(pid);
-    }
+    try_spin();
+    semaphore.join();
 
     0
 }
 
 fn try_spin() {
-    let pid = sys_fork();
+    let boy = thread::spawn(boy_spin);
 
-    if pid == 0 {
-        boy_spin();
-    } else {
-        mother_spin();
-        sys_wait_pid(pid);
-    }
+    mother_spin();
+    boy.join();
 }
 
 fn mother_spin() {
@@ -48,7 +40,7 @@ fn mother_spin() {
 
         println!("Mother - SPIN : Oh, I have to hang clothes out.");
 
-        sleep(1500);
+        sleep(Duration::from_millis(1500));
 
         println!(
             "Mother - SPIN : Oh, Jesus! There are {} cheese burgers",
@@ -61,7 +53,7 @@ fn mother_spin() {
 
 fn boy_spin() {
     unsafe {
-        sleep(200);
+        sleep(Duration::from_millis(200));
         let burger_ptr = &raw mut BURGER;
 
         LOCK.acquire();
@@ -76,15 +68,11 @@ fn boy_spin() {
 fn try_semaphore() {
     MUTEX.init(1);
 
-    let pid = sys_fork();
+    let boy = thread::spawn(boy_semaphore);
 
-    if pid == 0 {
-        boy_semaphore();
-    } else {
-        mother_semaphore();
-        sys_wait_pid(pid);
-        MUTEX.free();
-    }
+    mother_semaphore();
+    boy.join();
+    MUTEX.free();
 }
 
 fn mother_semaphore() {
@@ -101,7 +89,7 @@ fn mother_semaphore() {
 
         println!("Mother - SEMA : Oh, I have to hang clothes out.");
 
-        sleep(1500);
+        sleep(Duration::from_millis(1500));
 
         println!(
             "Mother - SEMA : Oh, Jesus! There are {} cheese burgers",
@@ -114,7 +102,7 @@ fn mother_semaphore() {
 
 fn boy_semaphore() {
     unsafe {
-        sleep(200);
+        sleep(Duration::from_millis(200));
         let burger_ptr = &raw mut BURGER_SEM;
 
         MUTEX.wait();
@@ -0,0 +1,92 @@
+//! Tab completion
+//!
+//! The first word of a command is completed with the builtins and the
+//! programs in `$PATH`, the others with paths, which are relative to the
+//! working directory unless they start with `/`. FAT names are matched ignoring case.
+
+use alloc::{format, string::*, vec::Vec};
+use lib::readline::Completer;
+use lib::*;
+
+use crate::shell::BUILTINS;
+
+pub struct Completion;
+
+fn has_prefix(name: &str, prefix: &str) -> bool {
+    name.get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+/// Names in the directory at `path`, with a `/` after those of directories
+fn names(path: &str) -> Vec<String> {
+    let Ok(entries) = sys_read_dir(path) else {
+        return Vec::new();
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| match entry.is_dir() {
+            true => format!("{}/", entry.name),
+            false => entry.name,
+        })
+        .collect()
+}
+
+impl Completion {
+    fn commands(&self, word: &str) -> Vec<String> {
+        let mut candidates: Vec<String> = BUILTINS
+            .iter()
+            .filter(|name| name.starts_with(word))
+            .map(|name| String::from(*name))
+            .collect();
+
+        for dir in lib::env::var("PATH").unwrap_or("/APP").split(':') {
+            candidates.extend(
+                names(dir)
+                    .into_iter()
+                    .filter(|name| !name.ends_with('/') && has_prefix(name, word)),
+            );
+        }
+
+        candidates
+    }
+
+    fn paths(&self, word: &str) -> Vec<String> {
+        // the directory as typed, and the start of the name in it
+        let (dir, prefix) = match word.rfind('/') {
+            Some(index) => word.split_at(index + 1),
+            None => ("", word),
+        };
+
+        let path = if dir.is_empty() { "." } else { dir };
+        names(path)
+            .into_iter()
+            .filter(|name| has_prefix(name, prefix))
+            .map(|name| format!("{}{}", dir, name))
+            .collect()
+    }
+}
+
+impl Completer for Completion {
+    fn complete(&self, line: &str) -> (usize, Vec<String>) {
+        let start = line
+            .rfind(|c: char| c.is_whitespace() || matches!(c, '|' | '&' | ';' | '<' | '>'))
+            .map_or(0, |index| index + 1);
+        let word = &line[start..];
+
+        // a command comes first, or after an operator
+        let before = line[..start].trim_end();
+        let is_command = before.is_empty() || before.ends_with(['|', '&', ';']);
+
+        let mut candidates = if is_command && !word.contains('/') {
+            self.commands(word)
+        } else {
+            self.paths(word)
+        };
+
+        candidates.sort();
+        candidates.dedup();
+
+        (start, candidates)
+    }
+}
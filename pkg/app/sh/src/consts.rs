@@ -1,3 +1,9 @@
+/// The kernel process, the parent of init
+pub const KERNEL_PID: u16 = 1;
+
+/// Script run at boot by the shell started as init
+pub const RC_PATH: &str = "/ETC/RC";
+
 pub const fn help_text() -> &'static str {
     concat!(
         "    GGOS shell v",
@@ -7,19 +13,83 @@ pub const fn help_text() -> &'static str {
 
 Usage:
     help        | show this help
-    ps          | show process list
-    ls          | list directory
-    cd <path>   | change directory
-    cat <file>  | show file content
-    exec <file> | execute file
-    nohup <file>| execute file in background
-    kill <pid>  | kill process
+    cd [dir]    | change directory
+    echo [args...]
+                | print arguments
+    <file> [args...]
+                | execute file, searched in $PATH unless it has a `/`
+    exec <file> [args...]
+                | same as above
+    nohup <file> [args...]
+                | execute file in background
+    kill [-<signal>] <pid | -pgid | %job>
+                | send a signal to process, SIGTERM by default,
+                  e.g. `kill -9 <pid>` for SIGKILL
+    renice <nice> <pid>
+                | set the nice value of process, from -20 to 19
+    mount <source> <target> <fstype>
+                | mount a filesystem, e.g. `mount /dev/hdb1 /mnt fat`
+    umount <target>
+                | unmount a filesystem
+    history     | list the lines entered before
+    jobs        | list background and stopped jobs
+    fg [%job]   | continue a job in foreground
+    bg [%job]   | continue a stopped job in background
+    true, false | succeed or fail
+    test <condition>, [ <condition> ]
+                | check a condition, e.g. `-f file`, `-d dir`, `-z text`,
+                  `a = b`, `a != b`, `n -lt m`, negated with `!`
+    export <name[=value]>...
+                | pass variables on to programs
+    unset <name>...
+                | remove variables
+    set         | list variables
+    source <file> [args...]
+                | run a script in this shell, also `. <file>`
+    shift [n]   | drop the first script arguments
+    break [n], continue [n]
+                | leave or restart a loop
     clear       | clear screen
-    exit        | exit shell
+    exit [code] | exit shell
+
+Syntax:
+    a | b       | pipe the output of `a` into `b`
+    a < f       | read input from file `f`
+    a > f       | write output to file `f`, `>>` to append
+    a 2>&1      | send errors where the output goes
+    a &         | run `a` in background
+    a ; b       | run `a` then `b`
+    a && b      | run `b` if `a` succeeded
+    a || b      | run `b` if `a` failed
+    'x' "x" \x  | quote, `$` is still expanded in `"`
+    # text      | comment up to the end of line
+    NAME=value  | set a variable, for `cmd` only before `NAME=value cmd`
+    $VAR ${VAR} | value of a variable
+    $? $$       | last status, shell pid
+    $0 $1 $# $@ | script name, its arguments, their count, all of them
+    if a; then b; elif c; then d; else e; fi
+    while a; do b; done, until a; do b; done
+    for x in a b c; do echo $x; done
+                | run commands again or if a command succeeds
+
+Programs:
+    ls, cat, hexdump, cp, mv, rm, mkdir, ps, free, uptime, wc, head,
+    tail and grep are programs in /APP, e.g. `ls -l /APP`, `ps | grep sh`.
+
+Scripts:
+    sh <file> [args...] runs a script, and so does a file starting with
+    `#!/APP/SH`. /ETC/RC is run at boot.
 
 Shortcuts:
     Ctrl + D    | exit shell
     Ctrl + C    | cancel current command
+    Ctrl + Z    | stop current command, see `fg` and `bg`
+    Up / Down   | recall the lines entered before
+    Ctrl + R    | search the lines entered before
+    Tab         | complete a command or path
+    Ctrl + A/E  | move to the start / end of line
+    Ctrl + U/K/W
+                | delete before / after the cursor, the word before it
 
 "#
     )
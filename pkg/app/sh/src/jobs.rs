@@ -0,0 +1,207 @@
+//! Jobs
+//!
+//! A job is the processes of one pipeline, in a process group of their
+//! own so Ctrl-C and Ctrl-Z reach all of them. Jobs in the background or
+//! stopped are kept in the table until they are done.
+
+use alloc::{string::String, vec::Vec};
+use lib::{signal::Signal, *};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    Stopped,
+}
+
+pub struct Job {
+    /// Number in the table, 0 until it is added
+    pub id: usize,
+    pub pgid: u16,
+    /// Processes that did not exit yet
+    pub pids: Vec<u16>,
+    /// Process of the last command, which the status comes from
+    pub last: Option<u16>,
+    /// Exit status of the job, that of a builtin at its end until the
+    /// last process exits
+    pub status: isize,
+    pub state: JobState,
+    pub text: String,
+}
+
+impl Job {
+    fn exited(&mut self, pid: u16, code: isize) {
+        self.pids.retain(|&p| p != pid);
+        if self.last == Some(pid) {
+            self.status = code;
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pids.is_empty()
+    }
+
+    /// Resume the processes if they are stopped
+    fn cont(&mut self) {
+        if self.state == JobState::Stopped {
+            sys_kill_group(self.pgid, Signal::SIGCONT);
+            self.state = JobState::Running;
+        }
+    }
+}
+
+/// Status of a job that was stopped, as shells report it
+pub const STOPPED_STATUS: isize = 128 + Signal::SIGTSTP as isize;
+
+#[derive(Default)]
+pub struct JobTable {
+    jobs: Vec<Job>,
+}
+
+impl JobTable {
+    fn insert(&mut self, mut job: Job) -> usize {
+        if job.id == 0 {
+            job.id = self.jobs.iter().map(|j| j.id).max().unwrap_or(0) + 1;
+        }
+
+        let id = job.id;
+        let index = self.jobs.partition_point(|j| j.id < id);
+        self.jobs.insert(index, job);
+        id
+    }
+
+    /// Keep `job` running in the background
+    pub fn background(&mut self, job: Job) -> usize {
+        self.insert(job)
+    }
+
+    /// The job `%n` or `n`, or the latest one
+    fn position(&self, spec: Option<&str>) -> Option<usize> {
+        match spec {
+            Some(spec) => {
+                let id = spec.trim_start_matches('%').parse::<usize>().ok()?;
+                self.jobs.iter().position(|j| j.id == id)
+            }
+            None => self.jobs.len().checked_sub(1),
+        }
+    }
+
+    /// Process group of the job `%n` or `n`
+    pub fn pgid(&self, spec: &str) -> Option<u16> {
+        self.position(Some(spec)).map(|index| self.jobs[index].pgid)
+    }
+
+    /// Note that `pid` exited or stopped, false if it is in no job
+    fn update(&mut self, pid: u16, status: WaitStatus) -> bool {
+        let Some(job) = self.jobs.iter_mut().find(|j| j.pids.contains(&pid)) else {
+            return false;
+        };
+
+        match status {
+            WaitStatus::Exited(code) => job.exited(pid, code),
+            WaitStatus::Stopped(_) if job.state == JobState::Running => {
+                job.state = JobState::Stopped;
+                println!("[{}]+ Stopped\t{}", job.id, job.text);
+            }
+            WaitStatus::Stopped(_) => (),
+        }
+
+        true
+    }
+
+    /// A child outside of the table, an orphan that init adopted
+    fn report(pid: u16, status: WaitStatus) {
+        if let WaitStatus::Exited(code) = status {
+            println!("[+] process #{} exited with code {}", pid, code);
+        }
+    }
+
+    /// Collect the children that exited or stopped, and report the jobs
+    /// that are done
+    pub fn reap(&mut self) {
+        while let Some((pid, status)) = sys_waitpid_untraced(None, true) {
+            if !self.update(pid, status) {
+                Self::report(pid, status);
+            }
+        }
+
+        self.jobs.retain(|job| {
+            if job.is_done() {
+                match job.status {
+                    0 => println!("[{}]  Done\t{}", job.id, job.text),
+                    code => println!("[{}]  Exit {}\t{}", job.id, code, job.text),
+                }
+            }
+            !job.is_done()
+        });
+    }
+
+    /// Run `job` in the foreground until it is done or stopped, returning
+    /// its status
+    pub fn foreground(&mut self, mut job: Job) -> isize {
+        sys_set_foreground(Some(job.pgid));
+        job.cont();
+
+        while !job.is_done() {
+            match sys_waitpid_untraced(None, false) {
+                Some((pid, WaitStatus::Exited(code))) if job.pids.contains(&pid) => {
+                    job.exited(pid, code)
+                }
+                Some((pid, WaitStatus::Stopped(_))) if job.pids.contains(&pid) => {
+                    job.state = JobState::Stopped;
+                    break;
+                }
+                Some((pid, status)) => {
+                    if !self.update(pid, status) {
+                        Self::report(pid, status);
+                    }
+                }
+                // collected by someone else
+                None => job.pids.clear(),
+            }
+        }
+
+        sys_set_foreground(None);
+
+        if job.is_done() {
+            return job.status;
+        }
+
+        let text = job.text.clone();
+        let id = self.insert(job);
+        println!();
+        println!("[{}]+ Stopped\t{}", id, text);
+
+        STOPPED_STATUS
+    }
+
+    /// `jobs`
+    pub fn list(&self) {
+        for job in &self.jobs {
+            let state = match job.state {
+                JobState::Running => "Running",
+                JobState::Stopped => "Stopped",
+            };
+            println!("[{}]  {}\t{}", job.id, state, job.text);
+        }
+    }
+
+    /// `fg [%n]`, None if there is no such job
+    pub fn fg(&mut self, spec: Option<&str>) -> Option<isize> {
+        let job = self.jobs.remove(self.position(spec)?);
+        println!("{}", job.text);
+        Some(self.foreground(job))
+    }
+
+    /// `bg [%n]`, false if there is no such job
+    pub fn bg(&mut self, spec: Option<&str>) -> bool {
+        let Some(index) = self.position(spec) else {
+            return false;
+        };
+
+        let job = &mut self.jobs[index];
+        job.cont();
+        println!("[{}]+ {} &", job.id, job.text);
+
+        true
+    }
+}
@@ -3,89 +3,80 @@
 
 extern crate alloc;
 
+mod completion;
 mod consts;
+mod jobs;
+mod parser;
 mod services;
+mod shell;
 
-use alloc::string::{String, ToString};
-use alloc::vec::Vec;
+use alloc::{format, string::String};
+use completion::Completion;
+use lib::readline::{Editor, HISTORY_SIZE, History, ReadError};
 use lib::*;
+use parser::ParseError;
+use shell::Shell;
 
 extern crate lib;
 
-fn main() -> isize {
-    let mut root_dir = String::from("/APP/");
+fn main(args: &[&str]) -> isize {
+    let mut shell = Shell::default();
+
+    // `sh <script> [args...]` runs the script and exits with its status
+    if let [_, script, ..] = args {
+        shell.args = args[1..].iter().map(|arg| String::from(*arg)).collect();
+        shell.source(script);
+        return shell.status;
+    }
+
+    // started by the kernel, the shell is init and runs the startup script
+    if sys_get_ppid() == consts::KERNEL_PID {
+        shell.source(consts::RC_PATH);
+    }
+
+    let history_file = lib::env::var("HISTFILE").unwrap_or("/.sh_history");
+    shell.editor = Editor::new(History::with_file(HISTORY_SIZE, history_file));
+
     println!("            <<< Welcome to GGOS shell >>>            ");
     println!("                                 type `help` for help");
-    loop {
-        print!("[{}] $ ", root_dir);
-        let input = stdin().read_line();
-        let line: Vec<&str> = input.trim().split(' ').collect();
-        match line[0] {
-            "\x04" | "exit" => {
-                println!();
-                break;
-            }
-            "ps" => sys_stat(),
-            "ls" => sys_list_dir(root_dir.as_str()),
-            "cat" => {
-                if line.len() < 2 {
-                    println!("Usage: cat <file>");
-                    continue;
-                }
-
-                services::cat(line[1], root_dir.as_str());
-            }
-            "cd" => {
-                if line.len() < 2 {
-                    println!("Usage: cd <dir>");
-                    continue;
-                }
 
-                services::cd(line[1], &mut root_dir);
-            }
-            "exec" => {
-                if line.len() < 2 {
-                    println!("Usage: exec <file>");
-                    continue;
-                }
+    // lines of a command that goes on past the end of a line
+    let mut text = String::new();
 
-                services::exec(line[1], root_dir.as_str());
-            }
-            "nohup" => {
-                if line.len() < 2 {
-                    println!("Usage: nohup <file>");
-                    continue;
-                }
+    while !shell.exited {
+        let prompt = if text.is_empty() {
+            shell.jobs.reap();
+            format!("[{}] $ ", sys_getcwd())
+        } else {
+            String::from("> ")
+        };
 
-                services::nohup(line[1], root_dir.as_str());
-            }
-            "kill" => {
-                if line.len() < 2 {
-                    println!("Usage: kill <pid>");
-                    continue;
-                }
-                let pid = line[1].to_string().parse::<u16>();
-
-                if pid.is_err() {
-                    errln!("Cannot parse pid");
-                    continue;
-                }
-
-                services::kill(pid.unwrap());
+        let line = match shell.editor.read_line(&prompt, Some(&Completion)) {
+            Ok(line) => line,
+            Err(ReadError::Interrupted) => {
+                text.clear();
+                continue;
             }
-            "help" => print!("{}", consts::help_text()),
-            "clear" => print!("\x1b[1;1H\x1b[2J"),
-            _ => {
-                if line[0].is_empty() {
-                    println!();
-                    continue;
-                }
-                println!("[=] you said \"{}\"", input)
+            Err(ReadError::Eof) => break,
+        };
+
+        shell.editor.history.add(&line);
+        text.push_str(&line);
+        text.push('\n');
+
+        match shell.run(&text) {
+            Err(ParseError::Incomplete) => continue,
+            Err(e) => {
+                errln!("sh: {}", e);
+                shell.status = 2;
             }
+            Ok(()) => (),
         }
+
+        text.clear();
     }
 
-    0
+    shell.status
 }
 
 entry!(main);
@@ -0,0 +1,711 @@
+//! Command lines and scripts
+//!
+//! A list is commands joined by newlines, `;`, `&`, `&&` and `||`. A
+//! command is a pipeline or an `if`, `while`, `until` or `for` holding
+//! lists of its own. A pipeline is simple commands joined by `|`, and a
+//! simple command is words and redirections. Words are split on blanks,
+//! quoted with `'` and `"` and escaped with `\`. Variables stay apart in
+//! the words and are expanded only when the command runs, so
+//! `false; echo $?` sees the status of `false`.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt;
+use core::iter::Peekable;
+use core::str::CharIndices;
+use lib::{Fd, FileMode};
+
+/// Words that start or end a compound command where a command starts
+const KEYWORDS: &[&str] = &[
+    "if", "then", "elif", "else", "fi", "while", "until", "for", "in", "do", "done",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The text ends in the middle of a command, more lines may finish it
+    Incomplete,
+    Syntax(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Incomplete => write!(f, "syntax error: unexpected end of file"),
+            ParseError::Syntax(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// A piece of a word
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Var(String),
+}
+
+/// A word as written, expanded into a string before it is used
+#[derive(Debug, Clone, Default)]
+pub struct Word(Vec<Part>);
+
+impl Word {
+    fn push(&mut self, c: char) {
+        match self.0.last_mut() {
+            Some(Part::Text(text)) => text.push(c),
+            _ => self.0.push(Part::Text(c.into())),
+        }
+    }
+
+    /// The text of a word without variables
+    fn as_text(&self) -> Option<&str> {
+        match self.0.as_slice() {
+            [Part::Text(text)] => Some(text),
+            _ => None,
+        }
+    }
+
+    /// The keyword the word is, if it is one
+    fn as_keyword(&self) -> Option<&'static str> {
+        let text = self.as_text()?;
+        KEYWORDS.iter().copied().find(|keyword| *keyword == text)
+    }
+
+    /// The fd of a redirection, when the word is only digits
+    fn as_fd(&self) -> Option<Fd> {
+        match self.0.as_slice() {
+            [Part::Text(text)] if text.bytes().all(|b| b.is_ascii_digit()) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The word with its variables replaced by what `var` gives
+    pub fn expand(&self, var: &impl Fn(&str) -> String) -> String {
+        self.0
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Var(name) => var(name),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Redirect {
+    /// `n< path`, `n> path` and `n>> path`
+    File { fd: Fd, path: Word, mode: FileMode },
+    /// `n>&m` and `n<&m`
+    Dup { fd: Fd, to: Fd },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Command {
+    pub words: Vec<Word>,
+    pub redirects: Vec<Redirect>,
+}
+
+impl Command {
+    fn is_empty(&self) -> bool {
+        self.words.is_empty() && self.redirects.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    pub commands: Vec<Command>,
+    /// Ended with `&`
+    pub background: bool,
+    /// As written, for the job table
+    pub text: String,
+}
+
+/// When a command of a list runs, depending on the one before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    Always,
+    /// After `&&`
+    IfSuccess,
+    /// After `||`
+    IfFailure,
+}
+
+/// A command of a list
+#[derive(Debug, Clone)]
+pub enum Node {
+    Pipeline(Pipeline),
+    /// `if c; then a; elif d; then b; else e; fi`
+    If {
+        branches: Vec<(List, List)>,
+        otherwise: Option<List>,
+    },
+    /// `while c; do a; done`, or `until c; do a; done` with `until` set
+    While {
+        condition: List,
+        body: List,
+        until: bool,
+    },
+    /// `for name in words; do a; done`, the arguments of the shell
+    /// without `in`
+    For {
+        name: String,
+        words: Option<Vec<Word>>,
+        body: List,
+    },
+}
+
+pub type List = Vec<(Condition, Node)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Newline,
+    Pipe,
+    Or,
+    Amp,
+    And,
+    Semi,
+    In,
+    Out,
+    Append,
+    DupIn,
+    DupOut,
+}
+
+impl Op {
+    fn as_str(self) -> &'static str {
+        match self {
+            Op::Newline => "newline",
+            Op::Pipe => "|",
+            Op::Or => "||",
+            Op::Amp => "&",
+            Op::And => "&&",
+            Op::Semi => ";",
+            Op::In => "<",
+            Op::Out => ">",
+            Op::Append => ">>",
+            Op::DupIn => "<&",
+            Op::DupOut => ">&",
+        }
+    }
+}
+
+enum Token {
+    Word(Word),
+    /// A redirection, with the fd written before it
+    Redirect(Option<Fd>, Op),
+    Op(Op),
+}
+
+/// A token and where it is in the line
+struct Spanned {
+    token: Token,
+    start: usize,
+    end: usize,
+}
+
+struct Lexer<'a> {
+    line: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Lexer<'_> {
+    fn next_is(&mut self, c: char) -> bool {
+        self.chars.next_if(|&(_, next)| next == c).is_some()
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.line.len(), |&(i, _)| i)
+    }
+
+    /// `$?`, `$$`, `$#`, `$@`, `$1`, `$NAME` or `${NAME}`, the `$` is
+    /// taken already
+    fn variable(&mut self, word: &mut Word) -> Result<(), ParseError> {
+        let name = match self.chars.peek() {
+            Some(&(_, c @ ('?' | '$' | '#' | '@' | '*' | '0'..='9'))) => {
+                self.chars.next();
+                String::from(c)
+            }
+            Some(&(_, '{')) => {
+                self.chars.next();
+                let mut name = String::new();
+                loop {
+                    match self.chars.next() {
+                        Some((_, '}')) => break,
+                        Some((_, c)) => name.push(c),
+                        None => return Err(ParseError::Syntax(String::from("unterminated `${`"))),
+                    }
+                }
+                name
+            }
+            Some(&(_, c)) if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some((_, c)) = self
+                    .chars
+                    .next_if(|&(_, c)| c.is_ascii_alphanumeric() || c == '_')
+                {
+                    name.push(c);
+                }
+                name
+            }
+            // a lone `$` is kept as it is
+            _ => {
+                word.push('$');
+                return Ok(());
+            }
+        };
+
+        word.0.push(Part::Var(name));
+        Ok(())
+    }
+
+    /// The rest of a `"` quoted string, the quote is taken already
+    fn double_quoted(&mut self, word: &mut Word) -> Result<(), ParseError> {
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(()),
+                Some((_, '\\')) => {
+                    match self.chars.next_if(|&(_, c)| matches!(c, '"' | '\\' | '$')) {
+                        Some((_, c)) => word.push(c),
+                        None => word.push('\\'),
+                    }
+                }
+                Some((_, '$')) => self.variable(word)?,
+                Some((_, c)) => word.push(c),
+                None => return Err(ParseError::Incomplete),
+            }
+        }
+    }
+
+    /// The operator starting with `c`, which is taken already
+    fn operator(&mut self, c: char) -> Op {
+        match c {
+            '|' if self.next_is('|') => Op::Or,
+            '|' => Op::Pipe,
+            '&' if self.next_is('&') => Op::And,
+            '&' => Op::Amp,
+            ';' => Op::Semi,
+            '\n' => Op::Newline,
+            '<' if self.next_is('&') => Op::DupIn,
+            '<' => Op::In,
+            '>' if self.next_is('>') => Op::Append,
+            '>' if self.next_is('&') => Op::DupOut,
+            _ => Op::Out,
+        }
+    }
+
+    fn tokens(mut self) -> Result<Vec<Spanned>, ParseError> {
+        let mut tokens = Vec::new();
+
+        loop {
+            // blanks between tokens, lines continued, and comments up to
+            // the end of the line
+            loop {
+                if self
+                    .chars
+                    .next_if(|&(_, c)| c.is_whitespace() && c != '\n')
+                    .is_some()
+                {
+                    continue;
+                }
+
+                let mut ahead = self.chars.clone();
+                match (ahead.next(), ahead.next()) {
+                    (Some((_, '\\')), Some((_, '\n'))) => {
+                        self.chars = ahead;
+                    }
+                    (Some((_, '#')), _) => {
+                        while self.chars.next_if(|&(_, c)| c != '\n').is_some() {}
+                    }
+                    _ => break,
+                }
+            }
+
+            let Some(&(start, c)) = self.chars.peek() else {
+                break;
+            };
+
+            if matches!(c, '|' | '&' | ';' | '<' | '>' | '\n') {
+                self.chars.next();
+                let op = self.operator(c);
+                let token = match op {
+                    Op::In | Op::Out | Op::Append | Op::DupIn | Op::DupOut => {
+                        Token::Redirect(None, op)
+                    }
+                    _ => Token::Op(op),
+                };
+                let end = self.offset();
+                tokens.push(Spanned { token, start, end });
+                continue;
+            }
+
+            let mut word = Word::default();
+            // digits right before `<` or `>` are the fd to redirect
+            let mut digits = true;
+
+            while let Some(&(_, c)) = self.chars.peek() {
+                if c.is_whitespace() || matches!(c, '|' | '&' | ';') {
+                    break;
+                }
+
+                if matches!(c, '<' | '>') {
+                    if digits && let Some(fd) = word.as_fd() {
+                        self.chars.next();
+                        let op = self.operator(c);
+                        let end = self.offset();
+                        tokens.push(Spanned {
+                            token: Token::Redirect(Some(fd), op),
+                            start,
+                            end,
+                        });
+                        word = Word::default();
+                    }
+                    break;
+                }
+
+                self.chars.next();
+                digits &= c.is_ascii_digit();
+
+                match c {
+                    '\'' => loop {
+                        match self.chars.next() {
+                            Some((_, '\'')) => break,
+                            Some((_, c)) => word.push(c),
+                            None => return Err(ParseError::Incomplete),
+                        }
+                    },
+                    '"' => self.double_quoted(&mut word)?,
+                    '\\' => match self.chars.next() {
+                        // a line continued
+                        Some((_, '\n')) => (),
+                        Some((_, c)) => word.push(c),
+                        None => return Err(ParseError::Incomplete),
+                    },
+                    '$' => self.variable(&mut word)?,
+                    c => word.push(c),
+                }
+
+                // `''` is an empty word, not nothing
+                if word.0.is_empty() {
+                    word.0.push(Part::Text(String::new()));
+                }
+            }
+
+            if !word.0.is_empty() {
+                let end = self.offset();
+                tokens.push(Spanned {
+                    token: Token::Word(word),
+                    start,
+                    end,
+                });
+            }
+        }
+
+        Ok(tokens)
+    }
+}
+
+fn unexpected(token: Option<&Token>) -> ParseError {
+    ParseError::Syntax(match token {
+        Some(Token::Word(word)) => match word.as_text() {
+            Some(text) => format!("syntax error near `{}`", text),
+            None => String::from("syntax error near a word"),
+        },
+        Some(Token::Redirect(_, op) | Token::Op(op)) => {
+            format!("syntax error near `{}`", op.as_str())
+        }
+        None => return ParseError::Incomplete,
+    })
+}
+
+struct Parser<'a> {
+    line: &'a str,
+    tokens: Peekable<vec::IntoIter<Spanned>>,
+}
+
+impl Parser<'_> {
+    fn peek(&mut self) -> Option<&Token> {
+        self.tokens.peek().map(|spanned| &spanned.token)
+    }
+
+    /// The keyword coming next, where a command starts
+    fn peek_keyword(&mut self) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Word(word)) => word.as_keyword(),
+            _ => None,
+        }
+    }
+
+    fn next_is(&mut self, op: Op) -> bool {
+        self.tokens
+            .next_if(|spanned| matches!(spanned.token, Token::Op(next) if next == op))
+            .is_some()
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.next_is(Op::Newline) {}
+    }
+
+    /// Take one of `keywords`, returning which
+    fn keyword(&mut self, keywords: &[&'static str]) -> Result<&'static str, ParseError> {
+        match self.peek_keyword() {
+            Some(keyword) if keywords.contains(&keyword) => {
+                self.tokens.next();
+                Ok(keyword)
+            }
+            _ => Err(unexpected(self.peek())),
+        }
+    }
+
+    /// Commands up to one of the keywords in `end`, or up to the end of
+    /// the text if there are none
+    fn list(&mut self, end: &[&str]) -> Result<List, ParseError> {
+        let mut list = Vec::new();
+        let mut condition = Condition::Always;
+
+        loop {
+            // `;` only ends a command, a newline may come after any
+            if condition == Condition::Always {
+                while self.next_is(Op::Semi) || self.next_is(Op::Newline) {}
+            } else {
+                self.skip_newlines();
+            }
+
+            if condition == Condition::Always {
+                match self.peek_keyword() {
+                    Some(keyword) if end.contains(&keyword) => {
+                        // an empty list is not allowed before a keyword
+                        if list.is_empty() {
+                            return Err(unexpected(self.peek()));
+                        }
+                        return Ok(list);
+                    }
+                    _ if self.peek().is_none() && end.is_empty() => return Ok(list),
+                    _ => (),
+                }
+            }
+
+            let mut node = self.node()?;
+
+            let next = condition;
+            condition = Condition::Always;
+
+            match self.peek() {
+                Some(Token::Op(Op::And)) => condition = Condition::IfSuccess,
+                Some(Token::Op(Op::Or)) => condition = Condition::IfFailure,
+                Some(Token::Op(Op::Semi | Op::Newline)) | None => (),
+                Some(Token::Op(Op::Amp)) => match &mut node {
+                    Node::Pipeline(pipeline) => pipeline.background = true,
+                    _ => return Err(unexpected(self.peek())),
+                },
+                token => return Err(unexpected(token)),
+            }
+
+            if condition != Condition::Always || matches!(self.peek(), Some(Token::Op(Op::Amp))) {
+                self.tokens.next();
+            }
+
+            list.push((next, node));
+        }
+    }
+
+    fn node(&mut self) -> Result<Node, ParseError> {
+        match self.peek_keyword() {
+            Some("if") => self.if_clause(),
+            Some("while" | "until") => self.while_clause(),
+            Some("for") => self.for_clause(),
+            Some(_) => Err(unexpected(self.peek())),
+            None => self.pipeline().map(Node::Pipeline),
+        }
+    }
+
+    fn if_clause(&mut self) -> Result<Node, ParseError> {
+        self.keyword(&["if"])?;
+
+        let mut branches = Vec::new();
+        loop {
+            let condition = self.list(&["then"])?;
+            self.keyword(&["then"])?;
+            let body = self.list(&["elif", "else", "fi"])?;
+            branches.push((condition, body));
+
+            match self.keyword(&["elif", "else", "fi"])? {
+                "elif" => continue,
+                "else" => {
+                    let otherwise = self.list(&["fi"])?;
+                    self.keyword(&["fi"])?;
+                    return Ok(Node::If {
+                        branches,
+                        otherwise: Some(otherwise),
+                    });
+                }
+                _ => {
+                    return Ok(Node::If {
+                        branches,
+                        otherwise: None,
+                    });
+                }
+            }
+        }
+    }
+
+    fn while_clause(&mut self) -> Result<Node, ParseError> {
+        let until = self.keyword(&["while", "until"])? == "until";
+        let condition = self.list(&["do"])?;
+        self.keyword(&["do"])?;
+        let body = self.list(&["done"])?;
+        self.keyword(&["done"])?;
+
+        Ok(Node::While {
+            condition,
+            body,
+            until,
+        })
+    }
+
+    fn for_clause(&mut self) -> Result<Node, ParseError> {
+        self.keyword(&["for"])?;
+
+        let next = self.tokens.next().map(|spanned| spanned.token);
+        let name = match &next {
+            Some(Token::Word(word)) => word.as_text().filter(|text| is_name(text)),
+            _ => None,
+        };
+        let Some(name) = name.map(String::from) else {
+            return Err(unexpected(next.as_ref()));
+        };
+
+        self.skip_newlines();
+
+        let words = match self.peek_keyword() {
+            Some("in") => {
+                self.tokens.next();
+                let mut words = Vec::new();
+                while let Some(Spanned {
+                    token: Token::Word(word),
+                    ..
+                }) = self
+                    .tokens
+                    .next_if(|spanned| matches!(spanned.token, Token::Word(_)))
+                {
+                    words.push(word);
+                }
+                Some(words)
+            }
+            _ => None,
+        };
+
+        while self.next_is(Op::Semi) || self.next_is(Op::Newline) {}
+
+        self.keyword(&["do"])?;
+        let body = self.list(&["done"])?;
+        self.keyword(&["done"])?;
+
+        Ok(Node::For { name, words, body })
+    }
+
+    /// Simple commands joined by `|`, up to any other operator
+    fn pipeline(&mut self) -> Result<Pipeline, ParseError> {
+        let mut pipeline = Pipeline::default();
+        let mut command = Command::default();
+        let mut span: Option<(usize, usize)> = None;
+
+        loop {
+            match self.peek() {
+                Some(Token::Word(_) | Token::Redirect(..)) => (),
+                Some(Token::Op(Op::Pipe)) if !command.is_empty() => {
+                    self.tokens.next();
+                    pipeline.commands.push(core::mem::take(&mut command));
+                    self.skip_newlines();
+                    // a command has to follow
+                    if self.peek().is_none() {
+                        return Err(ParseError::Incomplete);
+                    }
+                    continue;
+                }
+                token if command.is_empty() => return Err(unexpected(token)),
+                _ => break,
+            }
+
+            let Some(Spanned { token, start, end }) = self.tokens.next() else {
+                break;
+            };
+            let (first, _) = *span.get_or_insert((start, end));
+            span = Some((first, end));
+
+            match token {
+                Token::Word(word) => command.words.push(word),
+                Token::Redirect(fd, op) => {
+                    let target = match self.tokens.next() {
+                        Some(Spanned {
+                            token: Token::Word(word),
+                            end,
+                            ..
+                        }) => {
+                            span = Some((first, end));
+                            word
+                        }
+                        next => return Err(unexpected(next.as_ref().map(|s| &s.token))),
+                    };
+
+                    command.redirects.push(redirect(fd, op, target)?);
+                }
+                Token::Op(_) => unreachable!(),
+            }
+        }
+
+        pipeline.commands.push(command);
+
+        if let Some((first, end)) = span {
+            pipeline.text = String::from(self.line[first..end].trim());
+        }
+
+        Ok(pipeline)
+    }
+}
+
+/// Whether `text` can name a variable
+pub fn is_name(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn redirect(fd: Option<Fd>, op: Op, target: Word) -> Result<Redirect, ParseError> {
+    Ok(match op {
+        Op::In => Redirect::File {
+            fd: fd.unwrap_or(0),
+            path: target,
+            mode: FileMode::ReadOnly,
+        },
+        Op::Out => Redirect::File {
+            fd: fd.unwrap_or(1),
+            path: target,
+            mode: FileMode::ReadWriteCreateOrTruncate,
+        },
+        Op::Append => Redirect::File {
+            fd: fd.unwrap_or(1),
+            path: target,
+            mode: FileMode::ReadWriteCreateOrAppend,
+        },
+        _ => Redirect::Dup {
+            fd: fd.unwrap_or(if op == Op::DupIn { 0 } else { 1 }),
+            to: target
+                .as_fd()
+                .ok_or_else(|| ParseError::Syntax(format!("`{}` needs an fd", op.as_str())))?,
+        },
+    })
+}
+
+/// Parse a command line or a script into the commands to run, in order
+pub fn parse(text: &str) -> Result<List, ParseError> {
+    let lexer = Lexer {
+        line: text,
+        chars: text.char_indices().peekable(),
+    };
+
+    let mut parser = Parser {
+        line: text,
+        tokens: lexer.tokens()?.into_iter().peekable(),
+    };
+
+    parser.list(&[])
+}
@@ -0,0 +1,837 @@
+//! Running command lines
+//!
+//! Every command of a pipeline is started with its stdin and stdout moved
+//! onto the pipes around it and its redirections applied, all done on the
+//! fds of the shell itself since programs inherit them when spawned. The
+//! shell puts its own fds back once the command is started.
+//!
+//! Variables belong to the shell, the exported ones are passed on to the
+//! programs it starts as their environment.
+
+use alloc::collections::BTreeMap;
+use alloc::{format, string::*, vec::Vec};
+use lib::readline::Editor;
+use lib::{signal::Signal, *};
+
+use crate::consts;
+use crate::jobs::{Job, JobState, JobTable};
+use crate::parser::{self, Command, Condition, List, Node, ParseError, Pipeline, Redirect, Word};
+use crate::services;
+
+/// Commands run by the shell itself
+pub const BUILTINS: &[&str] = &[
+    ".", "[", "bg", "break", "cd", "clear", "continue", "echo", "exec", "exit", "export", "false",
+    "fg", "help", "history", "jobs", "kill", "mount", "nohup", "renice", "set", "shift", "source",
+    "test", "true", "umount", "unset",
+];
+
+/// `NAME` and `value` of a `NAME=value` word
+fn assignment(word: &str) -> Option<(&str, &str)> {
+    word.split_once('=')
+        .filter(|(name, _)| parser::is_name(name))
+}
+
+struct Variable {
+    value: String,
+    exported: bool,
+}
+
+/// Set by `break` and `continue` until the loop they are in sees it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Control {
+    Break,
+    Continue,
+}
+
+/// Fds of the shell moved aside for a command, put back by `restore`
+#[derive(Default)]
+struct SavedFds(Vec<(Fd, Option<Fd>)>);
+
+impl SavedFds {
+    /// Make `fd` refer to what `source` does
+    fn redirect(&mut self, fd: Fd, source: Fd) -> Result<(), Errno> {
+        if !self.0.iter().any(|&(f, _)| f == fd) {
+            // a copy that programs don't inherit, none if `fd` is not open
+            let saved = sys_dup(fd).ok();
+            if let Some(saved) = saved {
+                sys_set_cloexec(saved, true)?;
+            }
+            self.0.push((fd, saved));
+        }
+
+        sys_dup2(source, fd).map(|_| ())
+    }
+
+    fn restore(self) {
+        for (fd, saved) in self.0.into_iter().rev() {
+            match saved {
+                Some(saved) => {
+                    sys_dup2(saved, fd).ok();
+                    sys_close(saved);
+                }
+                None => {
+                    sys_close(fd);
+                }
+            }
+        }
+    }
+}
+
+pub struct Shell {
+    /// Status of the last pipeline, `$?`
+    pub status: isize,
+    pub jobs: JobTable,
+    pub editor: Editor,
+    /// Set by `exit`
+    pub exited: bool,
+    /// `$0`, the name of the shell or script, and the arguments after it
+    pub args: Vec<String>,
+    vars: BTreeMap<String, Variable>,
+    /// Loops being run, and what `break` or `continue` asked of them
+    loops: usize,
+    control: Option<(Control, usize)>,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        let vars = lib::env::vars()
+            .into_iter()
+            .map(|(name, value)| {
+                let value = Variable {
+                    value: String::from(value),
+                    exported: true,
+                };
+                (String::from(name), value)
+            })
+            .collect();
+
+        Self {
+            status: 0,
+            jobs: JobTable::default(),
+            editor: Editor::default(),
+            exited: false,
+            args: alloc::vec![String::from("sh")],
+            vars,
+            loops: 0,
+            control: None,
+        }
+    }
+}
+
+impl Shell {
+    /// Run a command line or a script, nothing runs unless all of it
+    /// parses
+    pub fn run(&mut self, text: &str) -> Result<(), ParseError> {
+        let list = parser::parse(text)?;
+        self.run_list(&list);
+        Ok(())
+    }
+
+    /// Run the script at `path` in this shell, false if it cannot be read
+    /// or parsed
+    pub fn source(&mut self, path: &str) -> bool {
+        let text = match lib::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                errln!("sh: {}: {}", path, e);
+                self.status = 1;
+                return false;
+            }
+        };
+
+        if let Err(e) = self.run(&text) {
+            errln!("sh: {}: {}", path, e);
+            self.status = 2;
+            return false;
+        }
+
+        true
+    }
+
+    /// Whether the commands of a list should stop, for `exit` or for
+    /// `break` and `continue`
+    fn interrupted(&self) -> bool {
+        self.exited || self.control.is_some()
+    }
+
+    fn run_list(&mut self, list: &List) {
+        for (condition, node) in list {
+            let skip = match condition {
+                Condition::Always => false,
+                Condition::IfSuccess => self.status != 0,
+                Condition::IfFailure => self.status == 0,
+            };
+
+            if !skip {
+                self.run_node(node);
+            }
+
+            if self.interrupted() {
+                break;
+            }
+        }
+    }
+
+    fn run_node(&mut self, node: &Node) {
+        match node {
+            Node::Pipeline(pipeline) => self.run_pipeline(pipeline),
+            Node::If {
+                branches,
+                otherwise,
+            } => {
+                for (condition, body) in branches {
+                    self.run_list(condition);
+                    if self.interrupted() {
+                        return;
+                    }
+                    if self.status == 0 {
+                        self.run_list(body);
+                        return;
+                    }
+                }
+
+                match otherwise {
+                    Some(otherwise) => self.run_list(otherwise),
+                    None => self.status = 0,
+                }
+            }
+            Node::While {
+                condition,
+                body,
+                until,
+            } => self.run_loop(|shell| {
+                shell.run_list(condition);
+                let succeeded = shell.status == 0;
+                (succeeded != *until).then_some(body)
+            }),
+            Node::For { name, words, body } => {
+                let mut values: Vec<String> = match words {
+                    Some(words) => words.iter().map(|w| self.expand(w)).collect(),
+                    None => self.args[1..].to_vec(),
+                };
+                values.reverse();
+
+                self.run_loop(|shell| {
+                    let value = values.pop()?;
+                    shell.set_var(name, value);
+                    Some(body)
+                })
+            }
+        }
+    }
+
+    /// Run the bodies `next` gives until it gives none, with the status of
+    /// the last body run
+    fn run_loop<'a>(&mut self, mut next: impl FnMut(&mut Self) -> Option<&'a List>) {
+        self.loops += 1;
+        let mut status = 0;
+
+        while !self.interrupted() {
+            let Some(body) = next(self) else {
+                break;
+            };
+            if self.interrupted() {
+                break;
+            }
+
+            self.run_list(body);
+            status = self.status;
+
+            // `break n` and `continue n` reach the loops outside
+            match self.control {
+                Some((control, 1)) => {
+                    self.control = None;
+                    if control == Control::Break {
+                        break;
+                    }
+                }
+                Some((control, n)) => {
+                    self.control = Some((control, n - 1));
+                    break;
+                }
+                None => (),
+            }
+        }
+
+        self.loops -= 1;
+        if !self.exited {
+            self.status = status;
+        }
+    }
+
+    fn var(&self, name: &str) -> String {
+        match name {
+            "?" => self.status.to_string(),
+            "$" => sys_get_pid().to_string(),
+            "#" => (self.args.len() - 1).to_string(),
+            "@" | "*" => self.args[1..].join(" "),
+            name if name.bytes().all(|b| b.is_ascii_digit()) => name
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| self.args.get(index))
+                .cloned()
+                .unwrap_or_default(),
+            name => self
+                .vars
+                .get(name)
+                .map(|var| var.value.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn set_var(&mut self, name: &str, value: String) {
+        match self.vars.get_mut(name) {
+            Some(var) => var.value = value,
+            None => {
+                let var = Variable {
+                    value,
+                    exported: false,
+                };
+                self.vars.insert(String::from(name), var);
+            }
+        }
+    }
+
+    /// The environment passed on to programs, `NAME=value` for every
+    /// exported variable
+    fn envs(&self) -> Vec<String> {
+        self.vars
+            .iter()
+            .filter(|(_, var)| var.exported)
+            .map(|(name, var)| format!("{}={}", name, var.value))
+            .collect()
+    }
+
+    fn expand(&self, word: &Word) -> String {
+        word.expand(&|name| self.var(name))
+    }
+
+    fn run_pipeline(&mut self, pipeline: &Pipeline) {
+        let mut commands: Vec<(Vec<String>, &[Redirect])> = pipeline
+            .commands
+            .iter()
+            .map(|Command { words, redirects }| {
+                (
+                    words.iter().map(|w| self.expand(w)).collect(),
+                    redirects.as_slice(),
+                )
+            })
+            .collect();
+
+        // `nohup cmd` is `cmd &`
+        let mut background = pipeline.background;
+        if let Some((args, _)) = commands.first_mut()
+            && args.first().is_some_and(|a| a == "nohup")
+        {
+            args.remove(0);
+            background = true;
+        }
+
+        // pipes[i] connects commands i and i + 1
+        let mut pipes = Vec::new();
+        for _ in 1..commands.len() {
+            match sys_pipe_cloexec() {
+                Ok(pipe) => pipes.push(pipe),
+                Err(e) => {
+                    errln!("sh: cannot create a pipe: {}", e);
+                    Self::close_pipes(&pipes);
+                    self.status = 1;
+                    return;
+                }
+            }
+        }
+
+        let mut pgid = None;
+        let mut pids = Vec::new();
+        let mut last = None;
+        let mut status = 0;
+
+        // readers first, so a builtin writing into a pipe has someone to
+        // drain it
+        for (index, (args, redirects)) in commands.iter().enumerate().rev() {
+            let stdin = index.checked_sub(1).map(|i| pipes[i].0);
+            let stdout = pipes.get(index).map(|&(_, write)| write);
+
+            let mut saved = SavedFds::default();
+            let ret = self.connect(&mut saved, stdin, stdout, redirects);
+
+            let result = match ret {
+                Ok(()) => self.run_command(args, pgid),
+                Err(e) => {
+                    errln!("sh: {}", e);
+                    Err(1)
+                }
+            };
+
+            saved.restore();
+
+            let is_last = index == commands.len() - 1;
+            match result {
+                Ok(pid) => {
+                    pgid.get_or_insert(pid);
+                    pids.push(pid);
+                    if is_last {
+                        last = Some(pid);
+                    }
+                }
+                Err(code) if is_last => status = code,
+                Err(_) => (),
+            }
+        }
+
+        Self::close_pipes(&pipes);
+
+        let Some(pgid) = pgid else {
+            self.status = status;
+            return;
+        };
+
+        let job = Job {
+            id: 0,
+            pgid,
+            pids,
+            last,
+            status,
+            state: JobState::Running,
+            text: pipeline.text.clone(),
+        };
+
+        self.status = if background {
+            let id = self.jobs.background(job);
+            println!("[{}] {}", id, pgid);
+            0
+        } else {
+            self.jobs.foreground(job)
+        };
+    }
+
+    fn close_pipes(pipes: &[(Fd, Fd)]) {
+        for &(read, write) in pipes {
+            sys_close(read);
+            sys_close(write);
+        }
+    }
+
+    /// Move the pipes and the redirections of a command onto the fds
+    fn connect(
+        &self,
+        saved: &mut SavedFds,
+        stdin: Option<Fd>,
+        stdout: Option<Fd>,
+        redirects: &[Redirect],
+    ) -> Result<(), String> {
+        if let Some(read) = stdin {
+            saved
+                .redirect(0, read)
+                .map_err(|e| format!("pipe: {}", e))?;
+        }
+        if let Some(write) = stdout {
+            saved
+                .redirect(1, write)
+                .map_err(|e| format!("pipe: {}", e))?;
+        }
+
+        for redirect in redirects {
+            match redirect {
+                Redirect::File { fd, path, mode } => {
+                    let path = self.expand(path);
+                    let file =
+                        sys_open_cloexec(&path, *mode).map_err(|e| format!("{}: {}", path, e))?;
+                    let ret = saved.redirect(*fd, file);
+                    sys_close(file);
+                    ret.map_err(|e| format!("{}: {}", fd, e))?;
+                }
+                Redirect::Dup { fd, to } => {
+                    saved
+                        .redirect(*fd, *to)
+                        .map_err(|e| format!("{}: {}", to, e))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run a builtin or start a program in the process group `pgid`, a new
+    /// one if None. Ok with the pid of the program, Err with the status of
+    /// the builtin or of the failure.
+    fn run_command(&mut self, args: &[String], pgid: Option<u16>) -> Result<u16, isize> {
+        // `NAME=value` before a command is for its environment only, and
+        // sets the variable without one
+        let count = args.iter().take_while(|a| assignment(a).is_some()).count();
+        let (assignments, args) = args.split_at(count);
+
+        if args.is_empty() {
+            for (name, value) in assignments.iter().filter_map(|a| assignment(a)) {
+                self.set_var(name, String::from(value));
+            }
+            return Err(0);
+        }
+
+        if let Some(status) = self.builtin(args) {
+            return Err(status);
+        }
+
+        // `exec cmd` runs `cmd`
+        let args = match args[0].as_str() {
+            "exec" if args.len() > 1 => &args[1..],
+            _ => args,
+        };
+
+        let Some(pid) = self.spawn(args, assignments) else {
+            errln!("sh: {}: command not found", args[0]);
+            return Err(127);
+        };
+
+        sys_set_pgid(pid, pgid.unwrap_or(0));
+
+        Ok(pid)
+    }
+
+    /// Where a program could be, by its name
+    fn candidates(&self, name: &str) -> Vec<String> {
+        if name.contains('/') {
+            return alloc::vec![String::from(name)];
+        }
+
+        lib::env::var("PATH")
+            .unwrap_or("/APP")
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| format!("{}/{}", dir.trim_end_matches('/'), name))
+            .collect()
+    }
+
+    fn spawn(&self, args: &[String], assignments: &[String]) -> Option<u16> {
+        let mut envs = self.envs();
+        for assignment in assignments {
+            let name = &assignment[..=assignment.find('=').unwrap_or(0)];
+            envs.retain(|env| !env.starts_with(name));
+            envs.push(assignment.clone());
+        }
+        let envs: Vec<&str> = envs.iter().map(String::as_str).collect();
+
+        self.candidates(&args[0]).into_iter().find_map(|path| {
+            let mut argv = Vec::with_capacity(args.len());
+            argv.push(path.as_str());
+            argv.extend(args[1..].iter().map(String::as_str));
+
+            match sys_spawn_with(&path, &argv, &envs) {
+                0 => None,
+                pid => Some(pid),
+            }
+        })
+    }
+
+    /// Run `args` if it is a builtin, returning its status
+    fn builtin(&mut self, args: &[String]) -> Option<isize> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        let status = match args[..] {
+            ["exit"] => {
+                self.exited = true;
+                self.status
+            }
+            ["exit", code] => {
+                self.exited = true;
+                code.parse().unwrap_or(2)
+            }
+            ["cd"] => services::cd("/"),
+            ["cd", dir] => services::cd(dir),
+            ["echo", ref words @ ..] => {
+                println!("{}", words.join(" "));
+                0
+            }
+            ["true", ..] => 0,
+            ["false", ..] => 1,
+            ["export"] => {
+                for (name, var) in self.vars.iter().filter(|(_, var)| var.exported) {
+                    println!("export {}={}", name, var.value);
+                }
+                0
+            }
+            ["export", ref words @ ..] => self.export(words),
+            ["unset", ref names @ ..] if !names.is_empty() => {
+                for name in names {
+                    self.vars.remove(*name);
+                }
+                0
+            }
+            ["set"] => {
+                for (name, var) in &self.vars {
+                    println!("{}={}", name, var.value);
+                }
+                0
+            }
+            ["source" | ".", path, ref rest @ ..] => {
+                // the arguments of the script, when it is given any
+                let args = (!rest.is_empty()).then(|| {
+                    let mut args = alloc::vec![String::from(path)];
+                    args.extend(rest.iter().map(|a| String::from(*a)));
+                    core::mem::replace(&mut self.args, args)
+                });
+
+                self.source(path);
+
+                if let Some(args) = args {
+                    self.args = args;
+                }
+                self.status
+            }
+            ["test", ref rest @ ..] => Self::test(rest),
+            ["[", ref rest @ .., "]"] => Self::test(rest),
+            ["break" | "continue", ref rest @ ..] if rest.len() < 2 => {
+                let control = match args[0] {
+                    "break" => Control::Break,
+                    _ => Control::Continue,
+                };
+
+                let Some(n) = rest.first().map_or(Some(1), |n| n.parse::<usize>().ok()) else {
+                    errln!("{}: {}: numeric argument required", args[0], rest[0]);
+                    return Some(2);
+                };
+
+                if self.loops == 0 || n == 0 {
+                    errln!("{}: only meaningful in a loop", args[0]);
+                    return Some(1);
+                }
+
+                self.control = Some((control, n.min(self.loops)));
+                0
+            }
+            ["shift", ref rest @ ..] if rest.len() < 2 => {
+                let n = rest.first().map_or(Some(1), |n| n.parse::<usize>().ok());
+                match n {
+                    Some(n) if n < self.args.len() => {
+                        self.args.drain(1..=n);
+                        0
+                    }
+                    _ => {
+                        errln!("shift: shift count out of range");
+                        1
+                    }
+                }
+            }
+            ["kill", ref rest @ ..] => self.kill(rest),
+            ["renice", nice, pid] => {
+                let (Ok(nice), Ok(pid)) = (nice.parse::<i8>(), pid.parse::<u16>()) else {
+                    errln!("Cannot parse nice or pid");
+                    return Some(2);
+                };
+
+                if !sys_set_priority(pid, nice) {
+                    errln!("no such process: {}", pid);
+                    return Some(1);
+                }
+                0
+            }
+            ["mount", source, target, fstype] => {
+                if let Err(e) = sys_mount(source, target, fstype) {
+                    errln!("mount: {} at {}: {}", source, target, e);
+                    return Some(1);
+                }
+                0
+            }
+            ["umount", target] => {
+                if let Err(e) = sys_umount(target) {
+                    errln!("umount: {}: {}", target, e);
+                    return Some(1);
+                }
+                0
+            }
+            ["history"] => {
+                for (index, line) in self.editor.history.iter().enumerate() {
+                    println!("{:>5}  {}", index + 1, line);
+                }
+                0
+            }
+            ["jobs"] => {
+                self.jobs.list();
+                0
+            }
+            ["fg"] | ["fg", _] => match self.jobs.fg(args.get(1).copied()) {
+                Some(status) => status,
+                None => {
+                    errln!("fg: no such job");
+                    1
+                }
+            },
+            ["bg"] | ["bg", _] => match self.jobs.bg(args.get(1).copied()) {
+                true => 0,
+                false => {
+                    errln!("bg: no such job");
+                    1
+                }
+            },
+            ["help"] => {
+                print!("{}", consts::help_text());
+                0
+            }
+            ["clear"] => {
+                print!("\x1b[1;1H\x1b[2J");
+                0
+            }
+            [name, ..] => {
+                return Self::usage(name).map(|usage| {
+                    println!("Usage: {}", usage);
+                    2
+                });
+            }
+            [] => 0,
+        };
+
+        Some(status)
+    }
+
+    /// How a builtin is used, when it was called with the wrong arguments
+    fn usage(name: &str) -> Option<&'static str> {
+        Some(match name {
+            "exit" => "exit [code]",
+            "unset" => "unset <name>...",
+            "set" => "set",
+            "source" | "." => "source <file> [args...]",
+            "[" => "[ <condition> ]",
+            "break" => "break [n]",
+            "continue" => "continue [n]",
+            "shift" => "shift [n]",
+            "cd" => "cd [dir]",
+            "renice" => "renice <nice> <pid>",
+            "mount" => "mount <source> <target> <fstype>",
+            "umount" => "umount <target>",
+            "history" => "history",
+            "jobs" => "jobs",
+            "fg" => "fg [%job]",
+            "bg" => "bg [%job]",
+            "help" => "help",
+            "clear" => "clear",
+            _ => return None,
+        })
+    }
+
+    /// `export <name[=value]>...`
+    fn export(&mut self, words: &[&str]) -> isize {
+        let mut status = 0;
+
+        for word in words {
+            let (name, value) = match assignment(word) {
+                Some((name, value)) => (name, Some(value)),
+                None if parser::is_name(word) => (*word, None),
+                None => {
+                    errln!("export: `{}`: not a valid name", word);
+                    status = 1;
+                    continue;
+                }
+            };
+
+            let var = self
+                .vars
+                .entry(String::from(name))
+                .or_insert_with(|| Variable {
+                    value: String::new(),
+                    exported: true,
+                });
+            var.exported = true;
+            if let Some(value) = value {
+                var.value = String::from(value);
+            }
+        }
+
+        status
+    }
+
+    /// `test <condition>`, 0 if it holds, 1 if not and 2 if it cannot be
+    /// understood
+    fn test(args: &[&str]) -> isize {
+        match Self::condition(args) {
+            Some(true) => 0,
+            Some(false) => 1,
+            None => {
+                errln!("test: unknown condition: {}", args.join(" "));
+                2
+            }
+        }
+    }
+
+    fn condition(args: &[&str]) -> Option<bool> {
+        let kind = |path: &str| sys_stat(path).ok().map(|stat| stat.kind);
+
+        Some(match args {
+            [] => false,
+            ["!", rest @ ..] => !Self::condition(rest)?,
+            [text] => !text.is_empty(),
+            ["-n", text] => !text.is_empty(),
+            ["-z", text] => text.is_empty(),
+            ["-e", path] => kind(path).is_some(),
+            ["-f", path] => kind(path) == Some(FileKind::File),
+            ["-d", path] => kind(path) == Some(FileKind::Directory),
+            [a, "=" | "==", b] => a == b,
+            [a, "!=", b] => a != b,
+            [a, op, b] => {
+                let a = a.parse::<isize>().ok()?;
+                let b = b.parse::<isize>().ok()?;
+                match *op {
+                    "-eq" => a == b,
+                    "-ne" => a != b,
+                    "-lt" => a < b,
+                    "-le" => a <= b,
+                    "-gt" => a > b,
+                    "-ge" => a >= b,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    /// `kill [-<signal>] <pid | -pgid | %job>`
+    fn kill(&self, args: &[&str]) -> isize {
+        let (signal, target) = match args {
+            [target] => (Some(Signal::SIGTERM), *target),
+            [signal, target] if signal.starts_with('-') => (
+                signal[1..]
+                    .parse::<u8>()
+                    .ok()
+                    .and_then(|sig| Signal::try_from(sig).ok()),
+                *target,
+            ),
+            _ => {
+                println!("Usage: kill [-<signal>] <pid | -pgid | %job>");
+                return 2;
+            }
+        };
+
+        let Some(signal) = signal else {
+            errln!("Cannot parse signal");
+            return 2;
+        };
+
+        let sent = if let Some(spec) = target.strip_prefix('%') {
+            match self.jobs.pgid(spec) {
+                Some(pgid) => sys_kill_group(pgid, signal),
+                None => {
+                    errln!("kill: no such job: {}", target);
+                    return 1;
+                }
+            }
+        } else if let Some(pgid) = target.strip_prefix('-') {
+            let Ok(pgid) = pgid.parse::<u16>() else {
+                errln!("Cannot parse pgid");
+                return 2;
+            };
+            sys_kill_group(pgid, signal)
+        } else {
+            let Ok(pid) = target.parse::<u16>() else {
+                errln!("Cannot parse pid");
+                return 2;
+            };
+            sys_kill(pid, signal)
+        };
+
+        if !sent {
+            errln!("no such process: {}", target);
+            return 1;
+        }
+
+        0
+    }
+}
@@ -18,7 +18,7 @@ enum CellState {
     Computer,
 }
 
-fn main() -> isize {
+fn main(_args: &[&str]) -> isize {
     let mut state = [
         CellState::Empty,
         CellState::Empty,
@@ -17,8 +17,6 @@ pub struct Config<'a> {
     pub initramfs: Option<&'a str>,
     /// Kernel command line
     pub cmdline: &'a str,
-    /// Load apps into memory, when no fs implemented in kernel
-    pub load_apps: bool,
     /// Log level
     pub log_level: &'a str,
 }
@@ -31,7 +29,6 @@ const DEFAULT_CONFIG: Config = Config {
     kernel_path: "\\KERNEL.ELF",
     initramfs: None,
     cmdline: "",
-    load_apps: false,
     log_level: "info",
 };
 
@@ -71,7 +68,6 @@ impl<'a> Config<'a> {
             "kernel_stack_auto_grow" => self.kernel_stack_auto_grow = r10,
             "initramfs" => self.initramfs = Some(value),
             "cmdline" => self.cmdline = value,
-            "load_apps" => self.load_apps = r10 != 0,
             "log_level" => self.log_level = value,
             _ => warn!("undefined config key: {}", key),
         }
@@ -1,11 +1,6 @@
-use arrayvec::{ArrayString, ArrayVec};
-use uefi::Char16;
 use uefi::boot::*;
 use uefi::proto::media::file::*;
 use uefi::proto::media::fs::SimpleFileSystem;
-use xmas_elf::ElfFile;
-
-use crate::App;
 
 /// Open root directory
 pub fn open_root() -> Directory {
@@ -55,61 +50,3 @@ pub fn load_file(file: &mut RegularFile) -> &'static mut [u8] {
     );
     &mut buf[..len]
 }
-
-/// Load apps into memory, when no fs implemented in kernel
-///
-/// List all file under "APP" and load them.
-pub fn load_apps() -> ArrayVec<App<'static>, 16> {
-    let mut root = open_root();
-
-    let mut buf = [0; 8];
-    let cstr_path = uefi::CStr16::from_str_with_buf("\\APP\\", &mut buf).unwrap();
-
-    let mut handle = root
-        .open(cstr_path, FileMode::Read, FileAttribute::empty())
-        .expect("Failed to open file")
-        .into_directory()
-        .expect("App directory not found");
-
-    let mut apps = ArrayVec::new();
-
-    let mut buffer = [0u8; 0x100];
-
-    loop {
-        let info = handle
-            .read_entry(&mut buffer)
-            .expect("Failed to read entry");
-
-        match info {
-            Some(info) => {
-                // skip when info begin with "."
-                if info.file_name().as_slice()[0] == Char16::try_from('.').unwrap() {
-                    continue;
-                }
-
-                let file = handle
-                    .open(info.file_name(), FileMode::Read, FileAttribute::empty())
-                    .expect("Failed to open file");
-
-                if file.is_directory().unwrap_or(true) {
-                    continue;
-                }
-
-                let mut file = file.into_regular_file().unwrap();
-                let buf = load_file(&mut file);
-
-                let elf = ElfFile::new(buf).expect("Failed to parse ELF file");
-                let mut name = ArrayString::<16>::new();
-
-                info.file_name().as_str_in_buf(&mut name).unwrap();
-
-                apps.push(App { name, elf });
-            }
-            None => break,
-        }
-    }
-
-    info!("Loaded {} apps", apps.len());
-
-    apps
-}
@@ -7,9 +7,8 @@ pub use uefi::data_types::chars::*;
 pub use uefi::data_types::*;
 pub use uefi::proto::console::gop::{GraphicsOutput, ModeInfo};
 
-use arrayvec::{ArrayString, ArrayVec};
+use arrayvec::ArrayVec;
 use x86_64::structures::paging::page::PageRangeInclusive;
-use xmas_elf::ElfFile;
 
 pub mod allocator;
 pub mod config;
@@ -35,19 +34,14 @@ pub struct BootInfo {
     /// The system table virtual address
     pub system_table: NonNull<core::ffi::c_void>,
 
-    /// Loaded apps
-    pub loaded_apps: Option<ArrayVec<App<'static>, 16>>,
+    /// The initramfs archive, addressed through the physical memory mapping
+    pub initramfs: Option<&'static [u8]>,
 
     /// Log Level
     pub log_level: &'static str,
-}
 
-/// App information
-pub struct App<'a> {
-    /// The name of app
-    pub name: ArrayString<16>,
-    /// The ELF file
-    pub elf: ElfFile<'a>,
+    /// The kernel command line, addressed through the physical memory mapping
+    pub cmdline: &'static str,
 }
 
 pub type MemoryMap = ArrayVec<MemoryDescriptor, 256>;
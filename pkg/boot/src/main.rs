@@ -48,12 +48,16 @@ fn main() -> Status {
         ENTRY = elf.header.pt2.entry_point() as usize;
     }
 
-    let apps = if config.load_apps {
-        info!("Loading apps...");
-        Some(load_apps())
-    } else {
-        info!("Skip loading apps");
-        None
+    let initramfs = match config.initramfs {
+        Some(path) => {
+            info!("Loading initramfs...");
+            let mut file = open_file(path);
+            Some(load_file(&mut file))
+        }
+        None => {
+            info!("No initramfs");
+            None
+        }
     };
 
     let mmap = uefi::boot::memory_map(MemoryType::LOADER_DATA).expect("Failed to get memory map");
@@ -124,7 +128,14 @@ fn main() -> Status {
         memory_map: mmap.entries().copied().collect(),
         kernel_pages: get_page_usage(&elf),
         physical_memory_offset: config.physical_memory_offset,
-        loaded_apps: apps,
+        initramfs: initramfs.map(|buf| physical_mapped(buf, config.physical_memory_offset)),
+        // the config is valid utf8, it was parsed as a str
+        cmdline: unsafe {
+            core::str::from_utf8_unchecked(physical_mapped(
+                config.cmdline.as_bytes(),
+                config.physical_memory_offset,
+            ))
+        },
         log_level: config.log_level,
         system_table,
         graphic_info,
@@ -136,6 +147,17 @@ fn main() -> Status {
     jump_to_entry(&bootinfo, stacktop);
 }
 
+/// The same memory as `buf`, addressed through the physical memory mapping,
+/// as the lower half belongs to user processes once the kernel runs
+fn physical_mapped(buf: &[u8], physical_memory_offset: u64) -> &'static [u8] {
+    unsafe {
+        core::slice::from_raw_parts(
+            (physical_memory_offset + buf.as_ptr() as u64) as *const u8,
+            buf.len(),
+        )
+    }
+}
+
 /// Get current page table from CR3
 fn current_page_table() -> OffsetPageTable<'static> {
     let p4_table_addr = Cr3::read().0.start_address().as_u64();
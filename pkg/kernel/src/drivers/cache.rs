@@ -43,4 +43,12 @@ impl CacheManager<Block512> for LruCacheImpl {
         let mut inner = self.inner.lock();
         inner.put(key, Arc::new(RwLock::new(value)));
     }
+
+    fn flush(&self) -> Result<()> {
+        let inner = self.inner.lock();
+        for (_, block) in inner.iter() {
+            block.write().sync()?;
+        }
+        Ok(())
+    }
 }
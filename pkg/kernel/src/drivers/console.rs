@@ -157,6 +157,7 @@ impl Console {
         // CSI n D          Cursor Backward
         // CSI y ; x H      Cursor Position
         // CSI n J          Erase in Display
+        // CSI n K          Erase in Line
 
         match chrs.next() {
             Some('[') => self.handle_csi(chrs),
@@ -220,6 +221,10 @@ impl Console {
                     self.set_cursor(x - 1, n - 1);
                     break;
                 }
+                'K' => {
+                    self.erase_in_line(n);
+                    break;
+                }
                 'J' => {
                     if n == 2 {
                         self.clear();
@@ -236,6 +241,21 @@ impl Console {
         count
     }
 
+    /// Blank the line after the cursor, before it or all of it, the cursor
+    /// stays where it is
+    pub fn erase_in_line(&mut self, n: isize) {
+        let width = self.size().0;
+        let range = match n {
+            0 => self.x_pos..width,
+            1 => 0..self.x_pos + 1,
+            _ => 0..width,
+        };
+
+        for x in range {
+            self.write_char_at(x, self.y_pos, ' ');
+        }
+    }
+
     pub fn move_cursor(&mut self, dx: isize, dy: isize) {
         self.x_pos = (self.x_pos + dx).max(0).min(self.size().0 - 1);
         self.y_pos = (self.y_pos + dy).max(0).min(self.size().1 - 1);
@@ -0,0 +1,303 @@
+//! Device filesystem
+//!
+//! Exposes kernel devices as files, it is mounted at `/dev`.
+
+use alloc::{borrow::ToOwned, boxed::Box, string::String, sync::Arc, vec, vec::Vec};
+use pc_keyboard::DecodedKey;
+use storage::{random::Random, *};
+use x86_64::instructions::interrupts;
+
+use super::{display::get_display, input::try_get_key, serial::get_serial};
+
+#[derive(Clone)]
+enum Node {
+    /// Discards writes, reads hit end of file
+    Null,
+    /// Discards writes, reads return zeros
+    Zero,
+    /// Random bytes, `random` and `urandom` never block here
+    Random(Random),
+    /// Keyboard input and the text console
+    Console,
+    /// Raw bytes on COM1, received bytes are fed to the console
+    Serial,
+    /// The raw pixels of the GOP framebuffer
+    Framebuffer,
+    /// A disk or a partition
+    Block(Arc<dyn BlockDevice<Block512>>),
+}
+
+impl Node {
+    /// Length of the device, if it has one
+    fn len(&self) -> usize {
+        match self {
+            Node::Framebuffer => get_display()
+                .map(|mut display| display.framebuffer().len())
+                .unwrap_or(0),
+            Node::Block(device) => device.block_count().unwrap_or(0) * Block512::size(),
+            _ => 0,
+        }
+    }
+}
+
+impl core::fmt::Debug for Node {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Node::Null => write!(f, "Null"),
+            Node::Zero => write!(f, "Zero"),
+            Node::Random(_) => write!(f, "Random"),
+            Node::Console => write!(f, "Console"),
+            Node::Serial => write!(f, "Serial"),
+            Node::Framebuffer => write!(f, "Framebuffer"),
+            Node::Block(_) => write!(f, "Block"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DevFs {
+    nodes: Vec<(Box<str>, Node)>,
+}
+
+impl DevFs {
+    /// A devfs with the character devices, block devices are added by
+    /// whoever probes the disks
+    pub fn new() -> Self {
+        let random = Random::new();
+
+        Self {
+            nodes: vec![
+                ("null".into(), Node::Null),
+                ("zero".into(), Node::Zero),
+                ("random".into(), Node::Random(random.clone())),
+                ("urandom".into(), Node::Random(random)),
+                ("console".into(), Node::Console),
+                ("serial0".into(), Node::Serial),
+                ("fb0".into(), Node::Framebuffer),
+            ],
+        }
+    }
+
+    pub fn add_block_device(&mut self, name: &str, device: Arc<dyn BlockDevice<Block512>>) {
+        self.nodes.push((name.into(), Node::Block(device)));
+    }
+
+    fn node(&self, path: &str) -> Result<(&str, &Node)> {
+        let name = path.trim_start_matches('/');
+
+        self.nodes
+            .iter()
+            .find(|(n, _)| **n == *name)
+            .map(|(n, node)| (n.as_ref(), node))
+            .ok_or(FsError::FileNotFound)
+    }
+
+    fn node_meta(name: &str, node: &Node) -> Metadata {
+        Metadata::new(
+            name.to_owned(),
+            FileType::File,
+            node.len(),
+            None,
+            None,
+            None,
+        )
+    }
+}
+
+impl Default for DevFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSystem for DevFs {
+    fn read_dir(&self, path: &str) -> Result<Box<dyn Iterator<Item = Metadata> + Send>> {
+        if !path.trim_start_matches('/').is_empty() {
+            return Err(match self.node(path) {
+                Ok(_) => FsError::NotADirectory,
+                Err(e) => e,
+            });
+        }
+
+        let entries: Vec<Metadata> = self
+            .nodes
+            .iter()
+            .map(|(name, node)| Self::node_meta(name, node))
+            .collect();
+
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn open_file(&self, path: &str) -> Result<FileHandle> {
+        let (name, node) = self.node(path)?;
+
+        Ok(FileHandle::new(
+            Self::node_meta(name, node),
+            Box::new(DeviceFile {
+                node: node.clone(),
+                offset: 0,
+            }),
+        ))
+    }
+
+    fn metadata(&self, path: &str) -> Result<Metadata> {
+        let (name, node) = self.node(path)?;
+        Ok(Self::node_meta(name, node))
+    }
+
+    fn exists(&self, path: &str) -> Result<bool> {
+        Ok(self.node(path).is_ok())
+    }
+
+    /// Devices can't be created, but opening one for writing is fine
+    fn create_file(&self, path: &str) -> Result<FileHandle> {
+        self.open_file(path)
+    }
+
+    fn append_file(&self, path: &str) -> Result<FileHandle> {
+        self.open_file(path)
+    }
+}
+
+/// Read from a disk at a byte offset, returning the bytes read
+fn read_at(device: &dyn BlockDevice<Block512>, offset: usize, buf: &mut [u8]) -> Result<usize> {
+    let block_size = Block512::size();
+    let end = (offset + buf.len()).min(device.block_count()? * block_size);
+    let mut block = Block512::default();
+    let mut pos = offset;
+
+    while pos < end {
+        let start = pos % block_size;
+        let count = (block_size - start).min(end - pos);
+
+        device.read_block(pos / block_size, &mut block)?;
+        buf[pos - offset..pos - offset + count].copy_from_slice(&block[start..start + count]);
+
+        pos += count;
+    }
+
+    Ok(pos.saturating_sub(offset))
+}
+
+/// Write to a disk at a byte offset, returning the bytes written
+fn write_at(device: &dyn BlockDevice<Block512>, offset: usize, buf: &[u8]) -> Result<usize> {
+    let block_size = Block512::size();
+    let end = (offset + buf.len()).min(device.block_count()? * block_size);
+    let mut block = Block512::default();
+    let mut pos = offset;
+
+    while pos < end {
+        let start = pos % block_size;
+        let count = (block_size - start).min(end - pos);
+
+        // only partial blocks need their old contents
+        if count < block_size {
+            device.read_block(pos / block_size, &mut block)?;
+        }
+        block.as_mut()[start..start + count]
+            .copy_from_slice(&buf[pos - offset..pos - offset + count]);
+        device.write_block(pos / block_size, &block)?;
+
+        pos += count;
+    }
+
+    Ok(pos.saturating_sub(offset))
+}
+
+/// An open device, only the framebuffer and block devices are seekable
+struct DeviceFile {
+    node: Node,
+    offset: usize,
+}
+
+impl Read for DeviceFile {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match &mut self.node {
+            Node::Null | Node::Serial => Ok(0),
+            Node::Zero => {
+                buf.fill(0);
+                Ok(buf.len())
+            }
+            Node::Random(random) => random.read(buf, 0, buf.len()),
+            Node::Console => Ok(if buf.len() < 4 {
+                0
+            } else if let Some(DecodedKey::Unicode(k)) = try_get_key() {
+                k.encode_utf8(buf).len()
+            } else {
+                0
+            }),
+            Node::Framebuffer => {
+                let mut display = get_display().ok_or(DeviceError::Busy)?;
+                let fb = display.framebuffer();
+                let start = self.offset.min(fb.len());
+                let count = buf.len().min(fb.len() - start);
+
+                buf[..count].copy_from_slice(&fb[start..start + count]);
+                self.offset += count;
+                Ok(count)
+            }
+            Node::Block(device) => {
+                let count = read_at(device.as_ref(), self.offset, buf)?;
+                self.offset += count;
+                Ok(count)
+            }
+        }
+    }
+}
+
+impl Write for DeviceFile {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        match &self.node {
+            Node::Null | Node::Zero | Node::Random(_) => Ok(buf.len()),
+            Node::Console => {
+                print!("{}", String::from_utf8_lossy(buf));
+                Ok(buf.len())
+            }
+            Node::Serial => interrupts::without_interrupts(|| {
+                let mut serial = get_serial().ok_or(DeviceError::Busy)?;
+                buf.iter().for_each(|&b| serial.send_raw(b));
+                Ok(buf.len())
+            }),
+            Node::Framebuffer => {
+                let mut display = get_display().ok_or(DeviceError::Busy)?;
+                let fb = display.framebuffer();
+                let start = self.offset.min(fb.len());
+                let count = buf.len().min(fb.len() - start);
+
+                fb[start..start + count].copy_from_slice(&buf[..count]);
+                self.offset += count;
+                Ok(count)
+            }
+            Node::Block(device) => {
+                let count = write_at(device.as_ref(), self.offset, buf)?;
+                self.offset += count;
+                Ok(count)
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match &self.node {
+            Node::Block(device) => device.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Seek for DeviceFile {
+    fn seek(&mut self, pos: SeekFrom) -> Result<usize> {
+        // streams have no position to move
+        if !matches!(self.node, Node::Framebuffer | Node::Block(_)) {
+            return Err(FsError::NotSupported);
+        }
+
+        let offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.offset.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.node.len().checked_add_signed(offset),
+        };
+
+        self.offset = offset.ok_or(FsError::InvalidOffset)?;
+        Ok(self.offset)
+    }
+}
@@ -1,78 +1,330 @@
 use super::ata::*;
 use super::cache::*;
-use alloc::boxed::Box;
-use chrono::DateTime;
-use storage::fat16::Fat16;
-use storage::mbr::*;
+use super::devfs::DevFs;
+use super::vfs::get_vfs;
+use alloc::{boxed::Box, format, string::String, sync::Arc, vec::Vec};
+use storage::archive::ArchiveFs;
+use storage::fat::{Fat, bpb::FatBpb};
+use storage::tmpfs::TmpFs;
 use storage::*;
+use syscall_def::{Errno, FileKind, FileMode, FileStat};
 
-pub static ROOTFS: spin::Once<Mount> = spin::Once::new();
+/// Bytes of file contents `/tmp` may hold, it lives on the kernel heap
+const TMPFS_SIZE: usize = 256 * 1024;
 
-pub fn get_rootfs() -> &'static Mount {
-    ROOTFS.get().unwrap()
-}
+type SharedBlockDevice = Arc<dyn BlockDevice<Block512>>;
+
+/// Disks and partitions found at boot, by their name under `/dev`
+static BLOCK_DEVICES: spin::Once<Vec<(String, SharedBlockDevice)>> = spin::Once::new();
+
+/// The block cache of each disk, which its partitions, their files under
+/// `/dev` and the filesystems on them all read and write through
+static CACHES: spin::Once<Vec<LruSharedInner>> = spin::Once::new();
 
-static CACHE: spin::Once<LruSharedInner> = spin::Once::new();
+/// Devices with a filesystem mounted from them, and where. A second
+/// driver for the same volume would not know where the first one writes.
+static MOUNTED_DEVICES: spin::Mutex<Vec<(String, String)>> = spin::Mutex::new(Vec::new());
 
+/// Blocks cached and the room for them, over all the disks
 pub fn cache_usage() -> (usize, usize) {
-    let cache = CACHE.get().unwrap().lock();
-    (cache.len(), cache.cap().into())
+    CACHES.get().map_or((0, 0), |caches| {
+        caches.iter().fold((0, 0), |(used, total), cache| {
+            let cache = cache.lock();
+            (used + cache.len(), total + cache.cap().get())
+        })
+    })
 }
 
-pub fn init() {
+pub fn init(boot_info: &'static boot::BootInfo) {
+    storage::set_clock(|| crate::utils::clock::now().and_utc());
+
     info!("Opening disk device...");
 
-    let drive = AtaDrive::open(0, 0).expect("Failed to open disk device");
+    let (devices, caches) = probe_block_devices();
+    BLOCK_DEVICES.call_once(|| devices);
+    CACHES.call_once(|| caches);
 
-    // only get the first partition
-    let part = MbrTable::parse(drive)
-        .expect("Failed to parse MBR")
-        .partitions()
-        .expect("Failed to get partitions")
-        .remove(0);
+    let initramfs = boot_info.initramfs.and_then(|data| {
+        ArchiveFs::new(data)
+            .inspect_err(|e| warn!("Failed to load initramfs: {:?}", e))
+            .ok()
+    });
 
-    let lru = LruCacheImpl::new();
+    info!("Mounting filesystem...");
 
-    CACHE.call_once(|| lru.inner());
+    let root = crate::utils::cmdline::get_cmdline().root;
+    let device = match root {
+        "initrd" => Err(FsError::FileNotFound),
+        _ => block_device(root),
+    };
 
-    let cache_layer = ATACachedDevice::new(part, lru);
+    // boot from the initramfs without a disk
+    match (device, initramfs) {
+        (Ok(part), initramfs) => {
+            get_vfs()
+                .mount(Box::new(Fat::new(part)), "/")
+                .expect("Failed to mount root filesystem");
+            MOUNTED_DEVICES.lock().push((root.into(), "/".into()));
 
-    info!("Mounting filesystem...");
+            if let Some(initramfs) = initramfs {
+                get_vfs()
+                    .mount(Box::new(initramfs), "/initrd")
+                    .expect("Failed to mount initramfs");
+            }
+        }
+        (Err(_), Some(initramfs)) => get_vfs()
+            .mount(Box::new(initramfs), "/")
+            .expect("Failed to mount initramfs"),
+        (Err(_), None) => panic!("No root device {} or initramfs to boot from", root),
+    }
 
-    ROOTFS.call_once(|| Mount::new(Box::new(Fat16::new(cache_layer)), "/".into()));
+    let mut devfs = DevFs::new();
+    for (name, device) in BLOCK_DEVICES.get().unwrap() {
+        devfs.add_block_device(name, device.clone());
+    }
 
-    trace!("Root filesystem: {:#?}", ROOTFS.get().unwrap());
+    get_vfs()
+        .mount(Box::new(devfs), "/dev")
+        .expect("Failed to mount devfs");
+
+    get_vfs()
+        .mount(Box::new(crate::proc::ProcFs::new()), "/proc")
+        .expect("Failed to mount procfs");
+
+    get_vfs()
+        .mount(Box::new(TmpFs::new(TMPFS_SIZE)), "/tmp")
+        .expect("Failed to mount tmpfs");
+
+    trace!("Root filesystem: {:#?}", get_vfs());
 
     info!("Initialized Filesystem.");
 }
 
-pub fn ls(root_path: &str) {
-    let iter = match get_rootfs().read_dir(root_path) {
-        Ok(iter) => iter,
-        Err(err) => {
-            warn!("{:?}", err);
-            return;
+/// Mount the `fstype` filesystem found on `source` at `target`
+pub fn mount(source: &str, target: &str, fstype: &str) -> core::result::Result<(), Errno> {
+    let name = source.strip_prefix("/dev/").unwrap_or(source);
+    let mut mounted = MOUNTED_DEVICES.lock();
+
+    let (fs, device): (Box<dyn FileSystem>, _) = match fstype {
+        "fat" | "vfat" => {
+            if mounted.iter().any(|(device, _)| device == name) {
+                return Err(Errno::EBUSY);
+            }
+            (
+                Box::new(fat_volume(name).map_err(|e| errno(&e))?),
+                Some(name),
+            )
         }
+        "tmpfs" => (Box::new(TmpFs::new(TMPFS_SIZE)), None),
+        _ => return Err(Errno::ENOTSUP),
     };
 
-    println!("  Size | Last Modified       | Name");
-
-    for meta in iter {
-        let (size, unit) = crate::humanized_size_short(meta.len as u64);
-        println!(
-            "{:>5.*}{} | {} | {}{}",
-            1,
-            size,
-            unit,
-            meta.modified
-                .map(|t| t.format("%Y/%m/%d %H:%M:%S"))
-                .unwrap_or(
-                    DateTime::from_timestamp_millis(0)
-                        .unwrap()
-                        .format("%Y/%m/%d %H:%M:%S")
-                ),
-            meta.name,
-            if meta.is_dir() { "/" } else { "" }
-        );
+    get_vfs().mount(fs, target).map_err(|e| mount_errno(&e))?;
+
+    if let Some(device) = device {
+        mounted.push((device.into(), target.into()));
+    }
+
+    Ok(())
+}
+
+pub fn umount(target: &str) -> core::result::Result<(), Errno> {
+    let mut mounted = MOUNTED_DEVICES.lock();
+
+    get_vfs().umount(target).map_err(|e| mount_errno(&e))?;
+    mounted.retain(|(_, mount_point)| mount_point != target);
+
+    Ok(())
+}
+
+/// The VFS refuses a mount point that is taken or has mounts below it
+/// as an invalid operation
+fn mount_errno(e: &FsError) -> Errno {
+    match e {
+        FsError::InvalidOperation => Errno::EBUSY,
+        e => errno(e),
+    }
+}
+
+/// The FAT volume on the block device called `name`
+fn fat_volume(name: &str) -> Result<Fat> {
+    let device = block_device(name)?;
+
+    // the driver expects a valid BPB, do not let a bad disk panic it
+    let mut block = Block512::default();
+    device.read_block(0, &mut block)?;
+    FatBpb::new(block.as_ref()).map_err(|_| FsError::NotSupported)?;
+
+    Ok(Fat::new(device))
+}
+
+fn block_device(name: &str) -> Result<SharedBlockDevice> {
+    BLOCK_DEVICES
+        .get()
+        .and_then(|devices| devices.iter().find(|(n, _)| n == name))
+        .map(|(_, device)| device.clone())
+        .ok_or(FsError::FileNotFound)
+}
+
+/// Look for disks on both ATA buses: `hda` to `hdd` are the master and
+/// slave drives of the primary and secondary buses, and their partitions
+/// are numbered from 1, e.g. `hda1`. Each disk has a cache, which its
+/// partitions share.
+fn probe_block_devices() -> (Vec<(String, SharedBlockDevice)>, Vec<LruSharedInner>) {
+    let mut devices: Vec<(String, SharedBlockDevice)> = Vec::new();
+    let mut caches = Vec::new();
+
+    for (name, bus, dsk) in [("hda", 0, 0), ("hdb", 0, 1), ("hdc", 1, 0), ("hdd", 1, 1)] {
+        let Some(drive) = AtaDrive::open(bus, dsk) else {
+            continue;
+        };
+
+        let lru = LruCacheImpl::new();
+        caches.push(lru.inner());
+
+        let disk: SharedBlockDevice = Arc::new(ATACachedDevice::new(drive, lru));
+        devices.push((name.into(), disk.clone()));
+
+        let table = match DiskTable::parse(disk) {
+            Ok(table) => table,
+            Err(e) => {
+                warn!("Failed to parse partition table of {}: {:?}", name, e);
+                continue;
+            }
+        };
+
+        if let DiskTable::Gpt(gpt) = &table {
+            info!("Found GPT disk {}", gpt.header().disk_guid());
+            for (i, entry) in gpt.entries().iter().enumerate() {
+                info!(
+                    "Partition {}{}: \"{}\" ({}), {} sectors",
+                    name,
+                    i + 1,
+                    entry.name(),
+                    entry.type_guid().type_name().unwrap_or("Unknown"),
+                    entry.total_lba()
+                );
+            }
+        }
+
+        for (i, part) in table
+            .partitions()
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+        {
+            devices.push((format!("{}{}", name, i + 1), Arc::new(part)));
+        }
+    }
+
+    (devices, caches)
+}
+
+/// Open a file on the root filesystem the way `mode` asks for
+pub fn open_file(path: &str, mode: FileMode) -> Result<FileHandle> {
+    let fs = get_vfs();
+
+    match mode {
+        FileMode::ReadOnly => fs.open_file(path),
+        FileMode::ReadWriteAppend => fs.append_file(path),
+        FileMode::ReadWriteTruncate => match fs.exists(path)? {
+            true => fs.create_file(path),
+            false => Err(FsError::FileNotFound),
+        },
+        FileMode::ReadWriteCreate => match fs.exists(path)? {
+            true => Err(FsError::InvalidOperation),
+            false => fs.create_file(path),
+        },
+        FileMode::ReadWriteCreateOrTruncate => fs.create_file(path),
+        FileMode::ReadWriteCreateOrAppend => match fs.exists(path)? {
+            true => fs.append_file(path),
+            false => fs.create_file(path),
+        },
+    }
+}
+
+/// Create an empty directory at `path`
+pub fn create_dir(path: &str) -> core::result::Result<(), Errno> {
+    let fs = get_vfs();
+
+    if fs.exists(path).unwrap_or_default() {
+        return Err(Errno::EEXIST);
+    }
+
+    fs.create_dir(path).map_err(|e| errno(&e))
+}
+
+/// Remove the directory at `path`, which has to be empty
+pub fn remove_dir(path: &str) -> core::result::Result<(), Errno> {
+    let fs = get_vfs();
+
+    if fs.read_dir(path).map_err(|e| errno(&e))?.next().is_some() {
+        return Err(Errno::ENOTEMPTY);
+    }
+
+    fs.remove_dir(path).map_err(|e| errno(&e))
+}
+
+pub fn remove_file(path: &str) -> core::result::Result<(), Errno> {
+    get_vfs().remove_file(path).map_err(|e| errno(&e))
+}
+
+/// `path` made absolute against the directory `cwd`, without `.`, `..`
+/// or empty components
+pub fn absolute_path(cwd: &str, path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+
+    let relative = if path.starts_with('/') { "" } else { cwd };
+    for part in relative.split('/').chain(path.split('/')) {
+        match part {
+            "" | "." => (),
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+
+    let mut absolute = String::new();
+    for part in parts {
+        absolute.push('/');
+        absolute.push_str(part);
+    }
+
+    if absolute.is_empty() {
+        absolute.push('/');
+    }
+
+    absolute
+}
+
+/// What `stat` tells of a file
+pub fn file_stat(meta: &Metadata) -> FileStat {
+    let millis = |time: Option<FsTime>| time.map_or(0, |t| t.timestamp_millis());
+
+    FileStat {
+        kind: match meta.entry_type {
+            FileType::File => FileKind::File,
+            FileType::Directory => FileKind::Directory,
+        },
+        len: meta.len as u64,
+        created: millis(meta.created),
+        modified: millis(meta.modified),
+        accessed: millis(meta.accessed),
+    }
+}
+
+/// The errno a syscall gives for a filesystem error
+pub fn errno(e: &FsError) -> Errno {
+    match e {
+        FsError::FileNotFound | FsError::InvalidPath(_) | FsError::FileNameError(_) => {
+            Errno::ENOENT
+        }
+        FsError::NotADirectory => Errno::ENOTDIR,
+        FsError::NotAFile => Errno::EISDIR,
+        FsError::ReadOnly => Errno::EROFS,
+        FsError::NotSupported => Errno::ENOTSUP,
+        FsError::InvalidOperation => Errno::EINVAL,
+        _ => Errno::EIO,
     }
 }
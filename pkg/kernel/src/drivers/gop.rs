@@ -76,6 +76,12 @@ impl<'a> GOPDisplay<'a> {
         self.info.mode.resolution()
     }
 
+    /// The pixels as raw bytes, in the order the GOP mode stores them
+    pub fn framebuffer(&mut self) -> &mut [u8] {
+        let len = core::mem::size_of_val(self.buffer);
+        unsafe { core::slice::from_raw_parts_mut(self.buffer.as_mut_ptr() as *mut u8, len) }
+    }
+
     pub fn clear(&mut self, color: Option<<GOPDisplay<'a> as DrawTarget>::Color>, base: usize) {
         let size = self.resolution();
         let color = color.unwrap_or(colors::BACKGROUND);
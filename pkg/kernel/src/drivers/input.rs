@@ -1,7 +1,8 @@
 use crate::drivers::{console, serial};
 use alloc::string::String;
 use crossbeam_queue::ArrayQueue;
-use pc_keyboard::DecodedKey;
+use pc_keyboard::{DecodedKey, KeyCode};
+use syscall_def::Signal;
 
 const DEFAULT_BUF_SIZE: usize = 128;
 
@@ -13,11 +14,57 @@ lazy_static! {
 
 #[inline]
 pub fn push_key(key: Key) {
+    // Ctrl-C and Ctrl-Z go to the foreground group as signals instead
+    let signal = match key {
+        DecodedKey::Unicode('\x03') => Some(Signal::SIGINT),
+        DecodedKey::Unicode('\x1a') => Some(Signal::SIGTSTP),
+        _ => None,
+    };
+
+    if signal.is_some_and(crate::proc::interrupt_foreground) {
+        return;
+    }
+
+    // keys without a character are sent as a terminal would, the serial
+    // port gets them this way already
+    let sequence = match key {
+        DecodedKey::RawKey(code) => match escape_sequence(code) {
+            Some(sequence) => sequence,
+            None => return,
+        },
+        // the keyboard gives DEL for Delete, the serial port never does
+        DecodedKey::Unicode('\x7f') => "\x1b[3~",
+        key => return push(key),
+    };
+
+    for c in sequence.chars() {
+        push(DecodedKey::Unicode(c));
+    }
+}
+
+#[inline]
+fn push(key: Key) {
     if INPUT_BUF.push(key).is_err() {
         warn!("Input buffer is full. Dropping key '{:?}'", key);
     }
 }
 
+/// What a VT100 terminal sends for `code`
+fn escape_sequence(code: KeyCode) -> Option<&'static str> {
+    Some(match code {
+        KeyCode::ArrowUp => "\x1b[A",
+        KeyCode::ArrowDown => "\x1b[B",
+        KeyCode::ArrowRight => "\x1b[C",
+        KeyCode::ArrowLeft => "\x1b[D",
+        KeyCode::Home => "\x1b[H",
+        KeyCode::End => "\x1b[F",
+        KeyCode::Insert => "\x1b[2~",
+        KeyCode::PageUp => "\x1b[5~",
+        KeyCode::PageDown => "\x1b[6~",
+        _ => return None,
+    })
+}
+
 #[inline]
 pub fn try_get_key() -> Option<Key> {
     INPUT_BUF.pop()
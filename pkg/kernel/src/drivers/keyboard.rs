@@ -9,7 +9,7 @@ pub fn init() {
     init_KEYBOARD(Keyboard::new(
         ScancodeSet1::new(),
         layouts::Us104Key,
-        HandleControl::Ignore,
+        HandleControl::MapLettersToUnicode,
     ));
     info!("Keyboard Initialized.");
 }
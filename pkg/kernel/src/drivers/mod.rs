@@ -4,11 +4,13 @@ mod uart16550;
 pub mod ata;
 pub mod cache;
 pub mod console;
+pub mod devfs;
 pub mod display;
 pub mod filesystem;
 pub mod input;
 pub mod keyboard;
 pub mod serial;
+pub mod vfs;
 
-pub use filesystem::get_rootfs;
 pub use input::{get_key, push_key};
+pub use vfs::get_vfs;
@@ -0,0 +1,200 @@
+//! Virtual filesystem
+//!
+//! Keeps every mounted filesystem in one table and sends each path to the
+//! mount with the longest matching mount point.
+
+use alloc::{borrow::ToOwned, boxed::Box, string::String, sync::Arc, vec::Vec};
+use spin::RwLock;
+use storage::*;
+
+static VFS: Vfs = Vfs::new();
+
+pub fn get_vfs() -> &'static Vfs {
+    &VFS
+}
+
+pub struct Vfs {
+    mounts: RwLock<Vec<Arc<Mount>>>,
+}
+
+impl Vfs {
+    const fn new() -> Self {
+        Self {
+            mounts: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Attach a filesystem at `mount_point`, which must not be in use
+    pub fn mount(&self, fs: Box<dyn FileSystem>, mount_point: &str) -> Result<()> {
+        let mount_point = normalize(mount_point)?;
+        let mut mounts = self.mounts.write();
+
+        if mounts.iter().any(|m| *m.mount_point == *mount_point) {
+            return Err(FsError::InvalidOperation);
+        }
+
+        info!("Mounted {:?} at {}", fs, mount_point);
+        mounts.push(Arc::new(Mount::new(fs, mount_point.into())));
+
+        Ok(())
+    }
+
+    /// Detach the filesystem at `mount_point`, refusing while other
+    /// filesystems are mounted below it
+    pub fn umount(&self, mount_point: &str) -> Result<()> {
+        let mount_point = normalize(mount_point)?;
+        let mut mounts = self.mounts.write();
+
+        let index = mounts
+            .iter()
+            .position(|m| *m.mount_point == *mount_point)
+            .ok_or(FsError::FileNotFound)?;
+
+        let busy = mounts.iter().any(|m| {
+            *m.mount_point != *mount_point && mounts[index].relative(&m.mount_point).is_some()
+        });
+
+        if busy {
+            return Err(FsError::InvalidOperation);
+        }
+
+        mounts.remove(index);
+        info!("Unmounted {}", mount_point);
+
+        Ok(())
+    }
+
+    /// Mount points in the order they were mounted
+    pub fn mount_points(&self) -> Vec<Box<str>> {
+        self.mounts
+            .read()
+            .iter()
+            .map(|m| m.mount_point.clone())
+            .collect()
+    }
+
+    /// Find the mount owning `path` and the path inside it
+    fn resolve<'a>(&self, path: &'a str) -> Result<(Arc<Mount>, &'a str)> {
+        self.mounts
+            .read()
+            .iter()
+            .filter_map(|m| m.relative(path).map(|rest| (m, rest)))
+            .max_by_key(|(m, _)| m.mount_point.len())
+            .map(|(m, rest)| (m.clone(), rest))
+            .ok_or(FsError::FileNotFound)
+    }
+}
+
+/// Turn a mount point into the form kept in the table: absolute, without
+/// empty components or a trailing separator
+fn normalize(path: &str) -> Result<String> {
+    if !path.starts_with('/') {
+        return Err(FsError::InvalidPath(path.to_owned()));
+    }
+
+    let mut normalized = String::new();
+
+    for part in path.split('/').filter(|p| !p.is_empty()) {
+        if part == "." || part == ".." {
+            return Err(FsError::InvalidPath(path.to_owned()));
+        }
+        normalized.push('/');
+        normalized.push_str(part);
+    }
+
+    if normalized.is_empty() {
+        normalized.push('/');
+    }
+
+    Ok(normalized)
+}
+
+impl FileSystem for Vfs {
+    fn read_dir(&self, path: &str) -> Result<Box<dyn Iterator<Item = Metadata> + Send>> {
+        let (mount, rest) = self.resolve(path)?;
+        let mut entries: Vec<Metadata> = mount.fs.read_dir(rest)?.collect();
+
+        // mount points directly below `path` show up as directories
+        let dir = path.trim_end_matches('/');
+        for point in self.mount_points() {
+            let name = match point.strip_prefix(dir).and_then(|p| p.strip_prefix('/')) {
+                Some(name) if !name.is_empty() && !name.contains('/') => name,
+                _ => continue,
+            };
+
+            if !entries.iter().any(|e| e.name == name) {
+                entries.push(Metadata::new(
+                    name.to_owned(),
+                    FileType::Directory,
+                    0,
+                    None,
+                    None,
+                    None,
+                ));
+            }
+        }
+
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn open_file(&self, path: &str) -> Result<FileHandle> {
+        let (mount, rest) = self.resolve(path)?;
+        mount.fs.open_file(rest)
+    }
+
+    fn metadata(&self, path: &str) -> Result<Metadata> {
+        let (mount, rest) = self.resolve(path)?;
+
+        // not every filesystem has an entry for its root
+        mount.fs.metadata(rest).or_else(|e| match rest {
+            "/" => {
+                let name = mount.mount_point.rsplit('/').next().unwrap_or_default();
+                Ok(Metadata::new(
+                    name.to_owned(),
+                    FileType::Directory,
+                    0,
+                    None,
+                    None,
+                    None,
+                ))
+            }
+            _ => Err(e),
+        })
+    }
+
+    fn exists(&self, path: &str) -> Result<bool> {
+        let (mount, rest) = self.resolve(path)?;
+        mount.fs.exists(rest)
+    }
+
+    fn create_file(&self, path: &str) -> Result<FileHandle> {
+        let (mount, rest) = self.resolve(path)?;
+        mount.fs.create_file(rest)
+    }
+
+    fn append_file(&self, path: &str) -> Result<FileHandle> {
+        let (mount, rest) = self.resolve(path)?;
+        mount.fs.append_file(rest)
+    }
+
+    fn remove_file(&self, path: &str) -> Result<()> {
+        let (mount, rest) = self.resolve(path)?;
+        mount.fs.remove_file(rest)
+    }
+
+    fn create_dir(&self, path: &str) -> Result<()> {
+        let (mount, rest) = self.resolve(path)?;
+        mount.fs.create_dir(rest)
+    }
+
+    fn remove_dir(&self, path: &str) -> Result<()> {
+        let (mount, rest) = self.resolve(path)?;
+        mount.fs.remove_dir(rest)
+    }
+}
+
+impl core::fmt::Debug for Vfs {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.mounts.read().iter()).finish()
+    }
+}
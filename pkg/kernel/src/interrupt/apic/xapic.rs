@@ -3,6 +3,9 @@ use bit_field::BitField;
 use core::fmt::{Debug, Error, Formatter};
 use core::ptr::{read_volatile, write_volatile};
 use x86::cpuid::CpuId;
+use x86_64::instructions::port::Port;
+
+use crate::utils::clock::TICK_HZ;
 
 pub struct XApic {
     addr: u64,
@@ -27,6 +30,45 @@ impl XApic {
     }
 }
 
+impl XApic {
+    /// Timer counts in one kernel tick, measured against PIT channel 2
+    unsafe fn calibrate(&mut self) -> u32 {
+        const PIT_HZ: u32 = 1_193_182;
+        const SAMPLE_MS: u32 = 10;
+
+        let mut gate = Port::<u8>::new(PIT_GATE);
+        let mut command = Port::<u8>::new(PIT_COMMAND);
+        let mut channel2 = Port::<u8>::new(PIT_CHANNEL2);
+
+        let pit_count = PIT_HZ * SAMPLE_MS / 1000;
+
+        unsafe {
+            // gate channel 2 on with the speaker off, one-shot mode
+            let value = gate.read();
+            gate.write((value & !0x02) | 0x01);
+            command.write(0b1011_0010);
+            channel2.write(pit_count as u8);
+            channel2.write((pit_count >> 8) as u8);
+
+            // a rising edge on the gate starts the count
+            let value = gate.read() & !0x01;
+            gate.write(value);
+            gate.write(value | 0x01);
+
+            self.write(TIMER, MASKED);
+            self.write(TICR, u32::MAX);
+
+            // OUT2 goes high when the count is done
+            while gate.read() & 0x20 == 0 {}
+
+            let elapsed = u32::MAX - self.read(TCCR);
+            self.write(TICR, 0);
+
+            (elapsed as u64 * 1000 / (SAMPLE_MS as u64 * TICK_HZ)) as u32
+        }
+    }
+}
+
 impl LocalApic for XApic {
     fn support() -> bool {
         CpuId::new().get_feature_info().unwrap().has_apic()
@@ -39,11 +81,18 @@ impl LocalApic for XApic {
 
             // The timer repeatedly counts down at bus frequency
             // from lapic[TICR] and then issues an interrupt.
-            // If xv6 cared more about precise timekeeping,
-            // TICR would be calibrated using an external time source.
+            // TICR is calibrated with the PIT, so that the kernel
+            // ticks at TICK_HZ.
             self.write(TDCR, X1);
+            let count = match self.calibrate() {
+                0 => {
+                    warn!("APIC timer calibration failed, tick length is unknown");
+                    0x20000
+                }
+                count => count,
+            };
             self.write(TIMER, PERIODIC | (T_IRQ0 + IRQ_TIMER));
-            self.write(TICR, 0x20000);
+            self.write(TICR, count);
 
             // Disable logical interrupt lines.
             self.write(LINT0, MASKED);
@@ -156,6 +205,10 @@ const TICR: u32 = 0x0380; // Timer Initial Count
 const TCCR: u32 = 0x0390; // Timer Current Count
 const TDCR: u32 = 0x03E0; // Timer Divide Configuration
 
+const PIT_CHANNEL2: u16 = 0x42;
+const PIT_COMMAND: u16 = 0x43;
+const PIT_GATE: u16 = 0x61; // Channel 2 gate and output
+
 const T_IRQ0: u32 = 32; // IRQ 0 corresponds to int T_IRQ
 const IRQ_TIMER: u32 = 0;
 const IRQ_KBD: u32 = 1;
@@ -11,7 +11,9 @@ pub unsafe fn reg_idt(idt: &mut InterruptDescriptorTable) {
 }
 
 pub extern "C" fn clock(mut context: ProcessContext) {
-    crate::proc::switch(&mut context);
+    crate::utils::clock::tick();
+    crate::proc::tick(&mut context);
+    crate::proc::handle_signals(&mut context);
     super::ack();
 }
 
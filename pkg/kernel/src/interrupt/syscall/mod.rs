@@ -18,6 +18,7 @@ pub unsafe fn reg_idt(idt: &mut InterruptDescriptorTable) {
 pub extern "C" fn syscall(mut context: ProcessContext) {
     x86_64::instructions::interrupts::without_interrupts(|| {
         super::syscall::dispatcher(&mut context);
+        crate::proc::handle_signals(&mut context);
     });
 }
 
@@ -29,49 +30,139 @@ pub struct SyscallArgs {
     pub arg0: usize,
     pub arg1: usize,
     pub arg2: usize,
+    pub arg3: usize,
+    pub arg4: usize,
+    pub arg5: usize,
 }
 
 pub fn dispatcher(context: &mut ProcessContext) {
     let args = super::syscall::SyscallArgs::new(
         Syscall::from(context.regs.rax as u16),
-        context.regs.rdi,
-        context.regs.rsi,
-        context.regs.rdx,
+        [
+            context.regs.rdi,
+            context.regs.rsi,
+            context.regs.rdx,
+            context.regs.r10,
+            context.regs.r8,
+            context.regs.r9,
+        ],
     );
 
     match args.syscall {
-        // fd: arg0 as u8, buf: &[u8] (arg1 as *const u8, arg2 as len)
-        Syscall::Read => context.set_rax(sys_read(&args)),
-        // fd: arg0 as u8, buf: &[u8] (arg1 as *const u8, arg2 as len)
-        Syscall::Write => context.set_rax(sys_write(&args)),
-        // path: &str (arg0 as *const u8, arg1 as len), mode: arg2 as u8 -> fd: u8
+        // fd: arg0 as u32, buf: &[u8] (arg1 as *const u8, arg2 as len) -> count: usize,
+        // -errno on failure, blocks while it is an empty pipe
+        Syscall::Read => sys_read(&args, context),
+        // fd: arg0 as u32, buf: &[u8] (arg1 as *const u8, arg2 as len) -> count: usize,
+        // -errno on failure, blocks while it is a full pipe
+        Syscall::Write => sys_write(&args, context),
+        // path: &str (arg0 as *const u8, arg1 as len), mode: arg2 as u8,
+        // flags: arg3 as usize (O_CLOEXEC) -> fd: u32, -errno on failure
         Syscall::Open => context.set_rax(sys_open(&args)),
-        // fd: arg0 as u8 -> success: bool
+        // fd: arg0 as u32 -> success: bool
         Syscall::Close => context.set_rax(sys_close(&args)),
+        // path: &str (arg0 as *const u8, arg1 as len), stat: arg2 as *mut FileStat
+        // -> ret: isize, -errno on failure
+        Syscall::Stat => context.set_rax(sys_stat(&args)),
+        // fd: arg0 as u32, stat: arg1 as *mut FileStat -> ret: isize, -errno on failure
+        Syscall::FStat => context.set_rax(sys_fstat(&args)),
+        // fd: arg0 as u32, offset: arg1 as isize, whence: arg2 as u8 -> offset: isize
+        Syscall::Seek => context.set_rax(sys_seek(&args)),
+        // fds: arg0 as *mut [u32; 2] (read end, write end), flags: arg1 as usize
+        // (O_CLOEXEC) -> ret: isize, -errno on failure
+        Syscall::Pipe => context.set_rax(sys_pipe(&args)),
+        // fd: arg0 as u32 -> new fd: u32, -errno on failure
+        Syscall::Dup => context.set_rax(sys_dup(&args)),
+        // fd: arg0 as u32, new fd: arg1 as u32 -> new fd: u32, -errno on failure
+        Syscall::Dup2 => context.set_rax(sys_dup2(&args)),
+        // fd: arg0 as u32, cmd: arg1 as usize (F_GETFD, F_SETFD), arg: arg2
+        // as usize (FD_CLOEXEC) -> flags: usize, -errno on failure
+        Syscall::Fcntl => context.set_rax(sys_fcntl(&args)),
+        // path: &str (arg0 as *const u8, arg1 as len), buf: &mut [u8] (arg2, arg3),
+        // start: arg4 as usize (entries to skip) -> len: usize, 0 after the last
+        // entry, -errno on failure or if the next entry does not fit
+        Syscall::GetDents => context.set_rax(sys_getdents(&args)),
+        // buf: &mut [u8] (arg0 as *mut u8, arg1 as len) -> len: usize, only copied
+        // if it fits
+        Syscall::GetCwd => context.set_rax(sys_getcwd(&args)),
+        // path: &str (arg0 as *const u8, arg1 as len) -> ret: isize, -errno on failure
+        Syscall::ChDir => context.set_rax(sys_chdir(&args)),
+        // path: &str (arg0 as *const u8, arg1 as len) -> ret: isize, -errno on failure
+        Syscall::MkDir => context.set_rax(sys_mkdir(&args)),
+        // path: &str (arg0 as *const u8, arg1 as len) -> ret: isize, -errno on failure
+        Syscall::RmDir => context.set_rax(sys_rmdir(&args)),
+        // path: &str (arg0 as *const u8, arg1 as len) -> ret: isize, -errno on failure
+        Syscall::Unlink => context.set_rax(sys_unlink(&args)),
         // addr: usize -> success: bool
         Syscall::Brk => context.set_rax(sys_brk(&args)),
+        // None
+        Syscall::SchedYield => sys_yield(context),
         // None -> pid: u16
         Syscall::GetPid => context.set_rax(sys_get_pid() as usize),
+        // None -> pid: u16, 0 if the parent is gone
+        Syscall::GetPpid => context.set_rax(sys_get_ppid() as usize),
+        // entry: extern "C" fn(arg), arg: usize, stack: usize (top, 0 for a
+        // stack slot of its own) -> tid: u16, 0 on failure
+        Syscall::ThreadCreate => context.set_rax(sys_thread_create(&args)),
         // None -> pid: u16 (diff from parent and child)
-        Syscall::VFork => sys_fork(context),
-        // path: &str (arg0 as *const u8, arg1 as len) -> pid: u16
+        Syscall::Fork => sys_fork(context),
+        // path: &str (arg0 as *const u8, arg1 as len), argv: &[u8] (arg2, arg3),
+        // envp: &[u8] (arg4, arg5), both NUL separated -> pid: u16
         Syscall::Spawn => context.set_rax(spawn_process(&args) as usize),
-        // pid: arg0 as u16
+        // ret: arg0 as isize, ends every thread of the process
         Syscall::Exit => exit_process(&args, context),
-        // pid: arg0 as u16 -> status: isize
+        // pid: arg0 as isize (-1 for any child), status: arg1 as *mut ChildStatus
+        // (0 to drop), flags: arg2 as usize (WNOHANG, WUNTRACED) -> pid: isize,
+        // 0 if none exited with WNOHANG, -1 if there is no such child, -EFAULT for
+        // a bad status pointer
         Syscall::WaitPid => sys_wait_pid(&args, context),
-        // pid: arg0 as u16
-        Syscall::Kill => sys_kill(&args, context),
+        // pid: arg0 as isize (-pgid for a process group), signal: arg1 as u8
+        // (0 only checks the pid) -> ret: isize, -ESRCH if there is no such process,
+        // -EPERM for the kernel, -EINVAL for a bad signal
+        Syscall::Kill => context.set_rax(sys_kill(&args)),
+        // signal: arg0 as u8, act: arg1 as *const SigAction (0 to only read),
+        // oldact: arg2 as *mut SigAction (0 to drop) -> ret: isize, -EINVAL for a
+        // bad signal or one that can't be caught, -EFAULT for a bad pointer
+        Syscall::SigAction => context.set_rax(sys_sigaction(&args)),
+        // how: arg0 as u8, set: arg1 as u64 -> old mask: u64, -EINVAL for a bad how
+        Syscall::SigProcMask => context.set_rax(sys_sigprocmask(&args)),
+        // None, the handler's frame is on the stack
+        Syscall::SigReturn => sys_sigreturn(context),
+        // pgid: arg0 as u16, 0 for none
+        Syscall::SetForeground => sys_set_foreground(&args),
+        // pid: arg0 as u16 (0 for self), pgid: arg1 as u16 (0 for pid) -> ret: isize,
+        // -ESRCH if pid is neither the caller nor a child of it
+        Syscall::SetPgid => context.set_rax(sys_set_pgid(&args)),
+        // pid: arg0 as u16 (0 for self) -> pgid: u16, -ESRCH if there is no such process
+        Syscall::GetPgid => context.set_rax(sys_get_pgid(&args)),
         // op: u8, key: u32, val: usize -> ret: any
         Syscall::Sem => sys_sem(&args, context),
+        // ret: arg0 as isize
+        Syscall::ThreadExit => sys_thread_exit(&args, context),
+        // tid: arg0 as u16 -> ret: isize, -ESRCH if it is not a thread of the process
+        Syscall::ThreadJoin => sys_thread_join(&args, context),
+        // pid: arg0 as u16 (0 for self) -> 20 - nice: usize, -ESRCH if there is no such process
+        Syscall::GetPriority => context.set_rax(sys_get_priority(&args)),
+        // pid: arg0 as u16 (0 for self), nice: arg1 as i8 -> ret: isize, -ESRCH if there
+        // is no such process
+        Syscall::SetPriority => context.set_rax(sys_set_priority(&args)),
+        // source: &str (arg0 as *const u8, arg1 as len), target: &str (arg2, arg3),
+        // fstype: &str (arg4, arg5) -> ret: isize, -errno on failure
+        Syscall::Mount => context.set_rax(sys_mount(&args)),
+        // target: &str (arg0 as *const u8, arg1 as len) -> ret: isize, -errno on failure
+        Syscall::Umount => context.set_rax(sys_umount(&args)),
         // None -> time: usize
         Syscall::Time => context.set_rax(sys_clock() as usize),
+        // clock: arg0 as u8 -> nanoseconds: u64, -EINVAL for an unknown clock
+        Syscall::ClockGetTime => context.set_rax(sys_clock_gettime(&args)),
+        // nanoseconds: arg0 as u64 -> ret: isize, -1 if a signal came first
+        Syscall::NanoSleep => sys_nanosleep(&args, context),
+        // path: &str (arg0 as *const u8, arg1 as len), argv: &[u8] (arg2, arg3),
+        // envp: &[u8] (arg4, arg5), both NUL separated -> success: bool, only on failure
+        Syscall::Exec => sys_exec(&args, context),
         // x: arg0 as i32, y: arg1 as i32, color: arg2 as u32
         Syscall::Draw => sys_draw(&args),
         // None
-        Syscall::Stat => list_process(),
-        // path: &str (arg0 as *const u8, arg1 as len)
-        Syscall::ListDir => list_dir(&args),
+        Syscall::ListProcess => list_process(),
         // layout: arg0 as *const Layout -> ptr: *mut u8
         Syscall::Allocate => context.set_rax(sys_allocate(&args)),
         // ptr: arg0 as *mut u8
@@ -82,12 +173,16 @@ pub fn dispatcher(context: &mut ProcessContext) {
 }
 
 impl SyscallArgs {
-    pub fn new(syscall: Syscall, arg0: usize, arg1: usize, arg2: usize) -> Self {
+    pub fn new(syscall: Syscall, args: [usize; 6]) -> Self {
+        let [arg0, arg1, arg2, arg3, arg4, arg5] = args;
         Self {
             syscall,
             arg0,
             arg1,
             arg2,
+            arg3,
+            arg4,
+            arg5,
         }
     }
 }
@@ -1,11 +1,20 @@
+use alloc::{string::String, vec::Vec};
 use core::alloc::Layout;
 
 use embedded_graphics::geometry::Point;
+use storage::{FileSystem, SeekFrom};
+use syscall_def::{
+    ChildStatus, ClockId, DirEntry, Errno, F_GETFD, F_SETFD, FD_CLOEXEC, Fd, FileMode, FileStat,
+    O_CLOEXEC, SigAction, SigHow, Signal, WNOHANG, WUNTRACED,
+};
+use x86_64::VirtAddr;
 
 use crate::display::get_display_for_sure;
+use crate::filesystem::{create_dir, errno, file_stat, remove_dir, remove_file};
 use crate::memory::*;
 use crate::proc::*;
 use crate::utils::*;
+use crate::vfs::get_vfs;
 
 use super::SyscallArgs;
 
@@ -16,6 +25,18 @@ pub fn sys_clock() -> i64 {
         .unwrap_or_default()
 }
 
+pub fn sys_clock_gettime(args: &SyscallArgs) -> usize {
+    match ClockId::try_from(args.arg0 as u8) {
+        Ok(ClockId::Realtime) => sys_clock() as usize,
+        Ok(ClockId::Monotonic) => clock::monotonic().as_nanos() as usize,
+        Err(_) => Errno::EINVAL.as_ret(),
+    }
+}
+
+pub fn sys_nanosleep(args: &SyscallArgs, context: &mut ProcessContext) {
+    sleep(args.arg0 as u64, context);
+}
+
 pub fn sys_draw(args: &SyscallArgs) {
     let _ = get_display_for_sure().draw_pixel_u32(
         Point::new(args.arg0 as i32, args.arg1 as i32),
@@ -77,18 +98,55 @@ pub fn sys_deallocate(args: &SyscallArgs) {
     }
 }
 
-pub fn spawn_process(args: &SyscallArgs) -> u16 {
+/// A NUL separated list of strings in user memory, e.g. argv
+fn as_user_str_list(ptr: usize, len: usize) -> Option<Vec<String>> {
+    if len == 0 {
+        return Some(Vec::new());
+    }
+
+    Some(
+        as_user_str(ptr, len)?
+            .split_terminator('\0')
+            .map(String::from)
+            .collect(),
+    )
+}
+
+/// Path, argv and envp of `spawn` and `exec`, argv defaults to the path
+fn program_args(args: &SyscallArgs) -> Option<(&'static str, ProgramArgs)> {
     if args.arg1 > 0x100 {
-        warn!("sys_spawn: path too long");
-        return 0;
+        warn!("program_args: path too long");
+        return None;
     }
 
-    let path = match as_user_str(args.arg0, args.arg1) {
-        Some(path) => path,
+    // both lengths come from the caller, the sum may overflow
+    if args
+        .arg3
+        .checked_add(args.arg5)
+        .is_none_or(|len| len > ARG_MAX)
+    {
+        warn!("program_args: arguments too long");
+        return None;
+    }
+
+    let path = as_user_str(args.arg0, args.arg1)?;
+    let mut argv = as_user_str_list(args.arg2, args.arg3)?;
+    let envp = as_user_str_list(args.arg4, args.arg5)?;
+
+    if argv.is_empty() {
+        argv.push(path.into());
+    }
+
+    Some((path, ProgramArgs::new(argv, envp)))
+}
+
+pub fn spawn_process(args: &SyscallArgs) -> u16 {
+    let (path, args) = match program_args(args) {
+        Some(args) => args,
         None => return 0,
     };
 
-    match fs_spawn(path) {
+    match fs_spawn(path, &args) {
         Some(pid) => pid.0,
         None => {
             warn!("spawn_process: failed to spawn: {}", path);
@@ -97,46 +155,133 @@ pub fn spawn_process(args: &SyscallArgs) -> u16 {
     }
 }
 
-pub fn sys_write(args: &SyscallArgs) -> usize {
+pub fn sys_exec(args: &SyscallArgs, context: &mut ProcessContext) {
+    let (path, args) = match program_args(args) {
+        Some(args) => args,
+        None => return context.set_rax(0),
+    };
+
+    if !exec(path, &args, context) {
+        warn!("sys_exec: failed to exec: {}", path);
+        context.set_rax(0);
+    }
+}
+
+pub fn sys_write(args: &SyscallArgs, context: &mut ProcessContext) {
     let buf = match as_user_slice(args.arg1, args.arg2) {
         Some(buf) => buf,
-        None => return usize::MAX,
+        None => return context.set_rax(Errno::EINVAL.as_ret()),
     };
 
-    let fd = args.arg0 as u8;
-    write(fd, buf) as usize
+    let fd = args.arg0 as Fd;
+    write(fd, buf, context)
 }
 
-pub fn sys_read(args: &SyscallArgs) -> usize {
+pub fn sys_read(args: &SyscallArgs, context: &mut ProcessContext) {
     let buf = match as_user_slice_mut(args.arg1, args.arg2) {
         Some(buf) => buf,
-        None => return usize::MAX,
+        None => return context.set_rax(Errno::EINVAL.as_ret()),
     };
 
-    let fd = args.arg0 as u8;
-    read(fd, buf) as usize
+    let fd = args.arg0 as Fd;
+    read(fd, buf, context)
+}
+
+pub fn sys_seek(args: &SyscallArgs) -> usize {
+    let offset = args.arg1 as isize;
+    let pos = match args.arg2 {
+        0 if offset < 0 => return Errno::EINVAL.as_ret(),
+        0 => SeekFrom::Start(offset as usize),
+        1 => SeekFrom::Current(offset),
+        2 => SeekFrom::End(offset),
+        _ => return Errno::EINVAL.as_ret(),
+    };
+
+    let fd = args.arg0 as Fd;
+    match seek(fd, pos) {
+        Ok(offset) => offset,
+        Err(errno) => errno.as_ret(),
+    }
 }
 
 pub fn sys_get_pid() -> u16 {
     current_pid().0
 }
 
+pub fn sys_get_ppid() -> u16 {
+    parent_pid().map_or(0, |pid| pid.0)
+}
+
 pub fn sys_fork(context: &mut ProcessContext) {
     fork(context)
 }
 
+/// The return value of a syscall giving an fd
+fn fd_result(result: Result<Fd, Errno>) -> usize {
+    match result {
+        Ok(fd) => fd as usize,
+        Err(errno) => errno.as_ret(),
+    }
+}
+
 pub fn sys_open(args: &SyscallArgs) -> usize {
     let path = match as_user_str(args.arg0, args.arg1) {
         Some(path) => path,
-        None => return 0,
+        None => return Errno::EINVAL.as_ret(),
     };
 
-    match open(path) {
-        Some(fd) => fd as usize,
-        None => {
-            warn!("sys_open: failed to open: {}", path);
+    let mode = match FileMode::try_from(args.arg2 as u8) {
+        Ok(mode) => mode,
+        Err(_) => return Errno::EINVAL.as_ret(),
+    };
+
+    let result = open(path, mode, args.arg3 & O_CLOEXEC != 0);
+    if let Err(errno) = result {
+        warn!("sys_open: failed to open {}: {:?}", path, errno);
+    }
+
+    fd_result(result)
+}
+
+pub fn sys_dup(args: &SyscallArgs) -> usize {
+    fd_result(dup(args.arg0 as Fd))
+}
+
+pub fn sys_dup2(args: &SyscallArgs) -> usize {
+    fd_result(dup2(args.arg0 as Fd, args.arg1 as Fd))
+}
+
+pub fn sys_pipe(args: &SyscallArgs) -> usize {
+    let fds = match as_user_slice_mut(args.arg0, 2 * size_of::<Fd>()) {
+        Some(buf) => buf.as_mut_ptr() as *mut Fd,
+        None => return Errno::EINVAL.as_ret(),
+    };
+
+    match pipe(args.arg1 & O_CLOEXEC != 0) {
+        Ok((read_fd, write_fd)) => {
+            // written with the lock released, it may be copy-on-write
+            unsafe {
+                fds.write_unaligned(read_fd);
+                fds.add(1).write_unaligned(write_fd);
+            }
             0
         }
+        Err(errno) => errno.as_ret(),
+    }
+}
+
+pub fn sys_fcntl(args: &SyscallArgs) -> usize {
+    let fd = args.arg0 as Fd;
+
+    let result = match args.arg1 {
+        F_GETFD => cloexec(fd).map(|cloexec| if cloexec { FD_CLOEXEC } else { 0 }),
+        F_SETFD => set_cloexec(fd, args.arg2 & FD_CLOEXEC != 0).map(|_| 0),
+        _ => Err(Errno::EINVAL),
+    };
+
+    match result {
+        Ok(flags) => flags,
+        Err(errno) => errno.as_ret(),
     }
 }
 
@@ -150,43 +295,340 @@ pub fn sys_brk(args: &SyscallArgs) -> usize {
 }
 
 pub fn sys_close(args: &SyscallArgs) -> usize {
-    close(args.arg0 as u8) as usize
+    close(args.arg0 as Fd) as usize
 }
 
 pub fn exit_process(args: &SyscallArgs, context: &mut ProcessContext) {
     process_exit(args.arg0 as isize, context);
 }
 
+pub fn sys_thread_create(args: &SyscallArgs) -> usize {
+    if !is_user_accessible(args.arg0) {
+        warn!("sys_thread_create: invalid entry {:#x}", args.arg0);
+        return 0;
+    }
+
+    let stack = match args.arg2 {
+        0 => None,
+        top if is_user_accessible(top - 1) => Some(VirtAddr::new(top as u64)),
+        top => {
+            warn!("sys_thread_create: invalid stack {:#x}", top);
+            return 0;
+        }
+    };
+
+    spawn_thread(VirtAddr::new(args.arg0 as u64), args.arg1, stack).map_or(0, |tid| tid.0 as usize)
+}
+
+pub fn sys_thread_exit(args: &SyscallArgs, context: &mut ProcessContext) {
+    thread_exit(args.arg0 as isize, context);
+}
+
+pub fn sys_thread_join(args: &SyscallArgs, context: &mut ProcessContext) {
+    if !thread_join(ProcessId(args.arg0 as u16), context) {
+        context.set_rax(Errno::ESRCH.as_ret());
+    }
+}
+
 pub fn list_process() {
     print_process_list();
 }
 
-pub fn list_dir(args: &SyscallArgs) {
-    if args.arg1 > 0x100 {
-        warn!("sys_list_dir: path too long");
-        return;
+/// Write `stat` to the user pointer `ptr`
+fn put_stat(ptr: usize, stat: FileStat) -> usize {
+    match as_user_slice_mut(ptr, size_of::<FileStat>()) {
+        Some(buf) => {
+            unsafe { (buf.as_mut_ptr() as *mut FileStat).write_unaligned(stat) };
+            0
+        }
+        None => Errno::EINVAL.as_ret(),
     }
+}
 
-    let path = match as_user_str(args.arg0, args.arg1) {
-        Some(path) => path,
-        None => return,
+pub fn sys_stat(args: &SyscallArgs) -> usize {
+    let Some(path) = as_user_str(args.arg0, args.arg1) else {
+        return Errno::EINVAL.as_ret();
     };
 
-    crate::filesystem::ls(path);
+    match get_vfs().metadata(&absolute_path(path)) {
+        Ok(meta) => put_stat(args.arg2, file_stat(&meta)),
+        Err(e) => errno(&e).as_ret(),
+    }
+}
+
+pub fn sys_fstat(args: &SyscallArgs) -> usize {
+    match fstat(args.arg0 as Fd) {
+        Ok(stat) => put_stat(args.arg1, stat),
+        Err(errno) => errno.as_ret(),
+    }
+}
+
+/// Entries of a directory from the `start`th on, as many as fit
+pub fn sys_getdents(args: &SyscallArgs) -> usize {
+    let (Some(path), Some(buf)) = (
+        as_user_str(args.arg0, args.arg1),
+        as_user_slice_mut(args.arg2, args.arg3),
+    ) else {
+        return Errno::EINVAL.as_ret();
+    };
+
+    let entries = match get_vfs().read_dir(&absolute_path(path)) {
+        Ok(entries) => entries,
+        Err(e) => return errno(&e).as_ret(),
+    };
+
+    let mut offset = 0;
+    for meta in entries.skip(args.arg4) {
+        let name = meta.name.as_bytes();
+        let len = DirEntry::record_len(name.len());
+        if offset + len > buf.len() {
+            // not even one entry fits
+            if offset == 0 {
+                return Errno::EINVAL.as_ret();
+            }
+            break;
+        }
+
+        let entry = DirEntry {
+            stat: file_stat(&meta),
+            name_len: name.len() as u16,
+        };
+        let record = &mut buf[offset..offset + len];
+        unsafe { (record.as_mut_ptr() as *mut DirEntry).write_unaligned(entry) };
+        record[size_of::<DirEntry>()..][..name.len()].copy_from_slice(name);
+
+        offset += len;
+    }
+
+    offset
+}
+
+/// The working directory, only copied if it fits
+pub fn sys_getcwd(args: &SyscallArgs) -> usize {
+    let Some(buf) = as_user_slice_mut(args.arg0, args.arg1) else {
+        return Errno::EINVAL.as_ret();
+    };
+
+    let cwd = cwd();
+    if cwd.len() <= buf.len() {
+        buf[..cwd.len()].copy_from_slice(cwd.as_bytes());
+    }
+
+    cwd.len()
+}
+
+pub fn sys_chdir(args: &SyscallArgs) -> usize {
+    let Some(path) = as_user_str(args.arg0, args.arg1) else {
+        return Errno::EINVAL.as_ret();
+    };
+
+    match chdir(path) {
+        Ok(()) => 0,
+        Err(errno) => errno.as_ret(),
+    }
+}
+
+/// Run `op` on the absolute path of the string in arg0 and arg1
+fn path_op(args: &SyscallArgs, op: fn(&str) -> Result<(), Errno>) -> usize {
+    let Some(path) = as_user_str(args.arg0, args.arg1) else {
+        return Errno::EINVAL.as_ret();
+    };
+
+    match op(&absolute_path(path)) {
+        Ok(()) => 0,
+        Err(errno) => errno.as_ret(),
+    }
+}
+
+pub fn sys_mkdir(args: &SyscallArgs) -> usize {
+    path_op(args, create_dir)
+}
+
+pub fn sys_rmdir(args: &SyscallArgs) -> usize {
+    path_op(args, remove_dir)
+}
+
+pub fn sys_unlink(args: &SyscallArgs) -> usize {
+    path_op(args, remove_file)
+}
+
+pub fn sys_mount(args: &SyscallArgs) -> usize {
+    let (source, target, fstype) = match (
+        as_user_str(args.arg0, args.arg1),
+        as_user_str(args.arg2, args.arg3),
+        as_user_str(args.arg4, args.arg5),
+    ) {
+        (Some(source), Some(target), Some(fstype)) => (source, target, fstype),
+        _ => return Errno::EINVAL.as_ret(),
+    };
+
+    match crate::filesystem::mount(source, &absolute_path(target), fstype) {
+        Ok(()) => 0,
+        Err(errno) => {
+            warn!(
+                "sys_mount: failed to mount {} at {}: {:?}",
+                source, target, errno
+            );
+            errno.as_ret()
+        }
+    }
+}
+
+pub fn sys_umount(args: &SyscallArgs) -> usize {
+    let target = match as_user_str(args.arg0, args.arg1) {
+        Some(target) => target,
+        None => return Errno::EINVAL.as_ret(),
+    };
+
+    match crate::filesystem::umount(&absolute_path(target)) {
+        Ok(()) => 0,
+        Err(errno) => {
+            warn!("sys_umount: failed to unmount {}: {:?}", target, errno);
+            errno.as_ret()
+        }
+    }
 }
 
 pub fn sys_wait_pid(args: &SyscallArgs, context: &mut ProcessContext) {
-    let pid = ProcessId(args.arg0 as u16);
-    wait_pid(pid, context);
+    let pid = match args.arg0 as isize {
+        pid if pid > 0 => Some(ProcessId(pid as u16)),
+        _ => None,
+    };
+
+    let status = match args.arg1 {
+        0 => None,
+        ptr => match as_user_slice_mut(ptr, size_of::<ChildStatus>()) {
+            Some(buf) => Some(buf),
+            None => return context.set_rax(Errno::EFAULT.as_ret()),
+        },
+    };
+
+    let nohang = args.arg2 & WNOHANG != 0;
+    let untraced = args.arg2 & WUNTRACED != 0;
+    wait_pid(pid, status, nohang, untraced, context);
 }
 
-pub fn sys_kill(args: &SyscallArgs, context: &mut ProcessContext) {
-    if args.arg0 == 1 {
+pub fn sys_kill(args: &SyscallArgs) -> usize {
+    // a negative pid is a process group
+    let target = args.arg0 as isize;
+    let pid = ProcessId(target.unsigned_abs() as u16);
+
+    if pid.0 == 1 {
         warn!("sys_kill: cannot kill kernel!");
-        return;
+        return Errno::EPERM.as_ret();
     }
 
-    kill(ProcessId(args.arg0 as u16), context);
+    // signal 0 only checks that the process exists
+    let sent = match args.arg1 {
+        0 if target > 0 => is_alive(pid),
+        0 => false,
+        sig => match Signal::try_from(sig as u8) {
+            Ok(signal) if sig < 64 && target > 0 => send_signal(pid, signal),
+            Ok(signal) if sig < 64 => send_group_signal(pid, signal),
+            _ => return Errno::EINVAL.as_ret(),
+        },
+    };
+
+    if sent { 0 } else { Errno::ESRCH.as_ret() }
+}
+
+pub fn sys_sigaction(args: &SyscallArgs) -> usize {
+    let Ok(signal) = Signal::try_from(args.arg0 as u8) else {
+        return Errno::EINVAL.as_ret();
+    };
+
+    let size = size_of::<SigAction>();
+
+    let action = match args.arg1 {
+        0 => None,
+        ptr => match as_user_slice(ptr, size) {
+            Some(buf) => Some(unsafe { (buf.as_ptr() as *const SigAction).read_unaligned() }),
+            None => return Errno::EFAULT.as_ret(),
+        },
+    };
+
+    // SIGKILL and SIGSTOP can't be caught
+    let Some(old) = sigaction(signal, action) else {
+        return Errno::EINVAL.as_ret();
+    };
+
+    if args.arg2 != 0 {
+        match as_user_slice_mut(args.arg2, size) {
+            Some(buf) => unsafe { (buf.as_mut_ptr() as *mut SigAction).write_unaligned(old) },
+            None => return Errno::EFAULT.as_ret(),
+        }
+    }
+
+    0
+}
+
+pub fn sys_sigprocmask(args: &SyscallArgs) -> usize {
+    match SigHow::try_from(args.arg0 as u8) {
+        Ok(how) => sigprocmask(how, args.arg1 as u64) as usize,
+        Err(_) => Errno::EINVAL.as_ret(),
+    }
+}
+
+pub fn sys_sigreturn(context: &mut ProcessContext) {
+    sigreturn(context);
+}
+
+pub fn sys_set_foreground(args: &SyscallArgs) {
+    match args.arg0 {
+        0 => set_foreground(None),
+        pgid => set_foreground(Some(ProcessId(pgid as u16))),
+    }
+}
+
+/// The pid a process group or priority syscall is about, 0 means the
+/// caller
+fn target_pid(pid: usize) -> ProcessId {
+    match pid {
+        0 => current_pid(),
+        pid => ProcessId(pid as u16),
+    }
+}
+
+pub fn sys_set_pgid(args: &SyscallArgs) -> usize {
+    let pid = target_pid(args.arg0);
+    let pgid = match args.arg1 {
+        0 => pid,
+        pgid => ProcessId(pgid as u16),
+    };
+
+    // only the caller and its children can be moved
+    if set_pgid(pid, pgid) {
+        0
+    } else {
+        Errno::ESRCH.as_ret()
+    }
+}
+
+pub fn sys_get_pgid(args: &SyscallArgs) -> usize {
+    match pgid(target_pid(args.arg0)) {
+        Some(pgid) => pgid.0 as usize,
+        None => Errno::ESRCH.as_ret(),
+    }
+}
+
+pub fn sys_yield(context: &mut ProcessContext) {
+    switch(context);
+}
+
+pub fn sys_get_priority(args: &SyscallArgs) -> usize {
+    match nice(target_pid(args.arg0)) {
+        // kept positive, so it can't be mistaken for an error
+        Some(nice) => (20 - nice as isize) as usize,
+        None => Errno::ESRCH.as_ret(),
+    }
+}
+
+pub fn sys_set_priority(args: &SyscallArgs) -> usize {
+    if set_nice(target_pid(args.arg0), args.arg1 as i8) {
+        0
+    } else {
+        Errno::ESRCH.as_ret()
+    }
 }
 
 pub fn sys_sem(args: &SyscallArgs, context: &mut ProcessContext) {
@@ -44,7 +44,9 @@ pub fn init(boot_info: &'static BootInfo) {
     }
 
     serial::init(); // init serial output
+    cmdline::init(boot_info); // parse kernel command line
     logger::init(boot_info); // init logger system
+    clock::init(); // record boot time
     memory::address::init(boot_info); // init memory address
     memory::gdt::init(); // init gdt
     memory::allocator::init(); // init kernel heap allocator
@@ -55,7 +57,7 @@ pub fn init(boot_info: &'static BootInfo) {
     memory::user::init(); // init user heap allocator
     proc::init(boot_info); // init process manager
     keyboard::init(); // init keyboard
-    filesystem::init(); // init filesystem
+    filesystem::init(boot_info); // init filesystem
 
     x86_64::instructions::interrupts::enable();
     info!("Interrupts Enabled.");
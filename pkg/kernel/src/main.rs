@@ -1,7 +1,7 @@
 #![no_std]
 #![no_main]
 
-use alloc::string::ToString;
+use alloc::{string::ToString, vec, vec::Vec};
 use ggos::*;
 use ggos_kernel as ggos;
 use log::*;
@@ -15,24 +15,21 @@ pub fn kernel_main(boot_info: &'static boot::BootInfo) -> ! {
 
     let mut executor = Executor::new();
 
-    let init = spawn_init(boot_info);
+    let init = spawn_init();
 
     // use executor.spawn() to spawn kernel tasks
     executor.run(init);
     ggos::shutdown();
 }
 
-pub fn spawn_init(boot_info: &'static boot::BootInfo) -> proc::ProcessId {
+pub fn spawn_init() -> proc::ProcessId {
     // print_serial!("\x1b[1;1H\x1b[2J");
 
-    if let Some(apps) = &boot_info.loaded_apps {
-        for app in apps {
-            if app.name.eq("sh") {
-                info!("Found sh in loaded apps, spawning...");
-                return proc::elf_spawn("sh".to_string(), &app.elf).unwrap();
-            }
-        }
-    }
+    let init = cmdline::get_cmdline().init;
 
-    proc::fs_spawn("/APP/SH").unwrap()
+    info!("Spawning init: {}", init);
+    let args = proc::ProgramArgs::new(vec![init.to_string()], Vec::new());
+    let pid = proc::fs_spawn(init, &args).unwrap();
+    proc::set_init(pid);
+    pid
 }
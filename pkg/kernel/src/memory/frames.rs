@@ -2,6 +2,8 @@
 // reference: https://github.com/xfoxfu/rust-xos/blob/main/kernel/src/memory.rs
 
 use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::collections::btree_map::Entry;
 use boot::{MemoryMap, MemoryType};
 use roaring::RoaringBitmap;
 use x86_64::PhysAddr;
@@ -21,6 +23,8 @@ pub struct BootInfoFrameAllocator {
     frames: BootInfoFrameIter,
     used: usize,
     recycled: RoaringBitmap,
+    /// Frames mapped by more than one page table, with their owner count
+    shared: BTreeMap<u32, usize>,
 }
 
 impl BootInfoFrameAllocator {
@@ -36,6 +40,7 @@ impl BootInfoFrameAllocator {
                 frames: create_frame_iter(memory_map),
                 used,
                 recycled: RoaringBitmap::new(),
+                shared: BTreeMap::new(),
             }
         }
     }
@@ -51,6 +56,21 @@ impl BootInfoFrameAllocator {
     pub fn frames_recycled(&self) -> usize {
         self.recycled.len() as usize
     }
+
+    pub fn frames_shared(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Add an owner to a frame, it is only recycled once every owner
+    /// has deallocated it
+    pub fn share_frame(&mut self, frame: PhysFrame) {
+        *self.shared.entry(phys_frame_to_u32(frame)).or_insert(1) += 1;
+    }
+
+    /// Whether the frame has more than one owner
+    pub fn is_shared(&self, frame: PhysFrame) -> bool {
+        self.shared.contains_key(&phys_frame_to_u32(frame))
+    }
 }
 
 unsafe impl FrameAllocator<Size4KiB> for BootInfoFrameAllocator {
@@ -68,6 +88,15 @@ unsafe impl FrameAllocator<Size4KiB> for BootInfoFrameAllocator {
 impl FrameDeallocator<Size4KiB> for BootInfoFrameAllocator {
     unsafe fn deallocate_frame(&mut self, frame: PhysFrame) {
         let key = phys_frame_to_u32(frame);
+
+        if let Entry::Occupied(mut owners) = self.shared.entry(key) {
+            *owners.get_mut() -= 1;
+            if *owners.get() == 1 {
+                owners.remove();
+            }
+            return;
+        }
+
         self.recycled.insert(key);
     }
 }
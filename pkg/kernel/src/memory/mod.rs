@@ -8,6 +8,8 @@ pub mod user;
 pub use address::*;
 pub use frames::*;
 
+use x86_64::registers::control::{Cr0, Cr0Flags};
+
 pub fn init(boot_info: &'static boot::BootInfo) {
     let memory_map = &boot_info.memory_map;
 
@@ -43,4 +45,8 @@ pub fn init(boot_info: &'static boot::BootInfo) {
     }
 
     info!("Frame Allocator initialized.");
+
+    // the kernel has to fault on read-only pages too, or its writes to
+    // user buffers would go to frames shared copy-on-write
+    unsafe { Cr0::update(|flags| flags.insert(Cr0Flags::WRITE_PROTECT)) };
 }
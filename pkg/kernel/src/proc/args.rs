@@ -0,0 +1,87 @@
+use alloc::{vec, vec::Vec};
+
+use super::*;
+use crate::memory::PAGE_SIZE;
+
+/// Most bytes of arguments and environment a program may be given
+pub const ARG_MAX: usize = 0x8000;
+
+const AT_NULL: u64 = 0;
+const AT_PAGESZ: u64 = 6;
+const AT_ENTRY: u64 = 9;
+
+/// Arguments and environment handed to a new program image
+#[derive(Debug, Clone, Default)]
+pub struct ProgramArgs {
+    pub argv: Vec<String>,
+    /// `KEY=value` strings
+    pub envp: Vec<String>,
+}
+
+impl ProgramArgs {
+    pub fn new(argv: Vec<String>, envp: Vec<String>) -> Self {
+        Self { argv, envp }
+    }
+
+    /// Bytes taken by the strings, counting their terminators
+    pub fn size(&self) -> usize {
+        self.argv
+            .iter()
+            .chain(&self.envp)
+            .map(|s| s.len() + 1)
+            .sum()
+    }
+
+    /// The environment as key-value pairs, entries without `=` are dropped
+    pub fn vars(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.envp.iter().filter_map(|var| var.split_once('='))
+    }
+
+    /// Lay out the initial stack of a program the SysV way, it ends at
+    /// `top` and the stack pointer is returned with the bytes above it
+    ///
+    /// ```text
+    /// sp -> argc
+    ///       argv[0] .. argv[argc - 1], NULL
+    ///       envp[0] .. envp[n - 1], NULL
+    ///       auxv pairs, AT_NULL
+    ///       strings
+    /// top
+    /// ```
+    pub fn stack_image(&self, top: u64, entry: u64) -> (u64, Vec<u8>) {
+        let auxv = [(AT_PAGESZ, PAGE_SIZE), (AT_ENTRY, entry), (AT_NULL, 0)];
+        let words = 1 + self.argv.len() + 1 + self.envp.len() + 1 + auxv.len() * 2;
+
+        // the stack pointer is 16 byte aligned on entry
+        let strings = (top - self.size() as u64) & !0xf;
+        let sp = (strings - words as u64 * 8) & !0xf;
+
+        let mut image = vec![0u8; (top - sp) as usize];
+        let mut pointers = Vec::with_capacity(words);
+        let mut addr = strings;
+
+        pointers.push(self.argv.len() as u64);
+
+        for list in [&self.argv, &self.envp] {
+            for s in list.iter() {
+                let offset = (addr - sp) as usize;
+                image[offset..offset + s.len()].copy_from_slice(s.as_bytes());
+
+                pointers.push(addr);
+                addr += s.len() as u64 + 1;
+            }
+            pointers.push(0);
+        }
+
+        for (key, value) in auxv {
+            pointers.push(key);
+            pointers.push(value);
+        }
+
+        for (i, word) in pointers.iter().enumerate() {
+            image[i * 8..i * 8 + 8].copy_from_slice(&word.to_ne_bytes());
+        }
+
+        (sp, image)
+    }
+}
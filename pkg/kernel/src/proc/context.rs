@@ -1,12 +1,15 @@
 use volatile::{VolatileRef, access::ReadOnly};
 use x86_64::{
-    VirtAddr,
+    PrivilegeLevel, VirtAddr,
     registers::rflags::RFlags,
     structures::{gdt::SegmentSelector, idt::InterruptStackFrameValue},
 };
 
 use crate::{RegistersValue, memory::gdt::get_user_selector};
 
+/// End of the lower half, where user addresses stay
+const USER_SPACE_END: u64 = 0x0000_8000_0000_0000;
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct ProcessContextValue {
@@ -36,9 +39,18 @@ impl ProcessContext {
         self.value.regs.rax = value;
     }
 
+    /// Set the first argument of the function the context starts in
+    #[inline]
+    pub fn set_rdi(&mut self, value: usize) {
+        self.value.regs.rdi = value;
+    }
+
+    /// Enter the kernel through the same syscall again once the context
+    /// resumes, the registers still hold its number and arguments
     #[inline]
-    pub fn set_stack_offset(&mut self, offset: u64) {
-        self.value.stack_frame.stack_pointer += offset;
+    pub fn restart_syscall(&mut self) {
+        // skip back over `int 0x80`
+        self.value.stack_frame.instruction_pointer -= 2u64;
     }
 
     #[inline]
@@ -63,6 +75,33 @@ impl ProcessContext {
 
         trace!("Init stack frame: {:#?}", &self.stack_frame);
     }
+
+    /// Whether the context returns to user mode
+    pub fn is_user(&self) -> bool {
+        self.value.stack_frame.code_segment.rpl() == PrivilegeLevel::Ring3
+    }
+
+    /// Continue from a context the user could have changed, which must not
+    /// gain kernel segments, addresses or odd flags on the way
+    pub fn restore_user(&mut self, value: &ProcessContextValue) -> bool {
+        let ip = value.stack_frame.instruction_pointer.as_u64();
+        let sp = value.stack_frame.stack_pointer.as_u64();
+        if ip >= USER_SPACE_END || sp >= USER_SPACE_END {
+            return false;
+        }
+
+        let mut flags = value.stack_frame.cpu_flags | RFlags::INTERRUPT_FLAG;
+        flags.remove(RFlags::VIRTUAL_8086_MODE | RFlags::NESTED_TASK);
+
+        self.value.regs = value.regs;
+        self.init_stack_frame(
+            value.stack_frame.instruction_pointer,
+            value.stack_frame.stack_pointer,
+        );
+        self.value.stack_frame.cpu_flags = flags;
+
+        true
+    }
 }
 
 impl Default for ProcessContextValue {
@@ -1,7 +1,9 @@
 use alloc::collections::BTreeMap;
 use spin::RwLock;
+use storage::SeekFrom;
 
 use crate::resource::ResourceSet;
+use syscall_def::{Errno, Fd, FileStat, SigAction, Signal};
 
 use super::*;
 
@@ -10,6 +12,9 @@ pub struct ProcessData {
     pub(super) env: Arc<RwLock<BTreeMap<String, String>>>,
     pub(super) resources: Arc<RwLock<ResourceSet>>,
     pub(super) semaphores: Arc<RwLock<SemaphoreSet>>,
+    pub(super) signal_actions: Arc<RwLock<SignalActions>>,
+    /// Working directory, absolute, shared by the threads
+    pub(super) cwd: Arc<RwLock<String>>,
 }
 
 impl Default for ProcessData {
@@ -18,6 +23,8 @@ impl Default for ProcessData {
             env: Arc::new(RwLock::new(BTreeMap::new())),
             semaphores: Arc::new(RwLock::new(SemaphoreSet::default())),
             resources: Arc::new(RwLock::new(ResourceSet::default())),
+            signal_actions: Arc::new(RwLock::new(SignalActions::default())),
+            cwd: Arc::new(RwLock::new(String::from("/"))),
         }
     }
 }
@@ -27,22 +34,96 @@ impl ProcessData {
         Self::default()
     }
 
-    pub fn open(&mut self, res: Resource) -> u8 {
-        self.resources.write().open(res)
+    /// Data of a forked child, which starts with the same handlers, fds
+    /// and working directory but changes them on its own
+    pub fn fork(&self) -> Self {
+        let actions = self.signal_actions.read().clone();
+        let resources = self.resources.read().clone();
+
+        Self {
+            signal_actions: Arc::new(RwLock::new(actions)),
+            resources: Arc::new(RwLock::new(resources)),
+            cwd: Arc::new(RwLock::new(self.cwd())),
+            ..self.clone()
+        }
     }
 
-    pub fn close(&mut self, fd: u8) -> bool {
+    /// Data of a program spawned by this process, which inherits the fds
+    /// that are not close-on-exec and the working directory
+    pub fn spawn(&self) -> Self {
+        let mut resources = self.resources.read().clone();
+        resources.close_on_exec();
+
+        Self {
+            resources: Arc::new(RwLock::new(resources)),
+            cwd: Arc::new(RwLock::new(self.cwd())),
+            ..Self::default()
+        }
+    }
+
+    pub fn cwd(&self) -> String {
+        self.cwd.read().clone()
+    }
+
+    /// Change the working directory, `path` is checked by the caller
+    pub fn set_cwd(&self, path: String) {
+        *self.cwd.write() = path;
+    }
+
+    /// `path` made absolute against the working directory
+    pub fn absolute_path(&self, path: &str) -> String {
+        crate::filesystem::absolute_path(&self.cwd.read(), path)
+    }
+
+    pub fn open(&mut self, res: Resource, cloexec: bool) -> Result<Fd, Errno> {
+        self.resources.write().open(res, cloexec)
+    }
+
+    pub fn close(&mut self, fd: Fd) -> bool {
         self.resources.write().close(fd)
     }
 
-    pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize {
+    pub fn dup(&mut self, fd: Fd) -> Result<Fd, Errno> {
+        self.resources.write().dup(fd)
+    }
+
+    pub fn dup2(&mut self, fd: Fd, new_fd: Fd) -> Result<Fd, Errno> {
+        self.resources.write().dup2(fd, new_fd)
+    }
+
+    pub fn cloexec(&self, fd: Fd) -> Result<bool, Errno> {
+        self.resources.read().cloexec(fd)
+    }
+
+    pub fn set_cloexec(&mut self, fd: Fd, cloexec: bool) -> Result<(), Errno> {
+        self.resources.write().set_cloexec(fd, cloexec)
+    }
+
+    /// Close the close-on-exec fds on exec, threads share the table
+    pub fn close_on_exec(&mut self) {
+        self.resources.write().close_on_exec();
+    }
+
+    pub fn pipe_id(&self, fd: Fd) -> Option<usize> {
+        self.resources.read().pipe_id(fd)
+    }
+
+    pub fn read(&self, fd: Fd, buf: &mut [u8]) -> Result<usize, Errno> {
         self.resources.read().read(fd, buf)
     }
 
-    pub fn write(&self, fd: u8, buf: &[u8]) -> isize {
+    pub fn write(&self, fd: Fd, buf: &[u8]) -> Result<usize, Errno> {
         self.resources.read().write(fd, buf)
     }
 
+    pub fn seek(&self, fd: Fd, pos: SeekFrom) -> Result<usize, Errno> {
+        self.resources.read().seek(fd, pos)
+    }
+
+    pub fn fstat(&self, fd: Fd) -> Result<FileStat, Errno> {
+        self.resources.read().fstat(fd)
+    }
+
     pub fn env(&self, key: &str) -> Option<String> {
         self.env.read().get(key).cloned()
     }
@@ -52,6 +133,32 @@ impl ProcessData {
         self
     }
 
+    /// Replace the environment, without touching the one of a parent
+    /// that shares it
+    pub fn set_envs(&mut self, args: &ProgramArgs) {
+        let env = args.vars().map(|(k, v)| (k.into(), v.into())).collect();
+        self.env = Arc::new(RwLock::new(env));
+    }
+
+    /// Drop the handlers of the old program on exec
+    pub fn reset_signal_handlers(&mut self) {
+        let mut actions = self.signal_actions.read().clone();
+        actions.reset_handlers();
+        self.signal_actions = Arc::new(RwLock::new(actions));
+    }
+
+    pub fn signal_action(&self, signal: Signal) -> SigAction {
+        self.signal_actions.read().get(signal)
+    }
+
+    pub fn set_signal_action(&self, signal: Signal, action: SigAction) -> Option<SigAction> {
+        self.signal_actions.write().set(signal, action)
+    }
+
+    pub fn disposition(&self, signal: Signal) -> Disposition {
+        self.signal_actions.read().disposition(signal)
+    }
+
     #[inline]
     pub fn new_sem(&mut self, key: u32, value: usize) -> bool {
         self.semaphores.write().insert(key, value)
@@ -1,25 +1,25 @@
 use alloc::collections::BTreeSet;
-use storage::random::Random;
 
 use super::*;
 use crate::{
-    filesystem::cache_usage,
+    filesystem::{cache_usage, open_file},
     memory::{
         PAGE_SIZE,
         allocator::{ALLOCATOR, HEAP_SIZE},
         get_frame_alloc_for_sure,
         user::{USER_ALLOCATOR, USER_HEAP_SIZE},
     },
-    utils::humanized_size,
+    utils::{clock, humanized_size},
 };
-use alloc::{collections::BTreeMap, collections::VecDeque, format, sync::Weak};
+use alloc::{boxed::Box, collections::BTreeMap, format, sync::Weak};
 use spin::{Mutex, RwLock};
+use syscall_def::{SigHow, Signal};
 
 pub static PROCESS_MANAGER: spin::Once<ProcessManager> = spin::Once::new();
 
-pub fn init(init: Arc<Process>) {
+pub fn init(init: Arc<Process>, scheduler: Box<dyn Scheduler>) {
     processor::set_pid(init.pid());
-    PROCESS_MANAGER.call_once(|| ProcessManager::new(init));
+    PROCESS_MANAGER.call_once(|| ProcessManager::new(init, scheduler));
 }
 
 pub fn get_process_manager() -> &'static ProcessManager {
@@ -28,31 +28,55 @@ pub fn get_process_manager() -> &'static ProcessManager {
         .expect("Process Manager has not been initialized")
 }
 
+/// What `waitpid` found
+pub enum WaitStatus {
+    Exited(ProcessId, isize),
+    Stopped(ProcessId, Signal),
+    Running,
+    NoChild,
+}
+
 pub struct ProcessManager {
     processes: RwLock<BTreeMap<ProcessId, Arc<Process>>>,
-    ready_queue: Mutex<VecDeque<ProcessId>>,
+    scheduler: Mutex<Box<dyn Scheduler>>,
+    timers: Mutex<TimerWheel>,
     wait_queue: Mutex<BTreeMap<ProcessId, BTreeSet<ProcessId>>>,
+    /// Processes blocked in `waitpid` until one of their children exits
+    child_waiters: Mutex<BTreeSet<ProcessId>>,
+    /// Processes blocked on a pipe, by pipe id
+    pipe_waiters: Mutex<BTreeMap<usize, BTreeSet<ProcessId>>>,
+    /// Adopts the orphans, the kernel until it is set
+    init: spin::Once<ProcessId>,
 }
 
 impl ProcessManager {
-    pub fn new(init: Arc<Process>) -> Self {
+    pub fn new(init: Arc<Process>, mut scheduler: Box<dyn Scheduler>) -> Self {
         let mut processes = BTreeMap::new();
         let pid = init.pid();
+        scheduler.add(pid, init.read().nice());
         processes.insert(pid, init);
+
+        info!("Scheduler: {}", scheduler.name());
+
         Self {
             processes: RwLock::new(processes),
-            ready_queue: Mutex::new(VecDeque::new()),
+            scheduler: Mutex::new(scheduler),
+            timers: Mutex::new(TimerWheel::default()),
             wait_queue: Mutex::new(BTreeMap::new()),
+            child_waiters: Mutex::new(BTreeSet::new()),
+            pipe_waiters: Mutex::new(BTreeMap::new()),
+            init: spin::Once::new(),
         }
     }
 
     #[inline]
     pub fn push_ready(&self, pid: ProcessId) {
-        self.ready_queue.lock().push_back(pid);
+        self.scheduler.lock().push(pid);
     }
 
     #[inline]
     fn add_proc(&self, pid: ProcessId, proc: Arc<Process>) {
+        self.scheduler.lock().add(pid, proc.read().nice());
         self.processes.write().insert(pid, proc);
     }
 
@@ -61,6 +85,15 @@ impl ProcessManager {
         self.processes.read().get(pid).cloned()
     }
 
+    /// Every process still known to the manager, in pid order
+    pub(super) fn processes(&self) -> Vec<Arc<Process>> {
+        self.processes.read().values().cloned().collect()
+    }
+
+    pub(super) fn process(&self, pid: ProcessId) -> Option<Arc<Process>> {
+        self.get_proc(&pid)
+    }
+
     pub fn current(&self) -> Arc<Process> {
         self.get_proc(&processor::current_pid())
             .expect("No current process")
@@ -73,16 +106,146 @@ impl ProcessManager {
         entry.insert(processor::current_pid());
     }
 
+    /// Wake the current process up when one of its children exits
+    pub fn wait_children(&self) {
+        self.child_waiters.lock().insert(processor::current_pid());
+    }
+
     pub(super) fn get_exit_code(&self, pid: ProcessId) -> Option<isize> {
         self.get_proc(&pid).and_then(|p| p.read().exit_code())
     }
 
+    pub fn set_init(&self, pid: ProcessId) {
+        self.init.call_once(|| pid);
+
+        // not in the group of the kernel, which can't be signalled
+        if let Some(proc) = self.get_proc(&pid) {
+            proc.write().set_pgid(pid);
+        }
+    }
+
+    /// Collect a child of the current process that exited, `pid` or any;
+    /// with `untraced` a child that stopped is reported once too
+    pub fn wait_child(&self, pid: Option<ProcessId>, untraced: bool) -> WaitStatus {
+        // threads are joined, not waited for
+        let children: Vec<_> = self
+            .current()
+            .read()
+            .children()
+            .iter()
+            .filter(|c| pid.is_none_or(|pid| c.pid() == pid))
+            .cloned()
+            .collect();
+
+        let children: Vec<_> = children
+            .into_iter()
+            .filter(|c| c.tgid() == c.pid())
+            .collect();
+
+        if children.is_empty() {
+            return WaitStatus::NoChild;
+        }
+
+        for child in children {
+            let exit_code = child.read().exit_code();
+            if let Some(code) = exit_code {
+                self.reap(child.pid());
+                return WaitStatus::Exited(child.pid(), code);
+            }
+
+            if untraced && let Some(signal) = child.write().take_stop() {
+                return WaitStatus::Stopped(child.pid(), signal);
+            }
+        }
+
+        WaitStatus::Running
+    }
+
+    /// Forget a process that exited once its exit code is collected,
+    /// along with the threads it left behind, and free their pids
+    pub fn reap(&self, pid: ProcessId) {
+        let Some(proc) = self.get_proc(&pid) else {
+            return;
+        };
+
+        let parent = proc.read().parent();
+        if let Some(parent) = parent {
+            parent.write().remove_child(pid);
+        }
+
+        let mut processes = self.processes.write();
+
+        let reaped: Vec<_> = processes
+            .values()
+            .filter(|p| p.pid() == pid || p.tgid() == pid)
+            .filter(|p| p.read().status() == ProgramStatus::Dead)
+            .map(|p| p.pid())
+            .collect();
+
+        for pid in reaped {
+            trace!("Reap #{}", pid);
+            processes.remove(&pid);
+            pid.free();
+        }
+    }
+
+    /// Hand `orphans` over to init, which collects them when they exit
+    fn adopt(&self, orphans: Vec<Arc<Process>>) {
+        let init = self
+            .init
+            .get()
+            .and_then(|pid| self.get_proc(pid))
+            .filter(|init| init.read().status() != ProgramStatus::Dead)
+            .or_else(|| self.get_proc(&KERNEL_PID))
+            .expect("No process to adopt orphans");
+
+        let mut exited = false;
+
+        for child in orphans {
+            // threads of a process that is gone are reaped with it
+            if child.tgid() != child.pid() {
+                continue;
+            }
+
+            trace!("Process #{} adopts #{}", init.pid(), child.pid());
+
+            let mut inner = child.write();
+            inner.set_parent(Arc::downgrade(&init));
+            exited |= inner.status() == ProgramStatus::Dead;
+            drop(inner);
+
+            init.write().add_child(child);
+        }
+
+        if exited {
+            self.wake_child_waiter(init.pid());
+        }
+    }
+
+    /// Let `parent` collect a child if it waits for one
+    fn wake_child_waiter(&self, parent: ProcessId) {
+        if self.child_waiters.lock().remove(&parent) {
+            self.wake_up(parent, None);
+        }
+    }
+
+    /// Tell the parent of a process that exited or stopped, threads end
+    /// and stop quietly
+    fn notify_parent(&self, proc: &Process) {
+        let parent = proc.read().parent();
+        if proc.tgid() == proc.pid()
+            && let Some(parent) = parent
+        {
+            self.send_signal(parent.pid(), Signal::SIGCHLD);
+            self.wake_child_waiter(parent.pid());
+        }
+    }
+
     pub fn save_current(&self, context: &ProcessContext) -> ProcessId {
         let current = self.current();
         let pid = current.pid();
 
         let mut current = current.write();
-        current.tick();
         current.save(context);
 
         // debug!("Save process {} #{}", current.name(), pid);
@@ -93,9 +256,13 @@ impl ProcessManager {
     pub fn switch_next(&self, context: &mut ProcessContext) -> ProcessId {
         let mut pid = processor::current_pid();
 
-        while let Some(next) = self.ready_queue.lock().pop_front() {
+        while let Some(next) = self.scheduler.lock().pop() {
             let map = self.processes.read();
-            let proc = map.get(&next).expect("Process not found");
+
+            // reaped while it was queued
+            let Some(proc) = map.get(&next) else {
+                continue;
+            };
 
             if !proc.read().is_ready() {
                 debug!("Process #{} is {:?}", next, proc.read().status());
@@ -114,60 +281,88 @@ impl ProcessManager {
         pid
     }
 
-    pub fn open(&self, path: &str) -> Option<u8> {
-        let res = match path {
-            // as a special case, we can open "/dev/random" to get random numbers
-            "/dev/random" => Resource::Random(Random::new()),
-            _ => match get_rootfs().open_file(path) {
-                Ok(file) => Resource::File(file),
-                Err(_) => return None,
-            },
+    /// Charge a timer tick to the current process, true if it has to give
+    /// the CPU up
+    pub fn tick(&self, context: &ProcessContext) -> bool {
+        let expired = self.timers.lock().advance(clock::ticks());
+        for pid in expired {
+            self.wake_up(pid, Some(0));
+        }
+
+        let current = self.current();
+        current.write().tick(context.is_user());
+        self.scheduler.lock().tick(current.pid())
+    }
+
+    /// Set the nice value of `pid`, false if there is no such process
+    pub fn set_nice(&self, pid: ProcessId, nice: i8) -> bool {
+        let Some(proc) = self.get_proc(&pid) else {
+            return false;
         };
 
-        trace!("Opening {}...", path);
+        let mut inner = proc.write();
+        if inner.status() == ProgramStatus::Dead {
+            return false;
+        }
 
-        let fd = self.current().write().open(res);
+        inner.set_nice(nice);
+        self.scheduler.lock().set_nice(pid, inner.nice());
 
-        Some(fd)
+        true
     }
 
-    pub fn close(&self, fd: u8) -> bool {
-        if fd < 3 {
-            false // stdin, stdout, stderr are reserved
-        } else {
-            self.current().write().close(fd)
-        }
+    /// Open `path`, relative to the working directory of the current
+    /// process unless it is absolute
+    pub fn open(&self, path: &str, mode: FileMode, cloexec: bool) -> Result<Fd, Errno> {
+        let current = self.current();
+        let path = current.read().absolute_path(path);
+
+        let res = match open_file(&path, mode) {
+            Ok(file) => Resource::File(file, mode),
+            Err(e) => {
+                debug!("Failed to open {}: {:?}", path, e);
+                return Err(crate::filesystem::errno(&e));
+            }
+        };
+
+        trace!("Opening {}...", path);
+
+        current.write().open(res, cloexec)
+    }
+
+    pub fn close(&self, fd: Fd) -> bool {
+        self.current().write().close(fd)
     }
 
     #[inline]
-    pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize {
+    pub fn read(&self, fd: Fd, buf: &mut [u8]) -> Result<usize, Errno> {
         self.current().read().read(fd, buf)
     }
 
     #[inline]
-    pub fn write(&self, fd: u8, buf: &[u8]) -> isize {
+    pub fn write(&self, fd: Fd, buf: &[u8]) -> Result<usize, Errno> {
         self.current().read().write(fd, buf)
     }
 
+    #[inline]
+    pub fn seek(&self, fd: Fd, pos: SeekFrom) -> Result<usize, Errno> {
+        self.current().read().seek(fd, pos)
+    }
+
     pub fn spawn(
         &self,
         elf: &ElfFile,
         name: String,
         parent: Option<Weak<Process>>,
         proc_data: Option<ProcessData>,
+        args: &ProgramArgs,
     ) -> ProcessId {
-        let kproc = self.get_proc(&KERNEL_PID).unwrap();
-        let page_table = kproc.read().clone_page_table();
-        let proc_vm = Some(ProcessVm::new(page_table));
+        let proc_vm = Some(ProcessVm::new(self.kernel_page_table()));
         let proc = Process::new(name, parent, proc_vm, proc_data);
 
         let mut inner = proc.write();
         inner.pause();
-        inner.load_elf(elf);
-        inner.init_stack_frame(
-            VirtAddr::new_truncate(elf.header.pt2.entry_point()),
-            VirtAddr::new_truncate(super::stack::STACK_INIT_TOP),
-        );
+        inner.load_elf(elf, args);
         drop(inner);
 
         trace!("New {:#?}", &proc);
@@ -179,21 +374,274 @@ impl ProcessManager {
         pid
     }
 
+    /// Run another program in the current process
+    pub fn exec(
+        &self,
+        elf: &ElfFile,
+        name: String,
+        args: &ProgramArgs,
+        context: &mut ProcessContext,
+    ) {
+        let page_table = self.kernel_page_table();
+        let proc = self.current();
+
+        // the new program starts with a single thread
+        for tid in self.threads_of(proc.pid()) {
+            self.kill(tid, 0);
+            self.reap(tid);
+        }
+
+        proc.write().exec(name, elf, args, page_table, context);
+
+        debug!("Process #{} now runs {}", proc.pid(), proc.read().name());
+    }
+
+    /// A new page table with only the kernel mapped
+    fn kernel_page_table(&self) -> PageTableContext {
+        let kproc = self.get_proc(&KERNEL_PID).unwrap();
+        kproc.read().clone_page_table()
+    }
+
     pub fn fork(&self) {
-        let proc = self.current().fork();
+        let page_table = self.kernel_page_table();
+        let proc = self.current().fork(page_table);
         let pid = proc.pid();
         self.add_proc(pid, proc);
         self.push_ready(pid);
-        debug!("Current queue: {:?}", self.ready_queue.lock());
+        debug!("Current queue: {:?}", self.scheduler.lock().queued());
     }
 
+    /// Start a thread of the current process, see [`Process::spawn_thread`]
+    pub fn spawn_thread(
+        &self,
+        entry: VirtAddr,
+        arg: usize,
+        stack: Option<VirtAddr>,
+    ) -> Option<ProcessId> {
+        let thread = self.current().spawn_thread(entry, arg, stack)?;
+        let tid = thread.pid();
+        self.add_proc(tid, thread);
+        self.push_ready(tid);
+        Some(tid)
+    }
+
+    /// The other living threads of the process `pid` belongs to
+    pub fn threads_of(&self, pid: ProcessId) -> Vec<ProcessId> {
+        let tgid = match self.get_proc(&pid) {
+            Some(proc) => proc.tgid(),
+            None => return Vec::new(),
+        };
+
+        self.processes
+            .read()
+            .values()
+            .filter(|p| p.pid() != pid && p.tgid() == tgid)
+            .filter(|p| p.read().status() != ProgramStatus::Dead)
+            .map(|p| p.pid())
+            .collect()
+    }
+
+    /// Whether `tid` is another thread of the current process
+    pub fn is_thread(&self, tid: ProcessId) -> bool {
+        let current = self.current();
+        tid != current.pid()
+            && self
+                .get_proc(&tid)
+                .is_some_and(|t| t.tgid() == current.tgid())
+    }
+
+    /// Exit the current process, taking all of its threads along
     pub fn kill_self(&self, ret: isize) {
+        self.kill_process(processor::current_pid(), ret);
+    }
+
+    /// Kill `pid` and all the other threads of its process
+    pub fn kill_process(&self, pid: ProcessId, ret: isize) {
+        for tid in self.threads_of(pid) {
+            self.kill(tid, ret);
+        }
+
+        self.kill(pid, ret);
+    }
+
+    /// Send `signal` to `pid`, false if there is no such process
+    ///
+    /// A signal that terminates is carried out at once for other
+    /// processes, anything else waits until `pid` returns to user mode.
+    pub fn send_signal(&self, pid: ProcessId, signal: Signal) -> bool {
+        let Some(proc) = self.get_proc(&pid) else {
+            return false;
+        };
+
+        let mut inner = proc.write();
+        if inner.status() == ProgramStatus::Dead {
+            return false;
+        }
+
+        // a stop and a continue cancel each other out, and `SIGCONT`
+        // resumes the process whatever its action is
+        let continued = signal == Signal::SIGCONT && inner.cont();
+        if signal == Signal::SIGCONT {
+            inner.signals_mut().discard(STOP_SIGNALS);
+        } else if STOP_SIGNALS & signal.bit() != 0 {
+            inner.signals_mut().discard(Signal::SIGCONT.bit());
+        }
+
+        match inner.disposition(signal) {
+            Disposition::Ignore => drop(inner),
+            Disposition::Terminate
+                if !inner.signals().is_blocked(signal) && pid != processor::current_pid() =>
+            {
+                drop(inner);
+                self.kill_process(pid, exit_code(signal));
+            }
+            _ => {
+                inner.signals_mut().raise(signal);
+
+                // a sleep is cut short by a signal it has to take
+                let interrupts = !inner.signals().is_blocked(signal);
+                drop(inner);
+
+                if interrupts && self.timers.lock().cancel(pid) {
+                    self.wake_up(pid, Some(-1));
+                }
+            }
+        }
+
+        if continued {
+            debug!("Process #{} continued", pid);
+            self.wake_up(pid, None);
+        }
+
+        true
+    }
+
+    /// Send `signal` to every process in the group `pgid`, false if there
+    /// is none
+    pub fn send_group_signal(&self, pgid: ProcessId, signal: Signal) -> bool {
+        let members: Vec<_> = self
+            .processes
+            .read()
+            .values()
+            .filter(|p| p.tgid() == p.pid() && p.read().pgid() == pgid)
+            .map(|p| p.pid())
+            .collect();
+
+        let mut sent = false;
+        for pid in members {
+            sent |= self.send_signal(pid, signal);
+        }
+
+        sent
+    }
+
+    /// Move `pid` to the group `pgid`, it has to be the current process
+    /// or one of its children
+    pub fn set_pgid(&self, pid: ProcessId, pgid: ProcessId) -> bool {
+        let current = self.current();
+
+        let proc = match current.pid() == pid {
+            true => current.clone(),
+            false => match current.read().children().iter().find(|c| c.pid() == pid) {
+                Some(child) => child.clone(),
+                None => return false,
+            },
+        };
+
+        proc.write().set_pgid(pgid);
+        true
+    }
+
+    pub fn pgid(&self, pid: ProcessId) -> Option<ProcessId> {
+        self.get_proc(&pid).map(|p| p.read().pgid())
+    }
+
+    /// Deliver the pending signals of the current process before it
+    /// returns to user mode, switching away if it has to die
+    pub fn handle_signals(&self, context: &mut ProcessContext) {
+        if !context.is_user() {
+            return;
+        }
+
+        let proc = self.current();
+
+        // killed by another thread of its process
+        if proc.read().status() == ProgramStatus::Dead {
+            self.switch_next(context);
+            return;
+        }
+
+        loop {
+            let mut inner = proc.write();
+            let Some(signal) = inner.signals_mut().take() else {
+                return;
+            };
+
+            match inner.disposition(signal) {
+                Disposition::Ignore => continue,
+                Disposition::Terminate => {
+                    drop(inner);
+                    debug!("Process #{} terminated by {:?}", proc.pid(), signal);
+                    self.kill_process(proc.pid(), exit_code(signal));
+                    self.switch_next(context);
+                }
+                Disposition::Stop => {
+                    inner.stop(signal);
+                    drop(inner);
+                    debug!("Process #{} stopped by {:?}", proc.pid(), signal);
+
+                    // resumed by `SIGCONT`, with the rest of its signals
+                    // taken on the next return to user mode
+                    let pid = self.save_current(context);
+                    self.block(pid);
+                    self.notify_parent(&proc);
+                    self.switch_next(context);
+                }
+                Disposition::Handle(action) => {
+                    let signals = inner.signals_mut();
+                    let mask = signals.set_mask(SigHow::Block, action.mask | signal.bit());
+                    drop(inner);
+
+                    if !push_frame(context, signal, &action, mask) {
+                        self.kill_process(proc.pid(), exit_code(Signal::SIGSEGV));
+                        self.switch_next(context);
+                    }
+                }
+            }
+
+            return;
+        }
+    }
+
+    /// Return from a signal handler to the code it interrupted
+    pub fn sigreturn(&self, context: &mut ProcessContext) {
+        let proc = self.current();
+
+        match pop_frame(context) {
+            Some(frame) if context.restore_user(&frame.context) => {
+                proc.write()
+                    .signals_mut()
+                    .set_mask(SigHow::SetMask, frame.mask);
+            }
+            _ => {
+                warn!("Process #{} returned from a bad signal frame", proc.pid());
+                self.kill_process(proc.pid(), exit_code(Signal::SIGSEGV));
+                self.switch_next(context);
+            }
+        }
+    }
+
+    /// Exit only the current thread
+    pub fn kill_thread(&self, ret: isize) {
         self.kill(processor::current_pid(), ret);
     }
 
     pub fn wake_up(&self, pid: ProcessId, ret: Option<isize>) {
         if let Some(proc) = self.get_proc(&pid) {
             let mut inner = proc.write();
+            if inner.status() == ProgramStatus::Dead || inner.is_stopped() {
+                return;
+            }
             if let Some(ret) = ret {
                 inner.set_return(ret as usize);
             }
@@ -202,6 +650,38 @@ impl ProcessManager {
         }
     }
 
+    /// Block the current process in a syscall on `fd` that would block,
+    /// it runs the syscall again once the pipe is read or written
+    pub fn wait_pipe(&self, fd: Fd, context: &mut ProcessContext) -> bool {
+        let Some(id) = self.current().read().pipe_id(fd) else {
+            return false;
+        };
+
+        context.restart_syscall();
+        let pid = self.save_current(context);
+        self.pipe_waiters.lock().entry(id).or_default().insert(pid);
+        self.block(pid);
+        self.switch_next(context);
+
+        true
+    }
+
+    /// Wake up the processes blocked on the pipe `id`
+    pub fn wake_pipe(&self, id: usize) {
+        let waiters = self.pipe_waiters.lock().remove(&id);
+        for pid in waiters.into_iter().flatten() {
+            self.wake_up(pid, None);
+        }
+    }
+
+    /// Block the current process for `ticks` timer ticks
+    pub fn sleep(&self, ticks: u64, context: &mut ProcessContext) {
+        let pid = self.save_current(context);
+        self.timers.lock().insert(clock::ticks() + ticks, pid);
+        self.block(pid);
+        self.switch_next(context);
+    }
+
     pub fn block(&self, pid: ProcessId) {
         if let Some(proc) = self.get_proc(&pid) {
             proc.write().block();
@@ -209,7 +689,13 @@ impl ProcessManager {
     }
 
     pub fn handle_page_fault(&self, addr: VirtAddr, err_code: PageFaultErrorCode) -> bool {
-        if !err_code.contains(PageFaultErrorCode::PROTECTION_VIOLATION) {
+        if err_code.contains(PageFaultErrorCode::PROTECTION_VIOLATION) {
+            // the kernel may be writing to a user buffer of the locked
+            // process, so only take it for reading
+            self.current()
+                .read()
+                .handle_protection_fault(addr, err_code)
+        } else {
             let cur_proc = self.current();
             trace!(
                 "Page Fault! Checking if {:#x} is on current process's stack",
@@ -222,8 +708,6 @@ impl ProcessManager {
 
             let mut inner = cur_proc.write();
             inner.handle_page_fault(addr)
-        } else {
-            false
         }
     }
 
@@ -239,23 +723,41 @@ impl ProcessManager {
 
         trace!("Kill {:#?}", &proc);
 
-        proc.kill(ret);
+        // closing its pipes wakes the waiters up, it must not be one of them
+        self.pipe_waiters.lock().retain(|_, waiters| {
+            waiters.remove(&pid);
+            !waiters.is_empty()
+        });
 
+        let orphans = proc.kill(ret);
+        self.scheduler.lock().remove(pid);
+
+        // nothing may wake the pid up once it is reused
+        self.timers.lock().cancel(pid);
+        self.child_waiters.lock().remove(&pid);
+        for waiters in self.wait_queue.lock().values_mut() {
+            waiters.remove(&pid);
+        }
+
+        self.adopt(orphans);
+        self.notify_parent(&proc);
+
+        // the waiters enter `waitpid` or `thread_join` again
         if let Some(pids) = self.wait_queue.lock().remove(&pid) {
             for p in pids {
-                self.wake_up(p, Some(ret));
+                self.wake_up(p, None);
             }
         }
     }
 
     pub fn print_process_list(&self) {
         let mut output =
-            String::from("  PID | PPID | Process Name |  Ticks  |   Memory  | Status\n");
+            String::from("  PID | PPID | Process Name |  NI |  Ticks  |   Memory  | Status\n");
 
+        // exited ones are kept until they are waited for
         self.processes
             .read()
             .values()
-            .filter(|p| p.read().status() != ProgramStatus::Dead)
             .for_each(|p| output += format!("{}\n", p).as_str());
 
         let heap_used = ALLOCATOR.lock().used();
@@ -282,7 +784,9 @@ impl ProcessManager {
 
         output += &format_res_usage("Cache", cache_used, cache_total);
 
-        output += format!("Queue  : {:?}\n", self.ready_queue.lock()).as_str();
+        let scheduler = self.scheduler.lock();
+        output += format!("Queue  : {:?} ({})\n", scheduler.queued(), scheduler.name()).as_str();
+        drop(scheduler);
 
         output += &processor::print_processors();
 
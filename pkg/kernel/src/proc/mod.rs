@@ -1,3 +1,4 @@
+mod args;
 mod context;
 mod data;
 mod manager;
@@ -5,25 +6,38 @@ mod paging;
 mod pid;
 mod process;
 mod processor;
+mod procfs;
+mod sched;
+mod signal;
 mod sync;
+mod timer;
 mod vm;
 
 use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
 use manager::*;
 use process::*;
-use storage::FileSystem;
+use sched::*;
+use storage::{FileSystem, SeekFrom};
 use sync::*;
+use syscall_def::{ChildStatus, Errno, Fd, FileMode, FileStat, SigAction, SigHow, Signal};
+use timer::*;
 
+pub use args::*;
 pub use context::ProcessContext;
 pub use data::ProcessData;
 pub use paging::PageTableContext;
 pub use pid::ProcessId;
+pub use procfs::ProcFs;
+pub use sched::{NICE_MAX, NICE_MIN};
+pub use signal::*;
 pub use vm::*;
 use xmas_elf::ElfFile;
 
 use crate::Resource;
-use crate::filesystem::get_rootfs;
+use crate::utils::cmdline::get_cmdline;
+use crate::vfs::get_vfs;
 use alloc::string::{String, ToString};
 use x86_64::VirtAddr;
 use x86_64::structures::idt::PageFaultErrorCode;
@@ -48,11 +62,25 @@ pub fn init(boot_info: &'static boot::BootInfo) {
     let kproc = Process::new(String::from("kernel"), None, Some(proc_vm), None);
 
     kproc.write().resume();
-    manager::init(kproc);
+    manager::init(kproc, sched::from_name(get_cmdline().sched));
 
     info!("Process Manager Initialized.");
 }
 
+/// Charge a timer tick to the current process and switch away once its
+/// time slice is over
+pub fn tick(context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        if manager.tick(context) {
+            let pid = manager.save_current(context);
+            manager.push_ready(pid);
+            manager.switch_next(context);
+        }
+    });
+}
+
+/// Give the CPU up to the next ready process
 pub fn switch(context: &mut ProcessContext) {
     x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
@@ -82,64 +110,359 @@ pub fn process_exit(ret: isize, context: &mut ProcessContext) {
     })
 }
 
-pub fn wait_pid(pid: ProcessId, context: &mut ProcessContext) {
+pub fn thread_exit(ret: isize, context: &mut ProcessContext) {
     x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
-        if let Some(ret) = manager.get_exit_code(pid) {
+        manager.kill_thread(ret);
+        manager.switch_next(context);
+    })
+}
+
+pub fn spawn_thread(entry: VirtAddr, arg: usize, stack: Option<VirtAddr>) -> Option<ProcessId> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().spawn_thread(entry, arg, stack)
+    })
+}
+
+/// Wait for another thread of the process, false if `tid` is not one
+pub fn thread_join(tid: ProcessId, context: &mut ProcessContext) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        if !manager.is_thread(tid) {
+            return false;
+        }
+
+        if let Some(ret) = manager.get_exit_code(tid) {
+            manager.reap(tid);
             context.set_rax(ret as usize);
         } else {
-            manager.wait_pid(pid);
-            manager.save_current(context);
-            manager.current().write().block();
+            // joins again once the thread exits
+            context.restart_syscall();
+            manager.wait_pid(tid);
+            let pid = manager.save_current(context);
+            manager.block(pid);
             manager.switch_next(context);
         }
+
+        true
     })
 }
 
+/// Collect a child that exited, `pid` or any, or with `untraced` one that
+/// stopped, and store how it ended up in the user buffer `status`; rax
+/// gets its pid, or 0 if none did yet with `nohang`, or -1 if there is no
+/// such child
+pub fn wait_pid(
+    pid: Option<ProcessId>,
+    status: Option<&mut [u8]>,
+    nohang: bool,
+    untraced: bool,
+    context: &mut ProcessContext,
+) {
+    let found = x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        match manager.wait_child(pid, untraced) {
+            WaitStatus::Running if !nohang => {
+                // waits again once a child exits
+                context.restart_syscall();
+                manager.wait_children();
+                let pid = manager.save_current(context);
+                manager.block(pid);
+                manager.switch_next(context);
+                None
+            }
+            found => Some(found),
+        }
+    });
+
+    let (child, child_status) = match found {
+        Some(WaitStatus::Exited(child, code)) => (child, ChildStatus { code, stopped: 0 }),
+        Some(WaitStatus::Stopped(child, signal)) => (
+            child,
+            ChildStatus {
+                code: 0,
+                stopped: signal as u8,
+            },
+        ),
+        Some(WaitStatus::Running) => return context.set_rax(0),
+        Some(WaitStatus::NoChild) => return context.set_rax(usize::MAX),
+        None => return,
+    };
+
+    // written with the lock released, it may be copy-on-write, and the
+    // user pointer need not be aligned
+    if let Some(status) = status.filter(|buf| buf.len() >= size_of::<ChildStatus>()) {
+        unsafe { (status.as_mut_ptr() as *mut ChildStatus).write_unaligned(child_status) };
+    }
+    context.set_rax(child.0 as usize);
+}
+
+/// Make `pid` the process that adopts orphans
+pub fn set_init(pid: ProcessId) {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().set_init(pid))
+}
+
 pub(crate) fn wait_no_block(pid: ProcessId) -> Option<isize> {
     x86_64::instructions::interrupts::without_interrupts(|| {
         get_process_manager().get_exit_code(pid)
     })
 }
 
-pub fn read(fd: u8, buf: &mut [u8]) -> isize {
-    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().read(fd, buf))
+/// Block the current process for `ns` nanoseconds, it returns 0 then or
+/// -1 if a signal comes first
+pub fn sleep(ns: u64, context: &mut ProcessContext) {
+    let ticks = crate::utils::clock::ticks_for(ns);
+    if ticks == 0 {
+        context.set_rax(0);
+        return;
+    }
+
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().sleep(ticks, context)
+    })
 }
 
-pub fn write(fd: u8, buf: &[u8]) -> isize {
-    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().write(fd, buf))
+/// Nice value of `pid`, None if there is no such process
+pub fn nice(pid: ProcessId) -> Option<i8> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager()
+            .process(pid)
+            .filter(|p| p.read().status() != ProgramStatus::Dead)
+            .map(|p| p.read().nice())
+    })
 }
 
-pub fn open(path: &str) -> Option<u8> {
-    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().open(path))
+/// Set the nice value of `pid`, false if there is no such process
+pub fn set_nice(pid: ProcessId, nice: i8) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_nice(pid, nice)
+    })
 }
 
-pub fn close(fd: u8) -> bool {
+/// Whether `pid` exists and has not exited
+pub fn is_alive(pid: ProcessId) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager()
+            .process(pid)
+            .is_some_and(|p| p.read().status() != ProgramStatus::Dead)
+    })
+}
+
+/// Read from `fd`, waiting while it is an empty pipe; rax gets the count
+/// or -errno
+pub fn read(fd: Fd, buf: &mut [u8], context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        match manager.read(fd, buf) {
+            Err(Errno::EAGAIN) if manager.wait_pipe(fd, context) => (),
+            Ok(count) => context.set_rax(count),
+            Err(errno) => context.set_rax(errno.as_ret()),
+        }
+    })
+}
+
+/// Write to `fd`, waiting while it is a full pipe; rax gets the count or
+/// -errno, and writing a pipe nobody reads raises `SIGPIPE`
+pub fn write(fd: Fd, buf: &[u8], context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        match manager.write(fd, buf) {
+            Err(Errno::EAGAIN) if manager.wait_pipe(fd, context) => (),
+            Ok(count) => context.set_rax(count),
+            Err(errno) => {
+                if errno == Errno::EPIPE {
+                    manager.send_signal(processor::current_pid(), Signal::SIGPIPE);
+                }
+                context.set_rax(errno.as_ret());
+            }
+        }
+    })
+}
+
+/// A new pipe in the fds of the current process, as (read, write)
+pub fn pipe(cloexec: bool) -> Result<(Fd, Fd), Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let current = get_process_manager().current();
+        let mut inner = current.write();
+        let (read_end, write_end) = crate::utils::pipe::pipe();
+
+        let read_fd = inner.open(Resource::Pipe(read_end), cloexec)?;
+        match inner.open(Resource::Pipe(write_end), cloexec) {
+            Ok(write_fd) => Ok((read_fd, write_fd)),
+            Err(errno) => {
+                inner.close(read_fd);
+                Err(errno)
+            }
+        }
+    })
+}
+
+/// Let the processes blocked on the pipe `id` try again
+pub fn wake_pipe(id: usize) {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().wake_pipe(id))
+}
+
+pub fn seek(fd: Fd, pos: SeekFrom) -> Result<usize, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().seek(fd, pos))
+}
+
+pub fn open(path: &str, mode: FileMode, cloexec: bool) -> Result<Fd, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().open(path, mode, cloexec)
+    })
+}
+
+pub fn close(fd: Fd) -> bool {
     x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().close(fd))
 }
 
+pub fn dup(fd: Fd) -> Result<Fd, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().write().dup(fd)
+    })
+}
+
+pub fn dup2(fd: Fd, new_fd: Fd) -> Result<Fd, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().write().dup2(fd, new_fd)
+    })
+}
+
+pub fn cloexec(fd: Fd) -> Result<bool, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().cloexec(fd)
+    })
+}
+
+pub fn set_cloexec(fd: Fd, cloexec: bool) -> Result<(), Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager()
+            .current()
+            .write()
+            .set_cloexec(fd, cloexec)
+    })
+}
+
+pub fn fstat(fd: Fd) -> Result<FileStat, Errno> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().fstat(fd)
+    })
+}
+
+/// `path` made absolute against the working directory of the current
+/// process
+pub fn absolute_path(path: &str) -> String {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().absolute_path(path)
+    })
+}
+
+pub fn cwd() -> String {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().cwd()
+    })
+}
+
+/// Move the current process to the directory at `path`
+pub fn chdir(path: &str) -> Result<(), Errno> {
+    let path = absolute_path(path);
+
+    let meta = get_vfs()
+        .metadata(&path)
+        .map_err(|e| crate::filesystem::errno(&e))?;
+    if !meta.is_dir() {
+        return Err(Errno::ENOTDIR);
+    }
+
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().set_cwd(path)
+    });
+
+    Ok(())
+}
+
 pub fn current_pid() -> ProcessId {
     x86_64::instructions::interrupts::without_interrupts(processor::current_pid)
 }
 
+pub fn parent_pid() -> Option<ProcessId> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let parent = get_process_manager().current().read().parent();
+        parent.map(|parent| parent.pid())
+    })
+}
+
 pub fn brk(addr: Option<usize>) -> usize {
     x86_64::instructions::interrupts::without_interrupts(|| {
         get_process_manager().current().read().brk(addr)
     })
 }
 
-pub fn kill(pid: ProcessId, context: &mut ProcessContext) {
+/// Send `signal` to `pid`, false if there is no such process
+pub fn send_signal(pid: ProcessId, signal: Signal) -> bool {
     x86_64::instructions::interrupts::without_interrupts(|| {
-        let manager = get_process_manager();
-        if pid == processor::current_pid() {
-            manager.kill_self(0xdead);
-            manager.switch_next(context);
-        } else {
-            manager.kill(pid, 0xdead);
+        get_process_manager().send_signal(pid, signal)
+    })
+}
+
+pub fn send_group_signal(pgid: ProcessId, signal: Signal) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().send_group_signal(pgid, signal)
+    })
+}
+
+/// Send `signal` to the foreground group, false if there is none
+pub fn interrupt_foreground(signal: Signal) -> bool {
+    foreground().is_some_and(|pgid| send_group_signal(pgid, signal))
+}
+
+/// Move `pid` to the group `pgid`, the current process or a child of it
+pub fn set_pgid(pid: ProcessId, pgid: ProcessId) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_pgid(pid, pgid)
+    })
+}
+
+pub fn pgid(pid: ProcessId) -> Option<ProcessId> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().pgid(pid))
+}
+
+pub fn handle_signals(context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().handle_signals(context)
+    })
+}
+
+/// Set the action of `signal` if given, returning the old one or None
+/// if the action can't be changed
+pub fn sigaction(signal: Signal, action: Option<SigAction>) -> Option<SigAction> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let current = get_process_manager().current();
+        let inner = current.read();
+        match action {
+            Some(action) => inner.set_signal_action(signal, action),
+            None => Some(inner.signal_action(signal)),
         }
     })
 }
 
+/// Change the signal mask of the current thread, returning the old one
+pub fn sigprocmask(how: SigHow, set: u64) -> u64 {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager()
+            .current()
+            .write()
+            .signals_mut()
+            .set_mask(how, set)
+    })
+}
+
+pub fn sigreturn(context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().sigreturn(context)
+    })
+}
+
 pub fn new_sem(key: u32, value: usize) -> usize {
     x86_64::instructions::interrupts::without_interrupts(|| {
         if get_process_manager().current().write().new_sem(key, value) {
@@ -191,21 +514,23 @@ pub fn sem_wait(key: u32, context: &mut ProcessContext) {
     })
 }
 
-pub fn spawn(name: String, file_buffer: Vec<u8>) -> Result<ProcessId, String> {
+pub fn spawn(name: String, file_buffer: Vec<u8>, args: &ProgramArgs) -> Result<ProcessId, String> {
     let elf = xmas_elf::ElfFile::new(&file_buffer).map_err(|e| e.to_string())?;
 
-    let pid = elf_spawn(name, &elf)?;
+    let pid = elf_spawn(name, &elf, args)?;
 
     Ok(pid)
 }
 
-pub fn elf_spawn(name: String, elf: &ElfFile) -> Result<ProcessId, String> {
+pub fn elf_spawn(name: String, elf: &ElfFile, args: &ProgramArgs) -> Result<ProcessId, String> {
     let pid = x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
         let process_name = name.to_lowercase();
 
-        let parent = Arc::downgrade(&manager.current());
-        let pid = manager.spawn(elf, name, Some(parent), None);
+        let current = manager.current();
+        let proc_data = current.read().spawn();
+        let parent = Arc::downgrade(&current);
+        let pid = manager.spawn(elf, name, Some(parent), Some(proc_data), args);
 
         debug!("Spawned process: {}#{}", process_name, pid);
         pid
@@ -214,24 +539,60 @@ pub fn elf_spawn(name: String, elf: &ElfFile) -> Result<ProcessId, String> {
     Ok(pid)
 }
 
-pub fn fs_spawn(path: &str) -> Option<ProcessId> {
-    let handle = get_rootfs().open_file(path);
-
-    if let Err(e) = handle {
-        warn!("fs_spawn: file error: {}, err: {:?}", path, e);
-        return None;
-    }
-
-    let mut handle = handle.unwrap();
+/// Read a program from the filesystem, returning its file name
+fn read_program(path: &str) -> Option<(String, Vec<u8>)> {
+    let mut handle = match get_vfs().open_file(path) {
+        Ok(handle) => handle,
+        Err(e) => {
+            warn!("read_program: file error: {}, err: {:?}", path, e);
+            return None;
+        }
+    };
 
     let mut file_buffer = Vec::new();
 
     if let Err(e) = handle.read_all(&mut file_buffer) {
-        warn!("fs_spawn: failed to read file: {}, err: {:?}", path, e);
+        warn!("read_program: failed to read file: {}, err: {:?}", path, e);
         return None;
     }
 
-    match spawn(handle.meta.name, file_buffer) {
+    Some((handle.meta.name, file_buffer))
+}
+
+/// A program to run and its arguments, `path` is relative to the working
+/// directory. A script starting with `#!` runs the interpreter named
+/// there, with the path of the script after the argument the line may
+/// give it.
+fn load_program(path: &str, args: &ProgramArgs) -> Option<(String, Vec<u8>, ProgramArgs)> {
+    let path = absolute_path(path);
+    let (name, file_buffer) = read_program(&path)?;
+
+    let Some(rest) = file_buffer.strip_prefix(b"#!") else {
+        return Some((name, file_buffer, args.clone()));
+    };
+
+    let line = rest.split(|&b| b == b'\n').next().unwrap_or_default();
+    let line = core::str::from_utf8(line).ok()?.trim();
+    let (interpreter, arg) = match line.split_once(char::is_whitespace) {
+        Some((interpreter, arg)) => (interpreter, Some(arg.trim())),
+        None => (line, None),
+    };
+
+    let mut argv = vec![interpreter.to_string()];
+    argv.extend(arg.map(String::from));
+    argv.push(path);
+    argv.extend(args.argv.iter().skip(1).cloned());
+
+    // named after the script, as it is what runs
+    let (_, file_buffer) = read_program(&absolute_path(interpreter))?;
+
+    Some((name, file_buffer, ProgramArgs::new(argv, args.envp.clone())))
+}
+
+pub fn fs_spawn(path: &str, args: &ProgramArgs) -> Option<ProcessId> {
+    let (name, file_buffer, args) = load_program(path, args)?;
+
+    match spawn(name, file_buffer, &args) {
         Ok(pid) => Some(pid),
         Err(e) => {
             warn!("fs_spawn: failed to spawn process: {}, {}", path, e);
@@ -240,6 +601,28 @@ pub fn fs_spawn(path: &str) -> Option<ProcessId> {
     }
 }
 
+/// Replace the current program with the one at `path`, only returns if
+/// it can't be loaded
+pub fn exec(path: &str, args: &ProgramArgs, context: &mut ProcessContext) -> bool {
+    let Some((name, file_buffer, args)) = load_program(path, args) else {
+        return false;
+    };
+
+    let elf = match xmas_elf::ElfFile::new(&file_buffer) {
+        Ok(elf) => elf,
+        Err(e) => {
+            warn!("exec: invalid program: {}, {}", path, e);
+            return false;
+        }
+    };
+
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().exec(&elf, name, &args, context)
+    });
+
+    true
+}
+
 pub fn fork(context: &mut ProcessContext) {
     x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
@@ -61,8 +61,8 @@ impl PageTableContext {
         unsafe { Cr3::write(self.reg.addr, self.reg.flags) }
     }
 
-    pub fn fork(&self) -> Self {
-        // forked process shares the page table
+    /// The same page table, for a thread of the process
+    pub fn share(&self) -> Self {
         Self {
             reg: self.reg.clone(),
         }
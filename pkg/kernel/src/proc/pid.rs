@@ -1,12 +1,40 @@
-use core::sync::atomic::{AtomicU16, Ordering};
+use alloc::collections::BTreeSet;
+use spin::Mutex;
+
+/// Pids in use, a pid is only handed out again once its process is reaped
+static PIDS: Mutex<PidAllocator> = Mutex::new(PidAllocator {
+    next: 1, // pid 0 is reserved for no process
+    used: BTreeSet::new(),
+});
+
+struct PidAllocator {
+    next: u16,
+    used: BTreeSet<u16>,
+}
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ProcessId(pub u16);
 
 impl ProcessId {
+    /// The next free pid, counting up so a freed pid is not reused soon
     pub fn new() -> Self {
-        static NEXT_PID: AtomicU16 = AtomicU16::new(1); // pid 0 is reserved for no process
-        ProcessId(NEXT_PID.fetch_add(1, Ordering::Relaxed))
+        let mut pids = PIDS.lock();
+
+        for _ in 0..u16::MAX {
+            let pid = pids.next;
+            pids.next = pids.next.checked_add(1).unwrap_or(1);
+
+            if pids.used.insert(pid) {
+                return ProcessId(pid);
+            }
+        }
+
+        panic!("Out of pids");
+    }
+
+    /// Give the pid back once nothing refers to its process anymore
+    pub fn free(self) {
+        PIDS.lock().used.remove(&self.0);
     }
 }
 
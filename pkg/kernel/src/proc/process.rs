@@ -1,6 +1,6 @@
 use super::*;
 use crate::humanized_size;
-use alloc::sync::Weak;
+use alloc::{format, sync::Weak};
 use spin::*;
 
 #[derive(Clone)]
@@ -12,11 +12,23 @@ pub struct Process {
 pub struct ProcessInner {
     name: String,
     parent: Option<Weak<Process>>,
+    // main thread of the process, for the other threads
+    leader: Option<ProcessId>,
     children: Vec<Arc<Process>>,
-    ticks_passed: usize,
+    /// Process group, which job control signals are sent to
+    pgid: ProcessId,
+    /// Ticks spent in user and in kernel mode
+    utime: usize,
+    stime: usize,
+    nice: i8,
     status: ProgramStatus,
     context: ProcessContext,
     exit_code: Option<isize>,
+    signals: SignalState,
+    /// Signal that stopped the process, until `SIGCONT` comes
+    stopped: Option<Signal>,
+    /// The stop was reported to `waitpid` already
+    stop_reported: bool,
     proc_data: Option<ProcessData>,
     proc_vm: Option<ProcessVm>,
 }
@@ -49,13 +61,26 @@ impl Process {
         let pid = ProcessId::new();
         let proc_vm = proc_vm.unwrap_or_else(|| ProcessVm::new(PageTableContext::new()));
 
+        // in the group of the parent, until it moves to another
+        let pgid = parent
+            .as_ref()
+            .and_then(Weak::upgrade)
+            .map_or(pid, |parent| parent.read().pgid());
+
         let inner = ProcessInner {
             name,
             parent,
+            leader: None,
+            pgid,
             status: ProgramStatus::Ready,
             context: ProcessContext::default(),
-            ticks_passed: 0,
+            utime: 0,
+            stime: 0,
+            nice: 0,
             exit_code: None,
+            signals: SignalState::default(),
+            stopped: None,
+            stop_reported: false,
             children: Vec::new(),
             proc_vm: Some(proc_vm),
             proc_data: Some(proc_data.unwrap_or_default()),
@@ -70,11 +95,11 @@ impl Process {
         })
     }
 
-    pub fn fork(self: &Arc<Self>) -> Arc<Self> {
+    pub fn fork(self: &Arc<Self>, page_table: PageTableContext) -> Arc<Self> {
         let mut inner = self.write();
 
         // create new process
-        let child_inner = inner.fork(Arc::downgrade(self));
+        let child_inner = inner.fork(Arc::downgrade(self), page_table);
         let child_pid = ProcessId::new();
 
         debug!(
@@ -99,7 +124,49 @@ impl Process {
         child
     }
 
-    pub fn kill(&self, ret: isize) {
+    /// Start a thread of this process at `entry` with `arg`, on `stack`
+    /// or a stack slot of its own
+    pub fn spawn_thread(
+        self: &Arc<Self>,
+        entry: VirtAddr,
+        arg: usize,
+        stack: Option<VirtAddr>,
+    ) -> Option<Arc<Self>> {
+        let mut inner = self.write();
+
+        let thread_inner = inner.thread(
+            Arc::downgrade(self),
+            self.tgid_of(&inner),
+            entry,
+            arg,
+            stack,
+        )?;
+        let tid = ProcessId::new();
+
+        debug!("Thread {}#{} started #{}.", inner.name, self.pid, tid);
+
+        let thread = Arc::new(Self {
+            pid: tid,
+            inner: Arc::new(RwLock::new(thread_inner)),
+        });
+
+        inner.add_child(thread.clone());
+
+        Some(thread)
+    }
+
+    /// Pid of the main thread of the process this thread belongs to
+    pub fn tgid(&self) -> ProcessId {
+        self.tgid_of(&self.read())
+    }
+
+    fn tgid_of(&self, inner: &ProcessInner) -> ProcessId {
+        inner.leader.unwrap_or(self.pid)
+    }
+
+    /// Release the resources of the process and keep its exit code for
+    /// the parent, returning the children it leaves behind
+    pub fn kill(&self, ret: isize) -> Vec<Arc<Process>> {
         let mut inner = self.inner.write();
 
         debug!(
@@ -109,7 +176,7 @@ impl Process {
             ret
         );
 
-        inner.kill(self.pid, ret);
+        inner.kill(ret)
     }
 }
 
@@ -118,8 +185,39 @@ impl ProcessInner {
         &self.name
     }
 
-    pub fn tick(&mut self) {
-        self.ticks_passed += 1;
+    /// Charge a timer tick to user or kernel mode
+    pub fn tick(&mut self, user: bool) {
+        if user {
+            self.utime += 1;
+        } else {
+            self.stime += 1;
+        }
+    }
+
+    pub fn ticks(&self) -> usize {
+        self.utime + self.stime
+    }
+
+    /// Ticks spent in user and in kernel mode
+    pub fn cpu_time(&self) -> (usize, usize) {
+        (self.utime, self.stime)
+    }
+
+    pub fn nice(&self) -> i8 {
+        self.nice
+    }
+
+    pub fn set_nice(&mut self, nice: i8) {
+        self.nice = nice.clamp(NICE_MIN, NICE_MAX);
+    }
+
+    /// Memory used by the process, 0 once its memory is released
+    pub fn memory_usage(&self) -> u64 {
+        self.proc_vm.as_ref().map_or(0, |vm| vm.memory_usage())
+    }
+
+    pub fn has_vm(&self) -> bool {
+        self.proc_vm.is_some()
     }
 
     pub fn status(&self) -> ProgramStatus {
@@ -142,6 +240,40 @@ impl ProcessInner {
         self.status == ProgramStatus::Ready
     }
 
+    pub fn pgid(&self) -> ProcessId {
+        self.pgid
+    }
+
+    pub fn set_pgid(&mut self, pgid: ProcessId) {
+        self.pgid = pgid;
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.is_some()
+    }
+
+    /// Stop the process until `SIGCONT` comes, it has to be blocked too
+    pub fn stop(&mut self, signal: Signal) {
+        self.stopped = Some(signal);
+        self.stop_reported = false;
+    }
+
+    /// Let a stopped process go on, false if it was not stopped
+    pub fn cont(&mut self) -> bool {
+        self.stopped.take().is_some()
+    }
+
+    /// The signal that stopped the process, once for `waitpid`
+    pub fn take_stop(&mut self) -> Option<Signal> {
+        match self.stopped {
+            Some(signal) if !self.stop_reported => {
+                self.stop_reported = true;
+                Some(signal)
+            }
+            _ => None,
+        }
+    }
+
     pub fn exit_code(&self) -> Option<isize> {
         self.exit_code
     }
@@ -158,12 +290,53 @@ impl ProcessInner {
         self.vm_mut().handle_page_fault(addr)
     }
 
+    pub fn handle_protection_fault(&self, addr: VirtAddr, err_code: PageFaultErrorCode) -> bool {
+        self.vm().handle_protection_fault(addr, err_code)
+    }
+
     pub fn clone_page_table(&self) -> PageTableContext {
         self.vm().page_table.clone_level_4()
     }
 
-    pub fn load_elf(&mut self, elf: &ElfFile) {
-        self.vm_mut().load_elf(elf)
+    /// Load the program and its arguments, ready to run from its entry
+    pub fn load_elf(&mut self, elf: &ElfFile, args: &ProgramArgs) {
+        let stack_top = self.vm_mut().load_elf(elf, args);
+
+        self.context = ProcessContext::default();
+        self.context.init_stack_frame(
+            VirtAddr::new_truncate(elf.header.pt2.entry_point()),
+            stack_top,
+        );
+        self.set_envs(args);
+    }
+
+    /// Replace the program of the running process, its old memory is
+    /// released once the new page table is loaded
+    pub fn exec(
+        &mut self,
+        name: String,
+        elf: &ElfFile,
+        args: &ProgramArgs,
+        page_table: PageTableContext,
+        context: &mut ProcessContext,
+    ) {
+        let old_vm = self.proc_vm.replace(ProcessVm::new(page_table));
+        self.load_elf(elf, args);
+        self.vm().page_table.load();
+        drop(old_vm);
+
+        self.name = name.to_ascii_lowercase();
+        self.reset_signal_handlers();
+        self.close_on_exec();
+        self.context.restore(context);
+    }
+
+    pub fn signals(&self) -> &SignalState {
+        &self.signals
+    }
+
+    pub fn signals_mut(&mut self) -> &mut SignalState {
+        &mut self.signals
     }
 
     pub fn set_return(&mut self, ret: usize) {
@@ -185,10 +358,6 @@ impl ProcessInner {
         self.status = ProgramStatus::Running;
     }
 
-    pub fn init_stack_frame(&mut self, entry: VirtAddr, stack_top: VirtAddr) {
-        self.context.init_stack_frame(entry, stack_top)
-    }
-
     pub fn add_child(&mut self, child: Arc<Process>) {
         self.children.push(child);
     }
@@ -216,14 +385,12 @@ impl ProcessInner {
         }
     }
 
-    pub fn fork(&mut self, parent: Weak<Process>) -> ProcessInner {
-        let new_vm = self.vm().fork(self.children.len() as u64 + 1);
-        let offset = new_vm.stack.stack_offset(&self.vm().stack);
+    pub fn fork(&mut self, parent: Weak<Process>, page_table: PageTableContext) -> ProcessInner {
+        let new_vm = self.vm().fork(page_table);
 
-        // make new stack frame
+        // the child continues from the same point with its own copy of
+        // the stack, so only the return value differs
         let mut new_context = self.context;
-        // cal new stack pointer
-        new_context.set_stack_offset(offset);
         // set rax to 0
         new_context.set_rax(0);
 
@@ -232,38 +399,68 @@ impl ProcessInner {
             name: self.name.clone(),
             exit_code: None,
             parent: Some(parent),
+            leader: None,
+            pgid: self.pgid,
             status: ProgramStatus::Ready,
-            ticks_passed: 0,
+            utime: 0,
+            stime: 0,
+            nice: self.nice,
             context: new_context,
+            // the mask is inherited, pending signals are not
+            signals: self.signals.forked(),
+            stopped: None,
+            stop_reported: false,
             children: Vec::new(),
             proc_vm: Some(new_vm),
-            proc_data: self.proc_data.clone(),
+            proc_data: self.proc_data.as_ref().map(ProcessData::fork),
         }
     }
 
-    pub fn kill(&mut self, pid: ProcessId, ret: isize) {
-        let children = self.children();
-
-        // remove self from parent, and set parent to children
-        if let Some(parent) = self.parent() {
-            if parent.read().exit_code().is_none() {
-                parent.write().remove_child(pid);
-                let weak = Arc::downgrade(&parent);
-                for child in children {
-                    child.write().set_parent(weak.clone());
-                }
-            } else {
-                // parent already exited, set parent to None
-                for child in children {
-                    child.write().set_parent(Weak::new());
-                }
-            }
-        }
+    fn thread(
+        &self,
+        parent: Weak<Process>,
+        leader: ProcessId,
+        entry: VirtAddr,
+        arg: usize,
+        stack: Option<VirtAddr>,
+    ) -> Option<ProcessInner> {
+        let vm = self.vm().thread(stack.is_some())?;
+
+        // leave room for a return address, as if `entry` had been called
+        let stack_top = stack.unwrap_or(vm.stack_top() - 8u64);
+
+        let mut context = ProcessContext::default();
+        context.init_stack_frame(entry, stack_top);
+        context.set_rdi(arg);
+
+        Some(Self {
+            name: self.name.clone(),
+            exit_code: None,
+            parent: Some(parent),
+            leader: Some(leader),
+            pgid: self.pgid,
+            status: ProgramStatus::Ready,
+            utime: 0,
+            stime: 0,
+            nice: self.nice,
+            context,
+            signals: self.signals.forked(),
+            stopped: None,
+            stop_reported: false,
+            children: Vec::new(),
+            proc_vm: Some(vm),
+            proc_data: self.proc_data.clone(),
+        })
+    }
 
+    pub fn kill(&mut self, ret: isize) -> Vec<Arc<Process>> {
         self.proc_vm.take();
         self.proc_data.take();
         self.exit_code = Some(ret);
+        self.stopped = None;
         self.status = ProgramStatus::Dead;
+
+        core::mem::take(&mut self.children)
     }
 }
 
@@ -301,7 +498,9 @@ impl core::fmt::Debug for Process {
             .field("name", &inner.name)
             .field("parent", &inner.parent().map(|p| p.pid))
             .field("status", &inner.status)
-            .field("ticks_passed", &inner.ticks_passed)
+            .field("utime", &inner.utime)
+            .field("stime", &inner.stime)
+            .field("nice", &inner.nice)
             .field("children", &inner.children.iter().map(|c| c.pid.0))
             .field("status", &inner.status)
             .field("context", &inner.context)
@@ -313,17 +512,22 @@ impl core::fmt::Debug for Process {
 impl core::fmt::Display for Process {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         let inner = self.inner.read();
-        let (size, unit) = humanized_size(inner.proc_vm.as_ref().map_or(0, |vm| vm.memory_usage()));
+        let (size, unit) = humanized_size(inner.memory_usage());
+        let status = match inner.stopped {
+            Some(_) => String::from("Stopped"),
+            None => format!("{:?}", inner.status),
+        };
         write!(
             f,
-            " #{:-3} | #{:-3} | {:12} | {:7} | {:>5.1} {} | {:?}",
+            " #{:-3} | #{:-3} | {:12} | {:>3} | {:7} | {:>5.1} {} | {}",
             self.pid.0,
             inner.parent().map(|p| p.pid.0).unwrap_or(0),
             inner.name,
-            inner.ticks_passed,
+            inner.nice,
+            inner.ticks(),
             size,
             unit,
-            inner.status
+            status
         )?;
         Ok(())
     }
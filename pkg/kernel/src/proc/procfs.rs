@@ -0,0 +1,263 @@
+//! Process filesystem
+//!
+//! Text files describing processes and the kernel, mounted at `/proc`.
+//! Each file is rendered when it is opened, reads see that snapshot.
+
+use alloc::{borrow::ToOwned, boxed::Box, format, string::String, vec::Vec};
+use storage::*;
+
+use super::*;
+use crate::{
+    filesystem::cache_usage,
+    memory::{
+        PAGE_SIZE,
+        allocator::{ALLOCATOR, HEAP_SIZE},
+        get_frame_alloc_for_sure,
+        user::{USER_ALLOCATOR, USER_HEAP_SIZE},
+    },
+    utils::clock,
+};
+
+const KERNEL_FILES: [&str; 2] = ["meminfo", "uptime"];
+const PROCESS_FILES: [&str; 3] = ["status", "maps", "fds"];
+
+#[derive(Debug, Default)]
+pub struct ProcFs;
+
+impl ProcFs {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Find a live process by its directory name, `self` is the caller
+    fn process(name: &str) -> Result<Arc<Process>> {
+        let manager = get_process_manager();
+
+        let proc = match name {
+            "self" => manager.current(),
+            _ => name
+                .parse()
+                .ok()
+                .and_then(|pid| manager.process(ProcessId(pid)))
+                .ok_or(FsError::FileNotFound)?,
+        };
+
+        let status = proc.read().status();
+        match status {
+            ProgramStatus::Dead => Err(FsError::FileNotFound),
+            _ => Ok(proc),
+        }
+    }
+
+    /// Render the file at `path`, `None` for directories
+    fn render(path: &str) -> Result<Option<String>> {
+        let mut parts = path.split('/').filter(|p| !p.is_empty());
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (None, _, _) => Ok(None),
+            (Some("meminfo"), None, _) => Ok(Some(meminfo())),
+            (Some("uptime"), None, _) => Ok(Some(uptime())),
+            (Some(dir), file, None) => {
+                let proc = Self::process(dir)?;
+                match file {
+                    None => Ok(None),
+                    Some("status") => Ok(Some(status(&proc))),
+                    Some("maps") => Ok(Some(maps(&proc))),
+                    Some("fds") => Ok(Some(fds(&proc))),
+                    Some(_) => Err(FsError::FileNotFound),
+                }
+            }
+            _ => Err(FsError::FileNotFound),
+        }
+    }
+
+    fn file_meta(name: &str, len: usize) -> Metadata {
+        Metadata::new(name.to_owned(), FileType::File, len, None, None, None)
+    }
+
+    fn dir_meta(name: &str) -> Metadata {
+        Metadata::new(name.to_owned(), FileType::Directory, 0, None, None, None)
+    }
+}
+
+impl FileSystem for ProcFs {
+    fn read_dir(&self, path: &str) -> Result<Box<dyn Iterator<Item = Metadata> + Send>> {
+        if Self::render(path)?.is_some() {
+            return Err(FsError::NotADirectory);
+        }
+
+        let dir = path.trim_matches('/');
+
+        // the files are rendered for their length, so it agrees with stat
+        // and read, a process that exits meanwhile is left out
+        let files = |names: &'static [&'static str]| {
+            names
+                .iter()
+                .filter_map(move |name| self.metadata(&format!("{}/{}", dir, name)).ok())
+        };
+
+        let entries: Vec<Metadata> = match dir {
+            "" => files(&KERNEL_FILES)
+                .chain(
+                    get_process_manager()
+                        .processes()
+                        .iter()
+                        .filter(|p| p.read().status() != ProgramStatus::Dead)
+                        .map(|p| Self::dir_meta(&format!("{}", p.pid().0))),
+                )
+                .collect(),
+            _ => files(&PROCESS_FILES).collect(),
+        };
+
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn open_file(&self, path: &str) -> Result<FileHandle> {
+        let data = Self::render(path)?.ok_or(FsError::NotAFile)?.into_bytes();
+        let name = path.rsplit('/').next().unwrap_or_default();
+
+        Ok(FileHandle::new(
+            Self::file_meta(name, data.len()),
+            Box::new(ProcFile { data, offset: 0 }),
+        ))
+    }
+
+    fn metadata(&self, path: &str) -> Result<Metadata> {
+        let name = path
+            .trim_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+
+        Ok(match Self::render(path)? {
+            Some(data) => Self::file_meta(name, data.len()),
+            None => Self::dir_meta(name),
+        })
+    }
+
+    fn exists(&self, path: &str) -> Result<bool> {
+        Ok(Self::render(path).is_ok())
+    }
+}
+
+/// A rendered file, read only
+struct ProcFile {
+    data: Vec<u8>,
+    offset: usize,
+}
+
+impl Read for ProcFile {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let rest = &self.data[self.offset.min(self.data.len())..];
+        let count = buf.len().min(rest.len());
+
+        buf[..count].copy_from_slice(&rest[..count]);
+        self.offset += count;
+
+        Ok(count)
+    }
+}
+
+impl Write for ProcFile {
+    fn write(&mut self, _buf: &[u8]) -> Result<usize> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for ProcFile {
+    fn seek(&mut self, pos: SeekFrom) -> Result<usize> {
+        let offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.offset.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.data.len().checked_add_signed(offset),
+        };
+
+        self.offset = offset.ok_or(FsError::InvalidOffset)?;
+        Ok(self.offset)
+    }
+}
+
+fn status(proc: &Process) -> String {
+    let tgid = proc.tgid();
+    let inner = proc.read();
+    let (utime, stime) = inner.cpu_time();
+
+    format!(
+        "Name:\t{}\nTgid:\t{}\nPid:\t{}\nPPid:\t{}\nState:\t{:?}\nNice:\t{}\nTicks:\t{}\nUtime:\t{}\nStime:\t{}\nMemory:\t{} kB\nChildren:\t{}\n",
+        inner.name(),
+        tgid.0,
+        proc.pid().0,
+        inner.parent().map(|p| p.pid().0).unwrap_or(0),
+        inner.status(),
+        inner.nice(),
+        inner.ticks(),
+        utime,
+        stime,
+        inner.memory_usage() / 1024,
+        inner
+            .children()
+            .iter()
+            .map(|c| format!("{}", c.pid().0))
+            .collect::<Vec<_>>()
+            .join(" ")
+    )
+}
+
+fn maps(proc: &Process) -> String {
+    let inner = proc.read();
+
+    match inner.has_vm() {
+        true => inner.vm().maps(),
+        false => String::new(),
+    }
+}
+
+fn fds(proc: &Process) -> String {
+    let inner = proc.read();
+
+    inner
+        .resources
+        .read()
+        .iter()
+        .map(|(fd, res)| format!("{}\t{:?}\n", fd, *res.lock()))
+        .collect()
+}
+
+fn meminfo() -> String {
+    let alloc = get_frame_alloc_for_sure();
+    let frames_used = alloc.frames_used() - alloc.frames_recycled();
+    let frames_total = alloc.frames_total();
+    let frames_shared = alloc.frames_shared();
+    drop(alloc);
+
+    let (cache_used, cache_total) = cache_usage();
+
+    format!(
+        "MemTotal:\t{} kB\nMemUsed:\t{} kB\nMemShared:\t{} kB\nKernelHeapTotal:\t{} kB\nKernelHeapUsed:\t{} kB\n\
+         UserHeapTotal:\t{} kB\nUserHeapUsed:\t{} kB\nCacheBlocks:\t{}\nCacheCapacity:\t{}\n",
+        frames_total * PAGE_SIZE as usize / 1024,
+        frames_used * PAGE_SIZE as usize / 1024,
+        frames_shared * PAGE_SIZE as usize / 1024,
+        HEAP_SIZE / 1024,
+        ALLOCATOR.lock().used() / 1024,
+        USER_HEAP_SIZE / 1024,
+        USER_ALLOCATOR.lock().used() / 1024,
+        cache_used,
+        cache_total
+    )
+}
+
+/// Seconds since boot, with two decimals like Linux
+fn uptime() -> String {
+    let uptime = clock::uptime();
+
+    format!(
+        "{}.{:02}\n",
+        uptime.num_seconds(),
+        uptime.subsec_nanos() / 10_000_000
+    )
+}
@@ -0,0 +1,136 @@
+//! Multi-level feedback queue
+//!
+//! Processes start on the highest level their nice value allows and drop
+//! one level every time they use up a time slice, which doubles on each
+//! level. Programs that wait for input give the CPU up early and stay on
+//! top, ahead of the ones computing. Every [`BOOST_TICKS`] all processes
+//! go back to the top, so nothing starves.
+
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    vec::Vec,
+};
+
+use super::*;
+
+const LEVELS: usize = 4;
+/// Time slice on the highest level
+const BASE_QUANTUM: usize = 1;
+/// Ticks between two priority boosts
+const BOOST_TICKS: usize = 100;
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    nice: i8,
+    level: usize,
+    /// Ticks used of the current time slice
+    used: usize,
+}
+
+impl Entry {
+    fn new(nice: i8) -> Self {
+        Self {
+            nice,
+            level: top_level(nice),
+            used: 0,
+        }
+    }
+
+    fn quantum(&self) -> usize {
+        quantum(self.level, self.nice)
+    }
+}
+
+/// Highest level a process may be on, a quarter of the nice range per level
+fn top_level(nice: i8) -> usize {
+    (nice.max(0) as usize * LEVELS / (NICE_MAX as usize + 1)).min(LEVELS - 1)
+}
+
+/// Length of a time slice, negative nice values stretch it
+fn quantum(level: usize, nice: i8) -> usize {
+    let stretch = 1 + (-nice).max(0) as usize / 5;
+    (BASE_QUANTUM << level) * stretch
+}
+
+#[derive(Debug, Default)]
+pub struct Mlfq {
+    queues: [VecDeque<ProcessId>; LEVELS],
+    entries: BTreeMap<ProcessId, Entry>,
+    ticks: usize,
+}
+
+impl Mlfq {
+    fn entry(&mut self, pid: ProcessId) -> &mut Entry {
+        self.entries.entry(pid).or_insert_with(|| Entry::new(0))
+    }
+
+    /// Move every process back to its highest level
+    fn boost(&mut self) {
+        for entry in self.entries.values_mut() {
+            entry.level = top_level(entry.nice);
+            entry.used = 0;
+        }
+
+        let queued: Vec<_> = self.queues.iter_mut().flat_map(|q| q.drain(..)).collect();
+
+        for pid in queued {
+            self.push(pid);
+        }
+    }
+}
+
+impl Scheduler for Mlfq {
+    fn name(&self) -> &'static str {
+        "mlfq"
+    }
+
+    fn add(&mut self, pid: ProcessId, nice: i8) {
+        self.entries.insert(pid, Entry::new(nice));
+    }
+
+    fn remove(&mut self, pid: ProcessId) {
+        self.entries.remove(&pid);
+    }
+
+    fn set_nice(&mut self, pid: ProcessId, nice: i8) {
+        if let Some(entry) = self.entries.get_mut(&pid) {
+            entry.nice = nice;
+            entry.level = entry.level.max(top_level(nice));
+        }
+    }
+
+    fn push(&mut self, pid: ProcessId) {
+        // exited processes may still be woken up, they are skipped later
+        let level = self.entries.get(&pid).map_or(0, |entry| entry.level);
+        self.queues[level].push_back(pid);
+    }
+
+    fn pop(&mut self) -> Option<ProcessId> {
+        self.queues.iter_mut().find_map(|queue| queue.pop_front())
+    }
+
+    fn tick(&mut self, pid: ProcessId) -> bool {
+        self.ticks += 1;
+        if self.ticks.is_multiple_of(BOOST_TICKS) {
+            self.boost();
+        }
+
+        let entry = self.entry(pid);
+        entry.used += 1;
+
+        if entry.used < entry.quantum() {
+            // a process waiting on a higher level goes first
+            let level = entry.level;
+            return self.queues[..level].iter().any(|q| !q.is_empty());
+        }
+
+        entry.level = (entry.level + 1).min(LEVELS - 1);
+        entry.used = 0;
+
+        true
+    }
+
+    fn queued(&self) -> Vec<ProcessId> {
+        self.queues.iter().flatten().copied().collect()
+    }
+}
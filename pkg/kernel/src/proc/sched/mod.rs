@@ -0,0 +1,57 @@
+//! Scheduling policies
+//!
+//! The manager asks a [`Scheduler`] which process runs next and, on every
+//! timer tick, whether the running one is preempted. The policy is picked
+//! with `sched=` on the kernel command line.
+
+use alloc::{boxed::Box, vec::Vec};
+
+use super::ProcessId;
+
+pub mod mlfq;
+pub mod round_robin;
+
+pub use self::{mlfq::Mlfq, round_robin::RoundRobin};
+
+/// Lowest nice value, for the most favoured processes
+pub const NICE_MIN: i8 = -20;
+/// Highest nice value, for the least favoured processes
+pub const NICE_MAX: i8 = 19;
+
+pub trait Scheduler: Send {
+    fn name(&self) -> &'static str;
+
+    /// Start scheduling a new process
+    fn add(&mut self, pid: ProcessId, nice: i8);
+
+    /// Stop scheduling a process that exited
+    fn remove(&mut self, pid: ProcessId);
+
+    /// Change the nice value of a process
+    fn set_nice(&mut self, pid: ProcessId, nice: i8);
+
+    /// Queue a process that is ready to run
+    fn push(&mut self, pid: ProcessId);
+
+    /// Take the next process to run, it may not be ready anymore
+    fn pop(&mut self) -> Option<ProcessId>;
+
+    /// Charge a timer tick to the running process, true if its time slice
+    /// is over
+    fn tick(&mut self, pid: ProcessId) -> bool;
+
+    /// Queued processes in the order they would run
+    fn queued(&self) -> Vec<ProcessId>;
+}
+
+/// The scheduler named on the command line, MLFQ by default
+pub fn from_name(name: &str) -> Box<dyn Scheduler> {
+    match name {
+        "rr" => Box::new(RoundRobin::default()),
+        "mlfq" => Box::new(Mlfq::default()),
+        _ => {
+            warn!("Unknown scheduler '{}', using mlfq", name);
+            Box::new(Mlfq::default())
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use alloc::{collections::VecDeque, vec::Vec};
+
+use super::*;
+
+/// Every process runs for one tick in turn, nice values are ignored
+#[derive(Debug, Default)]
+pub struct RoundRobin {
+    queue: VecDeque<ProcessId>,
+}
+
+impl Scheduler for RoundRobin {
+    fn name(&self) -> &'static str {
+        "rr"
+    }
+
+    fn add(&mut self, _pid: ProcessId, _nice: i8) {}
+
+    fn remove(&mut self, _pid: ProcessId) {}
+
+    fn set_nice(&mut self, _pid: ProcessId, _nice: i8) {}
+
+    fn push(&mut self, pid: ProcessId) {
+        self.queue.push_back(pid);
+    }
+
+    fn pop(&mut self) -> Option<ProcessId> {
+        self.queue.pop_front()
+    }
+
+    fn tick(&mut self, _pid: ProcessId) -> bool {
+        true
+    }
+
+    fn queued(&self) -> Vec<ProcessId> {
+        self.queue.iter().copied().collect()
+    }
+}
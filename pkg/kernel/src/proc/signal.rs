@@ -0,0 +1,227 @@
+//! Signals
+//!
+//! Handlers belong to the process and are shared by its threads, while
+//! every thread has its own mask and pending signals. A signal is taken
+//! when the thread returns to user mode: the handler runs on the thread's
+//! stack above a [`SignalFrame`] and returns through `sigreturn`.
+//!
+//! A stop signal blocks the thread that takes it until `SIGCONT` is sent,
+//! which resumes it whatever its action is.
+
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use syscall_def::{SIG_DFL, SIG_IGN, SigAction, SigHow, Signal};
+
+use x86_64::VirtAddr;
+use x86_64::structures::idt::PageFaultErrorCode;
+
+use super::{ProcessContext, ProcessId, context::ProcessContextValue};
+use crate::memory::is_user_accessible;
+
+/// Signals that can be neither blocked nor handled
+const UNBLOCKABLE: u64 = Signal::SIGKILL.bit() | Signal::SIGSTOP.bit();
+
+/// Signals that stop the process by default
+pub const STOP_SIGNALS: u64 = Signal::SIGSTOP.bit() | Signal::SIGTSTP.bit();
+
+/// Process group that Ctrl-C and Ctrl-Z are sent to, 0 when there is none
+static FOREGROUND: AtomicU16 = AtomicU16::new(0);
+
+pub fn set_foreground(pgid: Option<ProcessId>) {
+    FOREGROUND.store(pgid.map_or(0, |pgid| pgid.0), Ordering::Relaxed);
+}
+
+pub fn foreground() -> Option<ProcessId> {
+    match FOREGROUND.load(Ordering::Relaxed) {
+        0 => None,
+        pid => Some(ProcessId(pid)),
+    }
+}
+
+/// Exit code of a process killed by `signal`, as shells report it
+pub fn exit_code(signal: Signal) -> isize {
+    128 + signal as isize
+}
+
+/// What a signal does to the thread it is delivered to
+#[derive(Debug, Clone, Copy)]
+pub enum Disposition {
+    Ignore,
+    Terminate,
+    Stop,
+    Handle(SigAction),
+}
+
+/// Handlers of a process, indexed by signal number
+#[derive(Debug, Clone)]
+pub struct SignalActions([SigAction; 64]);
+
+impl Default for SignalActions {
+    fn default() -> Self {
+        Self([SigAction::default(); 64])
+    }
+}
+
+impl SignalActions {
+    pub fn get(&self, signal: Signal) -> SigAction {
+        self.0[signal as usize]
+    }
+
+    /// Replace the action of a signal, returning the old one
+    pub fn set(&mut self, signal: Signal, action: SigAction) -> Option<SigAction> {
+        if UNBLOCKABLE & signal.bit() != 0 {
+            return None;
+        }
+
+        Some(core::mem::replace(&mut self.0[signal as usize], action))
+    }
+
+    pub fn disposition(&self, signal: Signal) -> Disposition {
+        match self.get(signal).handler {
+            _ if signal == Signal::SIGKILL => Disposition::Terminate,
+            _ if signal == Signal::SIGSTOP => Disposition::Stop,
+            SIG_DFL if signal == Signal::SIGCHLD || signal == Signal::SIGCONT => {
+                Disposition::Ignore
+            }
+            SIG_DFL if STOP_SIGNALS & signal.bit() != 0 => Disposition::Stop,
+            SIG_DFL => Disposition::Terminate,
+            SIG_IGN => Disposition::Ignore,
+            _ => Disposition::Handle(self.get(signal)),
+        }
+    }
+
+    /// Handlers are gone with the program on exec, ignored signals stay
+    /// ignored
+    pub fn reset_handlers(&mut self) {
+        for action in self.0.iter_mut() {
+            if action.handler != SIG_IGN {
+                *action = SigAction::default();
+            }
+        }
+    }
+}
+
+/// Signals of a thread
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SignalState {
+    pending: u64,
+    mask: u64,
+}
+
+impl SignalState {
+    /// Signals of a new thread or forked child, which keeps the mask
+    pub fn forked(&self) -> Self {
+        Self {
+            pending: 0,
+            mask: self.mask,
+        }
+    }
+
+    pub fn raise(&mut self, signal: Signal) {
+        self.pending |= signal.bit();
+    }
+
+    /// Drop the pending signals in `set`
+    pub fn discard(&mut self, set: u64) {
+        self.pending &= !set;
+    }
+
+    pub fn is_blocked(&self, signal: Signal) -> bool {
+        self.mask & signal.bit() != 0
+    }
+
+    pub fn mask(&self) -> u64 {
+        self.mask
+    }
+
+    /// Change the mask as `sigprocmask` does, returning the old one
+    pub fn set_mask(&mut self, how: SigHow, set: u64) -> u64 {
+        let old = self.mask;
+
+        self.mask = match how {
+            SigHow::Block => old | set,
+            SigHow::Unblock => old & !set,
+            SigHow::SetMask => set,
+        } & !UNBLOCKABLE;
+
+        old
+    }
+
+    /// Take the lowest pending signal that is not blocked
+    pub fn take(&mut self) -> Option<Signal> {
+        let ready = self.pending & !self.mask;
+        if ready == 0 {
+            return None;
+        }
+
+        let number = ready.trailing_zeros();
+        self.pending &= !(1 << number);
+
+        // unknown numbers can only be raised by a bug, drop them
+        Signal::try_from(number as u8).ok()
+    }
+}
+
+/// Pushed on the user stack before a handler runs, `restorer` is where the
+/// handler returns to and the rest is what `sigreturn` restores
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SignalFrame {
+    pub restorer: usize,
+    pub context: ProcessContextValue,
+    pub mask: u64,
+}
+
+/// The user stack has to be mapped where the frame goes, it may need to
+/// grow first
+fn is_stack_mapped(addr: u64) -> bool {
+    is_user_accessible(addr as usize)
+        || (super::handle_page_fault(VirtAddr::new(addr), PageFaultErrorCode::USER_MODE)
+            && is_user_accessible(addr as usize))
+}
+
+/// Make `context` run the handler of `signal`, with a frame to return
+/// through pushed below the red zone of the interrupted code
+pub fn push_frame(
+    context: &mut ProcessContext,
+    signal: Signal,
+    action: &SigAction,
+    mask: u64,
+) -> bool {
+    let size = size_of::<SignalFrame>() as u64;
+    let sp = context.stack_frame.stack_pointer.as_u64();
+
+    // aligned as if `restorer` was pushed by a call
+    let addr = ((sp - 128 - size) & !0xf) - 8;
+
+    if !is_stack_mapped(addr) || !is_stack_mapped(addr + size - 1) {
+        warn!("Cannot push signal frame to {:#x}", addr);
+        return false;
+    }
+
+    let frame = SignalFrame {
+        restorer: action.restorer,
+        context: **context,
+        mask,
+    };
+
+    unsafe { (addr as *mut SignalFrame).write(frame) };
+
+    context.init_stack_frame(VirtAddr::new(action.handler as u64), VirtAddr::new(addr));
+    context.set_rdi(signal as usize);
+
+    true
+}
+
+/// The frame a handler returned from, `restorer` is already popped
+pub fn pop_frame(context: &ProcessContext) -> Option<SignalFrame> {
+    let size = size_of::<SignalFrame>();
+    let addr = context.stack_frame.stack_pointer.as_u64() as usize - 8;
+
+    if !is_user_accessible(addr) || !is_user_accessible(addr + size - 1) {
+        warn!("Cannot pop signal frame from {:#x}", addr);
+        return None;
+    }
+
+    Some(unsafe { (addr as *const SignalFrame).read() })
+}
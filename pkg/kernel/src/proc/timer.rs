@@ -0,0 +1,69 @@
+//! Sleeping processes
+//!
+//! A hashed timer wheel: a sleeper goes into the slot of its deadline tick,
+//! and every tick only the slot of that tick is looked at. Deadlines more
+//! than a turn away stay in their slot until their turn comes.
+
+use alloc::vec::Vec;
+
+use super::ProcessId;
+
+const SLOTS: usize = 64;
+
+pub struct TimerWheel {
+    slots: [Vec<(u64, ProcessId)>; SLOTS],
+    /// Last tick that was expired
+    now: u64,
+}
+
+impl Default for TimerWheel {
+    fn default() -> Self {
+        Self {
+            slots: core::array::from_fn(|_| Vec::new()),
+            now: 0,
+        }
+    }
+}
+
+impl TimerWheel {
+    /// Wake `pid` up at tick `deadline`, the next tick if it has passed
+    pub fn insert(&mut self, deadline: u64, pid: ProcessId) {
+        let deadline = deadline.max(self.now + 1);
+        self.slots[deadline as usize % SLOTS].push((deadline, pid));
+    }
+
+    /// Stop the timer of `pid`, false if it has none
+    pub fn cancel(&mut self, pid: ProcessId) -> bool {
+        for slot in self.slots.iter_mut() {
+            if let Some(index) = slot.iter().position(|&(_, p)| p == pid) {
+                slot.swap_remove(index);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Move on to tick `now`, returning the processes due by then
+    pub fn advance(&mut self, now: u64) -> Vec<ProcessId> {
+        let mut expired = Vec::new();
+
+        // ticks are only missed while interrupts are off, a full turn
+        // visits every slot anyway
+        let steps = now.saturating_sub(self.now).min(SLOTS as u64);
+
+        for tick in self.now + 1..=self.now + steps {
+            self.slots[tick as usize % SLOTS].retain(|&(deadline, pid)| {
+                let due = deadline <= now;
+                if due {
+                    expired.push(pid);
+                }
+                !due
+            });
+        }
+
+        self.now = self.now.max(now);
+
+        expired
+    }
+}
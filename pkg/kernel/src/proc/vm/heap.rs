@@ -25,13 +25,42 @@ pub struct Heap {
 }
 
 impl Heap {
-    pub fn fork(&self) -> Self {
+    /// The mapped part of the heap, `[base, end)`
+    pub fn range(&self) -> (VirtAddr, VirtAddr) {
+        (self.base, VirtAddr::new(self.end.load(Ordering::Relaxed)))
+    }
+
+    /// The same heap for a thread, growing it grows it for all of them
+    pub fn share(&self) -> Self {
         Self {
             base: self.base,
             end: self.end.clone(),
         }
     }
 
+    /// The same heap in a forked address space, it grows separately
+    pub fn fork(&self, parent: MapperRef, child: MapperRef, alloc: FrameAllocatorRef) -> Self {
+        let end = self.end.load(Ordering::Relaxed);
+
+        if end > self.base.as_u64() {
+            let start_page = Page::containing_address(self.base);
+            let end_page = Page::containing_address(VirtAddr::new(end));
+
+            share_pages(
+                Page::range_inclusive(start_page, end_page).into_iter(),
+                parent,
+                child,
+                alloc,
+            )
+            .expect("Failed to share heap with child.");
+        }
+
+        Self {
+            base: self.base,
+            end: Arc::new(AtomicU64::new(end)),
+        }
+    }
+
     pub fn brk(
         &self,
         new_end: Option<VirtAddr>,
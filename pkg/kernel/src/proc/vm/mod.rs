@@ -1,11 +1,16 @@
-use alloc::{format, vec::Vec};
+use core::ptr::copy_nonoverlapping;
+
+use alloc::{format, string::String, vec::Vec};
 use boot::KernelPages;
 use x86_64::{
     VirtAddr,
-    structures::paging::{
-        mapper::{CleanUp, UnmapError},
-        page::*,
-        *,
+    structures::{
+        idt::PageFaultErrorCode,
+        paging::{
+            mapper::{CleanUp, MapToError, MappedFrame, TranslateResult, UnmapError},
+            page::*,
+            *,
+        },
     },
 };
 use xmas_elf::ElfFile;
@@ -17,13 +22,17 @@ pub mod stack;
 
 use self::{heap::Heap, stack::Stack};
 
-use super::PageTableContext;
+use super::{PageTableContext, ProgramArgs};
 
 type MapperRef<'a> = &'a mut OffsetPageTable<'static>;
 type FrameAllocatorRef<'a> = &'a mut BootInfoFrameAllocator;
 
+/// Marks a page that is shared with a forked process, it is read-only
+/// until either side writes to it and gets a copy of its own
+const COPY_ON_WRITE: PageTableFlags = PageTableFlags::BIT_9;
+
 pub struct ProcessVm {
-    // page table is shared by parent and child
+    // every process has its own page table, shared by its threads
     pub(super) page_table: PageTableContext,
 
     // stack is pre-process allocated
@@ -32,8 +41,7 @@ pub struct ProcessVm {
     // heap is allocated by brk syscall
     pub(super) heap: Heap,
 
-    // code is hold by the first process
-    // these fields will be empty for other processes
+    // code pages are shared copy-on-write after fork
     pub(super) code: Vec<PageRangeInclusive>,
     pub(super) code_usage: u64,
 }
@@ -88,13 +96,20 @@ impl ProcessVm {
         )
     }
 
-    pub fn load_elf(&mut self, elf: &ElfFile) {
+    /// Load the program and put its arguments on a new stack, returning
+    /// the initial stack pointer
+    pub fn load_elf(&mut self, elf: &ElfFile, args: &ProgramArgs) -> VirtAddr {
         let mapper = &mut self.page_table.mapper();
 
         let alloc = &mut *get_frame_alloc_for_sure();
 
         self.load_elf_code(elf, mapper, alloc);
-        self.stack.init(mapper, alloc);
+
+        let (sp, image) = args.stack_image(stack::STACK_MAX, elf.header.pt2.entry_point());
+        self.stack.init(mapper, alloc, image.len() as u64);
+        write_to(mapper, VirtAddr::new(sp), &image);
+
+        VirtAddr::new(sp)
     }
 
     fn load_elf_code(&mut self, elf: &ElfFile, mapper: MapperRef, alloc: FrameAllocatorRef) {
@@ -105,23 +120,55 @@ impl ProcessVm {
         self.code_usage = usage as u64 * crate::memory::PAGE_SIZE
     }
 
-    pub fn fork(&self, stack_offset_count: u64) -> Self {
-        let owned_page_table = self.page_table.fork();
-        let mapper = &mut owned_page_table.mapper();
+    /// Copy the address space into `page_table`, the pages are shared
+    /// until one of the processes writes to them
+    pub fn fork(&self, page_table: PageTableContext) -> Self {
+        let parent = &mut self.page_table.mapper();
+        let child = &mut page_table.mapper();
 
         let alloc = &mut *get_frame_alloc_for_sure();
 
-        Self {
-            page_table: owned_page_table,
-            stack: self.stack.fork(mapper, alloc, stack_offset_count),
-            heap: self.heap.fork(),
+        let code = self.code.iter().flat_map(|range| range.into_iter());
+        share_pages(code, parent, child, alloc).expect("Failed to share code with child.");
 
-            // do not share code info
-            code: Vec::new(),
-            code_usage: 0,
+        let stack = self.stack.fork(parent, child, alloc);
+        let heap = self.heap.fork(parent, child, alloc);
+
+        Self {
+            page_table,
+            stack,
+            heap,
+            code: self.code.clone(),
+            code_usage: self.code_usage,
         }
     }
 
+    /// The same address space for a new thread, which gets a stack slot
+    /// of its own unless it runs on a stack of the user
+    pub fn thread(&self, user_stack: bool) -> Option<Self> {
+        let stack = if user_stack {
+            Stack::empty()
+        } else {
+            let mapper = &mut self.page_table.mapper();
+            let alloc = &mut *get_frame_alloc_for_sure();
+            Stack::thread(mapper, alloc)?
+        };
+
+        Some(Self {
+            page_table: self.page_table.share(),
+            stack,
+            heap: self.heap.share(),
+            // freed with the page table by the last thread
+            code: self.code.clone(),
+            code_usage: self.code_usage,
+        })
+    }
+
+    /// Top of the stack, where a new thread starts
+    pub fn stack_top(&self) -> VirtAddr {
+        self.stack.range().1
+    }
+
     pub fn handle_page_fault(&mut self, addr: VirtAddr) -> bool {
         let mapper = &mut self.page_table.mapper();
         let alloc = &mut *get_frame_alloc_for_sure();
@@ -129,6 +176,45 @@ impl ProcessVm {
         self.stack.handle_page_fault(addr, mapper, alloc)
     }
 
+    /// Resolve a write to a read-only page, which only succeeds for pages
+    /// shared copy-on-write
+    ///
+    /// It does not need the process mutably, as the kernel may fault on a
+    /// user buffer while it holds the process
+    pub fn handle_protection_fault(&self, addr: VirtAddr, err_code: PageFaultErrorCode) -> bool {
+        if !err_code.contains(PageFaultErrorCode::CAUSED_BY_WRITE) {
+            return false;
+        }
+
+        let mapper = &mut self.page_table.mapper();
+        let alloc = &mut *get_frame_alloc_for_sure();
+
+        copy_on_write(Page::containing_address(addr), mapper, alloc)
+    }
+
+    /// One `start-end name` line per mapped region
+    pub fn maps(&self) -> String {
+        let mut maps = String::new();
+
+        for range in self.code.iter() {
+            maps += &format!(
+                "{:016x}-{:016x} [code]\n",
+                range.start.start_address().as_u64(),
+                range.end.start_address().as_u64() + range.end.size()
+            );
+        }
+
+        let (base, end) = self.heap.range();
+        if end > base {
+            maps += &format!("{:016x}-{:016x} [heap]\n", base.as_u64(), end.as_u64());
+        }
+
+        let (bot, top) = self.stack.range();
+        maps += &format!("{:016x}-{:016x} [stack]\n", bot.as_u64(), top.as_u64());
+
+        maps
+    }
+
     pub(super) fn memory_usage(&self) -> u64 {
         self.stack.memory_usage() + self.heap.memory_usage() + self.code_usage
     }
@@ -173,6 +259,138 @@ impl ProcessVm {
     }
 }
 
+/// Map the mapped ones of `pages` into the page table of a forked child,
+/// writable pages become read-only in both until one of them writes
+fn share_pages(
+    pages: impl Iterator<Item = Page>,
+    parent: MapperRef,
+    child: MapperRef,
+    alloc: FrameAllocatorRef,
+) -> Result<(), MapToError<Size4KiB>> {
+    // tables stay writable, only the pages themselves are protected
+    let table_flags =
+        PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE;
+
+    for page in pages {
+        let (frame, mut flags) = match parent.translate(page.start_address()) {
+            TranslateResult::Mapped {
+                frame: MappedFrame::Size4KiB(frame),
+                flags,
+                ..
+            } => (frame, flags),
+            _ => continue,
+        };
+
+        if flags.contains(PageTableFlags::WRITABLE) {
+            flags.remove(PageTableFlags::WRITABLE);
+            flags.insert(COPY_ON_WRITE);
+
+            if let Ok(flush) = unsafe { parent.update_flags(page, flags) } {
+                flush.flush();
+            }
+        }
+
+        alloc.share_frame(frame);
+
+        unsafe {
+            child
+                .map_to_with_table_flags(page, frame, flags, table_flags, alloc)?
+                .ignore();
+        }
+    }
+
+    Ok(())
+}
+
+/// Make a copy-on-write page writable, copying it first if the frame is
+/// still shared with another process
+fn copy_on_write(page: Page, mapper: MapperRef, alloc: FrameAllocatorRef) -> bool {
+    let (frame, mut flags) = match mapper.translate(page.start_address()) {
+        TranslateResult::Mapped {
+            frame: MappedFrame::Size4KiB(frame),
+            flags,
+            ..
+        } if flags.contains(COPY_ON_WRITE) => (frame, flags),
+        _ => return false,
+    };
+
+    flags.remove(COPY_ON_WRITE);
+    flags.insert(PageTableFlags::WRITABLE);
+
+    if !alloc.is_shared(frame) {
+        // the other processes have already copied or released it
+        return match unsafe { mapper.update_flags(page, flags) } {
+            Ok(flush) => {
+                flush.flush();
+                true
+            }
+            Err(_) => false,
+        };
+    }
+
+    let copy = match alloc.allocate_frame() {
+        Some(copy) => copy,
+        None => {
+            error!("No frame left to copy {:#x}", page.start_address());
+            return false;
+        }
+    };
+
+    trace!(
+        "Copy on write: {:#x} -> {:#x}",
+        frame.start_address(),
+        copy.start_address()
+    );
+
+    unsafe {
+        copy_nonoverlapping(
+            physical_to_virtual(frame.start_address().as_u64()) as *const u8,
+            physical_to_virtual(copy.start_address().as_u64()) as *mut u8,
+            PAGE_SIZE as usize,
+        );
+
+        if let Ok((_, flush)) = mapper.unmap(page) {
+            flush.flush();
+        }
+
+        match mapper.map_to(page, copy, flags, alloc) {
+            Ok(flush) => flush.flush(),
+            Err(_) => return false,
+        }
+
+        // drop this process's share of the old frame
+        alloc.deallocate_frame(frame);
+    }
+
+    true
+}
+
+/// Write `data` to mapped memory of a page table that may not be loaded
+fn write_to(mapper: MapperRef, addr: VirtAddr, data: &[u8]) {
+    let mut written = 0;
+
+    while written < data.len() {
+        let addr = addr + written as u64;
+        let phys = mapper
+            .translate_addr(addr)
+            .expect("Writing to unmapped memory");
+
+        // stay within the page, the next one may be in another frame
+        let count =
+            (data.len() - written).min((PAGE_SIZE - u64::from(addr.page_offset())) as usize);
+
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                data[written..].as_ptr(),
+                physical_to_virtual(phys.as_u64()) as *mut u8,
+                count,
+            );
+        }
+
+        written += count;
+    }
+}
+
 impl Drop for ProcessVm {
     fn drop(&mut self) {
         if let Err(err) = self.clean_up() {
@@ -1,5 +1,3 @@
-use core::ptr::copy_nonoverlapping;
-
 use x86_64::{
     VirtAddr,
     structures::paging::{
@@ -63,6 +61,14 @@ impl Stack {
         }
     }
 
+    /// The pages reserved for this stack, `[start, end)`
+    pub fn range(&self) -> (VirtAddr, VirtAddr) {
+        (
+            self.range.start.start_address(),
+            self.range.end.start_address(),
+        )
+    }
+
     pub const fn kstack() -> Self {
         Self {
             range: Page::range(KSTACK_INIT_PAGE, KSTACK_INIT_TOP_PAGE),
@@ -70,51 +76,55 @@ impl Stack {
         }
     }
 
-    pub fn init(&mut self, mapper: MapperRef, alloc: FrameAllocatorRef) {
+    /// Map the initial stack, large enough to hold `size` bytes at its top
+    pub fn init(&mut self, mapper: MapperRef, alloc: FrameAllocatorRef, size: u64) {
         debug_assert!(self.usage == 0, "Stack is not empty.");
 
-        self.range = elf::map_pages(STACK_INIT_BOT, STACK_DEF_PAGE, mapper, alloc, true).unwrap();
-        self.usage = STACK_DEF_PAGE;
+        let pages = size.div_ceil(crate::memory::PAGE_SIZE).max(STACK_DEF_PAGE);
+        let bot = STACK_MAX - pages * crate::memory::PAGE_SIZE;
+
+        self.range = elf::map_pages(bot, pages, mapper, alloc, true).unwrap();
+        self.usage = pages;
     }
 
-    pub fn fork(
-        &self,
-        mapper: MapperRef,
-        alloc: FrameAllocatorRef,
-        stack_offset_count: u64,
-    ) -> Self {
-        let cur_stack_base = self.range.start.start_address().as_u64();
-        let mut new_stack_base = cur_stack_base - stack_offset_count * STACK_MAX_SIZE;
-
-        while elf::map_pages(new_stack_base, self.usage, mapper, alloc, true).is_err() {
-            trace!("Map thread stack to {:#x} failed.", new_stack_base);
-            new_stack_base -= STACK_MAX_SIZE; // stack grow down
+    /// Map a stack for a new thread in the first free slot below the
+    /// main stack, `None` once the slots reach the heap
+    pub fn thread(mapper: MapperRef, alloc: FrameAllocatorRef) -> Option<Self> {
+        let mut top = STACK_MAX - STACK_MAX_SIZE;
+
+        // a slot in use always has its top page mapped
+        while mapper
+            .translate_page(Page::<Size4KiB>::containing_address(VirtAddr::new(top - 8)))
+            .is_ok()
+        {
+            top -= STACK_MAX_SIZE;
+            if top - STACK_MAX_SIZE < heap::HEAP_START + heap::HEAP_SIZE {
+                return None;
+            }
         }
 
-        debug!("Map thread stack to {:#x} succeed.", new_stack_base);
+        let bot = top - STACK_DEF_SIZE;
+        let range = elf::map_pages(bot, STACK_DEF_PAGE, mapper, alloc, true).ok()?;
+
+        debug!("Map thread stack to {:#x}.", bot);
 
-        // clone new stack content
-        self.clone_range(cur_stack_base, new_stack_base, self.usage);
+        Some(Self {
+            range,
+            usage: STACK_DEF_PAGE,
+        })
+    }
 
-        let new_start = Page::containing_address(VirtAddr::new(new_stack_base));
+    /// The same stack in a forked address space
+    pub fn fork(&self, parent: MapperRef, child: MapperRef, alloc: FrameAllocatorRef) -> Self {
+        share_pages(self.range.into_iter(), parent, child, alloc)
+            .expect("Failed to share stack with child.");
 
         Self {
-            range: Page::range(new_start, new_start + self.usage),
+            range: self.range,
             usage: self.usage,
         }
     }
 
-    pub fn stack_offset(&self, old_stack: &Stack) -> u64 {
-        let cur_stack_base = self.range.start.start_address().as_u64();
-        let old_stack_base = old_stack.range.start.start_address().as_u64();
-        let offset = cur_stack_base - old_stack_base;
-        debug_assert!(
-            !offset.is_multiple_of(STACK_MAX_SIZE),
-            "Invalid stack offset."
-        );
-        offset
-    }
-
     pub fn handle_page_fault(
         &mut self,
         addr: VirtAddr,
@@ -174,17 +184,6 @@ impl Stack {
 
         Ok(())
     }
-
-    fn clone_range(&self, cur_addr: u64, dest_addr: u64, size: u64) {
-        trace!("Clone range: {:#x} -> {:#x}", cur_addr, dest_addr);
-        unsafe {
-            copy_nonoverlapping::<u64>(
-                cur_addr as *mut u64,
-                dest_addr as *mut u64,
-                (size * Size4KiB::SIZE / 8) as usize,
-            );
-        }
-    }
 }
 
 impl VmPartExt for Stack {
@@ -201,7 +200,8 @@ impl VmPartExt for Stack {
         dealloc: FrameAllocatorRef,
     ) -> Result<(), UnmapError> {
         if self.usage == 0 {
-            warn!("Stack is empty, no need to clean up.");
+            // threads may run on a stack of their own making
+            debug!("Stack is empty, no need to clean up.");
             return Ok(());
         }
 
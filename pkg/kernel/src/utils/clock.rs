@@ -1,6 +1,64 @@
-use chrono::{DateTime, naive::*};
+//! Kernel clocks
+//!
+//! The APIC timer interrupts `TICK_HZ` times a second and every interrupt
+//! advances the tick counter, which is the monotonic clock. Wall-clock time
+//! is read from UEFI once at boot and carried on by the ticks.
 
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+
+use chrono::{DateTime, TimeDelta, naive::*};
+
+/// Timer interrupts per second
+pub const TICK_HZ: u64 = 1000;
+/// Length of a tick
+pub const TICK_NS: u64 = 1_000_000_000 / TICK_HZ;
+
+static BOOT_TIME: spin::Once<NaiveDateTime> = spin::Once::new();
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+/// Remember when the kernel started
+pub fn init() {
+    BOOT_TIME.call_once(rtc_time);
+}
+
+/// Count a timer interrupt
+#[inline]
+pub fn tick() {
+    TICKS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Ticks since the timer started
+#[inline]
+pub fn ticks() -> u64 {
+    TICKS.load(Ordering::Relaxed)
+}
+
+/// Ticks needed for `ns` nanoseconds to pass, rounded up
+pub fn ticks_for(ns: u64) -> u64 {
+    ns.div_ceil(TICK_NS)
+}
+
+/// Time since the timer started, never goes back
+pub fn monotonic() -> Duration {
+    Duration::from_nanos(ticks() * TICK_NS)
+}
+
+/// Time passed since `init`
+pub fn uptime() -> TimeDelta {
+    TimeDelta::from_std(monotonic()).unwrap_or_default()
+}
+
+/// Wall-clock time
 pub fn now() -> NaiveDateTime {
+    match BOOT_TIME.get() {
+        Some(boot) => *boot + uptime(),
+        None => rtc_time(),
+    }
+}
+
+/// Time from the UEFI runtime services, slow to read
+fn rtc_time() -> NaiveDateTime {
     let time = match uefi::runtime::get_time() {
         Ok(time) => time,
         Err(_) => return DateTime::from_timestamp(0, 0).unwrap().naive_utc(),
@@ -0,0 +1,120 @@
+//! Kernel command line
+//!
+//! Space separated `key=value` options from `cmdline` in boot.conf, e.g.
+//! `init=/APP/TEST root=hdb1 sched=rr loglevel=debug log=proc:trace,ggos_storage:warn`
+//!
+//! It is parsed before the heap exists, so nothing here allocates.
+
+use log::LevelFilter;
+
+/// Program run as the first process
+const DEFAULT_INIT: &str = "/APP/SH";
+/// Block device mounted at `/`
+const DEFAULT_ROOT: &str = "hda1";
+/// Scheduling policy
+const DEFAULT_SCHED: &str = "mlfq";
+
+static CMDLINE: spin::Once<Cmdline> = spin::Once::new();
+
+#[derive(Debug, Clone, Copy)]
+pub struct Cmdline {
+    raw: &'static str,
+    /// Path of the init program
+    pub init: &'static str,
+    /// Block device to mount at `/`, `initrd` for the initramfs
+    pub root: &'static str,
+    /// `mlfq` or `rr` for round robin
+    pub sched: &'static str,
+    /// Overrides `log_level` from boot.conf
+    pub log_level: Option<LevelFilter>,
+    /// `module:level` pairs separated by commas
+    log_filters: &'static str,
+}
+
+pub fn init(boot_info: &'static boot::BootInfo) {
+    CMDLINE.call_once(|| Cmdline::parse(boot_info.cmdline));
+}
+
+pub fn get_cmdline() -> &'static Cmdline {
+    CMDLINE.get().expect("Command line not initialized")
+}
+
+pub fn parse_level(level: &str) -> Option<LevelFilter> {
+    match level {
+        "off" => Some(LevelFilter::Off),
+        "error" => Some(LevelFilter::Error),
+        "warn" => Some(LevelFilter::Warn),
+        "info" => Some(LevelFilter::Info),
+        "debug" => Some(LevelFilter::Debug),
+        "trace" => Some(LevelFilter::Trace),
+        _ => None,
+    }
+}
+
+impl Cmdline {
+    pub fn parse(raw: &'static str) -> Self {
+        let mut cmdline = Self {
+            raw,
+            init: DEFAULT_INIT,
+            root: DEFAULT_ROOT,
+            sched: DEFAULT_SCHED,
+            log_level: None,
+            log_filters: "",
+        };
+
+        for (key, value) in options(raw) {
+            match key {
+                "init" => cmdline.init = value,
+                "root" => cmdline.root = value.strip_prefix("/dev/").unwrap_or(value),
+                "sched" => cmdline.sched = value,
+                "loglevel" => cmdline.log_level = parse_level(value),
+                "log" => cmdline.log_filters = value,
+                _ => {}
+            }
+        }
+
+        cmdline
+    }
+
+    /// All options, a bare word has an empty value
+    pub fn options(&self) -> impl Iterator<Item = (&'static str, &'static str)> + use<> {
+        options(self.raw)
+    }
+
+    /// Options the kernel does not know, to be reported once logging works
+    pub fn unknown(&self) -> impl Iterator<Item = &'static str> {
+        self.options()
+            .map(|(key, _)| key)
+            .filter(|key| !matches!(*key, "init" | "root" | "sched" | "loglevel" | "log"))
+    }
+
+    /// Per-module levels, malformed entries are skipped
+    pub fn log_filters(&self) -> impl Iterator<Item = (&'static str, LevelFilter)> {
+        self.log_filters
+            .split(',')
+            .filter_map(|filter| filter.split_once(':'))
+            .filter_map(|(module, level)| Some((module, parse_level(level)?)))
+    }
+
+    /// Level of the most specific filter matching a log target, a filter
+    /// matches a module and its children, with or without the crate name
+    pub fn log_filter(&self, target: &str) -> Option<LevelFilter> {
+        let in_crate = target.split_once("::").map_or("", |(_, path)| path);
+
+        self.log_filters()
+            .filter(|(module, _)| is_module(target, module) || is_module(in_crate, module))
+            .max_by_key(|(module, _)| module.len())
+            .map(|(_, level)| level)
+    }
+}
+
+fn options(raw: &'static str) -> impl Iterator<Item = (&'static str, &'static str)> {
+    raw.split_whitespace()
+        .map(|option| option.split_once('=').unwrap_or((option, "")))
+}
+
+/// Whether `path` is `module` or inside it
+fn is_module(path: &str, module: &str) -> bool {
+    path.strip_prefix(module)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
@@ -1,18 +1,37 @@
+use super::cmdline::{get_cmdline, parse_level};
 use log::{LevelFilter, Metadata, Record};
 
+/// Level for targets without a filter on the command line
+static LEVEL: spin::Once<LevelFilter> = spin::Once::new();
+
 pub fn init(boot_info: &'static boot::BootInfo) {
     static LOGGER: Logger = Logger;
     log::set_logger(&LOGGER).unwrap();
-    log::set_max_level(match boot_info.log_level {
-        "error" => LevelFilter::Error,
-        "warn" => LevelFilter::Warn,
-        "info" => LevelFilter::Info,
-        "debug" => LevelFilter::Debug,
-        "trace" => LevelFilter::Trace,
-        _ => LevelFilter::Info,
+
+    let cmdline = get_cmdline();
+    let level = *LEVEL.call_once(|| {
+        cmdline
+            .log_level
+            .or(parse_level(boot_info.log_level))
+            .unwrap_or(LevelFilter::Info)
     });
 
-    info!("Current log level: {}", log::max_level());
+    // records are dropped above the max level before any filter sees them
+    let max_level = cmdline
+        .log_filters()
+        .map(|(_, level)| level)
+        .fold(level, |max, level| max.max(level));
+    log::set_max_level(max_level);
+
+    info!("Current log level: {}", level);
+
+    for (module, level) in cmdline.log_filters() {
+        info!("Log level of {}: {}", module, level);
+    }
+
+    for option in cmdline.unknown() {
+        warn!("Unknown kernel option: {}", option);
+    }
 
     info!("Logger Initialized.");
 }
@@ -21,7 +40,12 @@ struct Logger;
 
 impl log::Log for Logger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= metadata.level()
+        let level = get_cmdline()
+            .log_filter(metadata.target())
+            .or(LEVEL.get().copied())
+            .unwrap_or(LevelFilter::Info);
+
+        metadata.level() <= level
     }
 
     fn log(&self, record: &Record) {
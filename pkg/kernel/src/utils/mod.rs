@@ -4,10 +4,12 @@ mod macros;
 mod regs;
 
 pub mod clock;
+pub mod cmdline;
 pub mod colors;
 pub mod font;
 pub mod func;
 pub mod logger;
+pub mod pipe;
 pub mod resource;
 
 pub use macros::*;
@@ -0,0 +1,139 @@
+//! Anonymous pipes
+//!
+//! A pipe is a ring buffer with a read end and a write end, both of them
+//! resources that duplicated fds and forked children share. Reading an
+//! empty pipe or writing a full one gives `EAGAIN` and the caller waits
+//! for the other end to wake it up. Readers get end of file once every
+//! write end is closed, and writers get `EPIPE` once every read end is.
+
+use alloc::{collections::VecDeque, sync::Arc};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use spin::Mutex;
+use syscall_def::Errno;
+
+/// Bytes a pipe holds, writes up to this size are never split
+pub const PIPE_SIZE: usize = 4096;
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+struct PipeBuffer {
+    data: VecDeque<u8>,
+    readers: usize,
+    writers: usize,
+}
+
+pub struct PipeEnd {
+    /// What processes blocked on the pipe wait for
+    id: usize,
+    buffer: Arc<Mutex<PipeBuffer>>,
+    write: bool,
+}
+
+/// A new pipe, as its read end and write end
+pub fn pipe() -> (PipeEnd, PipeEnd) {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let buffer = Arc::new(Mutex::new(PipeBuffer {
+        data: VecDeque::with_capacity(PIPE_SIZE),
+        readers: 1,
+        writers: 1,
+    }));
+
+    let read_end = PipeEnd {
+        id,
+        buffer: buffer.clone(),
+        write: false,
+    };
+
+    let write_end = PipeEnd {
+        id,
+        buffer,
+        write: true,
+    };
+
+    (read_end, write_end)
+}
+
+impl PipeEnd {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize, Errno> {
+        if self.write {
+            return Err(Errno::EBADF);
+        }
+
+        let mut pipe = self.buffer.lock();
+
+        if pipe.data.is_empty() {
+            return match pipe.writers {
+                0 => Ok(0),
+                _ if buf.is_empty() => Ok(0),
+                _ => Err(Errno::EAGAIN),
+            };
+        }
+
+        let count = buf.len().min(pipe.data.len());
+        for (dst, src) in buf.iter_mut().zip(pipe.data.drain(..count)) {
+            *dst = src;
+        }
+        drop(pipe);
+
+        // there is room for the writers now
+        crate::proc::wake_pipe(self.id);
+
+        Ok(count)
+    }
+
+    pub fn write(&self, buf: &[u8]) -> Result<usize, Errno> {
+        if !self.write {
+            return Err(Errno::EBADF);
+        }
+
+        let mut pipe = self.buffer.lock();
+
+        if pipe.readers == 0 {
+            return Err(Errno::EPIPE);
+        }
+
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let free = PIPE_SIZE - pipe.data.len();
+        if free == 0 || (buf.len() <= PIPE_SIZE && free < buf.len()) {
+            return Err(Errno::EAGAIN);
+        }
+
+        let count = buf.len().min(free);
+        pipe.data.extend(&buf[..count]);
+        drop(pipe);
+
+        crate::proc::wake_pipe(self.id);
+
+        Ok(count)
+    }
+}
+
+impl Drop for PipeEnd {
+    fn drop(&mut self) {
+        let mut pipe = self.buffer.lock();
+        match self.write {
+            true => pipe.writers -= 1,
+            false => pipe.readers -= 1,
+        }
+        drop(pipe);
+
+        // the other end sees end of file or `EPIPE` instead of waiting
+        crate::proc::wake_pipe(self.id);
+    }
+}
+
+impl core::fmt::Debug for PipeEnd {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.write {
+            true => write!(f, "{}, write", self.id),
+            false => write!(f, "{}, read", self.id),
+        }
+    }
+}
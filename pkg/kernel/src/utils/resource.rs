@@ -1,10 +1,15 @@
-use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use alloc::{collections::BTreeMap, string::String, sync::Arc};
 use pc_keyboard::DecodedKey;
 use spin::Mutex;
-use storage::{Device, FileHandle, random::Random};
+use storage::{FileHandle, FsError, SeekFrom};
+use syscall_def::{Errno, Fd, FileKind, FileMode, FileStat};
 
+use super::pipe::PipeEnd;
 use crate::input::try_get_key;
 
+/// Most files a process may have open at once
+pub const MAX_FDS: usize = 1024;
+
 #[derive(Debug, Clone)]
 pub enum StdIO {
     Stdin,
@@ -12,87 +17,165 @@ pub enum StdIO {
     Stderr,
 }
 
-#[derive(Debug)]
+/// An entry of the fd table, the resource is shared with the fds it was
+/// duplicated to and with forked children, and so is its offset
+#[derive(Debug, Clone)]
+struct FileDescriptor {
+    resource: Arc<Mutex<Resource>>,
+    /// Closed when the process runs another program
+    cloexec: bool,
+}
+
+/// The fd table of a process
+#[derive(Debug, Clone)]
 pub struct ResourceSet {
-    pub handles: BTreeMap<u8, Mutex<Resource>>,
-    recycled: Vec<u8>,
+    handles: BTreeMap<Fd, FileDescriptor>,
 }
 
 impl Default for ResourceSet {
     fn default() -> Self {
         let mut res = Self {
             handles: BTreeMap::new(),
-            recycled: Vec::new(),
         };
 
-        res.open(Resource::Console(StdIO::Stdin));
-        res.open(Resource::Console(StdIO::Stdout));
-        res.open(Resource::Console(StdIO::Stderr));
+        for stdio in [StdIO::Stdin, StdIO::Stdout, StdIO::Stderr] {
+            res.open(Resource::Console(stdio), false)
+                .expect("No fd left for stdio");
+        }
 
         res
     }
 }
 
 impl ResourceSet {
-    pub fn open(&mut self, res: Resource) -> u8 {
-        let fd = self.recycled.pop().unwrap_or_else(|| {
-            let fd = self.handles.len();
-            if fd >= 255 {
-                panic!("Too many resource opened.");
-            }
-            fd as u8
-        });
-
-        self.handles.insert(fd, Mutex::new(res));
-        fd
-    }
-
-    pub fn close(&mut self, fd: u8) -> bool {
-        let success = self.handles.remove(&fd).is_some();
-        if success {
-            self.recycled.push(fd);
-        }
-        success
+    /// The lowest fd that is not open, starting at `from`
+    fn free_fd(&self, from: Fd) -> Result<Fd, Errno> {
+        (from..MAX_FDS as Fd)
+            .find(|fd| !self.handles.contains_key(fd))
+            .ok_or(Errno::EMFILE)
+    }
+
+    fn get(&self, fd: Fd) -> Result<&FileDescriptor, Errno> {
+        self.handles.get(&fd).ok_or(Errno::EBADF)
+    }
+
+    pub fn open(&mut self, res: Resource, cloexec: bool) -> Result<Fd, Errno> {
+        let fd = self.free_fd(0)?;
+
+        self.handles.insert(
+            fd,
+            FileDescriptor {
+                resource: Arc::new(Mutex::new(res)),
+                cloexec,
+            },
+        );
+
+        Ok(fd)
     }
 
-    pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize {
-        if let Some(count) = self.handles.get(&fd).and_then(|h| h.lock().read(buf)) {
-            count as isize
-        } else {
-            -1
+    pub fn close(&mut self, fd: Fd) -> bool {
+        self.handles.remove(&fd).is_some()
+    }
+
+    /// Open `fd` again at the lowest free fd
+    pub fn dup(&mut self, fd: Fd) -> Result<Fd, Errno> {
+        let resource = self.get(fd)?.resource.clone();
+        let new_fd = self.free_fd(0)?;
+
+        self.handles.insert(
+            new_fd,
+            FileDescriptor {
+                resource,
+                cloexec: false,
+            },
+        );
+
+        Ok(new_fd)
+    }
+
+    /// Open `fd` again at `new_fd`, closing what was open there
+    pub fn dup2(&mut self, fd: Fd, new_fd: Fd) -> Result<Fd, Errno> {
+        let resource = self.get(fd)?.resource.clone();
+
+        if new_fd as usize >= MAX_FDS {
+            return Err(Errno::EBADF);
+        }
+
+        if fd != new_fd {
+            self.handles.insert(
+                new_fd,
+                FileDescriptor {
+                    resource,
+                    cloexec: false,
+                },
+            );
         }
+
+        Ok(new_fd)
     }
 
-    pub fn write(&self, fd: u8, buf: &[u8]) -> isize {
-        if let Some(count) = self.handles.get(&fd).and_then(|h| h.lock().write(buf)) {
-            count as isize
-        } else {
-            -1
+    pub fn cloexec(&self, fd: Fd) -> Result<bool, Errno> {
+        Ok(self.get(fd)?.cloexec)
+    }
+
+    pub fn set_cloexec(&mut self, fd: Fd, cloexec: bool) -> Result<(), Errno> {
+        self.handles.get_mut(&fd).ok_or(Errno::EBADF)?.cloexec = cloexec;
+        Ok(())
+    }
+
+    /// The table a new program starts with, without the close-on-exec fds
+    pub fn close_on_exec(&mut self) {
+        self.handles.retain(|_, desc| !desc.cloexec);
+    }
+
+    /// Open fds and what they refer to
+    pub fn iter(&self) -> impl Iterator<Item = (Fd, &Mutex<Resource>)> {
+        self.handles
+            .iter()
+            .map(|(fd, desc)| (*fd, desc.resource.as_ref()))
+    }
+
+    /// The pipe `fd` is an end of, to wait on when it would block
+    pub fn pipe_id(&self, fd: Fd) -> Option<usize> {
+        match &*self.get(fd).ok()?.resource.lock() {
+            Resource::Pipe(end) => Some(end.id()),
+            _ => None,
         }
     }
+
+    pub fn read(&self, fd: Fd, buf: &mut [u8]) -> Result<usize, Errno> {
+        self.get(fd)?.resource.lock().read(buf)
+    }
+
+    pub fn write(&self, fd: Fd, buf: &[u8]) -> Result<usize, Errno> {
+        self.get(fd)?.resource.lock().write(buf)
+    }
+
+    pub fn seek(&self, fd: Fd, pos: SeekFrom) -> Result<usize, Errno> {
+        self.get(fd)?.resource.lock().seek(pos)
+    }
+
+    pub fn fstat(&self, fd: Fd) -> Result<FileStat, Errno> {
+        Ok(self.get(fd)?.resource.lock().stat())
+    }
 }
 
 pub enum Resource {
-    File(FileHandle),
+    /// A file and the mode it was opened with
+    File(FileHandle, FileMode),
     Console(StdIO),
-    Random(Random),
-    Null,
+    Pipe(PipeEnd),
 }
 
 impl Resource {
-    fn read(&mut self, buf: &mut [u8]) -> Option<usize> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Errno> {
         match self {
-            Resource::File(file) => {
-                let ret = file.read(buf);
-                if let Err(e) = ret {
-                    error!("Failed to read file: {:?}", e);
-                    None
-                } else {
-                    Some(ret.unwrap())
-                }
-            }
+            Resource::File(file, _) => file.read(buf).map_err(|e| {
+                error!("Failed to read file: {:?}", e);
+                Errno::EIO
+            }),
             Resource::Console(stdio) => match stdio {
-                &mut StdIO::Stdin => Some(if buf.len() < 4 {
+                &mut StdIO::Stdin => Ok(if buf.len() < 4 {
                     0
                 } else if let Some(DecodedKey::Unicode(k)) = try_get_key() {
                     let s = k.encode_utf8(buf);
@@ -100,29 +183,64 @@ impl Resource {
                 } else {
                     0
                 }),
-                _ => Some(0),
+                _ => Ok(0),
             },
-            Resource::Random(random) => Some(random.read(buf, 0, buf.len()).unwrap()),
-            Resource::Null => Some(0),
+            Resource::Pipe(end) => end.read(buf),
         }
     }
 
-    fn write(&self, buf: &[u8]) -> Option<usize> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Errno> {
         match self {
-            Resource::File(_) => None,
+            Resource::File(_, FileMode::ReadOnly) => Err(Errno::EBADF),
+            Resource::File(file, _) => file.write(buf).map_err(|e| {
+                error!("Failed to write file: {:?}", e);
+                Errno::EIO
+            }),
             Resource::Console(stdio) => match *stdio {
-                StdIO::Stdin => Some(0),
+                StdIO::Stdin => Ok(0),
                 StdIO::Stdout => {
                     print!("{}", String::from_utf8_lossy(buf));
-                    Some(buf.len())
+                    Ok(buf.len())
                 }
                 StdIO::Stderr => {
                     warn!("{}", String::from_utf8_lossy(buf));
-                    Some(buf.len())
+                    Ok(buf.len())
                 }
             },
-            Resource::Random(_) => Some(0),
-            Resource::Null => Some(buf.len()),
+            Resource::Pipe(end) => end.write(buf),
+        }
+    }
+
+    fn seek(&mut self, pos: SeekFrom) -> Result<usize, Errno> {
+        match self {
+            Resource::File(file, _) => file.seek(pos).map_err(|e| match e {
+                // a device that is a stream
+                FsError::NotSupported => Errno::ESPIPE,
+                e => {
+                    error!("Failed to seek file: {:?}", e);
+                    Errno::EINVAL
+                }
+            }),
+            // streams have no position to move
+            _ => Err(Errno::ESPIPE),
+        }
+    }
+}
+
+impl Resource {
+    fn stat(&self) -> FileStat {
+        let kind = match self {
+            Resource::File(file, _) => return crate::filesystem::file_stat(&file.meta),
+            Resource::Console(_) => FileKind::Console,
+            Resource::Pipe(_) => FileKind::Pipe,
+        };
+
+        FileStat {
+            kind,
+            len: 0,
+            created: 0,
+            modified: 0,
+            accessed: 0,
         }
     }
 }
@@ -130,10 +248,9 @@ impl Resource {
 impl core::fmt::Debug for Resource {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
-            Resource::File(h) => write!(f, "File({})", h.meta.name),
+            Resource::File(h, _) => write!(f, "File({})", h.meta.name),
             Resource::Console(c) => write!(f, "Console({:?})", c),
-            Resource::Random(_) => write!(f, "Random"),
-            Resource::Null => write!(f, "Null"),
+            Resource::Pipe(p) => write!(f, "Pipe({:?})", p),
         }
     }
 }
@@ -0,0 +1,68 @@
+//! Arguments and environment of the running program
+//!
+//! The kernel leaves them on the initial stack, `entry!` records where it
+//! is and they are read from there when asked for.
+
+use core::ffi::{CStr, c_char};
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use alloc::vec::Vec;
+
+/// The initial stack pointer, pointing at argc
+static STACK: AtomicPtr<usize> = AtomicPtr::new(core::ptr::null_mut());
+
+#[doc(hidden)]
+pub unsafe fn init(stack: *const usize) {
+    STACK.store(stack as *mut usize, Ordering::Relaxed);
+}
+
+/// The strings of a NULL terminated pointer array
+unsafe fn strings(mut ptr: *const *const c_char) -> impl Iterator<Item = &'static str> {
+    core::iter::from_fn(move || unsafe {
+        let s = ptr.read();
+        if s.is_null() {
+            return None;
+        }
+        ptr = ptr.add(1);
+        Some(CStr::from_ptr(s).to_str().unwrap_or_default())
+    })
+}
+
+/// The pointer arrays after argc, argv then envp
+fn argv_envp() -> Option<(*const *const c_char, *const *const c_char)> {
+    let stack = STACK.load(Ordering::Relaxed);
+    if stack.is_null() {
+        return None;
+    }
+
+    unsafe {
+        let argc = stack.read();
+        let argv = stack.add(1) as *const *const c_char;
+        Some((argv, argv.add(argc + 1)))
+    }
+}
+
+/// Arguments of the program, the first one names the program itself
+pub fn args() -> Vec<&'static str> {
+    match argv_envp() {
+        Some((argv, _)) => unsafe { strings(argv).collect() },
+        None => Vec::new(),
+    }
+}
+
+/// The environment as `(key, value)` pairs
+pub fn vars() -> Vec<(&'static str, &'static str)> {
+    match argv_envp() {
+        Some((_, envp)) => unsafe {
+            strings(envp)
+                .filter_map(|var| var.split_once('='))
+                .collect()
+        },
+        None => Vec::new(),
+    }
+}
+
+/// The value of the environment variable `key`
+pub fn var(key: &str) -> Option<&'static str> {
+    vars().into_iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+}
@@ -0,0 +1,458 @@
+//! Files as streams of bytes
+//!
+//! `File` reads and writes an fd through the `Read` and `Write` traits,
+//! and `BufReader` and `BufWriter` put a buffer in front of either, so
+//! lines can be read and small writes do not each take a syscall. The
+//! terminal only gives the keys typed so far, `Input` reads it a line at
+//! a time with the line editing of `readline` instead.
+
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt;
+use core::result::Result;
+
+use crate::readline::{Editor, ReadError};
+use crate::*;
+
+const DEFAULT_BUF_SIZE: usize = 1024;
+
+pub trait Read {
+    /// Read into `buf`, 0 at the end of the file
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Errno>;
+
+    /// Read up to the end of the file into `data`, returning the count
+    fn read_to_end(&mut self, data: &mut Vec<u8>) -> Result<usize, Errno> {
+        let start = data.len();
+        let mut buf = [0; 512];
+
+        loop {
+            match self.read(&mut buf)? {
+                0 => return Ok(data.len() - start),
+                count => data.extend_from_slice(&buf[..count]),
+            }
+        }
+    }
+
+    /// Read up to the end of the file into `text`, invalid UTF-8 replaced
+    fn read_to_string(&mut self, text: &mut String) -> Result<usize, Errno> {
+        let mut data = Vec::new();
+        let count = self.read_to_end(&mut data)?;
+        text.push_str(&String::from_utf8_lossy(&data));
+        Ok(count)
+    }
+}
+
+pub trait Write {
+    /// Write some of `buf`, returning how much
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Errno>;
+
+    /// Send what is buffered on
+    fn flush(&mut self) -> Result<(), Errno> {
+        Ok(())
+    }
+
+    fn write_all(&mut self, mut buf: &[u8]) -> Result<(), Errno> {
+        while !buf.is_empty() {
+            match self.write(buf)? {
+                0 => return Err(Errno::EIO),
+                count => buf = &buf[count..],
+            }
+        }
+        Ok(())
+    }
+
+    /// What `write!` calls, the first error stops it
+    fn write_fmt(&mut self, args: fmt::Arguments) -> Result<(), Errno> {
+        struct Adapter<'a, W: ?Sized> {
+            inner: &'a mut W,
+            error: Option<Errno>,
+        }
+
+        impl<W: Write + ?Sized> fmt::Write for Adapter<'_, W> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.inner.write_all(s.as_bytes()).map_err(|errno| {
+                    self.error = Some(errno);
+                    fmt::Error
+                })
+            }
+        }
+
+        let mut adapter = Adapter {
+            inner: self,
+            error: None,
+        };
+
+        match fmt::write(&mut adapter, args) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(adapter.error.unwrap_or(Errno::EIO)),
+        }
+    }
+}
+
+impl<R: Read + ?Sized> Read for &mut R {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Errno> {
+        (**self).read(buf)
+    }
+}
+
+impl<W: Write + ?Sized> Write for &mut W {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Errno> {
+        (**self).write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Errno> {
+        (**self).flush()
+    }
+}
+
+/// An open fd, closed on drop unless it is one of the standard ones
+#[derive(Debug)]
+pub struct File {
+    fd: Fd,
+    owned: bool,
+}
+
+impl File {
+    /// Open the file at `path` for reading
+    pub fn open(path: &str) -> Result<Self, Errno> {
+        Self::with_mode(path, FileMode::ReadOnly)
+    }
+
+    /// Open the file at `path` for writing, created or truncated
+    pub fn create(path: &str) -> Result<Self, Errno> {
+        Self::with_mode(path, FileMode::ReadWriteCreateOrTruncate)
+    }
+
+    pub fn with_mode(path: &str, mode: FileMode) -> Result<Self, Errno> {
+        Ok(Self {
+            fd: sys_open_cloexec(path, mode)?,
+            owned: true,
+        })
+    }
+
+    pub fn stdin() -> Self {
+        Self {
+            fd: 0,
+            owned: false,
+        }
+    }
+
+    pub fn stdout() -> Self {
+        Self {
+            fd: 1,
+            owned: false,
+        }
+    }
+
+    pub fn stderr() -> Self {
+        Self {
+            fd: 2,
+            owned: false,
+        }
+    }
+
+    pub fn fd(&self) -> Fd {
+        self.fd
+    }
+
+    pub fn stat(&self) -> Result<FileStat, Errno> {
+        sys_fstat(self.fd)
+    }
+
+    /// Whether the fd is the terminal
+    pub fn is_console(&self) -> bool {
+        self.stat().is_ok_and(|stat| stat.kind == FileKind::Console)
+    }
+}
+
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Errno> {
+        sys_read(self.fd, buf).ok_or(Errno::EIO)
+    }
+}
+
+impl Write for File {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Errno> {
+        sys_write(self.fd, buf).ok_or(Errno::EIO)
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        if self.owned {
+            sys_close(self.fd);
+        }
+    }
+}
+
+/// The lines typed on the terminal, up to Ctrl-D on an empty line
+#[derive(Default)]
+pub struct Terminal {
+    editor: Editor,
+    line: Vec<u8>,
+    pos: usize,
+    eof: bool,
+}
+
+impl Read for Terminal {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Errno> {
+        while self.pos == self.line.len() && !self.eof {
+            self.line.clear();
+            self.pos = 0;
+
+            match self.editor.read_line("", None) {
+                Ok(line) => {
+                    self.line = line.into_bytes();
+                    self.line.push(b'\n');
+                }
+                // the line is dropped, not the input
+                Err(ReadError::Interrupted) => (),
+                Err(ReadError::Eof) => self.eof = true,
+            }
+        }
+
+        let count = buf.len().min(self.line.len() - self.pos);
+        buf[..count].copy_from_slice(&self.line[self.pos..][..count]);
+        self.pos += count;
+
+        Ok(count)
+    }
+}
+
+/// What a program reads its data from, a file or standard input
+pub enum Input {
+    File(File),
+    Terminal(Terminal),
+}
+
+impl Input {
+    /// The file at `path`, or standard input for `-`
+    pub fn open(path: &str) -> Result<Self, Errno> {
+        match path {
+            "-" => Ok(Self::stdin()),
+            path => File::open(path).map(Self::File),
+        }
+    }
+
+    pub fn stdin() -> Self {
+        let file = File::stdin();
+        match file.is_console() {
+            true => Self::Terminal(Terminal::default()),
+            false => Self::File(file),
+        }
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Errno> {
+        match self {
+            Self::File(file) => file.read(buf),
+            Self::Terminal(terminal) => terminal.read(buf),
+        }
+    }
+}
+
+pub struct BufReader<R> {
+    inner: R,
+    buf: Vec<u8>,
+    /// The bytes of `buf` not read yet
+    pos: usize,
+    filled: usize,
+}
+
+impl<R: Read> BufReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        Self {
+            inner,
+            buf: vec![0; capacity],
+            pos: 0,
+            filled: 0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// The bytes buffered, read from the inner reader if there are none,
+    /// empty at the end of the file
+    pub fn fill_buf(&mut self) -> Result<&[u8], Errno> {
+        if self.pos == self.filled {
+            self.filled = self.inner.read(&mut self.buf)?;
+            self.pos = 0;
+        }
+        Ok(&self.buf[self.pos..self.filled])
+    }
+
+    /// Mark `count` bytes of `fill_buf` as read
+    pub fn consume(&mut self, count: usize) {
+        self.pos = (self.pos + count).min(self.filled);
+    }
+
+    /// Read into `data` up to and with `byte`, or to the end of the file,
+    /// returning the count
+    pub fn read_until(&mut self, byte: u8, data: &mut Vec<u8>) -> Result<usize, Errno> {
+        let mut count = 0;
+
+        loop {
+            let available = self.fill_buf()?;
+            if available.is_empty() {
+                return Ok(count);
+            }
+
+            let (used, done) = match available.iter().position(|&b| b == byte) {
+                Some(index) => (index + 1, true),
+                None => (available.len(), false),
+            };
+            data.extend_from_slice(&available[..used]);
+            self.consume(used);
+            count += used;
+
+            if done {
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Read a line into `line` with its `\n`, 0 at the end of the file
+    pub fn read_line(&mut self, line: &mut String) -> Result<usize, Errno> {
+        let mut data = Vec::new();
+        let count = self.read_until(b'\n', &mut data)?;
+        line.push_str(&String::from_utf8_lossy(&data));
+        Ok(count)
+    }
+
+    /// The lines up to the end of the file, without `\n` or `\r\n`
+    pub fn lines(self) -> Lines<R> {
+        Lines { reader: self }
+    }
+}
+
+impl<R: Read> Read for BufReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Errno> {
+        // nothing buffered and a large read, the buffer would not help
+        if self.pos == self.filled && buf.len() >= self.buf.len() {
+            return self.inner.read(buf);
+        }
+
+        let available = self.fill_buf()?;
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.consume(count);
+
+        Ok(count)
+    }
+}
+
+pub struct Lines<R> {
+    reader: BufReader<R>,
+}
+
+impl<R: Read> Iterator for Lines<R> {
+    type Item = Result<String, Errno>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => {
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+                Some(Ok(line))
+            }
+            Err(errno) => Some(Err(errno)),
+        }
+    }
+}
+
+/// Gathers writes and sends them on when it is full, when flushed and on
+/// drop, or at every line if it is line buffered
+pub struct BufWriter<W: Write> {
+    inner: W,
+    buf: Vec<u8>,
+    line_buffered: bool,
+}
+
+impl<W: Write> BufWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        Self {
+            inner,
+            buf: Vec::with_capacity(capacity),
+            line_buffered: false,
+        }
+    }
+
+    /// Flush at every `\n`, for output someone is watching
+    pub fn line_buffered(mut self, line_buffered: bool) -> Self {
+        self.line_buffered = line_buffered;
+        self
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    fn flush_buf(&mut self) -> Result<(), Errno> {
+        let result = self.inner.write_all(&self.buf);
+        self.buf.clear();
+        result
+    }
+}
+
+impl BufWriter<File> {
+    /// Standard output, line buffered when it is the terminal
+    pub fn stdout() -> Self {
+        let file = File::stdout();
+        let line_buffered = file.is_console();
+        Self::new(file).line_buffered(line_buffered)
+    }
+}
+
+impl<W: Write> Write for BufWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Errno> {
+        if self.buf.len() + buf.len() > self.buf.capacity() {
+            self.flush_buf()?;
+        }
+
+        if buf.len() >= self.buf.capacity() {
+            self.inner.write_all(buf)?;
+        } else {
+            self.buf.extend_from_slice(buf);
+        }
+
+        if self.line_buffered && buf.contains(&b'\n') {
+            self.flush_buf()?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Errno> {
+        self.flush_buf()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for BufWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// All of the file at `path`, as text
+pub fn read_to_string(path: &str) -> Result<String, Errno> {
+    let mut text = String::new();
+    File::open(path)?.read_to_string(&mut text)?;
+    Ok(text)
+}
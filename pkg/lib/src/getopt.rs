@@ -0,0 +1,109 @@
+//! Options of the command line
+//!
+//! Options are letters after a `-`, and several of them may share a word
+//! as in `-la`. In `spec` a letter followed by `:` takes a value, which is
+//! the rest of its word or the next word, as in `-n5` or `-n 5`. Options
+//! end before the first word that is not one, such as `-` alone, or after
+//! `--`; the words left are the operands.
+
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opt<'a> {
+    Flag(char),
+    Value(char, &'a str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptError {
+    /// A letter that is not in the spec
+    Unknown(char),
+    /// An option that takes a value at the end of the line
+    MissingValue(char),
+}
+
+impl fmt::Display for OptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(c) => write!(f, "invalid option -- '{}'", c),
+            Self::MissingValue(c) => write!(f, "option requires an argument -- '{}'", c),
+        }
+    }
+}
+
+pub struct GetOpt<'a> {
+    args: &'a [&'a str],
+    spec: &'a str,
+    /// The word being read, and the byte in it
+    index: usize,
+    offset: usize,
+}
+
+impl<'a> GetOpt<'a> {
+    /// Read the options in `args`, which start after the program name
+    pub fn new(args: &'a [&'a str], spec: &'a str) -> Self {
+        Self {
+            args,
+            spec,
+            index: 0,
+            offset: 0,
+        }
+    }
+
+    /// The words after the options, all of them once the iterator is done
+    pub fn operands(&self) -> &'a [&'a str] {
+        &self.args[self.index.min(self.args.len())..]
+    }
+
+    /// Whether `c` is in the spec, and if it takes a value
+    fn takes_value(&self, c: char) -> Option<bool> {
+        let index = self.spec.find(c).filter(|_| c != ':')?;
+        Some(self.spec[index + c.len_utf8()..].starts_with(':'))
+    }
+}
+
+impl<'a> Iterator for GetOpt<'a> {
+    type Item = Result<Opt<'a>, OptError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset == 0 {
+            let word = *self.args.get(self.index)?;
+            if word == "--" {
+                self.index += 1;
+                return None;
+            }
+            if word.len() < 2 || !word.starts_with('-') {
+                return None;
+            }
+            self.offset = 1;
+        }
+
+        let word = self.args[self.index];
+        let c = word[self.offset..].chars().next()?;
+        self.offset += c.len_utf8();
+
+        let rest = &word[self.offset..];
+        let at_end = rest.is_empty();
+        if at_end {
+            self.index += 1;
+            self.offset = 0;
+        }
+
+        match self.takes_value(c) {
+            None => Some(Err(OptError::Unknown(c))),
+            Some(false) => Some(Ok(Opt::Flag(c))),
+            Some(true) if !at_end => {
+                self.index += 1;
+                self.offset = 0;
+                Some(Ok(Opt::Value(c, rest)))
+            }
+            Some(true) => match self.args.get(self.index) {
+                Some(value) => {
+                    self.index += 1;
+                    Some(Ok(Opt::Value(c, value)))
+                }
+                None => Some(Err(OptError::MissingValue(c))),
+            },
+        }
+    }
+}
@@ -2,11 +2,24 @@ use crate::*;
 use alloc::string::*;
 use alloc::vec;
 
+pub use syscall_def::{Errno, Fd, FileKind, FileMode, FileStat};
+
+/// Enumeration of possible methods to seek within a file.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum SeekFrom {
+    /// Sets the offset to the provided number of bytes.
+    Start(usize),
+    /// Sets the offset to the size of this file plus the specified number of bytes.
+    End(isize),
+    /// Sets the offset to the current position plus the specified number of bytes.
+    Current(isize),
+}
+
 pub struct Stdin;
 pub struct Stdout;
 pub struct Stderr;
 
-pub struct Random(u8);
+pub struct Random(Fd);
 
 impl Stdin {
     fn new() -> Self {
@@ -34,41 +47,16 @@ impl Stdin {
         }
     }
 
+    /// A line typed by the user, empty after Ctrl-C and `"\x04"` after
+    /// Ctrl-D. See `readline::Editor` for history and completion.
     pub fn read_line(&self) -> String {
-        let mut string = String::new();
-        let mut buf = [0; 4];
-        loop {
-            if let Some(k) = self.read_char_with_buf(&mut buf[..4]) {
-                match k {
-                    '\n' => {
-                        stdout().write("\n");
-                        break;
-                    }
-                    '\x03' => {
-                        string.clear();
-                        break;
-                    }
-                    '\x04' => {
-                        string.clear();
-                        string.push('\x04');
-                        break;
-                    }
-                    '\x08' => {
-                        if !string.is_empty() {
-                            stdout().write("\x08");
-                            string.pop();
-                        }
-                    }
-                    // ignore other control characters
-                    '\x00'..='\x1F' => {}
-                    c => {
-                        self::print!("{}", k);
-                        string.push(c);
-                    }
-                }
-            }
+        use crate::readline::{Editor, ReadError};
+
+        match Editor::default().read_line("", None) {
+            Ok(line) => line,
+            Err(ReadError::Interrupted) => String::new(),
+            Err(ReadError::Eof) => String::from("\x04"),
         }
-        string
     }
 }
 
@@ -78,7 +66,7 @@ impl Stdout {
     }
 
     pub fn write(&self, s: &str) {
-        sys_write(1, s.as_bytes());
+        write_all(1, s.as_bytes());
     }
 }
 
@@ -88,13 +76,25 @@ impl Stderr {
     }
 
     pub fn write(&self, s: &str) {
-        sys_write(2, s.as_bytes());
+        write_all(2, s.as_bytes());
     }
 }
 
+/// Write the whole of `buf`, a pipe may take only part of it at a time
+pub fn write_all(fd: Fd, mut buf: &[u8]) -> bool {
+    while !buf.is_empty() {
+        match sys_write(fd, buf) {
+            Some(count) if count > 0 => buf = &buf[count..],
+            _ => return false,
+        }
+    }
+
+    true
+}
+
 impl Random {
     pub fn new() -> Self {
-        Self(sys_open("/dev/random", FileMode::ReadOnly))
+        Self(sys_open_cloexec("/dev/random", FileMode::ReadOnly).expect("Cannot open /dev/random"))
     }
 
     pub fn next_u32(&self) -> u32 {
@@ -147,21 +147,3 @@ pub fn stdout() -> Stdout {
 pub fn stderr() -> Stderr {
     Stderr::new()
 }
-
-/// The different ways we can open a file.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-#[repr(u8)]
-pub enum FileMode {
-    /// Open a file for reading, if it exists.
-    ReadOnly = 0,
-    /// Open a file for appending (writing to the end of the existing file), if it exists.
-    ReadWriteAppend = 1,
-    /// Open a file and remove all contents, before writing to the start of the existing file, if it exists.
-    ReadWriteTruncate = 2,
-    /// Create a new empty file. Fail if it exists.
-    ReadWriteCreate = 3,
-    /// Create a new empty file, or truncate an existing file.
-    ReadWriteCreateOrTruncate = 4,
-    /// Create a new empty file, or append to an existing file.
-    ReadWriteCreateOrAppend = 5,
-}
@@ -11,11 +11,17 @@ extern crate syscall_def;
 #[macro_use]
 pub mod io;
 pub mod allocator;
+pub mod env;
+pub mod fs;
+pub mod getopt;
+pub mod readline;
+pub mod signal;
 pub mod sync;
+pub mod thread;
+pub mod time;
 pub extern crate alloc;
 
 mod syscall;
-mod utils;
 
 use core::fmt::*;
 
@@ -24,7 +30,7 @@ pub use chrono::*;
 pub use io::*;
 pub use sync::*;
 pub use syscall::*;
-pub use utils::*;
+pub use time::sleep;
 
 pub fn init() {
     #[cfg(feature = "brk_alloc")]
@@ -1,13 +1,24 @@
 use crate::alloc::string::ToString;
 use crate::errln;
 
+/// Define the entry of a program, `fn main(args: &[&str]) -> isize`
+///
+/// The stack starts with argc, so `_start` passes it to the real entry
+/// before anything is pushed.
 #[macro_export]
 macro_rules! entry {
     ($fn:ident) => {
+        #[unsafe(naked)]
         #[unsafe(export_name = "_start")]
-        pub extern "C" fn __impl_start() {
+        pub extern "C" fn __impl_start() -> ! {
+            core::arch::naked_asm!("mov rdi, rsp", "call {}", sym __impl_main)
+        }
+
+        extern "C" fn __impl_main(stack: *const usize) -> ! {
             lib::init();
-            let ret = $fn();
+            unsafe { lib::env::init(stack) };
+            let args = lib::env::args();
+            let ret = $fn(&args);
             lib::sys_exit(ret);
         }
     };
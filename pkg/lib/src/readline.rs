@@ -0,0 +1,607 @@
+//! Line editing
+//!
+//! Lines are read from the terminal a key at a time. The cursor moves with
+//! the arrow keys, Home and End, Up and Down recall the lines entered
+//! before, Ctrl-R searches them and Tab completes the word before the
+//! cursor. Keys without a character come as VT100 escape sequences, which
+//! the kernel sends for the keyboard as a serial terminal does.
+
+use crate::*;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::*;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Lines a history keeps unless told otherwise
+pub const HISTORY_SIZE: usize = 128;
+
+/// Yields to wait for the rest of an escape sequence, a lone Escape
+/// after that
+const ESCAPE_WAIT: usize = 64;
+
+/// Why no line was read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadError {
+    /// Ctrl-C, the line is dropped
+    Interrupted,
+    /// Ctrl-D on an empty line
+    Eof,
+}
+
+/// Offers ways to finish what is being typed
+pub trait Completer {
+    /// Candidates for the end of `line`, which is the text before the
+    /// cursor, and the offset of the part they replace
+    fn complete(&self, line: &str) -> (usize, Vec<String>);
+}
+
+/// Lines entered before, the oldest dropped once there are `capacity`
+pub struct History {
+    lines: VecDeque<String>,
+    capacity: usize,
+    /// File the lines are appended to, None once it cannot be written
+    path: Option<String>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(HISTORY_SIZE)
+    }
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(capacity),
+            capacity,
+            path: None,
+        }
+    }
+
+    /// The history kept in the file at `path`, which lines are appended to
+    /// as they are added. It stays in memory if the file cannot be written.
+    pub fn with_file(capacity: usize, path: &str) -> Self {
+        let mut history = Self::new(capacity);
+
+        let content = read_file(path).unwrap_or_default();
+        let mut count = 0;
+        for line in content.lines() {
+            history.push(line);
+            count += 1;
+        }
+
+        // write only what is kept, so the file does not grow without end
+        if count > 2 * capacity {
+            history.rewrite(path);
+        }
+
+        history.path = Some(String::from(path));
+        history
+    }
+
+    /// Keep `line`, false if it is empty or the same as the last one
+    fn push(&mut self, line: &str) -> bool {
+        if line.trim().is_empty() || self.lines.back().is_some_and(|last| last == line) {
+            return false;
+        }
+
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+
+        self.lines.push_back(String::from(line));
+        true
+    }
+
+    pub fn add(&mut self, line: &str) {
+        if !self.push(line) {
+            return;
+        }
+
+        if let Some(path) = &self.path
+            && !write_file(path, &[line], FileMode::ReadWriteCreateOrAppend)
+        {
+            self.path = None;
+        }
+    }
+
+    fn rewrite(&self, path: &str) {
+        let lines: Vec<&str> = self.iter().collect();
+        write_file(path, &lines, FileMode::ReadWriteCreateOrTruncate);
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// The `index`th line, the oldest first
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.lines.get(index).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &str> + ExactSizeIterator {
+        self.lines.iter().map(String::as_str)
+    }
+}
+
+fn read_file(path: &str) -> Option<String> {
+    let fd = sys_open_cloexec(path, FileMode::ReadOnly).ok()?;
+
+    let mut data = Vec::new();
+    let mut buf = [0; 512];
+    while let Some(count) = sys_read(fd, &mut buf)
+        && count > 0
+    {
+        data.extend_from_slice(&buf[..count]);
+    }
+    sys_close(fd);
+
+    Some(String::from_utf8_lossy(&data).into_owned())
+}
+
+fn write_file(path: &str, lines: &[&str], mode: FileMode) -> bool {
+    let Ok(fd) = sys_open_cloexec(path, mode) else {
+        return false;
+    };
+
+    let mut data = String::new();
+    for line in lines {
+        data.push_str(line);
+        data.push('\n');
+    }
+
+    let written = write_all(fd, data.as_bytes());
+    sys_close(fd);
+    written
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Tab,
+    /// Ctrl-C
+    Interrupt,
+    /// Ctrl-D
+    Eof,
+    /// Ctrl-R
+    Search,
+    /// Ctrl-U, everything before the cursor
+    KillStart,
+    /// Ctrl-K, everything after the cursor
+    KillEnd,
+    /// Ctrl-W, the word before the cursor
+    KillWord,
+    Escape,
+    Other,
+}
+
+/// The next character typed, waiting for it unless `wait` runs out
+fn next_char(mut wait: Option<usize>) -> Option<char> {
+    let mut buf = [0; 4];
+    loop {
+        if let Some(c) = stdin().read_char_with_buf(&mut buf) {
+            return Some(c);
+        }
+
+        match wait.as_mut() {
+            Some(0) => return None,
+            Some(n) => *n -= 1,
+            None => (),
+        }
+
+        // nothing typed yet, let others run meanwhile
+        sys_yield();
+    }
+}
+
+/// The rest of an escape sequence, the `\x1b` is taken already
+fn escape() -> Key {
+    let Some(kind) = next_char(Some(ESCAPE_WAIT)) else {
+        return Key::Escape;
+    };
+
+    if kind != '[' && kind != 'O' {
+        return Key::Other;
+    }
+
+    // `CSI n ~` for the keys above the arrows, `CSI x` or `SS3 x` else
+    let mut number = 0;
+    loop {
+        let Some(c) = next_char(Some(ESCAPE_WAIT)) else {
+            return Key::Other;
+        };
+
+        match c {
+            '0'..='9' => number = number * 10 + (c as usize - '0' as usize),
+            'A' => return Key::Up,
+            'B' => return Key::Down,
+            'C' => return Key::Right,
+            'D' => return Key::Left,
+            'H' => return Key::Home,
+            'F' => return Key::End,
+            '~' => {
+                return match number {
+                    1 | 7 => Key::Home,
+                    3 => Key::Delete,
+                    4 | 8 => Key::End,
+                    _ => Key::Other,
+                };
+            }
+            ';' => (),
+            _ => return Key::Other,
+        }
+    }
+}
+
+fn read_key() -> Key {
+    match next_char(None) {
+        Some('\n' | '\r') => Key::Enter,
+        Some('\x08' | '\x7f') => Key::Backspace,
+        Some('\t') => Key::Tab,
+        Some('\x01') => Key::Home,
+        Some('\x02') => Key::Left,
+        Some('\x03') => Key::Interrupt,
+        Some('\x04') => Key::Eof,
+        Some('\x05') => Key::End,
+        Some('\x06') => Key::Right,
+        Some('\x0b') => Key::KillEnd,
+        Some('\x0e') => Key::Down,
+        Some('\x10') => Key::Up,
+        Some('\x12') => Key::Search,
+        Some('\x15') => Key::KillStart,
+        Some('\x17') => Key::KillWord,
+        Some('\x1b') => escape(),
+        Some(c) if c.is_control() => Key::Other,
+        Some(c) => Key::Char(c),
+        None => Key::Other,
+    }
+}
+
+/// The line being edited
+#[derive(Default)]
+struct Line {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl Line {
+    fn set(&mut self, text: &str) {
+        self.chars = text.chars().collect();
+        self.cursor = self.chars.len();
+    }
+
+    fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    fn before_cursor(&self) -> String {
+        self.chars[..self.cursor].iter().collect()
+    }
+
+    fn insert(&mut self, text: &str) {
+        for c in text.chars() {
+            self.chars.insert(self.cursor, c);
+            self.cursor += 1;
+        }
+    }
+
+    /// Remove the characters from `start` up to the cursor
+    fn remove_back_to(&mut self, start: usize) {
+        self.chars.drain(start..self.cursor);
+        self.cursor = start;
+    }
+
+    /// Where the word before the cursor starts
+    fn word_start(&self) -> usize {
+        let mut start = self.cursor;
+        while start > 0 && self.chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !self.chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        start
+    }
+}
+
+/// What is shown after the prompt, to draw it again after a change
+#[derive(Default)]
+struct Screen {
+    /// Columns between the end of the prompt and the cursor
+    cursor: usize,
+}
+
+impl Screen {
+    /// Show `text` in place of what was there, with the cursor after its
+    /// first `cursor` characters
+    fn draw(&mut self, text: &str, cursor: usize) {
+        let mut out = String::new();
+        if self.cursor > 0 {
+            write!(out, "\x1b[{}D", self.cursor).unwrap();
+        }
+
+        out.push_str(text);
+        out.push_str("\x1b[K");
+
+        let len = text.chars().count();
+        if len > cursor {
+            write!(out, "\x1b[{}D", len - cursor).unwrap();
+        }
+
+        self.cursor = cursor;
+        stdout().write(&out);
+    }
+
+    fn show(&mut self, line: &Line) {
+        self.draw(&line.text(), line.cursor);
+    }
+}
+
+/// Reads lines with editing, history and completion
+#[derive(Default)]
+pub struct Editor {
+    pub history: History,
+}
+
+impl Editor {
+    pub fn new(history: History) -> Self {
+        Self { history }
+    }
+
+    /// Show `prompt` and read a line. The line is not added to the
+    /// history, as not every line belongs there.
+    pub fn read_line(
+        &mut self,
+        prompt: &str,
+        completer: Option<&dyn Completer>,
+    ) -> core::result::Result<String, ReadError> {
+        crate::print!("{}", prompt);
+
+        let mut line = Line::default();
+        let mut screen = Screen::default();
+        // the history line shown, and the line typed before Up was pressed
+        let mut browsing: Option<usize> = None;
+        let mut draft = String::new();
+
+        loop {
+            match read_key() {
+                Key::Char(c) => {
+                    let at_end = line.cursor == line.chars.len();
+                    line.insert(c.encode_utf8(&mut [0; 4]));
+                    if at_end {
+                        crate::print!("{}", c);
+                        screen.cursor += 1;
+                    } else {
+                        screen.show(&line);
+                    }
+                }
+                Key::Enter => break,
+                Key::Interrupt => {
+                    crate::println!();
+                    return Err(ReadError::Interrupted);
+                }
+                Key::Eof if line.chars.is_empty() => {
+                    crate::println!();
+                    return Err(ReadError::Eof);
+                }
+                Key::Eof | Key::Delete => {
+                    if line.cursor < line.chars.len() {
+                        line.chars.remove(line.cursor);
+                        screen.show(&line);
+                    }
+                }
+                Key::Backspace => {
+                    if line.cursor > 0 {
+                        line.remove_back_to(line.cursor - 1);
+                        screen.show(&line);
+                    }
+                }
+                Key::Left => {
+                    line.cursor = line.cursor.saturating_sub(1);
+                    screen.show(&line);
+                }
+                Key::Right => {
+                    line.cursor = (line.cursor + 1).min(line.chars.len());
+                    screen.show(&line);
+                }
+                Key::Home => {
+                    line.cursor = 0;
+                    screen.show(&line);
+                }
+                Key::End => {
+                    line.cursor = line.chars.len();
+                    screen.show(&line);
+                }
+                Key::KillStart => {
+                    line.remove_back_to(0);
+                    screen.show(&line);
+                }
+                Key::KillEnd => {
+                    line.chars.truncate(line.cursor);
+                    screen.show(&line);
+                }
+                Key::KillWord => {
+                    line.remove_back_to(line.word_start());
+                    screen.show(&line);
+                }
+                Key::Up => {
+                    let index = match browsing {
+                        Some(0) => continue,
+                        Some(index) => index - 1,
+                        None if self.history.is_empty() => continue,
+                        None => {
+                            draft = line.text();
+                            self.history.len() - 1
+                        }
+                    };
+
+                    browsing = Some(index);
+                    line.set(self.history.get(index).unwrap_or_default());
+                    screen.show(&line);
+                }
+                Key::Down => {
+                    let Some(index) = browsing else {
+                        continue;
+                    };
+
+                    if index + 1 < self.history.len() {
+                        browsing = Some(index + 1);
+                        line.set(self.history.get(index + 1).unwrap_or_default());
+                    } else {
+                        browsing = None;
+                        line.set(&draft);
+                    }
+                    screen.show(&line);
+                }
+                Key::Tab => {
+                    if let Some(completer) = completer {
+                        Self::complete(completer, prompt, &mut line, &mut screen);
+                    }
+                }
+                Key::Search => {
+                    if self.search(&mut line, &mut screen) {
+                        break;
+                    }
+                }
+                Key::Escape | Key::Other => (),
+            }
+        }
+
+        crate::println!();
+        Ok(line.text())
+    }
+
+    /// Complete the word before the cursor, or list the ways to when
+    /// there is more than one
+    fn complete(completer: &dyn Completer, prompt: &str, line: &mut Line, screen: &mut Screen) {
+        let before = line.before_cursor();
+        let (start, candidates) = completer.complete(&before);
+        let start = before.get(..start).map_or(0, |s| s.chars().count());
+        let typed: String = line.chars[start..line.cursor].iter().collect();
+
+        let Some(first) = candidates.first() else {
+            return;
+        };
+
+        if let [only] = candidates.as_slice() {
+            line.remove_back_to(start);
+            line.insert(only);
+            // a directory may go on, anything else is a whole word
+            if !only.ends_with('/') {
+                line.insert(" ");
+            }
+            screen.show(line);
+            return;
+        }
+
+        // what all of them start with
+        let mut common = first.len();
+        for candidate in &candidates[1..] {
+            common = first
+                .char_indices()
+                .zip(candidate.chars())
+                .take_while(|((_, a), b)| a == b)
+                .last()
+                .map_or(0, |((i, a), _)| (i + a.len_utf8()).min(common));
+        }
+
+        if common > typed.len() {
+            line.remove_back_to(start);
+            line.insert(&first[..common]);
+            screen.show(line);
+            return;
+        }
+
+        crate::println!();
+        crate::println!("{}", candidates.join("  "));
+        crate::print!("{}", prompt);
+        *screen = Screen::default();
+        screen.show(line);
+    }
+
+    /// Ctrl-R, look through the history for a line holding what is typed.
+    /// True if the line found is to be run at once.
+    fn search(&self, line: &mut Line, screen: &mut Screen) -> bool {
+        let original = line.text();
+        let mut query = String::new();
+        // the line found, searched before for the next Ctrl-R
+        let mut found: Option<usize> = None;
+        let mut failed = false;
+
+        let find = |query: &str, before: usize| {
+            self.history
+                .iter()
+                .take(before)
+                .rposition(|text| text.contains(query))
+        };
+
+        loop {
+            let shown = found
+                .and_then(|index| self.history.get(index))
+                .unwrap_or("");
+            let status = if failed {
+                "failed reverse-i-search"
+            } else {
+                "reverse-i-search"
+            };
+            let text = format!("({})`{}': {}", status, query, shown);
+            let cursor = text.chars().count() - shown.chars().count();
+            screen.draw(&text, cursor);
+
+            match read_key() {
+                Key::Char(c) => {
+                    query.push(c);
+                    let from = found.map_or(self.history.len(), |index| index + 1);
+                    match find(&query, from) {
+                        Some(index) => {
+                            found = Some(index);
+                            failed = false;
+                        }
+                        None => failed = true,
+                    }
+                }
+                Key::Backspace => {
+                    query.pop();
+                    found = find(&query, self.history.len()).filter(|_| !query.is_empty());
+                    failed = false;
+                }
+                Key::Search => {
+                    let from = found.unwrap_or(self.history.len());
+                    match find(&query, from) {
+                        Some(index) => {
+                            found = Some(index);
+                            failed = false;
+                        }
+                        None => failed = true,
+                    }
+                }
+                Key::Interrupt | Key::Escape => {
+                    line.set(&original);
+                    screen.show(line);
+                    return false;
+                }
+                key => {
+                    if let Some(text) = found.and_then(|index| self.history.get(index)) {
+                        line.set(text);
+                    }
+                    screen.show(line);
+                    return key == Key::Enter;
+                }
+            }
+        }
+    }
+}
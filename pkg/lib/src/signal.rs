@@ -0,0 +1,57 @@
+//! Signal handlers
+//!
+//! The kernel runs a handler on the stack of the thread the signal is
+//! delivered to, and the handler returns into [`restorer`], which asks the
+//! kernel to continue where the thread was interrupted.
+
+use syscall_def::Syscall;
+
+pub use syscall_def::{SIG_DFL, SIG_IGN, SigAction, SigHow, Signal};
+
+use crate::*;
+
+/// Where every handler set here returns to
+#[unsafe(naked)]
+extern "C" fn restorer() -> ! {
+    core::arch::naked_asm!("mov rax, {}", "int 0x80", const Syscall::SigReturn as u16)
+}
+
+/// Run `handler` when `signal` arrives, returning the old handler or None
+/// if the signal can't be caught
+pub fn signal(signal: Signal, handler: extern "C" fn(Signal)) -> Option<usize> {
+    set_handler(signal, handler as usize)
+}
+
+/// Ignore `signal`
+pub fn ignore(signal: Signal) -> Option<usize> {
+    set_handler(signal, SIG_IGN)
+}
+
+/// Take the default action of `signal` again
+pub fn reset(signal: Signal) -> Option<usize> {
+    set_handler(signal, SIG_DFL)
+}
+
+fn set_handler(signal: Signal, handler: usize) -> Option<usize> {
+    let action = SigAction {
+        handler,
+        mask: 0,
+        restorer: restorer as *const () as usize,
+    };
+
+    sys_sigaction(signal, Some(&action)).map(|old| old.handler)
+}
+
+/// Block `signals` until they are unblocked, returning the old mask
+pub fn block(signals: &[Signal]) -> u64 {
+    sys_sigprocmask(SigHow::Block, mask_of(signals))
+}
+
+/// Unblock `signals`, returning the old mask
+pub fn unblock(signals: &[Signal]) -> u64 {
+    sys_sigprocmask(SigHow::Unblock, mask_of(signals))
+}
+
+fn mask_of(signals: &[Signal]) -> u64 {
+    signals.iter().fold(0, |mask, signal| mask | signal.bit())
+}
@@ -1,5 +1,12 @@
 use chrono::{DateTime, Utc};
-use syscall_def::Syscall;
+use core::mem::MaybeUninit;
+use core::time::Duration;
+use syscall_def::{
+    ChildStatus, ClockId, DirEntry, Errno, F_GETFD, F_SETFD, FD_CLOEXEC, Fd, FileStat, O_CLOEXEC,
+    SigAction, SigHow, Signal, Syscall, WNOHANG, WUNTRACED,
+};
+
+use alloc::{format, string::String, vec, vec::Vec};
 
 #[inline(always)]
 pub fn sys_draw(x: i32, y: i32, color: u32) -> usize {
@@ -7,7 +14,7 @@ pub fn sys_draw(x: i32, y: i32, color: u32) -> usize {
 }
 
 #[inline(always)]
-pub fn sys_write(fd: u8, buf: &[u8]) -> Option<usize> {
+pub fn sys_write(fd: Fd, buf: &[u8]) -> Option<usize> {
     let ret = syscall!(
         Syscall::Write,
         fd as u64,
@@ -22,7 +29,7 @@ pub fn sys_write(fd: u8, buf: &[u8]) -> Option<usize> {
 }
 
 #[inline(always)]
-pub fn sys_read(fd: u8, buf: &mut [u8]) -> Option<usize> {
+pub fn sys_read(fd: Fd, buf: &mut [u8]) -> Option<usize> {
     let ret = syscall!(
         Syscall::Read,
         fd as u64,
@@ -36,6 +43,21 @@ pub fn sys_read(fd: u8, buf: &mut [u8]) -> Option<usize> {
     }
 }
 
+#[inline(always)]
+pub fn sys_seek(fd: Fd, pos: crate::SeekFrom) -> Option<usize> {
+    let (offset, whence) = match pos {
+        crate::SeekFrom::Start(offset) => (offset as isize, 0),
+        crate::SeekFrom::Current(offset) => (offset, 1),
+        crate::SeekFrom::End(offset) => (offset, 2),
+    };
+    let ret = syscall!(Syscall::Seek, fd as u64, offset as u64, whence as u64) as isize;
+    if ret.is_negative() {
+        None
+    } else {
+        Some(ret as usize)
+    }
+}
+
 #[inline(always)]
 pub fn sys_allocate(layout: &core::alloc::Layout) -> *mut u8 {
     syscall!(Syscall::Allocate, layout as *const _) as *mut u8
@@ -54,7 +76,65 @@ pub fn sys_exit(code: isize) -> ! {
 
 #[inline(always)]
 pub fn sys_wait_pid(pid: u16) -> isize {
-    syscall!(Syscall::WaitPid, pid as u64) as isize
+    sys_waitpid(Some(pid), false).map_or(-1, |(_, status)| status)
+}
+
+/// Wait for the child `pid`, or any child, to exit and collect it,
+/// returning its pid and exit code; None if there is no such child, or
+/// with `nohang` if none has exited yet
+#[inline(always)]
+pub fn sys_waitpid(pid: Option<u16>, nohang: bool) -> Option<(u16, isize)> {
+    let flags = if nohang { WNOHANG } else { 0 };
+
+    waitpid(pid, flags).map(|(pid, status)| (pid, status.code))
+}
+
+/// How a child `waitpid` returned for ended up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitStatus {
+    Exited(isize),
+    Stopped(Signal),
+}
+
+/// Like `sys_waitpid`, but also returns once for a child that stopped
+#[inline(always)]
+pub fn sys_waitpid_untraced(pid: Option<u16>, nohang: bool) -> Option<(u16, WaitStatus)> {
+    let flags = if nohang { WNOHANG } else { 0 } | WUNTRACED;
+
+    waitpid(pid, flags).map(|(pid, status)| match Signal::try_from(status.stopped) {
+        Ok(signal) => (pid, WaitStatus::Stopped(signal)),
+        Err(_) => (pid, WaitStatus::Exited(status.code)),
+    })
+}
+
+#[inline(always)]
+fn waitpid(pid: Option<u16>, flags: usize) -> Option<(u16, ChildStatus)> {
+    let mut status = ChildStatus::default();
+    let pid = pid.map_or(-1, |pid| pid as isize);
+
+    match syscall!(
+        Syscall::WaitPid,
+        pid,
+        &mut status as *mut ChildStatus,
+        flags
+    ) as isize
+    {
+        pid if pid > 0 => Some((pid as u16, status)),
+        _ => None,
+    }
+}
+
+/// Nanoseconds on `clock`
+#[inline(always)]
+pub fn sys_clock_gettime(clock: ClockId) -> Duration {
+    Duration::from_nanos(syscall!(Syscall::ClockGetTime, clock as u64) as u64)
+}
+
+/// Block for `duration`, false if a signal ended it early
+#[inline(always)]
+pub fn sys_nanosleep(duration: Duration) -> bool {
+    let ns = duration.as_nanos().min(u64::MAX as u128) as u64;
+    syscall!(Syscall::NanoSleep, ns) == 0
 }
 
 #[inline(always)]
@@ -65,49 +145,444 @@ pub fn sys_time() -> DateTime<Utc> {
 }
 
 #[inline(always)]
-pub fn sys_list_dir(root: &str) {
-    syscall!(Syscall::ListDir, root.as_ptr() as u64, root.len() as u64);
+pub fn sys_list_process() {
+    syscall!(Syscall::ListProcess);
 }
 
-#[inline(always)]
-pub fn sys_stat() {
-    syscall!(Syscall::Stat);
+/// A file or directory, as `stat` and `read_dir` tell of it
+#[derive(Debug, Clone)]
+pub struct Metadata {
+    pub name: String,
+    pub stat: FileStat,
+}
+
+impl Metadata {
+    pub fn is_dir(&self) -> bool {
+        self.stat.is_dir()
+    }
+
+    pub fn len(&self) -> usize {
+        self.stat.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stat.len == 0
+    }
 }
 
+/// What the pointer of `stat` and `fstat` got, or the error they failed with
+fn stat_result(ret: usize, stat: MaybeUninit<FileStat>) -> Result<FileStat, Errno> {
+    match Errno::from_ret(ret) {
+        Some(errno) => Err(errno),
+        None => Ok(unsafe { stat.assume_init() }),
+    }
+}
+
+pub fn sys_stat(path: &str) -> Result<FileStat, Errno> {
+    let mut stat = MaybeUninit::<FileStat>::uninit();
+    let ret = syscall!(
+        Syscall::Stat,
+        path.as_ptr() as u64,
+        path.len() as u64,
+        stat.as_mut_ptr() as u64
+    );
+    stat_result(ret, stat)
+}
+
+pub fn sys_fstat(fd: Fd) -> Result<FileStat, Errno> {
+    let mut stat = MaybeUninit::<FileStat>::uninit();
+    let ret = syscall!(Syscall::FStat, fd as u64, stat.as_mut_ptr() as u64);
+    stat_result(ret, stat)
+}
+
+/// The entries of the directory at `path`
+pub fn sys_read_dir(path: &str) -> Result<Vec<Metadata>, Errno> {
+    let mut entries = Vec::new();
+    let mut buf = vec![0u8; 1024];
+
+    loop {
+        let ret = syscall!(
+            Syscall::GetDents,
+            path.as_ptr() as u64,
+            path.len() as u64,
+            buf.as_mut_ptr() as u64,
+            buf.len() as u64,
+            entries.len() as u64
+        );
+
+        match Errno::from_ret(ret) {
+            // an entry with a long name, try again with room for it
+            Some(Errno::EINVAL) if buf.len() < 16 * 1024 => {
+                buf.resize(buf.len() * 2, 0);
+                continue;
+            }
+            Some(errno) => return Err(errno),
+            None if ret == 0 => return Ok(entries),
+            None => (),
+        }
+
+        let mut offset = 0;
+        while offset < ret {
+            let record = &buf[offset..ret];
+            let entry = unsafe { (record.as_ptr() as *const DirEntry).read_unaligned() };
+            let name = &record[size_of::<DirEntry>()..][..entry.name_len as usize];
+
+            entries.push(Metadata {
+                name: String::from_utf8_lossy(name).into_owned(),
+                stat: entry.stat,
+            });
+            offset += DirEntry::record_len(name.len());
+        }
+    }
+}
+
+/// The working directory of the process
+pub fn sys_getcwd() -> String {
+    let mut buf = vec![0u8; 256];
+    loop {
+        let len = syscall!(Syscall::GetCwd, buf.as_mut_ptr() as u64, buf.len() as u64);
+
+        // it did not fit, try again with room for it
+        if len > buf.len() {
+            buf.resize(len, 0);
+            continue;
+        }
+
+        return String::from_utf8_lossy(&buf[..len]).into_owned();
+    }
+}
+
+/// Move to the directory at `path`, relative to the working directory
+pub fn sys_chdir(path: &str) -> Result<(), Errno> {
+    let ret = syscall!(Syscall::ChDir, path.as_ptr() as u64, path.len() as u64);
+    match Errno::from_ret(ret) {
+        Some(errno) => Err(errno),
+        None => Ok(()),
+    }
+}
+
+/// Nothing if a syscall succeeded, or the error it failed with
 #[inline(always)]
+fn unit_result(ret: usize) -> Result<(), Errno> {
+    match Errno::from_ret(ret) {
+        Some(errno) => Err(errno),
+        None => Ok(()),
+    }
+}
+
+/// Create an empty directory at `path`
+pub fn sys_mkdir(path: &str) -> Result<(), Errno> {
+    unit_result(syscall!(
+        Syscall::MkDir,
+        path.as_ptr() as u64,
+        path.len() as u64
+    ))
+}
+
+/// Remove the empty directory at `path`
+pub fn sys_rmdir(path: &str) -> Result<(), Errno> {
+    unit_result(syscall!(
+        Syscall::RmDir,
+        path.as_ptr() as u64,
+        path.len() as u64
+    ))
+}
+
+/// Remove the file at `path`
+pub fn sys_unlink(path: &str) -> Result<(), Errno> {
+    unit_result(syscall!(
+        Syscall::Unlink,
+        path.as_ptr() as u64,
+        path.len() as u64
+    ))
+}
+
+/// Pack strings for the kernel, each one NUL terminated
+fn pack_strs<'a>(strs: impl IntoIterator<Item = &'a str>) -> Vec<u8> {
+    let mut packed = Vec::new();
+    for s in strs {
+        packed.extend_from_slice(s.as_bytes());
+        packed.push(0);
+    }
+    packed
+}
+
+/// Spawn the program at `path` with the current environment
 pub fn sys_spawn(path: &str) -> u16 {
-    syscall!(Syscall::Spawn, path.as_ptr() as u64, path.len() as u64) as u16
+    let envs: Vec<String> = crate::env::vars()
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect();
+    let envs: Vec<&str> = envs.iter().map(String::as_str).collect();
+
+    sys_spawn_with(path, &[path], &envs)
 }
 
-#[inline(always)]
-pub fn sys_open(path: &str, mode: crate::FileMode) -> u8 {
+/// Spawn the program at `path` with `args` and `KEY=value` environment
+/// strings
+pub fn sys_spawn_with(path: &str, args: &[&str], envs: &[&str]) -> u16 {
+    let argv = pack_strs(args.iter().copied());
+    let envp = pack_strs(envs.iter().copied());
+
     syscall!(
+        Syscall::Spawn,
+        path.as_ptr() as u64,
+        path.len() as u64,
+        argv.as_ptr() as u64,
+        argv.len() as u64,
+        envp.as_ptr() as u64,
+        envp.len() as u64
+    ) as u16
+}
+
+/// Replace the current program, only returns if it could not be loaded
+pub fn sys_exec(path: &str, args: &[&str], envs: &[&str]) {
+    let argv = pack_strs(args.iter().copied());
+    let envp = pack_strs(envs.iter().copied());
+
+    syscall!(
+        Syscall::Exec,
+        path.as_ptr() as u64,
+        path.len() as u64,
+        argv.as_ptr() as u64,
+        argv.len() as u64,
+        envp.as_ptr() as u64,
+        envp.len() as u64
+    );
+}
+
+/// The fd a syscall returned, or the error it failed with
+#[inline(always)]
+fn fd_result(ret: usize) -> Result<Fd, Errno> {
+    match Errno::from_ret(ret) {
+        Some(errno) => Err(errno),
+        None => Ok(ret as Fd),
+    }
+}
+
+#[inline(always)]
+pub fn sys_open(path: &str, mode: crate::FileMode) -> Result<Fd, Errno> {
+    fd_result(syscall!(
         Syscall::Open,
         path.as_ptr() as u64,
         path.len() as u64,
         mode as u64
-    ) as u8
+    ))
+}
+
+/// Open a file that is closed when the process runs another program
+#[inline(always)]
+pub fn sys_open_cloexec(path: &str, mode: crate::FileMode) -> Result<Fd, Errno> {
+    fd_result(syscall!(
+        Syscall::Open,
+        path.as_ptr() as u64,
+        path.len() as u64,
+        mode as u64,
+        O_CLOEXEC as u64
+    ))
 }
 
 #[inline(always)]
-pub fn sys_close(fd: u8) -> bool {
+pub fn sys_close(fd: Fd) -> bool {
     syscall!(Syscall::Close, fd as u64) != 0
 }
 
+/// A new pipe, as its read end and write end
+#[inline(always)]
+pub fn sys_pipe() -> Result<(Fd, Fd), Errno> {
+    let mut fds: [Fd; 2] = [0; 2];
+    let ret = syscall!(Syscall::Pipe, fds.as_mut_ptr() as u64);
+    match Errno::from_ret(ret) {
+        Some(errno) => Err(errno),
+        None => Ok((fds[0], fds[1])),
+    }
+}
+
+/// A new pipe whose ends are closed when the process runs another program
+#[inline(always)]
+pub fn sys_pipe_cloexec() -> Result<(Fd, Fd), Errno> {
+    let mut fds: [Fd; 2] = [0; 2];
+    let ret = syscall!(Syscall::Pipe, fds.as_mut_ptr() as u64, O_CLOEXEC as u64);
+    match Errno::from_ret(ret) {
+        Some(errno) => Err(errno),
+        None => Ok((fds[0], fds[1])),
+    }
+}
+
+#[inline(always)]
+pub fn sys_dup(fd: Fd) -> Result<Fd, Errno> {
+    fd_result(syscall!(Syscall::Dup, fd as u64))
+}
+
+/// Make `new_fd` refer to what `fd` does, closing what it was before
+#[inline(always)]
+pub fn sys_dup2(fd: Fd, new_fd: Fd) -> Result<Fd, Errno> {
+    fd_result(syscall!(Syscall::Dup2, fd as u64, new_fd as u64))
+}
+
+/// Whether `fd` is closed when the process runs another program
+#[inline(always)]
+pub fn sys_get_cloexec(fd: Fd) -> Result<bool, Errno> {
+    let ret = syscall!(Syscall::Fcntl, fd as u64, F_GETFD as u64);
+    match Errno::from_ret(ret) {
+        Some(errno) => Err(errno),
+        None => Ok(ret & FD_CLOEXEC != 0),
+    }
+}
+
+#[inline(always)]
+pub fn sys_set_cloexec(fd: Fd, cloexec: bool) -> Result<(), Errno> {
+    let flags = if cloexec { FD_CLOEXEC } else { 0 };
+    let ret = syscall!(Syscall::Fcntl, fd as u64, F_SETFD as u64, flags as u64);
+    match Errno::from_ret(ret) {
+        Some(errno) => Err(errno),
+        None => Ok(()),
+    }
+}
+
+/// Mount the `fstype` filesystem found on the device `source` at `target`
+#[inline(always)]
+pub fn sys_mount(source: &str, target: &str, fstype: &str) -> Result<(), Errno> {
+    unit_result(syscall!(
+        Syscall::Mount,
+        source.as_ptr() as u64,
+        source.len() as u64,
+        target.as_ptr() as u64,
+        target.len() as u64,
+        fstype.as_ptr() as u64,
+        fstype.len() as u64
+    ))
+}
+
+#[inline(always)]
+pub fn sys_umount(target: &str) -> Result<(), Errno> {
+    unit_result(syscall!(
+        Syscall::Umount,
+        target.as_ptr() as u64,
+        target.len() as u64
+    ))
+}
+
 #[inline(always)]
 pub fn sys_get_pid() -> u16 {
     syscall!(Syscall::GetPid) as u16
 }
 
+/// Pid of the parent, 0 if it is gone
+#[inline(always)]
+pub fn sys_get_ppid() -> u16 {
+    syscall!(Syscall::GetPpid) as u16
+}
+
+/// Give the CPU up to another process
+#[inline(always)]
+pub fn sys_yield() {
+    syscall!(Syscall::SchedYield);
+}
+
+/// Nice value of `pid`, or of the caller when it is 0
+#[inline(always)]
+pub fn sys_get_priority(pid: u16) -> Option<i8> {
+    match syscall!(Syscall::GetPriority, pid as u64) as isize {
+        ..0 => None,
+        prio => Some((20 - prio) as i8),
+    }
+}
+
+/// Set the nice value of `pid`, or of the caller when it is 0, between
+/// -20 and 19
+#[inline(always)]
+pub fn sys_set_priority(pid: u16, nice: i8) -> bool {
+    syscall!(Syscall::SetPriority, pid as u64, nice as u64) == 0
+}
+
+/// Add `inc` to the nice value of the caller, returning the new one
+#[inline(always)]
+pub fn sys_nice(inc: i8) -> Option<i8> {
+    let nice = sys_get_priority(0)?.saturating_add(inc).clamp(-20, 19);
+    sys_set_priority(0, nice).then_some(nice)
+}
+
 #[inline(always)]
 pub fn sys_fork() -> u16 {
-    let pid = syscall!(Syscall::VFork);
+    let pid = syscall!(Syscall::Fork);
     pid as u16
 }
 
+/// Start a thread running `entry(arg)`, on the stack ending at `stack` or
+/// one the kernel maps when it is 0, returning its tid or 0 on failure
+#[inline(always)]
+pub fn sys_thread_create(entry: extern "C" fn(usize) -> !, arg: usize, stack: usize) -> u16 {
+    syscall!(Syscall::ThreadCreate, entry as usize, arg, stack) as u16
+}
+
+#[inline(always)]
+pub fn sys_thread_exit(code: isize) -> ! {
+    syscall!(Syscall::ThreadExit, code as usize);
+    unreachable!();
+}
+
+#[inline(always)]
+pub fn sys_thread_join(tid: u16) -> isize {
+    syscall!(Syscall::ThreadJoin, tid as u64) as isize
+}
+
+/// Send `signal` to `pid`, false if there is no such process
+#[inline(always)]
+pub fn sys_kill(pid: u16, signal: Signal) -> bool {
+    syscall!(Syscall::Kill, pid as u64, signal as u64) == 0
+}
+
+/// Set the action of `signal` if `action` is given, returning the old one
+/// or None if it can't be changed
+#[inline(always)]
+pub fn sys_sigaction(signal: Signal, action: Option<&SigAction>) -> Option<SigAction> {
+    let mut old = SigAction::default();
+    let action = action.map_or(0, |action| action as *const SigAction as usize);
+    let ret = syscall!(
+        Syscall::SigAction,
+        signal as u64,
+        action,
+        &mut old as *mut SigAction as usize
+    );
+
+    (ret == 0).then_some(old)
+}
+
+/// Change the signal mask of the thread, returning the old one
+#[inline(always)]
+pub fn sys_sigprocmask(how: SigHow, set: u64) -> u64 {
+    syscall!(Syscall::SigProcMask, how as u64, set) as u64
+}
+
+/// Send Ctrl-C and Ctrl-Z to the process group `pgid` from now on, or to
+/// no one
+#[inline(always)]
+pub fn sys_set_foreground(pgid: Option<u16>) {
+    syscall!(Syscall::SetForeground, pgid.unwrap_or(0) as u64);
+}
+
+/// Send `signal` to every process in the group `pgid`, false if there is
+/// none
 #[inline(always)]
-pub fn sys_kill(pid: u16) {
-    syscall!(Syscall::Kill, pid as u64);
+pub fn sys_kill_group(pgid: u16, signal: Signal) -> bool {
+    syscall!(Syscall::Kill, -(pgid as isize), signal as u64) == 0
+}
+
+/// Move `pid`, the caller when it is 0, to the group `pgid`, a new group
+/// led by `pid` when it is 0
+#[inline(always)]
+pub fn sys_set_pgid(pid: u16, pgid: u16) -> bool {
+    syscall!(Syscall::SetPgid, pid as u64, pgid as u64) == 0
+}
+
+/// Process group of `pid`, or of the caller when it is 0
+#[inline(always)]
+pub fn sys_get_pgid(pid: u16) -> Option<u16> {
+    match syscall!(Syscall::GetPgid, pid as u64) as isize {
+        ..0 => None,
+        pgid => Some(pgid as u16),
+    }
 }
 
 #[inline(always)]
@@ -0,0 +1,78 @@
+//! Threads sharing the memory of the process
+//!
+//! Every thread gets a stack slot of its own from the kernel, the heap,
+//! globals and open files are shared. Returning from `main` ends all of
+//! them.
+
+use core::cell::UnsafeCell;
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+use crate::*;
+
+/// Where a thread leaves its result for the one joining it
+struct Packet<T>(UnsafeCell<Option<T>>);
+
+// written by the thread before it exits, read only once it is joined
+unsafe impl<T: Send> Sync for Packet<T> {}
+
+struct ThreadMain<F, T> {
+    f: F,
+    packet: Arc<Packet<T>>,
+}
+
+/// An owned permission to join a thread, it keeps running if this is dropped
+pub struct JoinHandle<T> {
+    tid: u16,
+    packet: Arc<Packet<T>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// The tid of the thread
+    pub fn id(&self) -> u16 {
+        self.tid
+    }
+
+    /// Wait for the thread to finish and take its result
+    pub fn join(self) -> T {
+        sys_thread_join(self.tid);
+
+        unsafe { (*self.packet.0.get()).take() }.expect("Thread exited without a result")
+    }
+}
+
+/// Run `f` in a new thread
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let packet = Arc::new(Packet(UnsafeCell::new(None)));
+    let main = Box::into_raw(Box::new(ThreadMain {
+        f,
+        packet: packet.clone(),
+    }));
+
+    let tid = sys_thread_create(start::<F, T>, main as usize, 0);
+
+    if tid == 0 {
+        drop(unsafe { Box::from_raw(main) });
+        panic!("Failed to spawn thread");
+    }
+
+    JoinHandle { tid, packet }
+}
+
+extern "C" fn start<F, T>(main: usize) -> !
+where
+    F: FnOnce() -> T,
+{
+    let ThreadMain { f, packet } = *unsafe { Box::from_raw(main as *mut ThreadMain<F, T>) };
+    let result = f();
+
+    unsafe { *packet.0.get() = Some(result) };
+    drop(packet);
+
+    sys_thread_exit(0)
+}
@@ -0,0 +1,68 @@
+//! Time measurement and sleeping
+//!
+//! [`Instant`] reads the monotonic clock of the kernel, which advances
+//! with timer ticks, so it never goes back but is only as precise as a
+//! tick.
+
+use core::ops::{Add, AddAssign, Sub};
+
+pub use core::time::Duration;
+
+use syscall_def::ClockId;
+
+use crate::*;
+
+/// A point in monotonic time, only useful compared to another one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(Duration);
+
+impl Instant {
+    pub fn now() -> Self {
+        Self(sys_clock_gettime(ClockId::Monotonic))
+    }
+
+    /// Time from `earlier` to this instant, zero if `earlier` is later
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.0.saturating_sub(earlier.0)
+    }
+
+    /// Time passed since this instant
+    pub fn elapsed(&self) -> Duration {
+        Self::now().duration_since(*self)
+    }
+
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_add(duration).map(Self)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, rhs: Duration) -> Instant {
+        self.checked_add(rhs)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Instant) -> Duration {
+        self.duration_since(rhs)
+    }
+}
+
+/// Block for at least `duration`, other processes run meanwhile
+pub fn sleep(duration: Duration) {
+    let deadline = Instant::now() + duration;
+
+    // a signal may end the sleep early
+    while !sys_nanosleep(deadline.duration_since(Instant::now())) {}
+}
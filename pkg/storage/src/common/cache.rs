@@ -39,6 +39,15 @@ impl<B: BlockTrait> BlockCache<B> {
         data.as_mut().copy_from_slice(self.inner.as_ref());
         Ok(())
     }
+
+    /// Write the block back to the device if it has been modified
+    pub fn sync(&mut self) -> Result<()> {
+        if self.modified {
+            self.device.write_block(self.offset, &self.inner)?;
+            self.modified = false;
+        }
+        Ok(())
+    }
 }
 
 impl<B: BlockTrait> Drop for BlockCache<B> {
@@ -64,6 +73,9 @@ where
 
     /// Put a block into the cache
     fn put(&self, key: usize, value: BlockCache<B>);
+
+    /// Write all modified blocks back to the device
+    fn flush(&self) -> Result<()>;
 }
 
 pub struct CachedDevice<B, C>
@@ -129,4 +141,9 @@ where
         };
         Ok(())
     }
+
+    fn flush(&self) -> Result<()> {
+        self.cache.flush()?;
+        self.device.flush()
+    }
 }
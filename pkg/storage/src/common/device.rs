@@ -25,4 +25,72 @@ where
     fn block_size(&self) -> usize {
         B::size()
     }
+
+    /// Writes any buffered blocks back to the device
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<B, T> BlockDevice<B> for Arc<T>
+where
+    B: BlockTrait,
+    T: BlockDevice<B> + ?Sized,
+{
+    fn block_count(&self) -> Result<usize> {
+        (**self).block_count()
+    }
+
+    fn read_block(&self, offset: usize, block: &mut B) -> Result<()> {
+        (**self).read_block(offset, block)
+    }
+
+    fn write_block(&self, offset: usize, block: &B) -> Result<()> {
+        (**self).write_block(offset, block)
+    }
+
+    fn flush(&self) -> Result<()> {
+        (**self).flush()
+    }
+}
+
+/// A disk kept in memory, for the tests of the filesystems and partition
+/// tables
+#[cfg(test)]
+pub struct MemoryDisk {
+    data: spin::Mutex<Vec<u8>>,
+}
+
+#[cfg(test)]
+impl MemoryDisk {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self {
+            data: spin::Mutex::new(data),
+        }
+    }
+}
+
+#[cfg(test)]
+impl<B: BlockTrait> BlockDevice<B> for MemoryDisk {
+    fn block_count(&self) -> Result<usize> {
+        Ok(self.data.lock().len() / B::size())
+    }
+
+    fn read_block(&self, offset: usize, block: &mut B) -> Result<()> {
+        let data = self.data.lock();
+        let range = offset * B::size()..(offset + 1) * B::size();
+        block
+            .as_mut()
+            .copy_from_slice(data.get(range).ok_or(FsError::InvalidOffset)?);
+        Ok(())
+    }
+
+    fn write_block(&self, offset: usize, block: &B) -> Result<()> {
+        let mut data = self.data.lock();
+        let range = offset * B::size()..(offset + 1) * B::size();
+        data.get_mut(range)
+            .ok_or(FsError::InvalidOffset)?
+            .copy_from_slice(block.as_ref());
+        Ok(())
+    }
 }
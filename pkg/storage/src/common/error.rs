@@ -32,6 +32,10 @@ pub enum FsError {
     DeviceError(DeviceError),
     /// Invalid path.
     InvalidPath(String),
+    /// The partition table is missing or corrupted.
+    InvalidPartitionTable,
+    /// The archive is corrupted or in an unknown format.
+    InvalidArchive,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -59,7 +63,7 @@ pub enum FilenameError {
     InvalidCharacter,
     /// Tried to create a file with no file name.
     FilenameEmpty,
-    /// Given name was too long (we are limited to 8.3).
+    /// Given name was too long (8.3 for short names, 255 characters for long names).
     NameTooLong,
     /// Can't start a file with a period, or after 8 characters.
     MisplacedPeriod,
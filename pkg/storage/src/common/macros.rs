@@ -39,7 +39,7 @@ macro_rules! define_field {
         paste::item! {
             #[doc = "Get u16 from the " $name " field"]
             pub fn $name(&self) -> u16 {
-                u16::from_le_bytes(self.data[$offset..$offset + 2].try_into().unwrap_or([0; 2]))
+                u16::from_le_bytes(self.data[$offset..][..2].try_into().unwrap_or([0; 2]))
             }
         }
     };
@@ -48,7 +48,16 @@ macro_rules! define_field {
         paste::item! {
                 #[doc = "Get u32 from the " $name " field"]
             pub fn $name(&self) -> u32 {
-                u32::from_le_bytes(self.data[$offset..$offset + 4].try_into().unwrap_or([0; 4]))
+                u32::from_le_bytes(self.data[$offset..][..4].try_into().unwrap_or([0; 4]))
+            }
+        }
+    };
+
+    (u64, $offset:expr, $name:ident) => {
+        paste::item! {
+            #[doc = "Get u64 from the " $name " field"]
+            pub fn $name(&self) -> u64 {
+                u64::from_le_bytes(self.data[$offset..][..8].try_into().unwrap_or([0; 8]))
             }
         }
     };
@@ -57,14 +66,14 @@ macro_rules! define_field {
         paste::item! {
             #[doc = "Get `&[u8]` from the " $name " field"]
             pub fn $name(&self) -> &[u8; $len] {
-                (&self.data[$offset..$offset + $len])
+                (&self.data[$offset..][..$len])
                     .try_into()
                     .unwrap_or(&[0; $len])
             }
 
             #[doc = "Get `&str` from the " $name " field"]
             pub fn [<$name _str>](&self) -> &str {
-                core::str::from_utf8(&self.data[$offset..$offset+$len]).unwrap_or("")
+                core::str::from_utf8(&self.data[$offset..][..$len]).unwrap_or("")
             }
         }
     };
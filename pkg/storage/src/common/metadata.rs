@@ -3,6 +3,18 @@ use chrono::{DateTime, Utc};
 
 pub type FsTime = DateTime<Utc>;
 
+static CLOCK: spin::Once<fn() -> FsTime> = spin::Once::new();
+
+/// Register the clock used to timestamp entries on writable filesystems
+pub fn set_clock(clock: fn() -> FsTime) {
+    CLOCK.call_once(|| clock);
+}
+
+/// Current time of the registered clock, or the Unix epoch if there is none
+pub fn now() -> FsTime {
+    CLOCK.get().map(|clock| clock()).unwrap_or_default()
+}
+
 /// Type of file entry
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum FileType {
@@ -11,9 +11,20 @@ impl Mount {
         Self { fs, mount_point }
     }
 
+    /// The path inside this mount, if `path` is the mount point or below it
+    pub fn relative<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let rest = path.strip_prefix(self.mount_point.trim_end_matches('/'))?;
+
+        match rest {
+            "" => Some("/"),
+            rest if rest.starts_with('/') => Some(rest),
+            _ => None,
+        }
+    }
+
     #[inline]
     fn trim_mount_point<'a>(&self, path: &'a str) -> &'a str {
-        path.trim_start_matches(self.mount_point.as_ref())
+        self.relative(path).unwrap_or(path)
     }
 }
 
@@ -37,6 +48,30 @@ impl FileSystem for Mount {
     fn exists(&self, path: &str) -> Result<bool> {
         self.fs.exists(self.trim_mount_point(path))
     }
+
+    #[inline]
+    fn create_file(&self, path: &str) -> Result<FileHandle> {
+        self.fs.create_file(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn append_file(&self, path: &str) -> Result<FileHandle> {
+        self.fs.append_file(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn remove_file(&self, path: &str) -> Result<()> {
+        self.fs.remove_file(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn create_dir(&self, path: &str) -> Result<()> {
+        self.fs.create_dir(self.trim_mount_point(path))
+    }
+
+    fn remove_dir(&self, path: &str) -> Result<()> {
+        self.fs.remove_dir(self.trim_mount_point(path))
+    }
 }
 
 impl core::fmt::Debug for Mount {
@@ -47,3 +82,42 @@ impl core::fmt::Debug for Mount {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Empty;
+
+    impl FileSystem for Empty {
+        fn read_dir(&self, _: &str) -> Result<Box<dyn Iterator<Item = Metadata> + Send>> {
+            Err(FsError::NotSupported)
+        }
+
+        fn open_file(&self, _: &str) -> Result<FileHandle> {
+            Err(FsError::NotSupported)
+        }
+
+        fn metadata(&self, _: &str) -> Result<Metadata> {
+            Err(FsError::NotSupported)
+        }
+
+        fn exists(&self, _: &str) -> Result<bool> {
+            Ok(false)
+        }
+    }
+
+    #[test]
+    fn relative_path() {
+        let root = Mount::new(Box::new(Empty), "/".into());
+        let dev = Mount::new(Box::new(Empty), "/dev".into());
+
+        assert_eq!(root.relative("/APP/SH"), Some("/APP/SH"));
+        assert_eq!(root.relative("/"), Some("/"));
+        assert_eq!(dev.relative("/dev"), Some("/"));
+        assert_eq!(dev.relative("/dev/random"), Some("/random"));
+        assert_eq!(dev.relative("/devices"), None);
+        assert_eq!(dev.relative("/APP/SH"), None);
+    }
+}
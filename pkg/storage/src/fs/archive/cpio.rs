@@ -0,0 +1,157 @@
+//! cpio "newc" archives
+//!
+//! reference:
+//! - <https://www.kernel.org/doc/html/latest/driver-api/early-userspace/buffer-format.html>
+//! - <https://man.archlinux.org/man/cpio.5>
+
+use super::ArchiveEntry;
+use crate::*;
+
+const HEADER_SIZE: usize = 110;
+const TRAILER: &str = "TRAILER!!!";
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+
+/// newc without and with checksums
+pub fn is_cpio(data: &[u8]) -> bool {
+    data.starts_with(b"070701") || data.starts_with(b"070702")
+}
+
+/// Read the `index`th 8 digit hex field of the header at `data`
+fn field(data: &[u8], index: usize) -> Result<u32> {
+    let start = 6 + index * 8;
+    let digits = data
+        .get(start..start + 8)
+        .and_then(|digits| core::str::from_utf8(digits).ok())
+        .ok_or(FsError::InvalidArchive)?;
+
+    u32::from_str_radix(digits, 16).map_err(|_| FsError::InvalidArchive)
+}
+
+const fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+/// List the files and directories of the archive, other entries such as
+/// symlinks and devices are skipped
+pub fn parse(data: &'static [u8]) -> Result<Vec<ArchiveEntry>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let header = data.get(offset..).ok_or(FsError::InvalidArchive)?;
+        if !is_cpio(header) || header.len() < HEADER_SIZE {
+            return Err(FsError::InvalidArchive);
+        }
+
+        let mode = field(header, 1)?;
+        let mtime = field(header, 5)?;
+        let size = field(header, 6)? as usize;
+        let name_size = field(header, 11)? as usize;
+
+        // the name is NUL terminated and the data starts 4 byte aligned
+        let name_start = offset + HEADER_SIZE;
+        let data_start = align4(name_start + name_size);
+        let data_end = data_start + size;
+
+        let name = data
+            .get(name_start..name_start + name_size.saturating_sub(1))
+            .and_then(|name| core::str::from_utf8(name).ok())
+            .ok_or(FsError::InvalidArchive)?;
+        let contents = data
+            .get(data_start..data_end)
+            .ok_or(FsError::InvalidArchive)?;
+
+        if name == TRAILER {
+            break;
+        }
+
+        let kind = match mode & S_IFMT {
+            S_IFDIR => Some(FileType::Directory),
+            S_IFREG => Some(FileType::File),
+            _ => None,
+        };
+
+        if let Some(kind) = kind {
+            entries.push(ArchiveEntry {
+                path: name.into(),
+                kind,
+                data: contents,
+                mtime: mtime as u64,
+            });
+        }
+
+        offset = align4(data_end);
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ArchiveFs;
+    use super::*;
+
+    fn push_entry(archive: &mut Vec<u8>, name: &str, mode: u32, data: &[u8]) {
+        let size = data.len() as u32;
+        let name_size = name.len() as u32 + 1;
+        let fields = [
+            0,
+            mode,
+            0,
+            0,
+            1,
+            1_700_000_000,
+            size,
+            0,
+            0,
+            0,
+            0,
+            name_size,
+            0,
+        ];
+
+        archive.extend_from_slice(b"070701");
+        for field in fields {
+            archive.extend_from_slice(std::format!("{:08X}", field).as_bytes());
+        }
+        archive.extend_from_slice(name.as_bytes());
+        archive.push(0);
+        archive.resize(align4(archive.len()), 0);
+        archive.extend_from_slice(data);
+        archive.resize(align4(archive.len()), 0);
+    }
+
+    #[test]
+    fn cpio_fs() {
+        let mut archive = Vec::new();
+        push_entry(&mut archive, ".", S_IFDIR | 0o755, b"");
+        push_entry(&mut archive, "APP/SH", S_IFREG | 0o755, b"\x7fELF");
+        push_entry(&mut archive, "ETC", S_IFDIR | 0o755, b"");
+        push_entry(&mut archive, "ETC/motd", S_IFREG | 0o644, b"hello\n");
+        push_entry(&mut archive, TRAILER, 0, b"");
+
+        let fs = ArchiveFs::new(archive.leak()).unwrap();
+        assert_eq!(fs.len(), 4);
+
+        let mut names: Vec<String> = fs.read_dir("/").unwrap().map(|m| m.name).collect();
+        names.sort();
+        assert_eq!(names, ["APP", "ETC"]);
+
+        let mut buf = Vec::new();
+        fs.open_file("/ETC/motd")
+            .unwrap()
+            .read_all(&mut buf)
+            .unwrap();
+        assert_eq!(buf, b"hello\n");
+
+        let meta = fs.metadata("/APP/SH").unwrap();
+        assert_eq!(meta.len, 4);
+        assert_eq!(meta.modified.unwrap().timestamp(), 1_700_000_000);
+
+        assert_eq!(fs.metadata("/APP").unwrap().entry_type, FileType::Directory);
+        assert!(fs.create_file("/ETC/new").is_err());
+    }
+}
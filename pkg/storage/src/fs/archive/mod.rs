@@ -0,0 +1,247 @@
+//! Read-only filesystem over an in-memory archive
+//!
+//! Used for the initramfs, the archive may be a cpio (newc) or ustar file.
+//! It is indexed once when the filesystem is created, file contents are
+//! read straight from the archive.
+
+pub mod cpio;
+pub mod tar;
+
+use crate::*;
+use alloc::collections::BTreeMap;
+use chrono::DateTime;
+
+/// An entry of an archive, as yielded by the format parsers
+pub struct ArchiveEntry {
+    /// Path inside the archive, without a leading `/` or `./`
+    pub path: String,
+    pub kind: FileType,
+    pub data: &'static [u8],
+    /// Modification time in seconds since the Unix epoch
+    pub mtime: u64,
+}
+
+struct Entry {
+    kind: FileType,
+    data: &'static [u8],
+    modified: Option<FsTime>,
+}
+
+pub struct ArchiveFs {
+    /// Entries by their path, the root is the empty path
+    entries: BTreeMap<String, Entry>,
+    format: &'static str,
+}
+
+impl ArchiveFs {
+    /// Index the archive in `data`, picking the format from its magic
+    pub fn new(data: &'static [u8]) -> Result<Self> {
+        let (format, entries) = if cpio::is_cpio(data) {
+            ("cpio", cpio::parse(data)?)
+        } else if tar::is_tar(data) {
+            ("tar", tar::parse(data)?)
+        } else {
+            return Err(FsError::InvalidArchive);
+        };
+
+        let mut fs = Self {
+            entries: BTreeMap::new(),
+            format,
+        };
+
+        fs.insert_dir(String::new());
+
+        for entry in entries {
+            let path = normalize(&entry.path);
+            if path.is_empty() {
+                continue;
+            }
+
+            // archives don't have to list the parents of their files
+            let mut parent = path.as_str();
+            while let Some((dir, _)) = parent.rsplit_once(PATH_SEPARATOR) {
+                fs.insert_dir(dir.into());
+                parent = dir;
+            }
+
+            let modified = DateTime::from_timestamp(entry.mtime as i64, 0);
+            fs.entries.insert(
+                path,
+                Entry {
+                    kind: entry.kind,
+                    data: entry.data,
+                    modified,
+                },
+            );
+        }
+
+        Ok(fs)
+    }
+
+    /// Number of files and directories, the root excluded
+    pub fn len(&self) -> usize {
+        self.entries.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn insert_dir(&mut self, path: String) {
+        self.entries.entry(path).or_insert(Entry {
+            kind: FileType::Directory,
+            data: &[],
+            modified: None,
+        });
+    }
+
+    fn entry(&self, path: &str) -> Result<(String, &Entry)> {
+        let path = normalize(path);
+        let entry = self.entries.get(&path).ok_or(FsError::FileNotFound)?;
+        Ok((path, entry))
+    }
+}
+
+/// Turn a path into the form used as a key: no leading, trailing or
+/// repeated separators and no `.` components
+fn normalize(path: &str) -> String {
+    path.split(PATH_SEPARATOR)
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(PATH_SEPARATOR).next().unwrap_or_default()
+}
+
+impl Entry {
+    fn meta(&self, name: &str) -> Metadata {
+        Metadata::new(
+            name.into(),
+            self.kind,
+            self.data.len(),
+            None,
+            self.modified,
+            None,
+        )
+    }
+}
+
+impl FileSystem for ArchiveFs {
+    fn read_dir(&self, path: &str) -> Result<Box<dyn Iterator<Item = Metadata> + Send>> {
+        let (dir, entry) = self.entry(path)?;
+
+        if entry.kind != FileType::Directory {
+            return Err(FsError::NotADirectory);
+        }
+
+        let entries: Vec<Metadata> = self
+            .entries
+            .iter()
+            .filter(|(path, _)| !path.is_empty())
+            .filter(|(path, _)| {
+                path.rsplit_once(PATH_SEPARATOR)
+                    .map_or(dir.is_empty(), |(parent, _)| parent == dir)
+            })
+            .map(|(path, entry)| entry.meta(file_name(path)))
+            .collect();
+
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn open_file(&self, path: &str) -> Result<FileHandle> {
+        let (path, entry) = self.entry(path)?;
+
+        if entry.kind != FileType::File {
+            return Err(FsError::NotAFile);
+        }
+
+        Ok(FileHandle::new(
+            entry.meta(file_name(&path)),
+            Box::new(ArchiveFile {
+                data: entry.data,
+                offset: 0,
+            }),
+        ))
+    }
+
+    fn metadata(&self, path: &str) -> Result<Metadata> {
+        let (path, entry) = self.entry(path)?;
+        Ok(entry.meta(file_name(&path)))
+    }
+
+    fn exists(&self, path: &str) -> Result<bool> {
+        Ok(self.entry(path).is_ok())
+    }
+
+    fn create_file(&self, _path: &str) -> Result<FileHandle> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn append_file(&self, _path: &str) -> Result<FileHandle> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn remove_file(&self, _path: &str) -> Result<()> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn create_dir(&self, _path: &str) -> Result<()> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn remove_dir(&self, _path: &str) -> Result<()> {
+        Err(FsError::ReadOnly)
+    }
+}
+
+impl core::fmt::Debug for ArchiveFs {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ArchiveFs")
+            .field("format", &self.format)
+            .field("entries", &self.len())
+            .finish()
+    }
+}
+
+/// An open file of the archive, read only
+struct ArchiveFile {
+    data: &'static [u8],
+    offset: usize,
+}
+
+impl Read for ArchiveFile {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let rest = self.data.get(self.offset..).unwrap_or_default();
+        let count = buf.len().min(rest.len());
+
+        buf[..count].copy_from_slice(&rest[..count]);
+        self.offset += count;
+
+        Ok(count)
+    }
+}
+
+impl Write for ArchiveFile {
+    fn write(&mut self, _buf: &[u8]) -> Result<usize> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for ArchiveFile {
+    fn seek(&mut self, pos: SeekFrom) -> Result<usize> {
+        let offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.offset.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.data.len().checked_add_signed(offset),
+        };
+
+        self.offset = offset.ok_or(FsError::InvalidOffset)?;
+        Ok(self.offset)
+    }
+}
@@ -0,0 +1,147 @@
+//! ustar archives
+//!
+//! reference:
+//! - <https://www.gnu.org/software/tar/manual/html_node/Standard.html>
+//! - <https://man.archlinux.org/man/tar.5>
+
+use super::ArchiveEntry;
+use crate::*;
+
+const BLOCK_SIZE: usize = 512;
+
+pub fn is_tar(data: &[u8]) -> bool {
+    data.get(257..262) == Some(b"ustar")
+}
+
+/// Parse a NUL or space terminated octal field
+fn octal(field: &[u8]) -> Result<u64> {
+    let digits = field
+        .split(|&b| b == 0 || b == b' ')
+        .find(|digits| !digits.is_empty())
+        .unwrap_or(b"0");
+
+    core::str::from_utf8(digits)
+        .ok()
+        .and_then(|digits| u64::from_str_radix(digits, 8).ok())
+        .ok_or(FsError::InvalidArchive)
+}
+
+/// A NUL terminated string field
+fn string(field: &[u8]) -> Result<&str> {
+    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    core::str::from_utf8(&field[..len]).map_err(|_| FsError::InvalidArchive)
+}
+
+/// List the files and directories of the archive, other entries such as
+/// links and extended headers are skipped
+pub fn parse(data: &'static [u8]) -> Result<Vec<ArchiveEntry>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+
+    while let Some(header) = data.get(offset..offset + BLOCK_SIZE) {
+        // the archive ends with zeroed blocks
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+
+        if !is_tar(header) {
+            return Err(FsError::InvalidArchive);
+        }
+
+        let checksum = octal(&header[148..156])?;
+        let sum: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as u64)
+            .sum();
+
+        if checksum != sum {
+            return Err(FsError::InvalidArchive);
+        }
+
+        let size = octal(&header[124..136])? as usize;
+        let mtime = octal(&header[136..148])?;
+        let name = string(&header[0..100])?;
+        let prefix = string(&header[345..500])?;
+
+        let data_start = offset + BLOCK_SIZE;
+        let contents = data
+            .get(data_start..data_start + size)
+            .ok_or(FsError::InvalidArchive)?;
+
+        let kind = match header[156] {
+            b'0' | 0 => Some(FileType::File),
+            b'5' => Some(FileType::Directory),
+            _ => None,
+        };
+
+        if let Some(kind) = kind {
+            let path = match prefix {
+                "" => name.into(),
+                _ => format!("{}/{}", prefix, name),
+            };
+
+            entries.push(ArchiveEntry {
+                path,
+                kind,
+                data: contents,
+                mtime,
+            });
+        }
+
+        offset = data_start + size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ArchiveFs;
+    use super::*;
+
+    fn push_entry(archive: &mut Vec<u8>, name: &str, kind: u8, data: &[u8]) {
+        let mut header = [0u8; BLOCK_SIZE];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..108].copy_from_slice(b"0000644\0");
+        header[124..136].copy_from_slice(std::format!("{:011o}\0", data.len()).as_bytes());
+        header[136..148].copy_from_slice(b"14524277400\0");
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&b| b as u32).sum();
+        header[148..156].copy_from_slice(std::format!("{:06o}\0 ", sum).as_bytes());
+
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(data);
+        archive.resize(archive.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+    }
+
+    #[test]
+    fn tar_fs() {
+        let mut archive = Vec::new();
+        push_entry(&mut archive, "./APP/", b'5', b"");
+        push_entry(&mut archive, "./APP/SH", b'0', b"\x7fELF");
+        push_entry(&mut archive, "./APP/link", b'2', b"");
+        archive.resize(archive.len() + 2 * BLOCK_SIZE, 0);
+
+        let fs = ArchiveFs::new(archive.leak()).unwrap();
+
+        let names: Vec<String> = fs.read_dir("/APP").unwrap().map(|m| m.name).collect();
+        assert_eq!(names, ["SH"]);
+
+        let mut buf = Vec::new();
+        fs.open_file("/APP/SH").unwrap().read_all(&mut buf).unwrap();
+        assert_eq!(buf, b"\x7fELF");
+        assert_eq!(
+            fs.metadata("/APP/SH")
+                .unwrap()
+                .modified
+                .unwrap()
+                .timestamp(),
+            0o14524277400
+        );
+    }
+}
@@ -0,0 +1,393 @@
+//! FAT BIOS Parameter Block
+//!
+//! reference:
+//! - <https://en.wikipedia.org/wiki/BIOS_parameter_block>
+//! - <https://wiki.osdev.org/FAT#Boot_Record>
+//! - <https://github.com/xfoxfu/rust-xos/blob/main/fatpart/src/struct/bpb.rs>
+//! - <https://github.com/rust-embedded-community/embedded-sdmmc-rs/blob/develop/src/fat.rs>
+
+/// The FAT variant of a volume, named after the width of its FAT entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FatType {
+    Fat12,
+    Fat16,
+    Fat32,
+}
+
+impl FatType {
+    /// FAT entry value marking a bad cluster
+    pub fn bad_cluster(&self) -> u32 {
+        match self {
+            FatType::Fat12 => 0xFF7,
+            FatType::Fat16 => 0xFFF7,
+            FatType::Fat32 => 0x0FFF_FFF7,
+        }
+    }
+
+    /// FAT entry value written at the end of a cluster chain
+    pub fn end_of_chain(&self) -> u32 {
+        match self {
+            FatType::Fat12 => 0xFFF,
+            FatType::Fat16 => 0xFFFF,
+            FatType::Fat32 => 0x0FFF_FFFF,
+        }
+    }
+}
+
+/// Represents a Boot Parameter Block. This is the first sector of a FAT
+/// formatted partition, and it describes various properties of the FAT
+/// filesystem.
+///
+/// FAT12 and FAT16 share the layout after the common BPB fields, while
+/// FAT32 has an extended BPB at the same offsets, see the `*_32` fields.
+pub struct FatBpb {
+    data: [u8; 512],
+}
+
+impl FatBpb {
+    /// Attempt to parse a Boot Parameter Block from a 512 byte sector.
+    ///
+    /// The geometry is checked as well as the signature, the driver
+    /// divides by the cluster size and only reads 512 byte sectors.
+    pub fn new(data: &[u8]) -> Result<FatBpb, &'static str> {
+        let data = data.try_into().map_err(|_| "Bad BPB size")?;
+        let bpb = FatBpb { data };
+
+        if bpb.trail() != 0xAA55 {
+            return Err("Bad BPB format");
+        }
+
+        if bpb.bytes_per_sector() != 512 {
+            return Err("Unsupported sector size");
+        }
+
+        if bpb.sectors_per_cluster() == 0 || bpb.fat_count() == 0 {
+            return Err("Bad BPB geometry");
+        }
+
+        Ok(bpb)
+    }
+
+    pub fn total_sectors(&self) -> u32 {
+        if self.total_sectors_16() == 0 {
+            self.total_sectors_32()
+        } else {
+            self.total_sectors_16() as u32
+        }
+    }
+
+    /// Sectors occupied by one FAT
+    pub fn fat_size(&self) -> u32 {
+        if self.sectors_per_fat() == 0 {
+            self.sectors_per_fat_32()
+        } else {
+            self.sectors_per_fat() as u32
+        }
+    }
+
+    /// Sectors occupied by the fixed root directory, 0 on FAT32
+    pub fn root_dir_sectors(&self) -> u32 {
+        let bytes_per_sector = self.bytes_per_sector() as u32;
+        (self.root_entries_count() as u32 * 32).div_ceil(bytes_per_sector)
+    }
+
+    /// Number of clusters in the data region
+    pub fn cluster_count(&self) -> u32 {
+        // DataSec = TotSec – (BPB_ResvdSecCnt + (BPB_NumFATs * FATSz) + RootDirSectors);
+        let data_sectors = self.total_sectors().saturating_sub(
+            self.reserved_sector_count() as u32
+                + self.fat_count() as u32 * self.fat_size()
+                + self.root_dir_sectors(),
+        );
+        data_sectors / self.sectors_per_cluster().max(1) as u32
+    }
+
+    /// The FAT type is determined by the count of clusters only, never by the
+    /// system identifier string
+    pub fn fat_type(&self) -> FatType {
+        match self.cluster_count() {
+            0..4085 => FatType::Fat12,
+            4085..65525 => FatType::Fat16,
+            _ => FatType::Fat32,
+        }
+    }
+
+    define_field!([u8; 8], 0x03, oem_name);
+    define_field!(u16, 0x0b, bytes_per_sector);
+    define_field!(u8, 0x0d, sectors_per_cluster);
+    define_field!(u16, 0x0e, reserved_sector_count);
+    define_field!(u8, 0x10, fat_count);
+    define_field!(u16, 0x11, root_entries_count);
+    define_field!(u16, 0x13, total_sectors_16);
+    define_field!(u8, 0x15, media_descriptor);
+    define_field!(u16, 0x16, sectors_per_fat);
+    define_field!(u16, 0x18, sectors_per_track);
+    define_field!(u16, 0x1a, track_count);
+    define_field!(u32, 0x1c, hidden_sectors);
+    define_field!(u32, 0x20, total_sectors_32);
+    define_field!(u8, 0x24, drive_number);
+    define_field!(u8, 0x25, reserved_flags);
+    define_field!(u8, 0x26, boot_signature);
+    define_field!(u32, 0x27, volume_id);
+    define_field!([u8; 11], 0x2b, volume_label);
+    define_field!([u8; 8], 0x36, system_identifier);
+
+    // FAT32 Extended BPB
+    define_field!(u32, 0x24, sectors_per_fat_32);
+    define_field!(u16, 0x28, ext_flags);
+    define_field!(u16, 0x2a, fs_version);
+    define_field!(u32, 0x2c, root_cluster);
+    define_field!(u16, 0x30, fs_info_sector);
+    define_field!(u16, 0x32, backup_boot_sector);
+    define_field!(u8, 0x40, drive_number_32);
+    define_field!(u8, 0x42, boot_signature_32);
+    define_field!(u32, 0x43, volume_id_32);
+    define_field!([u8; 11], 0x47, volume_label_32);
+    define_field!([u8; 8], 0x52, system_identifier_32);
+
+    define_field!(u16, 0x1fe, trail);
+}
+
+impl core::fmt::Debug for FatBpb {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.fat_type() == FatType::Fat32 {
+            return f
+                .debug_struct("FAT32 BPB")
+                .field("OEM Name", &self.oem_name_str())
+                .field("Bytes per Sector", &self.bytes_per_sector())
+                .field("Sectors per Cluster", &self.sectors_per_cluster())
+                .field("Reserved Sector Count", &self.reserved_sector_count())
+                .field("FAT Count", &self.fat_count())
+                .field("Total Sectors", &self.total_sectors())
+                .field("Media Descriptor", &self.media_descriptor())
+                .field("Sectors per FAT", &self.sectors_per_fat_32())
+                .field("Hidden Sectors", &self.hidden_sectors())
+                .field("Ext Flags", &self.ext_flags())
+                .field("FS Version", &self.fs_version())
+                .field("Root Cluster", &self.root_cluster())
+                .field("FSInfo Sector", &self.fs_info_sector())
+                .field("Backup Boot Sector", &self.backup_boot_sector())
+                .field("Drive Number", &self.drive_number_32())
+                .field("Boot Signature", &self.boot_signature_32())
+                .field("Volume ID", &self.volume_id_32())
+                .field("Volume Label", &self.volume_label_32_str())
+                .field("System Identifier", &self.system_identifier_32_str())
+                .field("Trail", &self.trail())
+                .finish();
+        }
+
+        f.debug_struct("FAT BPB")
+            .field("OEM Name", &self.oem_name_str())
+            .field("Bytes per Sector", &self.bytes_per_sector())
+            .field("Sectors per Cluster", &self.sectors_per_cluster())
+            .field("Reserved Sector Count", &self.reserved_sector_count())
+            .field("FAT Count", &self.fat_count())
+            .field("Root Entries Count", &self.root_entries_count())
+            .field("Total Sectors", &self.total_sectors())
+            .field("Media Descriptor", &self.media_descriptor())
+            .field("Sectors per FAT", &self.sectors_per_fat())
+            .field("Sectors per Track", &self.sectors_per_track())
+            .field("Track Count", &self.track_count())
+            .field("Hidden Sectors", &self.hidden_sectors())
+            .field("Total Sectors", &self.total_sectors())
+            .field("Drive Number", &self.drive_number())
+            .field("Reserved Flags", &self.reserved_flags())
+            .field("Boot Signature", &self.boot_signature())
+            .field("Volume ID", &self.volume_id())
+            .field("Volume Label", &self.volume_label_str())
+            .field("System Identifier", &self.system_identifier_str())
+            .field("Trail", &self.trail())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fat16_bpb_1() {
+        // Taken from a Raspberry Pi bootable SD-Card
+        const DATA: [u8; 192] = hex_literal::hex!(
+            "EB 3C 90 6D 6B 66 73 2E 66 61 74 00 02 10 01 00
+        02 00 02 00 00 F8 20 00 3F 00 FF 00 00 00 00 00
+        00 E0 01 00 80 01 29 BB B0 71 77 62 6F 6F 74 20
+        20 20 20 20 20 20 46 41 54 31 36 20 20 20 0E 1F
+        BE 5B 7C AC 22 C0 74 0B 56 B4 0E BB 07 00 CD 10
+        5E EB F0 32 E4 CD 16 CD 19 EB FE 54 68 69 73 20
+        69 73 20 6E 6F 74 20 61 20 62 6F 6F 74 61 62 6C
+        65 20 64 69 73 6B 2E 20 20 50 6C 65 61 73 65 20
+        69 6E 73 65 72 74 20 61 20 62 6F 6F 74 61 62 6C
+        65 20 66 6C 6F 70 70 79 20 61 6E 64 0D 0A 70 72
+        65 73 73 20 61 6E 79 20 6B 65 79 20 74 6F 20 74
+        72 79 20 61 67 61 69 6E 20 2E 2E 2E 20 0D 0A 00"
+        );
+
+        const PADDING: &[u8] = concat_bytes!([0x00; 318], [0x55, 0xAA]);
+
+        let mut bpb_data = DATA.to_vec();
+        bpb_data.extend_from_slice(PADDING);
+
+        let bpb = FatBpb::new(&bpb_data).unwrap();
+
+        assert_eq!(bpb.oem_name(), b"mkfs.fat");
+        assert_eq!(bpb.bytes_per_sector(), 512);
+        assert_eq!(bpb.sectors_per_cluster(), 16);
+        assert_eq!(bpb.reserved_sector_count(), 1);
+        assert_eq!(bpb.fat_count(), 2);
+        assert_eq!(bpb.root_entries_count(), 512);
+        assert_eq!(bpb.total_sectors_16(), 0);
+        assert_eq!(bpb.media_descriptor(), 0xf8);
+        assert_eq!(bpb.sectors_per_fat(), 32);
+        assert_eq!(bpb.sectors_per_track(), 63);
+        assert_eq!(bpb.track_count(), 255);
+        assert_eq!(bpb.hidden_sectors(), 0);
+        assert_eq!(bpb.total_sectors_32(), 0x1e000);
+        assert_eq!(bpb.drive_number(), 128);
+        assert_eq!(bpb.reserved_flags(), 1);
+        assert_eq!(bpb.boot_signature(), 0x29);
+        assert_eq!(bpb.volume_id(), 0x7771b0bb);
+        assert_eq!(bpb.volume_label(), b"boot       ");
+        assert_eq!(bpb.system_identifier(), b"FAT16   ");
+
+        assert_eq!(bpb.total_sectors(), 0x1e000);
+        assert_eq!(bpb.fat_type(), FatType::Fat16);
+
+        println!("{:#?}", bpb);
+    }
+
+    #[test]
+    fn test_fat16_bpb_2() {
+        // Taken from a Raspberry Pi bootable SD-Card
+        const DATA: [u8; 64] = hex_literal::hex!(
+            "EB 3E 90 4D 53 57 49 4E 34 2E 31 00 02 10 01 00
+        02 00 02 00 00 F8 FC 00 3F 00 10 00 3F 00 00 00
+        C1 BF 0F 00 80 00 29 FD 1A BE FA 51 45 4D 55 20
+        56 56 46 41 54 20 46 41 54 31 36 20 20 20 00 00"
+        );
+
+        const PADDING: &[u8] = concat_bytes!([0x00; 446], [0x55, 0xAA]);
+
+        let mut bpb_data = DATA.to_vec();
+        bpb_data.extend_from_slice(PADDING);
+
+        let bpb = FatBpb::new(&bpb_data).unwrap();
+
+        assert_eq!(bpb.oem_name(), b"MSWIN4.1");
+        assert_eq!(bpb.oem_name_str(), "MSWIN4.1");
+        assert_eq!(bpb.bytes_per_sector(), 512);
+        assert_eq!(bpb.sectors_per_cluster(), 16);
+        assert_eq!(bpb.reserved_sector_count(), 1);
+        assert_eq!(bpb.fat_count(), 2);
+        assert_eq!(bpb.root_entries_count(), 512);
+        assert_eq!(bpb.total_sectors_16(), 0);
+        assert_eq!(bpb.media_descriptor(), 0xf8);
+        assert_eq!(bpb.sectors_per_fat(), 0xfc);
+        assert_eq!(bpb.sectors_per_track(), 63);
+        assert_eq!(bpb.track_count(), 16);
+        assert_eq!(bpb.hidden_sectors(), 63);
+        assert_eq!(bpb.total_sectors_32(), 0xfbfc1);
+        assert_eq!(bpb.drive_number(), 128);
+        assert_eq!(bpb.reserved_flags(), 0);
+        assert_eq!(bpb.boot_signature(), 0x29);
+        assert_eq!(bpb.volume_id(), 0xfabe1afd);
+        assert_eq!(bpb.volume_label(), b"QEMU VVFAT ");
+        assert_eq!(bpb.volume_label_str(), "QEMU VVFAT ");
+        assert_eq!(bpb.system_identifier(), b"FAT16   ");
+        assert_eq!(bpb.system_identifier_str(), "FAT16   ");
+
+        assert_eq!(bpb.total_sectors(), 0xfbfc1);
+        assert_eq!(bpb.fat_type(), FatType::Fat16);
+
+        println!("{:#?}", bpb);
+    }
+
+    #[test]
+    fn test_fat32_bpb() {
+        // A 256 MiB volume formatted as FAT32 with one sector per cluster
+        const DATA: [u8; 90] = hex_literal::hex!(
+            "EB 58 90 6D 6B 66 73 2E 66 61 74 00 02 01 20 00
+        02 00 00 00 00 F8 00 00 20 00 08 00 00 00 00 00
+        00 00 08 00 C1 0F 00 00 00 00 00 00 02 00 00 00
+        01 00 06 00 00 00 00 00 00 00 00 00 00 00 00 00
+        80 00 29 CD AB 34 12 4E 4F 20 4E 41 4D 45 20 20
+        20 20 46 41 54 33 32 20 20 20"
+        );
+
+        const PADDING: &[u8] = concat_bytes!([0x00; 420], [0x55, 0xAA]);
+
+        let mut bpb_data = DATA.to_vec();
+        bpb_data.extend_from_slice(PADDING);
+
+        let bpb = FatBpb::new(&bpb_data).unwrap();
+
+        assert_eq!(bpb.sectors_per_cluster(), 1);
+        assert_eq!(bpb.reserved_sector_count(), 32);
+        assert_eq!(bpb.root_entries_count(), 0);
+        assert_eq!(bpb.sectors_per_fat(), 0);
+        assert_eq!(bpb.sectors_per_fat_32(), 4033);
+        assert_eq!(bpb.fat_size(), 4033);
+        assert_eq!(bpb.root_cluster(), 2);
+        assert_eq!(bpb.fs_info_sector(), 1);
+        assert_eq!(bpb.backup_boot_sector(), 6);
+        assert_eq!(bpb.drive_number_32(), 0x80);
+        assert_eq!(bpb.boot_signature_32(), 0x29);
+        assert_eq!(bpb.volume_id_32(), 0x1234abcd);
+        assert_eq!(bpb.volume_label_32(), b"NO NAME    ");
+        assert_eq!(bpb.system_identifier_32_str(), "FAT32   ");
+
+        assert_eq!(bpb.total_sectors(), 524288);
+        assert_eq!(bpb.root_dir_sectors(), 0);
+        assert_eq!(bpb.cluster_count(), 516190);
+        assert_eq!(bpb.fat_type(), FatType::Fat32);
+
+        println!("{:#?}", bpb);
+    }
+
+    /// A valid FAT16 boot sector to break
+    fn sector() -> [u8; 512] {
+        const DATA: [u8; 64] = hex_literal::hex!(
+            "EB 3E 90 4D 53 57 49 4E 34 2E 31 00 02 10 01 00
+        02 00 02 00 00 F8 FC 00 3F 00 10 00 3F 00 00 00
+        C1 BF 0F 00 80 00 29 FD 1A BE FA 51 45 4D 55 20
+        56 56 46 41 54 20 46 41 54 31 36 20 20 20 00 00"
+        );
+
+        let mut data = [0; 512];
+        data[..64].copy_from_slice(&DATA);
+        data[0x1fe..].copy_from_slice(&[0x55, 0xAA]);
+        assert!(FatBpb::new(&data).is_ok());
+        data
+    }
+
+    #[test]
+    fn bad_signature() {
+        let mut data = sector();
+        data[0x1fe] = 0;
+        assert!(FatBpb::new(&data).is_err());
+        assert!(FatBpb::new(&data[..256]).is_err());
+    }
+
+    #[test]
+    fn bad_bytes_per_sector() {
+        for size in [0u16, 256, 1024, 4096] {
+            let mut data = sector();
+            data[0x0b..0x0d].copy_from_slice(&size.to_le_bytes());
+            assert!(FatBpb::new(&data).is_err(), "{} bytes per sector", size);
+        }
+    }
+
+    #[test]
+    fn zero_sectors_per_cluster() {
+        let mut data = sector();
+        data[0x0d] = 0;
+        assert!(FatBpb::new(&data).is_err());
+    }
+
+    #[test]
+    fn zero_fat_count() {
+        let mut data = sector();
+        data[0x10] = 0;
+        assert!(FatBpb::new(&data).is_err());
+    }
+}
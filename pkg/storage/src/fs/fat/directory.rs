@@ -23,13 +23,6 @@ impl Directory {
         }
     }
 
-    pub const fn root() -> Self {
-        Directory {
-            cluster: Cluster::ROOT_DIR,
-            entry: None,
-        }
-    }
-
     pub fn from_entry(entry: DirEntry) -> Self {
         Directory {
             cluster: entry.cluster,
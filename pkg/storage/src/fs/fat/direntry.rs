@@ -15,12 +15,27 @@ use core::ops::*;
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct DirEntry {
     pub filename: ShortFileName,
+    /// VFAT long file name, if the entry has one
+    pub long_name: Option<String>,
     pub moditified_time: FsTime,
     pub created_time: FsTime,
     pub accessed_time: FsTime,
     pub cluster: Cluster,
     pub attributes: Attributes,
     pub size: u32,
+    /// Reserved byte, which Windows uses for the case of the short name
+    pub case_flags: u8,
+    /// Tenths of a second to add to `created_time`, up to 199
+    pub created_tenths: u8,
+}
+
+/// Location of a directory entry on the volume
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DirEntryPos {
+    /// The sector holding the entry
+    pub sector: usize,
+    /// Index of the entry within the sector
+    pub index: usize,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -36,13 +51,30 @@ bitflags! {
         const VOLUME_ID = 0x08;
         const DIRECTORY = 0x10;
         const ARCHIVE   = 0x20;
-        const LFN       = 0x0f; // Long File Name, Not Implemented
+        const LFN       = 0x0f; // Long File Name, see `lfn`
     }
 }
 
 impl DirEntry {
     pub const LEN: usize = 0x20;
 
+    /// Create an empty entry stamped with the current time
+    pub fn new(filename: ShortFileName, attributes: Attributes) -> Self {
+        let time = now();
+        Self {
+            filename,
+            long_name: None,
+            moditified_time: time,
+            created_time: time,
+            accessed_time: time,
+            cluster: Cluster::EMPTY,
+            attributes,
+            size: 0,
+            case_flags: 0,
+            created_tenths: 0,
+        }
+    }
+
     pub fn is_readonly(&self) -> bool {
         self.attributes.contains(Attributes::READ_ONLY)
     }
@@ -83,14 +115,32 @@ impl DirEntry {
         !self.is_eod() && !self.is_unused()
     }
 
+    /// The `.` and `..` entries a subdirectory starts with
+    pub fn is_dot(&self) -> bool {
+        self.filename.name[0] == b'.'
+    }
+
     pub fn filename(&self) -> String {
-        if self.is_valid() && !self.is_long_name() {
+        if let Some(name) = &self.long_name {
+            name.clone()
+        } else if self.is_valid() && !self.is_long_name() {
             format!("{}", self.filename)
         } else {
             String::from("unknown")
         }
     }
 
+    /// Compare the long or the short name of this entry with `name`,
+    /// ignoring case like Windows does
+    pub fn matches(&self, name: &str, sfn: Option<&ShortFileName>) -> bool {
+        let lowercase = |s: &str| s.chars().flat_map(char::to_lowercase).collect::<String>();
+
+        self.long_name
+            .as_deref()
+            .is_some_and(|long| lowercase(long) == lowercase(name))
+            || sfn.is_some_and(|sfn| self.filename.matches(sfn))
+    }
+
     /// For Standard 8.3 format
     pub fn parse(data: &[u8]) -> Result<DirEntry> {
         // trace!(
@@ -103,15 +153,15 @@ impl DirEntry {
 
         let filename = ShortFileName::new(&data[..11]);
 
-        // TODO: parse long file name
-        // if filename.is_eod() || filename.is_unused() {
-        //     return Err(FilenameError::UnableToParse);
-        // }
+        // long file names are assembled by the directory walker,
+        // see `lfn::LfnBuilder`
 
         let attributes = Attributes::from_bits_truncate(data[11]);
 
-        // 12: Reserved. Must be set to zero
-        // 13: CrtTimeTenth, not supported, set to zero
+        // 12: Reserved, but Windows keeps the case of the short name here
+        // 13: CrtTimeTenth, kept as is and written back unchanged
+        let case_flags = data[12];
+        let created_tenths = data[13];
 
         let mut time = u32::from_le_bytes([data[14], data[15], data[16], data[17]]);
         let created_time = prase_datetime(time);
@@ -131,20 +181,58 @@ impl DirEntry {
 
         Ok(DirEntry {
             filename,
+            long_name: None,
             moditified_time,
             created_time,
             accessed_time,
             cluster: Cluster(cluster),
             attributes,
             size,
+            case_flags,
+            created_tenths,
         })
     }
 
+    /// Serialize the entry into its 32 byte on-disk form
+    pub fn as_bytes(&self) -> [u8; DirEntry::LEN] {
+        let mut data = [0u8; DirEntry::LEN];
+
+        data[..8].copy_from_slice(&self.filename.name);
+        data[8..11].copy_from_slice(&self.filename.ext);
+        data[11] = self.attributes.bits();
+        data[12] = self.case_flags;
+        data[13] = self.created_tenths;
+
+        data[14..18].copy_from_slice(&encode_datetime(&self.created_time).to_le_bytes());
+        data[18..20].copy_from_slice(&encode_datetime(&self.accessed_time).to_le_bytes()[2..]);
+        data[20..22].copy_from_slice(&((self.cluster.0 >> 16) as u16).to_le_bytes());
+        data[22..26].copy_from_slice(&encode_datetime(&self.moditified_time).to_le_bytes());
+        data[26..28].copy_from_slice(&(self.cluster.0 as u16).to_le_bytes());
+        data[28..32].copy_from_slice(&self.size.to_le_bytes());
+
+        data
+    }
+
     pub fn as_meta(&self) -> Metadata {
         self.into()
     }
 }
 
+/// Pack a timestamp into the FAT date (high half) and time (low half) format
+fn encode_datetime(time: &FsTime) -> u32 {
+    use chrono::{Datelike, Timelike};
+
+    // FAT dates start from 1980 and cannot go beyond 2107
+    let year = time.year().clamp(1980, 2107) as u32 - 1980;
+
+    (year << 25)
+        | (time.month() << 21)
+        | (time.day() << 16)
+        | (time.hour() << 11)
+        | (time.minute() << 5)
+        | (time.second() / 2)
+}
+
 fn prase_datetime(time: u32) -> FsTime {
     let year = ((time >> 25) + 1980) as i32;
     let month = (time >> 21) & 0x0f;
@@ -194,6 +282,50 @@ impl ShortFileName {
         self.name == sfn.name && self.ext == sfn.ext
     }
 
+    /// Checksum stored in the LFN entries belonging to this name
+    pub fn checksum(&self) -> u8 {
+        self.name
+            .iter()
+            .chain(self.ext.iter())
+            .fold(0u8, |sum, &ch| sum.rotate_right(1).wrapping_add(ch))
+    }
+
+    /// The `n`-th numeric-tail alias of a long name, like `LONGFI~1.TXT`
+    pub fn alias(name: &str, n: usize) -> ShortFileName {
+        let (base, ext) = match name.trim_start_matches('.').rsplit_once('.') {
+            Some((base, ext)) => (base, ext),
+            None => (name, ""),
+        };
+
+        // spaces and periods are dropped, anything else not allowed becomes '_'
+        let clean = |s: &str| -> Vec<u8> {
+            s.chars()
+                .filter(|&c| c != ' ' && c != '.')
+                .map(|c| match c {
+                    c if c.is_ascii() && !is_invalid_char(c as u8) => c.to_ascii_uppercase() as u8,
+                    _ => b'_',
+                })
+                .collect()
+        };
+
+        let tail = format!("~{}", n);
+        let mut sfn = ShortFileName {
+            name: [0x20; 8],
+            ext: [0x20; 3],
+        };
+
+        let base = clean(base);
+        let base_len = base.len().min(8 - tail.len());
+        sfn.name[..base_len].copy_from_slice(&base[..base_len]);
+        sfn.name[base_len..base_len + tail.len()].copy_from_slice(tail.as_bytes());
+
+        let ext = clean(ext);
+        let ext_len = ext.len().min(3);
+        sfn.ext[..ext_len].copy_from_slice(&ext[..ext_len]);
+
+        sfn
+    }
+
     pub fn parse(name: &str) -> Result<ShortFileName> {
         let mut sfn = ShortFileName {
             name: [0x20; 8],
@@ -203,24 +335,7 @@ impl ShortFileName {
         let mut seen_dot = false;
         for ch in name.bytes() {
             match ch {
-                // Microsoft say these are the invalid characters
-                0x00..=0x1F
-                | 0x20
-                | 0x22
-                | 0x2A
-                | 0x2B
-                | 0x2C
-                | 0x2F
-                | 0x3A
-                | 0x3B
-                | 0x3C
-                | 0x3D
-                | 0x3E
-                | 0x3F
-                | 0x5B
-                | 0x5C
-                | 0x5D
-                | 0x7C => {
+                ch if is_invalid_char(ch) => {
                     return Err(FilenameError::InvalidCharacter.into());
                 }
                 // Denotes the start of the file extension
@@ -257,6 +372,30 @@ impl ShortFileName {
     }
 }
 
+/// Microsoft say these are the invalid characters in a short name
+fn is_invalid_char(ch: u8) -> bool {
+    matches!(
+        ch,
+        0x00..=0x1F
+            | 0x20
+            | 0x22
+            | 0x2A
+            | 0x2B
+            | 0x2C
+            | 0x2F
+            | 0x3A
+            | 0x3B
+            | 0x3C
+            | 0x3D
+            | 0x3E
+            | 0x3F
+            | 0x5B
+            | 0x5C
+            | 0x5D
+            | 0x7C
+    )
+}
+
 impl Debug for ShortFileName {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(f, "{}", self)
@@ -286,7 +425,7 @@ impl Cluster {
     /// Magic value indicating a empty cluster.
     pub const EMPTY: Cluster = Cluster(0x0000_0000);
     /// Magic value indicating the cluster holding the root directory (which
-    /// doesn't have a number in Fat16 as there's a reserved region).
+    /// doesn't have a number in FAT12/16 as there's a reserved region).
     pub const ROOT_DIR: Cluster = Cluster(0xFFFF_FFFC);
     /// Magic value indicating that the cluster is allocated and is the final cluster for the file
     pub const END_OF_FILE: Cluster = Cluster(0xFFFF_FFFF);
@@ -380,4 +519,35 @@ mod tests {
 
         println!("{:#?}", res);
     }
+
+    #[test]
+    fn test_dir_entry_roundtrip() {
+        let data = hex_literal::hex!(
+            "4b 45 52 4e 45 4c 20 20 45 4c 46 20 00 00 0f be
+             d0 50 d0 50 00 00 0f be d0 50 02 00 f0 e4 0e 00"
+        );
+
+        let res = DirEntry::parse(&data).unwrap();
+
+        assert_eq!(res.as_bytes(), data);
+        assert_eq!(DirEntry::parse(&res.as_bytes()).unwrap(), res);
+    }
+
+    #[test]
+    fn test_dir_entry_keeps_reserved() {
+        // a lower case "kernel.elf" as Windows writes it, created at :31.5
+        let data = hex_literal::hex!(
+            "4b 45 52 4e 45 4c 20 20 45 4c 46 20 18 96 0f be
+             d0 50 d0 50 00 00 0f be d0 50 02 00 f0 e4 0e 00"
+        );
+
+        let mut res = DirEntry::parse(&data).unwrap();
+        assert_eq!(res.case_flags, 0x18);
+        assert_eq!(res.created_tenths, 150);
+
+        res.size = 0x1000;
+        let bytes = res.as_bytes();
+        assert_eq!(bytes[12..14], [0x18, 0x96]);
+        assert_eq!(bytes[28..32], 0x1000u32.to_le_bytes());
+    }
 }
@@ -0,0 +1,222 @@
+//! File
+//!
+//! reference:
+//! - <https://wiki.osdev.org/FAT#Directories_on_FAT12.2F16.2F32>
+//! - <https://github.com/rust-embedded-community/embedded-sdmmc-rs/blob/develop/src/filesystem.rs>
+
+use super::*;
+
+/// An open file. It writes its `DirEntry` back when dropped, so it is
+/// not `Clone`: a copy would write back a stale size and cluster.
+#[derive(Debug)]
+pub struct File {
+    /// The current offset in the file.
+    pub offset: usize,
+    /// DirEntry of this file
+    entry: DirEntry,
+    /// Where the DirEntry of this file lives
+    pos: DirEntryPos,
+    /// The current cluster of this file
+    current: Cluster,
+    /// Index of the current cluster in the cluster chain
+    index: usize,
+    /// Whether the DirEntry needs to be written back
+    dirty: bool,
+    /// The file system handle that contains this file.
+    handle: FatHandle,
+}
+
+impl File {
+    pub fn new(handle: FatHandle, entry: DirEntry, pos: DirEntryPos) -> Self {
+        Self {
+            offset: 0,
+            current: entry.cluster,
+            index: 0,
+            entry,
+            pos,
+            dirty: false,
+            handle,
+        }
+    }
+
+    pub fn length(&self) -> usize {
+        self.entry.size as usize
+    }
+
+    fn cluster_size(&self) -> usize {
+        let sector_pre_cluster = self.handle.bpb.sectors_per_cluster() as usize;
+        let sector_size = self.handle.bpb.bytes_per_sector() as usize;
+        sector_pre_cluster * sector_size
+    }
+
+    /// Move `current` to the `index`-th cluster of the chain.
+    ///
+    /// When `alloc` is set, missing clusters are allocated on the way,
+    /// otherwise `false` is returned if the chain is too short.
+    fn seek_cluster(&mut self, index: usize, alloc: bool) -> Result<bool> {
+        if self.entry.cluster == Cluster::EMPTY {
+            if !alloc {
+                return Ok(false);
+            }
+
+            self.entry.cluster = self.handle.alloc_cluster(None)?;
+            self.current = self.entry.cluster;
+            self.index = 0;
+            self.dirty = true;
+        }
+
+        if index < self.index {
+            self.current = self.entry.cluster;
+            self.index = 0;
+        }
+
+        while self.index < index {
+            self.current = match self.handle.next_cluster(&self.current) {
+                Ok(next) => next,
+                Err(FsError::EndOfFile) if alloc => {
+                    self.handle.alloc_cluster(Some(&self.current))?
+                }
+                Err(FsError::EndOfFile) => return Ok(false),
+                Err(e) => return Err(e),
+            };
+            self.index += 1;
+        }
+
+        Ok(true)
+    }
+}
+
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let length = self.length();
+
+        if self.offset >= length {
+            return Ok(0);
+        }
+
+        let cluster_size = self.cluster_size();
+
+        let mut block = Block::default();
+        let mut bytes_read = 0;
+
+        while bytes_read < buf.len() && self.offset < length {
+            if !self.seek_cluster(self.offset / cluster_size, false)? {
+                break;
+            }
+
+            let cluster_sector = self.handle.cluster_to_sector(&self.current);
+            let cluster_offset = self.offset % cluster_size;
+            let current_sector = cluster_sector + cluster_offset / BLOCK_SIZE;
+
+            self.handle.inner.read_block(current_sector, &mut block)?;
+
+            let current_offset = self.offset % BLOCK_SIZE;
+            let block_remain = BLOCK_SIZE - current_offset;
+            let file_remain = length - self.offset;
+            let buf_remain = buf.len() - bytes_read;
+            let to_read = buf_remain.min(block_remain).min(file_remain);
+
+            buf[bytes_read..bytes_read + to_read]
+                .copy_from_slice(&block[current_offset..current_offset + to_read]);
+
+            bytes_read += to_read;
+            self.offset += to_read;
+        }
+
+        Ok(bytes_read)
+    }
+}
+
+impl Seek for File {
+    fn seek(&mut self, pos: SeekFrom) -> Result<usize> {
+        let offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.length().checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.offset.checked_add_signed(delta),
+        }
+        .ok_or(FsError::InvalidOffset)?;
+
+        // offsets past the end are allowed, the gap is filled on the next write
+        if offset < self.length() {
+            self.seek_cluster(offset / self.cluster_size(), false)?;
+        }
+
+        self.offset = offset;
+        Ok(offset)
+    }
+}
+
+impl Write for File {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.entry.is_readonly() {
+            return Err(FsError::ReadOnly);
+        } else if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let cluster_size = self.cluster_size();
+
+        let mut block = Block::default();
+        let mut bytes_written = 0;
+
+        while bytes_written < buf.len() {
+            match self.seek_cluster(self.offset / cluster_size, true) {
+                Ok(_) => {}
+                // report a short write if the volume fills up halfway
+                Err(FsError::WriteZero) if bytes_written > 0 => break,
+                Err(e) => return Err(e),
+            }
+
+            let cluster_sector = self.handle.cluster_to_sector(&self.current);
+            let cluster_offset = self.offset % cluster_size;
+            let current_sector = cluster_sector + cluster_offset / BLOCK_SIZE;
+
+            let current_offset = self.offset % BLOCK_SIZE;
+            let block_remain = BLOCK_SIZE - current_offset;
+            let buf_remain = buf.len() - bytes_written;
+            let to_write = buf_remain.min(block_remain);
+
+            // only a partially overwritten block has to be read first
+            if to_write < BLOCK_SIZE {
+                self.handle.inner.read_block(current_sector, &mut block)?;
+            }
+
+            block.as_mut()[current_offset..current_offset + to_write]
+                .copy_from_slice(&buf[bytes_written..bytes_written + to_write]);
+
+            self.handle.inner.write_block(current_sector, &block)?;
+
+            bytes_written += to_write;
+            self.offset += to_write;
+        }
+
+        if self.offset > self.length() {
+            self.entry.size = self.offset as u32;
+        }
+
+        self.entry.moditified_time = now();
+        self.entry.attributes |= Attributes::ARCHIVE;
+        self.dirty = true;
+
+        Ok(bytes_written)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if self.dirty {
+            self.handle.write_dir_entry(&self.entry, &self.pos)?;
+            self.dirty = false;
+        }
+
+        self.handle.inner.flush()
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        if self.dirty
+            && let Err(e) = self.flush()
+        {
+            error!("Failed to flush file {}: {:?}", self.entry.filename(), e);
+        }
+    }
+}
@@ -0,0 +1,1308 @@
+use super::*;
+
+impl FatImpl {
+    pub fn new(inner: impl BlockDevice<Block512>) -> Self {
+        let mut block = Block::default();
+
+        inner.read_block(0, &mut block).unwrap();
+        let bpb = FatBpb::new(block.as_ref()).unwrap();
+
+        trace!("Loading FAT Volume: {:#?}", bpb);
+
+        let fat_type = bpb.fat_type();
+
+        // FirstDataSector = BPB_ResvdSecCnt + (BPB_NumFATs * FATSz) + RootDirSectors;
+        let fat_start = bpb.reserved_sector_count() as usize;
+        let first_root_dir_sector =
+            fat_start + (bpb.fat_count() as usize * bpb.fat_size() as usize);
+        let first_data_sector = first_root_dir_sector + bpb.root_dir_sectors() as usize;
+
+        let cluster_count = bpb.cluster_count() as usize;
+
+        let root_cluster = match fat_type {
+            FatType::Fat32 => Cluster(bpb.root_cluster()),
+            _ => Cluster::ROOT_DIR,
+        };
+
+        let mut fs_info = None;
+        let mut next_free = 2;
+
+        if fat_type == FatType::Fat32 && bpb.fs_info_sector() != 0 {
+            let sector = bpb.fs_info_sector() as usize;
+            inner.read_block(sector, &mut block).unwrap();
+
+            match FsInfo::new(block.as_ref()) {
+                Ok(info) => {
+                    trace!("Loading FSInfo: {:#?}", info);
+                    if (2..cluster_count as u32 + 2).contains(&info.next_free()) {
+                        next_free = info.next_free();
+                    }
+                    fs_info = Some(sector);
+                }
+                Err(e) => warn!("Ignoring FSInfo: {}", e),
+            }
+        }
+
+        debug!(
+            "Mounting {:?} volume with {} clusters",
+            fat_type, cluster_count
+        );
+
+        Self {
+            bpb,
+            inner: Box::new(inner),
+            fat_type,
+            fat_start,
+            first_data_sector,
+            first_root_dir_sector,
+            cluster_count,
+            root_cluster,
+            fs_info,
+            next_free: spin::Mutex::new(next_free),
+        }
+    }
+
+    pub fn cluster_to_sector(&self, cluster: &Cluster) -> usize {
+        match *cluster {
+            Cluster::ROOT_DIR => self.first_root_dir_sector,
+            Cluster(c) => {
+                // FirstSectorofCluster = ((N – 2) * BPB_SecPerClus) + FirstDataSector;
+                let first_sector_of_cluster = (c - 2) * self.bpb.sectors_per_cluster() as u32;
+                self.first_data_sector + first_sector_of_cluster as usize
+            }
+        }
+    }
+
+    /// The root directory, a fixed region on FAT12/16 and a cluster chain on FAT32
+    pub fn root_dir(&self) -> Directory {
+        Directory::new(self.root_cluster)
+    }
+
+    /// The FATs to keep up to date, every copy unless FAT32 mirroring is disabled
+    fn active_fats(&self) -> core::ops::Range<usize> {
+        let flags = self.bpb.ext_flags();
+
+        if self.fat_type == FatType::Fat32 && flags & 0x80 != 0 {
+            let active = (flags & 0x0f) as usize;
+            active..active + 1
+        } else {
+            0..self.bpb.fat_count() as usize
+        }
+    }
+
+    /// Byte offset of the entry of a cluster within a FAT
+    fn fat_entry_offset(&self, cluster: &Cluster) -> usize {
+        let n = cluster.0 as usize;
+        match self.fat_type {
+            FatType::Fat12 => n + n / 2,
+            FatType::Fat16 => n * 2,
+            FatType::Fat32 => n * 4,
+        }
+    }
+
+    /// Bytes to access for an entry, a FAT12 entry shares its bytes with a neighbour
+    fn fat_entry_width(&self) -> usize {
+        match self.fat_type {
+            FatType::Fat12 | FatType::Fat16 => 2,
+            FatType::Fat32 => 4,
+        }
+    }
+
+    /// Read bytes of the FAT starting at sector `fat`, FAT12 entries may
+    /// straddle a sector boundary
+    fn read_fat_bytes(&self, fat: usize, offset: usize, buf: &mut [u8]) -> Result<()> {
+        let block_size = Block512::size();
+        let mut block = Block::default();
+        let mut loaded = None;
+
+        for (i, byte) in buf.iter_mut().enumerate() {
+            let sector = fat + (offset + i) / block_size;
+            if loaded != Some(sector) {
+                self.inner.read_block(sector, &mut block)?;
+                loaded = Some(sector);
+            }
+            *byte = block[(offset + i) % block_size];
+        }
+
+        Ok(())
+    }
+
+    fn write_fat_bytes(&self, fat: usize, offset: usize, buf: &[u8]) -> Result<()> {
+        let block_size = Block512::size();
+        let mut block = Block::default();
+        let mut loaded = None;
+
+        for (i, byte) in buf.iter().enumerate() {
+            let sector = fat + (offset + i) / block_size;
+            if loaded != Some(sector) {
+                if let Some(prev) = loaded {
+                    self.inner.write_block(prev, &block)?;
+                }
+                self.inner.read_block(sector, &mut block)?;
+                loaded = Some(sector);
+            }
+            block.as_mut()[(offset + i) % block_size] = *byte;
+        }
+
+        match loaded {
+            Some(sector) => self.inner.write_block(sector, &block),
+            None => Ok(()),
+        }
+    }
+
+    fn read_fat_entry(&self, cluster: &Cluster) -> Result<u32> {
+        let fat = self.fat_start + self.active_fats().start * self.bpb.fat_size() as usize;
+        let mut buf = [0u8; 4];
+
+        self.read_fat_bytes(
+            fat,
+            self.fat_entry_offset(cluster),
+            &mut buf[..self.fat_entry_width()],
+        )?;
+
+        let raw = u32::from_le_bytes(buf);
+        Ok(match self.fat_type {
+            FatType::Fat12 if cluster.0 & 1 == 1 => raw >> 4,
+            FatType::Fat12 => raw & 0x0FFF,
+            FatType::Fat16 => raw,
+            // the high 4 bits of a FAT32 entry are reserved
+            FatType::Fat32 => raw & 0x0FFF_FFFF,
+        })
+    }
+
+    /// Update the entry of a cluster in every active copy of the FAT
+    fn write_fat_entry(&self, cluster: &Cluster, value: u32) -> Result<()> {
+        let offset = self.fat_entry_offset(cluster);
+        let width = self.fat_entry_width();
+        let fat_size = self.bpb.fat_size() as usize;
+
+        for fat in self.active_fats() {
+            let fat = self.fat_start + fat * fat_size;
+            let mut buf = [0u8; 4];
+
+            self.read_fat_bytes(fat, offset, &mut buf[..width])?;
+
+            let old = u32::from_le_bytes(buf);
+            let new = match self.fat_type {
+                FatType::Fat12 if cluster.0 & 1 == 1 => (old & 0x000F) | (value << 4),
+                FatType::Fat12 => (old & 0xF000) | (value & 0x0FFF),
+                FatType::Fat16 => value & 0xFFFF,
+                FatType::Fat32 => (old & 0xF000_0000) | (value & 0x0FFF_FFFF),
+            };
+
+            self.write_fat_bytes(fat, offset, &new.to_le_bytes()[..width])?;
+        }
+
+        Ok(())
+    }
+
+    /// look for next cluster in FAT
+    pub fn next_cluster(&self, cluster: &Cluster) -> Result<Cluster> {
+        let bad = self.fat_type.bad_cluster();
+
+        match self.read_fat_entry(cluster)? {
+            f if f == bad => Err(FsError::BadCluster), // Bad cluster
+            f if f > bad => Err(FsError::EndOfFile),   // There is no next cluster
+            f => Ok(Cluster(f)),                       // Seems legit
+        }
+    }
+
+    /// Allocate a zeroed cluster and append it to the chain ending at `prev`
+    pub fn alloc_cluster(&self, prev: Option<&Cluster>) -> Result<Cluster> {
+        let mut next_free = self.next_free.lock();
+        let count = self.cluster_count as u32;
+
+        for i in 0..count {
+            let cluster = Cluster(2 + (*next_free - 2 + i) % count);
+
+            if self.read_fat_entry(&cluster)? != 0 {
+                continue;
+            }
+
+            self.write_fat_entry(&cluster, self.fat_type.end_of_chain())?;
+            if let Some(prev) = prev {
+                self.write_fat_entry(prev, cluster.0)?;
+            }
+            *next_free = cluster.0 + 1;
+
+            self.update_fs_info(-1, *next_free)?;
+            self.zero_cluster(&cluster)?;
+            return Ok(cluster);
+        }
+
+        Err(FsError::WriteZero)
+    }
+
+    /// Release every cluster of the chain starting at `start`
+    pub fn free_chain(&self, start: &Cluster) -> Result<()> {
+        let mut next_free = self.next_free.lock();
+        let mut current = *start;
+        let mut freed = 0;
+
+        while (2..self.fat_type.bad_cluster()).contains(&current.0) {
+            let next = self.read_fat_entry(&current)?;
+            self.write_fat_entry(&current, 0)?;
+            current = Cluster(next);
+            freed += 1;
+        }
+
+        *next_free = (*next_free).min(start.0.max(2));
+        self.update_fs_info(freed, *next_free)
+    }
+
+    /// Keep the free cluster hints of a FAT32 volume in step with the FAT
+    fn update_fs_info(&self, free_delta: i32, next_free: u32) -> Result<()> {
+        let Some(sector) = self.fs_info else {
+            return Ok(());
+        };
+
+        let mut block = Block::default();
+        self.inner.read_block(sector, &mut block)?;
+
+        let mut info = match FsInfo::new(block.as_ref()) {
+            Ok(info) => info,
+            Err(_) => return Ok(()),
+        };
+
+        if info.free_count() != FsInfo::UNKNOWN {
+            info.set_free_count(info.free_count().wrapping_add_signed(free_delta));
+        }
+        info.set_next_free(next_free);
+
+        self.inner.write_block(sector, &Block::new(info.as_bytes()))
+    }
+
+    fn zero_cluster(&self, cluster: &Cluster) -> Result<()> {
+        let block = Block::default();
+        let first_sector = self.cluster_to_sector(cluster);
+
+        for sector in first_sector..first_sector + self.bpb.sectors_per_cluster() as usize {
+            self.inner.write_block(sector, &block)?;
+        }
+
+        Ok(())
+    }
+
+    /// Visit every raw entry slot of a directory in on-disk order,
+    /// stopping as soon as `func` returns `Some`
+    fn visit_dir<T, F>(&self, dir: &Directory, mut func: F) -> Result<Option<T>>
+    where
+        F: FnMut(&[u8], DirEntryPos) -> Option<T>,
+    {
+        let mut current_cluster = Some(dir.cluster);
+        let mut dir_sector_num = self.cluster_to_sector(&dir.cluster);
+        let dir_size = match dir.cluster {
+            Cluster::ROOT_DIR => self.first_data_sector - self.first_root_dir_sector,
+            _ => self.bpb.sectors_per_cluster() as usize,
+        };
+
+        let mut block = Block::default();
+        let block_size = Block512::size();
+        while let Some(cluster) = current_cluster {
+            for sector in dir_sector_num..dir_sector_num + dir_size {
+                self.inner.read_block(sector, &mut block)?;
+                for index in 0..block_size / DirEntry::LEN {
+                    let start = index * DirEntry::LEN;
+                    let end = (index + 1) * DirEntry::LEN;
+
+                    if let Some(ret) = func(&block[start..end], DirEntryPos { sector, index }) {
+                        return Ok(Some(ret));
+                    }
+                }
+            }
+            current_cluster = if cluster != Cluster::ROOT_DIR {
+                match self.next_cluster(&cluster) {
+                    Ok(n) => {
+                        dir_sector_num = self.cluster_to_sector(&n);
+                        Some(n)
+                    }
+                    _ => None,
+                }
+            } else {
+                None
+            }
+        }
+        Ok(None)
+    }
+
+    /// Visit the entries of a directory with their long file names attached,
+    /// stopping at the end of the directory or as soon as `func` returns `Some`
+    fn visit_entries<T, F>(&self, dir: &Directory, mut func: F) -> Result<Option<T>>
+    where
+        F: FnMut(DirEntry, DirEntryPos) -> Option<T>,
+    {
+        let mut lfn = lfn::LfnBuilder::default();
+
+        let ret = self.visit_dir(dir, |data, pos| {
+            if let Ok(entry) = lfn::LfnEntry::parse(data) {
+                lfn.push(&entry);
+                return None;
+            }
+
+            let mut entry = match DirEntry::parse(data) {
+                Ok(entry) => entry,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if entry.is_eod() {
+                return Some(Ok(None));
+            } else if !entry.is_valid() {
+                lfn.reset();
+                return None;
+            }
+
+            entry.long_name = lfn.finish(&entry.filename);
+            func(entry, pos).map(|ret| Ok(Some(ret)))
+        })?;
+
+        ret.unwrap_or(Ok(None))
+    }
+
+    pub fn iterate_dir<F>(&self, dir: &directory::Directory, mut func: F) -> Result<()>
+    where
+        F: FnMut(&DirEntry),
+    {
+        if let Some(entry) = &dir.entry {
+            trace!("Iterating directory: {}", entry.filename());
+        }
+
+        self.visit_entries(dir, |entry, _| {
+            func(&entry);
+            None::<()>
+        })?;
+
+        Ok(())
+    }
+
+    /// Get an entry from the given directory, matching either its long or its short name
+    fn find_directory_entry(&self, dir: &Directory, name: &str) -> Result<(DirEntry, DirEntryPos)> {
+        let short_name = ShortFileName::parse(name).ok();
+
+        self.visit_entries(dir, |entry, pos| {
+            entry
+                .matches(name, short_name.as_ref())
+                .then_some((entry, pos))
+        })?
+        .ok_or(FsError::FileNotFound)
+    }
+
+    /// Find `count` consecutive free slots in the given directory,
+    /// growing it by a cluster if there is no room left
+    fn alloc_dir_slots(&self, dir: &Directory, count: usize) -> Result<Vec<DirEntryPos>> {
+        let mut run = Vec::with_capacity(count);
+
+        let found = self.visit_dir(dir, |data, pos| {
+            if data[0] == 0x00 || data[0] == 0xE5 {
+                run.push(pos);
+            } else {
+                run.clear();
+            }
+            (run.len() == count).then_some(())
+        })?;
+
+        if found.is_some() {
+            return Ok(run);
+        }
+
+        // the root directory has a fixed size in FAT12/16
+        if dir.cluster == Cluster::ROOT_DIR {
+            return Err(FsError::WriteZero);
+        }
+
+        let mut last = dir.cluster;
+        while let Ok(next) = self.next_cluster(&last) {
+            last = next;
+        }
+
+        // continue the free run at the end of the directory into the new cluster
+        let cluster = self.alloc_cluster(Some(&last))?;
+        let first_sector = self.cluster_to_sector(&cluster);
+        let per_sector = Block512::size() / DirEntry::LEN;
+
+        for slot in 0..count - run.len() {
+            run.push(DirEntryPos {
+                sector: first_sector + slot / per_sector,
+                index: slot % per_sector,
+            });
+        }
+
+        Ok(run)
+    }
+
+    /// Pick the short name for a new entry, adding a long name when
+    /// `name` is not a plain 8.3 name
+    fn short_name_for(&self, dir: &Directory, name: &str) -> Result<(ShortFileName, bool)> {
+        if let Ok(sfn) = ShortFileName::parse(name) {
+            let short = format!("{}", sfn);
+            if short.eq_ignore_ascii_case(name) {
+                // keep the case of the name through an LFN if it is not upper case
+                return Ok((sfn, short != name));
+            }
+        }
+
+        lfn::validate(name)?;
+
+        let mut taken = Vec::new();
+        self.visit_entries(dir, |entry, _| {
+            taken.push(entry.filename);
+            None::<()>
+        })?;
+
+        (1..1_000_000)
+            .map(|n| ShortFileName::alias(name, n))
+            .find(|sfn| !taken.iter().any(|taken| taken.matches(sfn)))
+            .map(|sfn| (sfn, true))
+            .ok_or(FsError::FileNameError(FilenameError::NameTooLong))
+    }
+
+    /// Create a new entry in the given directory
+    fn create_dir_entry(
+        &self,
+        dir: &Directory,
+        name: &str,
+        attributes: Attributes,
+    ) -> Result<(DirEntry, DirEntryPos)> {
+        let (sfn, long) = self.short_name_for(dir, name)?;
+
+        let mut slots = match long {
+            true => lfn::encode(name, &sfn),
+            false => Vec::new(),
+        };
+
+        let mut entry = DirEntry::new(sfn, attributes);
+        if long {
+            entry.long_name = Some(name.into());
+        }
+        slots.push(entry.as_bytes());
+
+        let positions = self.alloc_dir_slots(dir, slots.len())?;
+        for (data, pos) in slots.iter().zip(&positions) {
+            self.write_dir_slot(data, pos)?;
+        }
+
+        Ok((entry, *positions.last().unwrap()))
+    }
+
+    fn write_dir_slot(&self, data: &[u8; DirEntry::LEN], pos: &DirEntryPos) -> Result<()> {
+        let mut block = Block::default();
+        let start = pos.index * DirEntry::LEN;
+
+        self.inner.read_block(pos.sector, &mut block)?;
+        block.as_mut()[start..start + DirEntry::LEN].copy_from_slice(data);
+        self.inner.write_block(pos.sector, &block)
+    }
+
+    /// Write a directory entry back to its slot
+    pub fn write_dir_entry(&self, entry: &DirEntry, pos: &DirEntryPos) -> Result<()> {
+        self.write_dir_slot(&entry.as_bytes(), pos)
+    }
+
+    /// Mark the directory entry in the given slot and its long name as deleted
+    fn delete_dir_entry(&self, dir: &Directory, pos: &DirEntryPos) -> Result<()> {
+        let mut slots = Vec::new();
+
+        self.visit_dir(dir, |data, slot| {
+            if slot == *pos {
+                slots.push(slot);
+                Some(())
+            } else {
+                if !lfn::LfnEntry::is_lfn(data) {
+                    slots.clear();
+                } else {
+                    slots.push(slot);
+                }
+                None
+            }
+        })?
+        .ok_or(FsError::FileNotFound)?;
+
+        let mut block = Block::default();
+        for slot in slots {
+            self.inner.read_block(slot.sector, &mut block)?;
+            block.as_mut()[slot.index * DirEntry::LEN] = 0xE5;
+            self.inner.write_block(slot.sector, &block)?;
+        }
+
+        Ok(())
+    }
+
+    /// Release the clusters of a file and reset its size to zero
+    fn truncate(&self, entry: &mut DirEntry, pos: &DirEntryPos) -> Result<()> {
+        if entry.cluster != Cluster::EMPTY {
+            self.free_chain(&entry.cluster)?;
+        }
+
+        entry.cluster = Cluster::EMPTY;
+        entry.size = 0;
+        entry.moditified_time = now();
+
+        self.write_dir_entry(entry, pos)
+    }
+
+    /// Create an empty directory called `name` in `dir`, which starts with
+    /// the `.` and `..` entries
+    fn make_dir(&self, dir: &Directory, name: &str) -> Result<()> {
+        let cluster = self.alloc_cluster(None)?;
+
+        let (mut entry, pos) = match self.create_dir_entry(dir, name, Attributes::DIRECTORY) {
+            Ok(created) => created,
+            Err(e) => {
+                self.free_chain(&cluster)?;
+                return Err(e);
+            }
+        };
+
+        entry.cluster = cluster;
+        self.write_dir_entry(&entry, &pos)?;
+
+        // ".." of a top level directory points to cluster 0
+        let parent = match dir.cluster {
+            c if c == self.root_cluster => Cluster::EMPTY,
+            c => c,
+        };
+
+        let sector = self.cluster_to_sector(&cluster);
+        for (index, (dots, cluster)) in [(".", cluster), ("..", parent)].into_iter().enumerate() {
+            let mut filename = [b' '; 11];
+            filename[..dots.len()].copy_from_slice(dots.as_bytes());
+
+            let mut dot = DirEntry::new(ShortFileName::new(&filename), Attributes::DIRECTORY);
+            dot.cluster = cluster;
+            dot.created_time = entry.created_time;
+            dot.moditified_time = entry.moditified_time;
+
+            self.write_dir_entry(&dot, &DirEntryPos { sector, index })?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether a directory has no entries but `.` and `..`
+    fn is_empty_dir(&self, dir: &Directory) -> Result<bool> {
+        if dir.cluster == Cluster::EMPTY {
+            return Ok(true);
+        }
+
+        let found = self.visit_entries(dir, |entry, _| (!entry.is_dot()).then_some(()))?;
+        Ok(found.is_none())
+    }
+
+    /// Walk down the path, every component of which must be a directory
+    fn get_dir(&self, path: &str) -> Result<Directory> {
+        let mut current = self.root_dir();
+
+        for dir in path.split(PATH_SEPARATOR) {
+            if dir.is_empty() {
+                continue;
+            }
+
+            let (entry, _) = self.find_directory_entry(&current, dir)?;
+
+            if !entry.is_directory() {
+                return Err(FsError::NotADirectory);
+            }
+
+            // ".." of a top level directory points to cluster 0
+            current = match entry.cluster {
+                Cluster::EMPTY => self.root_dir(),
+                _ => Directory::from_entry(entry),
+            };
+        }
+
+        Ok(current)
+    }
+
+    fn get_dir_entry(&self, path: &str) -> Result<(DirEntry, DirEntryPos)> {
+        let (parent, name) = split_path(path);
+        let parent = self.get_dir(parent)?;
+
+        self.find_directory_entry(&parent, name)
+    }
+}
+
+/// Split a path into its parent directory and the last component
+fn split_path(path: &str) -> (&str, &str) {
+    let path = path.trim_end_matches(PATH_SEPARATOR);
+    path.rsplit_once(PATH_SEPARATOR).unwrap_or(("", path))
+}
+
+impl Fat {
+    fn file_handle(&self, entry: DirEntry, pos: DirEntryPos) -> FileHandle {
+        let meta = entry.as_meta();
+        let file = Box::new(File::new(self.handle.clone(), entry, pos));
+
+        FileHandle::new(meta, file)
+    }
+}
+
+impl FileSystem for Fat {
+    fn read_dir(&self, path: &str) -> Result<Box<dyn Iterator<Item = Metadata> + Send>> {
+        let dir = self.handle.get_dir(path)?;
+        let mut entries = Vec::new();
+
+        self.handle.iterate_dir(&dir, |entry| {
+            if !entry.is_dot() {
+                entries.push(entry.as_meta());
+            }
+        })?;
+
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn open_file(&self, path: &str) -> Result<FileHandle> {
+        let (entry, pos) = self.handle.get_dir_entry(path)?;
+
+        if entry.is_directory() {
+            return Err(FsError::NotAFile);
+        }
+
+        Ok(self.file_handle(entry, pos))
+    }
+
+    fn metadata(&self, path: &str) -> Result<Metadata> {
+        Ok(self.handle.get_dir_entry(path)?.0.as_meta())
+    }
+
+    fn exists(&self, path: &str) -> Result<bool> {
+        Ok(self.handle.get_dir_entry(path).is_ok())
+    }
+
+    fn create_file(&self, path: &str) -> Result<FileHandle> {
+        let (parent, name) = split_path(path);
+        let dir = self.handle.get_dir(parent)?;
+
+        let (entry, pos) = match self.handle.find_directory_entry(&dir, name) {
+            Ok((mut entry, pos)) => {
+                if entry.is_directory() {
+                    return Err(FsError::NotAFile);
+                } else if entry.is_readonly() {
+                    return Err(FsError::ReadOnly);
+                }
+
+                self.handle.truncate(&mut entry, &pos)?;
+                (entry, pos)
+            }
+            Err(FsError::FileNotFound) => {
+                self.handle
+                    .create_dir_entry(&dir, name, Attributes::ARCHIVE)?
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(self.file_handle(entry, pos))
+    }
+
+    fn append_file(&self, path: &str) -> Result<FileHandle> {
+        let (entry, pos) = self.handle.get_dir_entry(path)?;
+
+        if entry.is_directory() {
+            return Err(FsError::NotAFile);
+        }
+
+        let meta = entry.as_meta();
+        let mut file = File::new(self.handle.clone(), entry, pos);
+        file.offset = file.length();
+
+        Ok(FileHandle::new(meta, Box::new(file)))
+    }
+
+    fn remove_file(&self, path: &str) -> Result<()> {
+        let (parent, name) = split_path(path);
+        let dir = self.handle.get_dir(parent)?;
+        let (entry, pos) = self.handle.find_directory_entry(&dir, name)?;
+
+        if entry.is_directory() {
+            return Err(FsError::NotAFile);
+        } else if entry.is_readonly() {
+            return Err(FsError::ReadOnly);
+        }
+
+        if entry.cluster != Cluster::EMPTY {
+            self.handle.free_chain(&entry.cluster)?;
+        }
+
+        self.handle.delete_dir_entry(&dir, &pos)?;
+        self.handle.inner.flush()
+    }
+
+    fn create_dir(&self, path: &str) -> Result<()> {
+        let (parent, name) = split_path(path);
+        let dir = self.handle.get_dir(parent)?;
+
+        match self.handle.find_directory_entry(&dir, name) {
+            Ok(_) => return Err(FsError::InvalidOperation),
+            Err(FsError::FileNotFound) => (),
+            Err(e) => return Err(e),
+        }
+
+        self.handle.make_dir(&dir, name)?;
+        self.handle.inner.flush()
+    }
+
+    fn remove_dir(&self, path: &str) -> Result<()> {
+        let (parent, name) = split_path(path);
+        let dir = self.handle.get_dir(parent)?;
+        let (entry, pos) = self.handle.find_directory_entry(&dir, name)?;
+
+        if !entry.is_directory() {
+            return Err(FsError::NotADirectory);
+        } else if entry.is_readonly() {
+            return Err(FsError::ReadOnly);
+        }
+
+        let cluster = entry.cluster;
+        if !self.handle.is_empty_dir(&Directory::from_entry(entry))? {
+            return Err(FsError::InvalidOperation);
+        }
+
+        if cluster != Cluster::EMPTY {
+            self.handle.free_chain(&cluster)?;
+        }
+
+        self.handle.delete_dir_entry(&dir, &pos)?;
+        self.handle.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    const SECTORS_PER_FAT: usize = 32;
+    const CLUSTER_SIZE: usize = 1024;
+
+    /// The layout of a blank volume, with two FATs
+    struct Geometry {
+        fat_type: FatType,
+        sectors: usize,
+        sectors_per_cluster: usize,
+        reserved: usize,
+        sectors_per_fat: usize,
+        root_entries: usize,
+    }
+
+    impl Geometry {
+        fn cluster_count(&self) -> usize {
+            let root_dir_sectors = (self.root_entries * DirEntry::LEN).div_ceil(BLOCK_SIZE);
+            let data_sectors =
+                self.sectors - self.reserved - 2 * self.sectors_per_fat - root_dir_sectors;
+            data_sectors / self.sectors_per_cluster
+        }
+    }
+
+    /// 8 MiB with two sectors a cluster
+    const FAT16: Geometry = Geometry {
+        fat_type: FatType::Fat16,
+        sectors: 16384,
+        sectors_per_cluster: CLUSTER_SIZE / BLOCK_SIZE,
+        reserved: 1,
+        sectors_per_fat: SECTORS_PER_FAT,
+        root_entries: 512,
+    };
+
+    /// 2 MiB with a sector a cluster, the FAT entries of clusters 341 and
+    /// 682 straddle a sector
+    const FAT12: Geometry = Geometry {
+        fat_type: FatType::Fat12,
+        sectors: 4096,
+        sectors_per_cluster: 1,
+        reserved: 1,
+        sectors_per_fat: 12,
+        root_entries: 224,
+    };
+
+    /// Just over 32 MiB with a sector a cluster, the fewest clusters FAT32
+    /// allows and then some
+    const FAT32: Geometry = Geometry {
+        fat_type: FatType::Fat32,
+        sectors: 66600,
+        sectors_per_cluster: 1,
+        reserved: 32,
+        sectors_per_fat: 516,
+        root_entries: 0,
+    };
+
+    /// Sector of the FSInfo structure of the FAT32 volume
+    const FS_INFO_SECTOR: usize = 1;
+
+    /// A blank volume laid out as `geometry` says, as `mkfs.fat` would
+    /// format it
+    fn format(geometry: &Geometry) -> Fat {
+        let mut data = vec![0u8; geometry.sectors * BLOCK_SIZE];
+        let fat32 = geometry.fat_type == FatType::Fat32;
+
+        let boot = &mut data[..BLOCK_SIZE];
+        boot[..3].copy_from_slice(&[0xeb, 0x3c, 0x90]);
+        boot[0x03..0x0b].copy_from_slice(b"GGOS    ");
+        boot[0x0b..0x0d].copy_from_slice(&512u16.to_le_bytes());
+        boot[0x0d] = geometry.sectors_per_cluster as u8;
+        boot[0x0e..0x10].copy_from_slice(&(geometry.reserved as u16).to_le_bytes());
+        boot[0x10] = 2;
+        boot[0x11..0x13].copy_from_slice(&(geometry.root_entries as u16).to_le_bytes());
+        match u16::try_from(geometry.sectors) {
+            Ok(sectors) => boot[0x13..0x15].copy_from_slice(&sectors.to_le_bytes()),
+            Err(_) => boot[0x20..0x24].copy_from_slice(&(geometry.sectors as u32).to_le_bytes()),
+        }
+        boot[0x15] = 0xf8;
+        boot[0x1fe..].copy_from_slice(&[0x55, 0xaa]);
+
+        if fat32 {
+            boot[0x24..0x28].copy_from_slice(&(geometry.sectors_per_fat as u32).to_le_bytes());
+            boot[0x2c..0x30].copy_from_slice(&2u32.to_le_bytes());
+            boot[0x30..0x32].copy_from_slice(&(FS_INFO_SECTOR as u16).to_le_bytes());
+            boot[0x42] = 0x29;
+            boot[0x47..0x52].copy_from_slice(b"NO NAME    ");
+            boot[0x52..0x5a].copy_from_slice(b"FAT32   ");
+        } else {
+            boot[0x16..0x18].copy_from_slice(&(geometry.sectors_per_fat as u16).to_le_bytes());
+            boot[0x26] = 0x29;
+            boot[0x2b..0x36].copy_from_slice(b"NO NAME    ");
+            boot[0x36..0x3e].copy_from_slice(match geometry.fat_type {
+                FatType::Fat12 => b"FAT12   ",
+                _ => b"FAT16   ",
+            });
+        }
+
+        // the first two entries of each FAT are reserved, the third is
+        // the root directory on FAT32
+        let reserved: &[u8] = match geometry.fat_type {
+            FatType::Fat12 => &[0xf8, 0xff, 0xff],
+            FatType::Fat16 => &[0xf8, 0xff, 0xff, 0xff],
+            FatType::Fat32 => &[
+                0xf8, 0xff, 0xff, 0x0f, 0xff, 0xff, 0xff, 0x0f, 0xff, 0xff, 0xff, 0x0f,
+            ],
+        };
+        for fat in 0..2 {
+            let start = (geometry.reserved + fat * geometry.sectors_per_fat) * BLOCK_SIZE;
+            data[start..start + reserved.len()].copy_from_slice(reserved);
+        }
+
+        if fat32 {
+            let info = &mut data[FS_INFO_SECTOR * BLOCK_SIZE..][..BLOCK_SIZE];
+            info[..4].copy_from_slice(&0x4161_5252u32.to_le_bytes());
+            info[0x1e4..0x1e8].copy_from_slice(&0x6141_7272u32.to_le_bytes());
+            info[0x1e8..0x1ec]
+                .copy_from_slice(&(geometry.cluster_count() as u32 - 1).to_le_bytes());
+            info[0x1ec..0x1f0].copy_from_slice(&3u32.to_le_bytes());
+            info[0x1fc..].copy_from_slice(&0xAA55_0000u32.to_le_bytes());
+        }
+
+        set_clock(clock);
+
+        let fat = Fat::new(MemoryDisk::new(data));
+        assert_eq!(fat.fat_type(), geometry.fat_type);
+        assert_eq!(fat.handle.cluster_count, geometry.cluster_count());
+        fat
+    }
+
+    fn volume() -> Fat {
+        format(&FAT16)
+    }
+
+    fn clock() -> FsTime {
+        Utc.with_ymd_and_hms(2024, 5, 6, 7, 8, 10).unwrap()
+    }
+
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn read(fat: &Fat, path: &str) -> Vec<u8> {
+        let mut buf = Vec::new();
+        fat.open_file(path).unwrap().read_all(&mut buf).unwrap();
+        buf
+    }
+
+    /// The clusters of the chain starting at `start`
+    fn chain(fat: &Fat, start: Cluster) -> Vec<Cluster> {
+        let mut clusters = vec![start];
+        while let Ok(next) = fat.handle.next_cluster(clusters.last().unwrap()) {
+            clusters.push(next);
+        }
+        clusters
+    }
+
+    fn is_free(fat: &Fat, cluster: &Cluster) -> bool {
+        fat.handle.read_fat_entry(cluster).unwrap() == 0
+    }
+
+    #[test]
+    fn write_across_clusters() {
+        let fat = volume();
+        let data = pattern(CLUSTER_SIZE * 2 + 952);
+
+        let mut file = fat.create_file("/DATA.BIN").unwrap();
+        file.write_all(&data[..700]).unwrap();
+        file.write_all(&data[700..]).unwrap();
+        drop(file);
+
+        assert_eq!(read(&fat, "/DATA.BIN"), data);
+        assert_eq!(fat.metadata("/DATA.BIN").unwrap().len, data.len());
+
+        let (entry, _) = fat.handle.get_dir_entry("/DATA.BIN").unwrap();
+        let clusters = chain(&fat, entry.cluster);
+        assert_eq!(clusters.len(), 3);
+
+        // both copies of the FAT are kept in step
+        let offset = fat.handle.fat_entry_offset(&clusters[0]);
+        let mut copies = [[0u8; 2]; 2];
+        for (fat_index, copy) in copies.iter_mut().enumerate() {
+            let start = fat.handle.fat_start + fat_index * SECTORS_PER_FAT;
+            fat.handle.read_fat_bytes(start, offset, copy).unwrap();
+        }
+        assert_eq!(copies[0], copies[1]);
+        assert_eq!(u16::from_le_bytes(copies[0]) as u32, clusters[1].0);
+    }
+
+    #[test]
+    fn truncate_frees_chain() {
+        let fat = volume();
+
+        let mut file = fat.create_file("/DATA.BIN").unwrap();
+        file.write_all(&pattern(CLUSTER_SIZE * 3)).unwrap();
+        drop(file);
+
+        let (entry, _) = fat.handle.get_dir_entry("/DATA.BIN").unwrap();
+        let clusters = chain(&fat, entry.cluster);
+        assert_eq!(clusters.len(), 3);
+
+        // creating it again truncates it
+        drop(fat.create_file("/DATA.BIN").unwrap());
+
+        let (entry, _) = fat.handle.get_dir_entry("/DATA.BIN").unwrap();
+        assert_eq!(entry.cluster, Cluster::EMPTY);
+        assert_eq!(entry.size, 0);
+        assert!(clusters.iter().all(|cluster| is_free(&fat, cluster)));
+        assert!(read(&fat, "/DATA.BIN").is_empty());
+
+        // the freed clusters are used again
+        let mut file = fat.create_file("/DATA.BIN").unwrap();
+        file.write_all(b"again").unwrap();
+        drop(file);
+
+        let (entry, _) = fat.handle.get_dir_entry("/DATA.BIN").unwrap();
+        assert_eq!(entry.cluster, clusters[0]);
+    }
+
+    #[test]
+    fn remove_file() {
+        let fat = volume();
+
+        // a mixed case name, so it has a long name entry before it
+        let mut file = fat.create_file("/Notes.txt").unwrap();
+        file.write_all(&pattern(CLUSTER_SIZE + 1)).unwrap();
+        drop(file);
+
+        let (entry, pos) = fat.handle.get_dir_entry("/Notes.txt").unwrap();
+        let clusters = chain(&fat, entry.cluster);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(pos.index, 1);
+
+        fat.remove_file("/Notes.txt").unwrap();
+
+        let mut block = Block512::default();
+        fat.handle.inner.read_block(pos.sector, &mut block).unwrap();
+        assert_eq!(block[0], 0xE5);
+        assert_eq!(block[DirEntry::LEN], 0xE5);
+
+        assert!(clusters.iter().all(|cluster| is_free(&fat, cluster)));
+        assert!(!fat.exists("/Notes.txt").unwrap());
+        assert_eq!(fat.read_dir("/").unwrap().count(), 0);
+        assert_eq!(fat.remove_file("/Notes.txt"), Err(FsError::FileNotFound));
+    }
+
+    #[test]
+    fn append_updates_size_and_mtime() {
+        let fat = volume();
+
+        let mut file = fat.create_file("/LOG.TXT").unwrap();
+        file.write_all(b"hello, ").unwrap();
+        drop(file);
+
+        // date it back, as if it were written long ago
+        let (mut entry, pos) = fat.handle.get_dir_entry("/LOG.TXT").unwrap();
+        let old = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap();
+        entry.moditified_time = old;
+        fat.handle.write_dir_entry(&entry, &pos).unwrap();
+        assert_eq!(fat.metadata("/LOG.TXT").unwrap().modified, Some(old));
+
+        let mut file = fat.append_file("/LOG.TXT").unwrap();
+        file.write_all(b"world").unwrap();
+        drop(file);
+
+        let meta = fat.metadata("/LOG.TXT").unwrap();
+        assert_eq!(meta.len, 12);
+        assert_eq!(meta.modified, Some(clock()));
+        assert_eq!(read(&fat, "/LOG.TXT"), b"hello, world");
+    }
+
+    #[test]
+    fn create_and_remove_dir() {
+        let fat = volume();
+
+        fat.create_dir("/Docs").unwrap();
+        assert_eq!(fat.create_dir("/DOCS"), Err(FsError::InvalidOperation));
+        assert!(fat.metadata("/Docs").unwrap().is_dir());
+        assert_eq!(fat.read_dir("/Docs").unwrap().count(), 0);
+
+        // it starts with "." and "..", the root being cluster 0
+        let (entry, _) = fat.handle.get_dir_entry("/Docs").unwrap();
+        let mut block = Block512::default();
+        let sector = fat.handle.cluster_to_sector(&entry.cluster);
+        fat.handle.inner.read_block(sector, &mut block).unwrap();
+
+        let dot = DirEntry::parse(&block[..DirEntry::LEN]).unwrap();
+        let dotdot = DirEntry::parse(&block[DirEntry::LEN..DirEntry::LEN * 2]).unwrap();
+        assert_eq!(format!("{}", dot.filename), ".");
+        assert_eq!(dot.cluster, entry.cluster);
+        assert!(dot.is_directory());
+        assert_eq!(format!("{}", dotdot.filename), "..");
+        assert_eq!(dotdot.cluster, Cluster::EMPTY);
+
+        // a directory within it points back to it
+        fat.create_dir("/Docs/old").unwrap();
+        let (inner, _) = fat.handle.get_dir_entry("/Docs/old").unwrap();
+        let sector = fat.handle.cluster_to_sector(&inner.cluster);
+        fat.handle.inner.read_block(sector, &mut block).unwrap();
+        let dotdot = DirEntry::parse(&block[DirEntry::LEN..DirEntry::LEN * 2]).unwrap();
+        assert_eq!(dotdot.cluster, entry.cluster);
+
+        let mut file = fat.create_file("/Docs/old/a.txt").unwrap();
+        file.write_all(b"a").unwrap();
+        drop(file);
+
+        let names: Vec<String> = fat.read_dir("/Docs").unwrap().map(|m| m.name).collect();
+        assert_eq!(names, ["old"]);
+
+        assert_eq!(fat.remove_dir("/Docs"), Err(FsError::InvalidOperation));
+        assert_eq!(
+            fat.remove_dir("/Docs/old/a.txt"),
+            Err(FsError::NotADirectory)
+        );
+        assert_eq!(fat.remove_file("/Docs/old"), Err(FsError::NotAFile));
+
+        fat.remove_file("/Docs/old/a.txt").unwrap();
+        fat.remove_dir("/Docs/old").unwrap();
+        fat.remove_dir("/Docs").unwrap();
+
+        assert!(is_free(&fat, &entry.cluster));
+        assert!(is_free(&fat, &inner.cluster));
+        assert!(!fat.exists("/Docs").unwrap());
+        assert_eq!(fat.read_dir("/").unwrap().count(), 0);
+    }
+
+    #[test]
+    fn seek() {
+        let fat = volume();
+        let data = pattern(CLUSTER_SIZE * 4);
+
+        let mut file = fat.create_file("/DATA.BIN").unwrap();
+        file.write_all(&data).unwrap();
+        drop(file);
+
+        let mut file = fat.open_file("/DATA.BIN").unwrap();
+        let mut buf = [0u8; 100];
+
+        // into the third cluster, across a sector of it
+        assert_eq!(file.seek(SeekFrom::Start(2500)), Ok(2500));
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[2500..2600]);
+
+        // back over two clusters, reading across the end of the first
+        assert_eq!(file.seek(SeekFrom::Current(-1650)), Ok(950));
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[950..1050]);
+
+        assert_eq!(file.seek(SeekFrom::End(-100)), Ok(data.len() - 100));
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[data.len() - 100..]);
+        assert_eq!(file.read(&mut buf), Ok(0));
+
+        assert_eq!(file.seek(SeekFrom::Start(0)), Ok(0));
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[..100]);
+
+        // before the start is an error and leaves the offset as it was
+        assert_eq!(
+            file.seek(SeekFrom::Current(-101)),
+            Err(FsError::InvalidOffset)
+        );
+        assert_eq!(
+            file.seek(SeekFrom::End(-(data.len() as isize) - 1)),
+            Err(FsError::InvalidOffset)
+        );
+        assert_eq!(file.seek(SeekFrom::Current(0)), Ok(100));
+
+        // past the end reads nothing
+        assert_eq!(file.seek(SeekFrom::End(10)), Ok(data.len() + 10));
+        assert_eq!(file.read(&mut buf), Ok(0));
+    }
+
+    #[test]
+    fn seek_past_end_and_write() {
+        let fat = volume();
+
+        let mut file = fat.create_file("/SPARSE.BIN").unwrap();
+        file.write_all(&[0xaa; 100]).unwrap();
+
+        // the gap up to a later cluster reads back as zeros
+        assert_eq!(file.seek(SeekFrom::Start(CLUSTER_SIZE * 2 + 10)), Ok(2058));
+        file.write_all(b"end").unwrap();
+        drop(file);
+
+        let data = read(&fat, "/SPARSE.BIN");
+        assert_eq!(data.len(), CLUSTER_SIZE * 2 + 13);
+        assert!(data[..100].iter().all(|&b| b == 0xaa));
+        assert!(data[100..CLUSTER_SIZE * 2 + 10].iter().all(|&b| b == 0));
+        assert_eq!(&data[CLUSTER_SIZE * 2 + 10..], b"end");
+
+        let (entry, _) = fat.handle.get_dir_entry("/SPARSE.BIN").unwrap();
+        assert_eq!(chain(&fat, entry.cluster).len(), 3);
+    }
+
+    #[test]
+    fn fat12_round_trip() {
+        let fat = format(&FAT12);
+
+        // clusters 2 to 342, the entry of 341 straddles the first two
+        // sectors of the FAT
+        let first = pattern(BLOCK_SIZE * 341);
+        let mut file = fat.create_file("/FIRST.BIN").unwrap();
+        file.write_all(&first).unwrap();
+        drop(file);
+
+        let second = pattern(BLOCK_SIZE * 4 + 17);
+        let mut file = fat.create_file("/SECOND.BIN").unwrap();
+        file.write_all(&second).unwrap();
+        drop(file);
+
+        assert_eq!(read(&fat, "/FIRST.BIN"), first);
+        assert_eq!(read(&fat, "/SECOND.BIN"), second);
+
+        let (entry, _) = fat.handle.get_dir_entry("/FIRST.BIN").unwrap();
+        let clusters: Vec<u32> = chain(&fat, entry.cluster).iter().map(|c| c.0).collect();
+        assert_eq!(clusters, (2..=342).collect::<Vec<_>>());
+
+        let (entry, _) = fat.handle.get_dir_entry("/SECOND.BIN").unwrap();
+        let clusters: Vec<u32> = chain(&fat, entry.cluster).iter().map(|c| c.0).collect();
+        assert_eq!(clusters, (343..=347).collect::<Vec<_>>());
+
+        // 340 (even) -> 341 and 341 (odd) -> 342, packed in three bytes
+        // on either side of the sector boundary, in both FATs
+        for copy in 0..2 {
+            let start = fat.handle.fat_start + copy * FAT12.sectors_per_fat;
+            let mut raw = [0u8; 3];
+            fat.handle.read_fat_bytes(start, 510, &mut raw).unwrap();
+            assert_eq!(raw, [0x55, 0x61, 0x15]);
+        }
+
+        // freeing the first file keeps the half bytes of the second,
+        // 342 (even) shares a byte with 343 (odd)
+        fat.remove_file("/FIRST.BIN").unwrap();
+        assert!((2..=342).all(|c| is_free(&fat, &Cluster(c))));
+        assert_eq!(read(&fat, "/SECOND.BIN"), second);
+        assert_eq!(chain(&fat, entry.cluster).len(), 5);
+
+        // and the space is used again from the start
+        let third = pattern(BLOCK_SIZE * 3);
+        let mut file = fat.create_file("/THIRD.BIN").unwrap();
+        file.write_all(&third).unwrap();
+        drop(file);
+
+        let (entry, _) = fat.handle.get_dir_entry("/THIRD.BIN").unwrap();
+        let clusters: Vec<u32> = chain(&fat, entry.cluster).iter().map(|c| c.0).collect();
+        assert_eq!(clusters, [2, 3, 4]);
+        assert_eq!(read(&fat, "/THIRD.BIN"), third);
+        assert_eq!(read(&fat, "/SECOND.BIN"), second);
+    }
+
+    #[test]
+    fn fat32_root_dir_grows() {
+        let fat = format(&FAT32);
+        let root = fat.handle.root_cluster;
+        assert_eq!(root, Cluster(2));
+
+        // 16 entries fit in a cluster of the root directory
+        let names: Vec<String> = (0..40).map(|i| format!("FILE{:02}.TXT", i)).collect();
+        for name in &names {
+            let mut file = fat.create_file(&format!("/{}", name)).unwrap();
+            file.write_all(name.as_bytes()).unwrap();
+        }
+
+        assert_eq!(chain(&fat, root).len(), 3);
+
+        let listed: Vec<String> = fat.read_dir("/").unwrap().map(|m| m.name).collect();
+        assert_eq!(listed, names);
+        for name in &names {
+            assert_eq!(read(&fat, &format!("/{}", name)), name.as_bytes());
+        }
+
+        // a file in a directory in the root, past its first cluster
+        fat.create_dir("/Logs").unwrap();
+        let mut file = fat.create_file("/Logs/boot.log").unwrap();
+        file.write_all(&pattern(BLOCK_SIZE * 3)).unwrap();
+        drop(file);
+        assert_eq!(read(&fat, "/Logs/boot.log"), pattern(BLOCK_SIZE * 3));
+
+        fat.remove_file("/FILE00.TXT").unwrap();
+        assert!(!fat.exists("/FILE00.TXT").unwrap());
+        assert_eq!(fat.read_dir("/").unwrap().count(), 40);
+    }
+
+    #[test]
+    fn fat32_fs_info() {
+        let fat = format(&FAT32);
+        let free = FAT32.cluster_count() as u32 - 1;
+
+        let fs_info = || {
+            let mut block = Block512::default();
+            fat.handle
+                .inner
+                .read_block(FS_INFO_SECTOR, &mut block)
+                .unwrap();
+            let info = FsInfo::new(block.as_ref()).unwrap();
+            (info.free_count(), info.next_free())
+        };
+        assert_eq!(fs_info(), (free, 3));
+
+        let data = pattern(BLOCK_SIZE * 5 + 1);
+        let mut file = fat.create_file("/DATA.BIN").unwrap();
+        file.write_all(&data).unwrap();
+        drop(file);
+
+        let (entry, _) = fat.handle.get_dir_entry("/DATA.BIN").unwrap();
+        let clusters: Vec<u32> = chain(&fat, entry.cluster).iter().map(|c| c.0).collect();
+        assert_eq!(clusters, (3..=8).collect::<Vec<_>>());
+        assert_eq!(fs_info(), (free - 6, 9));
+        assert_eq!(read(&fat, "/DATA.BIN"), data);
+
+        // the reserved high bits of an entry are kept, and ignored
+        let offset = fat.handle.fat_entry_offset(&Cluster(3));
+        let mut raw = [0u8; 4];
+        fat.handle
+            .read_fat_bytes(fat.handle.fat_start, offset, &mut raw)
+            .unwrap();
+        assert_eq!(u32::from_le_bytes(raw), 4);
+        raw[3] |= 0xf0;
+        fat.handle
+            .write_fat_bytes(fat.handle.fat_start, offset, &raw)
+            .unwrap();
+        assert_eq!(fat.handle.next_cluster(&Cluster(3)), Ok(Cluster(4)));
+
+        fat.remove_file("/DATA.BIN").unwrap();
+        assert_eq!(fs_info(), (free, 3));
+        fat.handle
+            .read_fat_bytes(fat.handle.fat_start, offset, &mut raw)
+            .unwrap();
+        assert_eq!(u32::from_le_bytes(raw), 0xf000_0000);
+
+        // the freed clusters are used first
+        let mut file = fat.create_file("/MORE.BIN").unwrap();
+        file.write_all(b"more").unwrap();
+        drop(file);
+        let (entry, _) = fat.handle.get_dir_entry("/MORE.BIN").unwrap();
+        assert_eq!(entry.cluster, Cluster(3));
+        assert_eq!(fs_info(), (free - 1, 4));
+    }
+}
@@ -0,0 +1,216 @@
+//! VFAT Long File Name
+//!
+//! reference:
+//! - <https://wiki.osdev.org/FAT#Long_File_Names>
+//! - <https://en.wikipedia.org/wiki/Design_of_the_FAT_file_system#VFAT_long_file_names>
+
+use super::*;
+
+/// Characters of a long name held by a single LFN entry
+const CHARS_PER_ENTRY: usize = 13;
+
+/// Longest name a chain of LFN entries can describe
+pub const MAX_LONG_NAME: usize = 255;
+
+/// Byte offsets of the UCS-2 characters within an LFN entry
+const CHAR_OFFSETS: [usize; CHARS_PER_ENTRY] = [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+
+/// Set on the sequence number of the last (first on disk) entry of a chain
+const LAST_ENTRY: u8 = 0x40;
+
+/// A single slot of a long file name chain
+pub struct LfnEntry {
+    data: [u8; DirEntry::LEN],
+}
+
+impl LfnEntry {
+    /// Returns true if the raw directory slot holds a long file name entry
+    pub fn is_lfn(data: &[u8]) -> bool {
+        data[0] != 0x00 && data[0] != 0xE5 && data[11] & 0x3f == Attributes::LFN.bits()
+    }
+
+    pub fn parse(data: &[u8]) -> Result<LfnEntry> {
+        if !Self::is_lfn(data) {
+            return Err(FilenameError::UnableToParse.into());
+        }
+
+        Ok(LfnEntry {
+            data: data.try_into().unwrap(),
+        })
+    }
+
+    /// Position of this entry in the chain, starting from 1
+    pub fn order(&self) -> u8 {
+        self.data[0] & !LAST_ENTRY
+    }
+
+    pub fn is_last(&self) -> bool {
+        self.data[0] & LAST_ENTRY != 0
+    }
+
+    /// Checksum of the short name this entry belongs to
+    pub fn checksum(&self) -> u8 {
+        self.data[13]
+    }
+
+    pub fn chars(&self) -> [u16; CHARS_PER_ENTRY] {
+        CHAR_OFFSETS.map(|off| u16::from_le_bytes([self.data[off], self.data[off + 1]]))
+    }
+}
+
+/// Collects LFN entries while walking a directory and validates them
+/// against the short entry that follows the chain.
+#[derive(Default)]
+pub struct LfnBuilder {
+    parts: Vec<[u16; CHARS_PER_ENTRY]>,
+    checksum: u8,
+    /// Order of the entry expected next, 0 once the chain is complete
+    expected: u8,
+}
+
+impl LfnBuilder {
+    pub fn push(&mut self, entry: &LfnEntry) {
+        if entry.is_last() {
+            self.parts = vec![[0xFFFF; CHARS_PER_ENTRY]; entry.order() as usize];
+            self.checksum = entry.checksum();
+            self.expected = entry.order();
+        }
+
+        if self.parts.is_empty()
+            || entry.order() == 0
+            || entry.order() != self.expected
+            || entry.checksum() != self.checksum
+        {
+            // orphaned or out of order, drop the whole chain
+            self.reset();
+            return;
+        }
+
+        self.parts[entry.order() as usize - 1] = entry.chars();
+        self.expected -= 1;
+    }
+
+    /// Finish the chain with its short entry, returning the long name if
+    /// the chain is complete and belongs to `sfn`
+    pub fn finish(&mut self, sfn: &ShortFileName) -> Option<String> {
+        let name =
+            if !self.parts.is_empty() && self.expected == 0 && self.checksum == sfn.checksum() {
+                let chars = self.parts.iter().flatten().copied().take_while(|&c| c != 0);
+                Some(
+                    char::decode_utf16(chars)
+                        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                        .collect(),
+                )
+            } else {
+                None
+            };
+
+        self.reset();
+        name
+    }
+
+    pub fn reset(&mut self) {
+        self.parts.clear();
+        self.expected = 0;
+    }
+}
+
+/// Check that `name` can be stored as a long file name
+pub fn validate(name: &str) -> Result<()> {
+    if name.is_empty() {
+        return Err(FilenameError::FilenameEmpty.into());
+    }
+
+    if name.encode_utf16().count() > MAX_LONG_NAME {
+        return Err(FilenameError::NameTooLong.into());
+    }
+
+    if name
+        .chars()
+        .any(|c| c < ' ' || matches!(c, '"' | '*' | '/' | ':' | '<' | '>' | '?' | '\\' | '|'))
+        || name.trim_end_matches(['.', ' ']).is_empty()
+    {
+        return Err(FilenameError::InvalidCharacter.into());
+    }
+
+    Ok(())
+}
+
+/// Build the LFN entries for `name` in on-disk order (last part first)
+pub fn encode(name: &str, sfn: &ShortFileName) -> Vec<[u8; DirEntry::LEN]> {
+    let mut chars: Vec<u16> = name.encode_utf16().collect();
+
+    // the name is NUL terminated unless it fills the last entry, then padded with 0xFFFF
+    if !chars.len().is_multiple_of(CHARS_PER_ENTRY) {
+        chars.push(0);
+    }
+    chars.resize(
+        chars.len().div_ceil(CHARS_PER_ENTRY) * CHARS_PER_ENTRY,
+        0xFFFF,
+    );
+
+    let count = chars.len() / CHARS_PER_ENTRY;
+    let checksum = sfn.checksum();
+
+    (0..count)
+        .rev()
+        .map(|idx| {
+            let mut data = [0u8; DirEntry::LEN];
+
+            data[0] = (idx + 1) as u8 | if idx + 1 == count { LAST_ENTRY } else { 0 };
+            data[11] = Attributes::LFN.bits();
+            data[13] = checksum;
+
+            let part = &chars[idx * CHARS_PER_ENTRY..(idx + 1) * CHARS_PER_ENTRY];
+            for (ch, off) in part.iter().zip(CHAR_OFFSETS) {
+                data[off..off + 2].copy_from_slice(&ch.to_le_bytes());
+            }
+
+            data
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lfn_entries() {
+        // "Long file name.txt" followed by its short entry LONGFI~1.TXT
+        let data = hex_literal::hex!(
+            "42 65 00 2e 00 74 00 78 00 74 00 0f 00 d4 00 00
+             ff ff ff ff ff ff ff ff ff ff 00 00 ff ff ff ff
+             01 4c 00 6f 00 6e 00 67 00 20 00 0f 00 d4 66 00
+             69 00 6c 00 65 00 20 00 6e 00 00 00 61 00 6d 00
+             4c 4f 4e 47 46 49 7e 31 54 58 54 20 00 00 00 00
+             00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00"
+        );
+
+        let sfn = ShortFileName::new(&data[64..75]);
+        assert_eq!(sfn.checksum(), 0xd4);
+
+        let mut builder = LfnBuilder::default();
+        builder.push(&LfnEntry::parse(&data[..32]).unwrap());
+        builder.push(&LfnEntry::parse(&data[32..64]).unwrap());
+        assert_eq!(builder.finish(&sfn).as_deref(), Some("Long file name.txt"));
+
+        let encoded = encode("Long file name.txt", &sfn);
+        assert_eq!(encoded.len(), 2);
+        assert_eq!(encoded[0], data[..32]);
+        assert_eq!(encoded[1], data[32..64]);
+    }
+
+    #[test]
+    fn test_lfn_bad_checksum() {
+        let sfn = ShortFileName::parse("README~1.MD").unwrap();
+        let other = ShortFileName::parse("OTHER.MD").unwrap();
+
+        let mut builder = LfnBuilder::default();
+        for entry in encode("readme.markdown", &sfn) {
+            builder.push(&LfnEntry::parse(&entry).unwrap());
+        }
+
+        assert_eq!(builder.finish(&other), None);
+    }
+}
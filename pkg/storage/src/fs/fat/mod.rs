@@ -0,0 +1,67 @@
+pub mod bpb;
+pub mod directory;
+pub mod direntry;
+pub mod file;
+pub mod fsinfo;
+pub mod impls;
+pub mod lfn;
+
+use crate::*;
+use directory::Directory;
+use direntry::*;
+use file::File;
+
+use bpb::{FatBpb, FatType};
+use fsinfo::FsInfo;
+
+const BLOCK_SIZE: usize = 512;
+
+/// Identifies a FAT12, FAT16 or FAT32 Volume on the disk.
+pub struct Fat {
+    handle: FatHandle,
+}
+
+impl Fat {
+    pub fn new(inner: impl BlockDevice<Block512>) -> Self {
+        Self {
+            handle: Arc::new(FatImpl::new(inner)),
+        }
+    }
+
+    pub fn fat_type(&self) -> FatType {
+        self.handle.fat_type
+    }
+}
+
+type FatHandle = Arc<FatImpl>;
+
+pub struct FatImpl {
+    pub(crate) inner: Box<dyn BlockDevice<Block512>>,
+    pub bpb: FatBpb,
+    pub fat_type: FatType,
+    pub fat_start: usize,
+    pub first_data_sector: usize,
+    pub first_root_dir_sector: usize,
+    pub cluster_count: usize,
+    /// First cluster of the root directory, `Cluster::ROOT_DIR` on FAT12/16
+    pub root_cluster: Cluster,
+    /// Sector of the FAT32 FSInfo structure, if the volume has a valid one
+    fs_info: Option<usize>,
+    /// Where to start looking for a free cluster, also serializes FAT updates
+    next_free: spin::Mutex<u32>,
+}
+
+impl core::fmt::Debug for Fat {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Fat")
+            .field("type", &self.handle.fat_type)
+            .field("bpb", &self.handle.bpb)
+            .finish()
+    }
+}
+
+impl core::fmt::Debug for FatImpl {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FatImpl").field("bpb", &self.bpb).finish()
+    }
+}
@@ -1,2 +1,4 @@
-pub mod fat16;
+pub mod archive;
+pub mod fat;
 pub mod random;
+pub mod tmpfs;
@@ -0,0 +1,461 @@
+//! In-memory filesystem
+//!
+//! Files and directories only live in memory and are lost on unmount. The
+//! size limit counts the bytes of file contents, a removed file keeps its
+//! space until the last handle to it is dropped.
+
+use crate::*;
+use alloc::collections::BTreeMap;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use spin::RwLock;
+
+pub struct TmpFs {
+    root: RwLock<DirNode>,
+    usage: Arc<Usage>,
+}
+
+/// Bytes used by file contents, shared with every file of the mount
+struct Usage {
+    used: AtomicUsize,
+    limit: usize,
+}
+
+impl Usage {
+    /// Reserve up to `size` bytes, returning how many were granted
+    fn reserve(&self, size: usize) -> usize {
+        let mut used = self.used.load(Ordering::Acquire);
+
+        loop {
+            let granted = size.min(self.limit.saturating_sub(used));
+
+            match self.used.compare_exchange_weak(
+                used,
+                used + granted,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return granted,
+                Err(current) => used = current,
+            }
+        }
+    }
+
+    fn release(&self, size: usize) {
+        self.used.fetch_sub(size, Ordering::AcqRel);
+    }
+}
+
+enum Node {
+    File(Arc<RwLock<FileNode>>),
+    Dir(DirNode),
+}
+
+struct FileNode {
+    data: Vec<u8>,
+    created: FsTime,
+    modified: FsTime,
+    accessed: FsTime,
+    usage: Arc<Usage>,
+}
+
+struct DirNode {
+    entries: BTreeMap<String, Node>,
+    created: FsTime,
+    modified: FsTime,
+}
+
+impl FileNode {
+    fn new(usage: Arc<Usage>) -> Self {
+        let time = now();
+        Self {
+            data: Vec::new(),
+            created: time,
+            modified: time,
+            accessed: time,
+            usage,
+        }
+    }
+
+    fn meta(&self, name: &str) -> Metadata {
+        Metadata::new(
+            name.into(),
+            FileType::File,
+            self.data.len(),
+            Some(self.created),
+            Some(self.modified),
+            Some(self.accessed),
+        )
+    }
+
+    /// Drop the contents, giving their space back
+    fn truncate(&mut self) {
+        self.usage.release(self.data.len());
+        self.data = Vec::new();
+        self.modified = now();
+    }
+}
+
+impl Drop for FileNode {
+    fn drop(&mut self) {
+        self.usage.release(self.data.len());
+    }
+}
+
+impl DirNode {
+    fn new() -> Self {
+        let time = now();
+        Self {
+            entries: BTreeMap::new(),
+            created: time,
+            modified: time,
+        }
+    }
+
+    fn meta(&self, name: &str) -> Metadata {
+        Metadata::new(
+            name.into(),
+            FileType::Directory,
+            0,
+            Some(self.created),
+            Some(self.modified),
+            None,
+        )
+    }
+}
+
+impl Node {
+    fn meta(&self, name: &str) -> Metadata {
+        match self {
+            Node::File(file) => file.read().meta(name),
+            Node::Dir(dir) => dir.meta(name),
+        }
+    }
+}
+
+/// Components of a path, ignoring empty ones and `.`
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split(PATH_SEPARATOR)
+        .filter(|part| !part.is_empty() && *part != ".")
+}
+
+/// Split a path into its parent directory and the last component
+fn split_path(path: &str) -> Result<(&str, &str)> {
+    let path = path.trim_end_matches(PATH_SEPARATOR);
+    let (parent, name) = path.rsplit_once(PATH_SEPARATOR).unwrap_or(("", path));
+
+    match name {
+        "" | "." | ".." => Err(FsError::InvalidPath(path.into())),
+        _ => Ok((parent, name)),
+    }
+}
+
+impl DirNode {
+    fn dir(&self, path: &str) -> Result<&DirNode> {
+        components(path).try_fold(self, |dir, part| match dir.entries.get(part) {
+            Some(Node::Dir(dir)) => Ok(dir),
+            Some(Node::File(_)) => Err(FsError::NotADirectory),
+            None => Err(FsError::FileNotFound),
+        })
+    }
+
+    fn dir_mut(&mut self, path: &str) -> Result<&mut DirNode> {
+        components(path).try_fold(self, |dir, part| match dir.entries.get_mut(part) {
+            Some(Node::Dir(dir)) => Ok(dir),
+            Some(Node::File(_)) => Err(FsError::NotADirectory),
+            None => Err(FsError::FileNotFound),
+        })
+    }
+}
+
+impl TmpFs {
+    /// An empty filesystem holding at most `limit` bytes of file contents
+    pub fn new(limit: usize) -> Self {
+        Self {
+            root: RwLock::new(DirNode::new()),
+            usage: Arc::new(Usage {
+                used: AtomicUsize::new(0),
+                limit,
+            }),
+        }
+    }
+
+    /// Bytes used by file contents
+    pub fn used(&self) -> usize {
+        self.usage.used.load(Ordering::Acquire)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.usage.limit
+    }
+
+    fn file(&self, path: &str) -> Result<(String, Arc<RwLock<FileNode>>)> {
+        let (parent, name) = split_path(path)?;
+        let root = self.root.read();
+
+        match root.dir(parent)?.entries.get(name) {
+            Some(Node::File(file)) => Ok((name.into(), file.clone())),
+            Some(Node::Dir(_)) => Err(FsError::NotAFile),
+            None => Err(FsError::FileNotFound),
+        }
+    }
+
+    fn handle(name: String, file: Arc<RwLock<FileNode>>, offset: usize) -> FileHandle {
+        let meta = {
+            let mut node = file.write();
+            node.accessed = now();
+            node.meta(&name)
+        };
+
+        FileHandle::new(meta, Box::new(TmpFile { file, offset }))
+    }
+}
+
+impl FileSystem for TmpFs {
+    fn read_dir(&self, path: &str) -> Result<Box<dyn Iterator<Item = Metadata> + Send>> {
+        let root = self.root.read();
+
+        let entries: Vec<Metadata> = root
+            .dir(path)?
+            .entries
+            .iter()
+            .map(|(name, node)| node.meta(name))
+            .collect();
+
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn open_file(&self, path: &str) -> Result<FileHandle> {
+        let (name, file) = self.file(path)?;
+        Ok(Self::handle(name, file, 0))
+    }
+
+    fn metadata(&self, path: &str) -> Result<Metadata> {
+        let root = self.root.read();
+
+        let (parent, name) = match split_path(path) {
+            Ok(split) => split,
+            // the root itself
+            Err(_) if components(path).next().is_none() => return Ok(root.meta("/")),
+            Err(e) => return Err(e),
+        };
+
+        root.dir(parent)?
+            .entries
+            .get(name)
+            .map(|node| node.meta(name))
+            .ok_or(FsError::FileNotFound)
+    }
+
+    fn exists(&self, path: &str) -> Result<bool> {
+        Ok(self.metadata(path).is_ok())
+    }
+
+    fn create_file(&self, path: &str) -> Result<FileHandle> {
+        let (parent, name) = split_path(path)?;
+        let mut root = self.root.write();
+        let dir = root.dir_mut(parent)?;
+
+        let file = match dir.entries.get(name) {
+            Some(Node::File(file)) => {
+                file.write().truncate();
+                file.clone()
+            }
+            Some(Node::Dir(_)) => return Err(FsError::NotAFile),
+            None => {
+                let file = Arc::new(RwLock::new(FileNode::new(self.usage.clone())));
+                dir.entries.insert(name.into(), Node::File(file.clone()));
+                dir.modified = now();
+                file
+            }
+        };
+
+        Ok(Self::handle(name.into(), file, 0))
+    }
+
+    fn append_file(&self, path: &str) -> Result<FileHandle> {
+        let (name, file) = self.file(path)?;
+        let len = file.read().data.len();
+        Ok(Self::handle(name, file, len))
+    }
+
+    fn remove_file(&self, path: &str) -> Result<()> {
+        let (parent, name) = split_path(path)?;
+        let mut root = self.root.write();
+        let dir = root.dir_mut(parent)?;
+
+        match dir.entries.get(name) {
+            Some(Node::File(_)) => {
+                dir.entries.remove(name);
+                dir.modified = now();
+                Ok(())
+            }
+            Some(Node::Dir(_)) => Err(FsError::NotAFile),
+            None => Err(FsError::FileNotFound),
+        }
+    }
+
+    fn create_dir(&self, path: &str) -> Result<()> {
+        let (parent, name) = split_path(path)?;
+        let mut root = self.root.write();
+        let dir = root.dir_mut(parent)?;
+
+        if dir.entries.contains_key(name) {
+            return Err(FsError::InvalidOperation);
+        }
+
+        dir.entries.insert(name.into(), Node::Dir(DirNode::new()));
+        dir.modified = now();
+        Ok(())
+    }
+
+    fn remove_dir(&self, path: &str) -> Result<()> {
+        let (parent, name) = split_path(path)?;
+        let mut root = self.root.write();
+        let dir = root.dir_mut(parent)?;
+
+        match dir.entries.get(name) {
+            Some(Node::Dir(child)) if !child.entries.is_empty() => Err(FsError::InvalidOperation),
+            Some(Node::Dir(_)) => {
+                dir.entries.remove(name);
+                dir.modified = now();
+                Ok(())
+            }
+            Some(Node::File(_)) => Err(FsError::NotADirectory),
+            None => Err(FsError::FileNotFound),
+        }
+    }
+}
+
+impl core::fmt::Debug for TmpFs {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TmpFs")
+            .field("used", &self.used())
+            .field("limit", &self.limit())
+            .finish()
+    }
+}
+
+/// An open tmpfs file, removing the file does not affect open handles
+struct TmpFile {
+    file: Arc<RwLock<FileNode>>,
+    offset: usize,
+}
+
+impl Read for TmpFile {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut node = self.file.write();
+        let rest = node.data.get(self.offset..).unwrap_or_default();
+        let count = buf.len().min(rest.len());
+
+        buf[..count].copy_from_slice(&rest[..count]);
+        self.offset += count;
+        node.accessed = now();
+
+        Ok(count)
+    }
+}
+
+impl Write for TmpFile {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let mut node = self.file.write();
+        let len = node.data.len();
+        let end = self.offset + buf.len();
+
+        // grow as far as the limit allows, a short write means it is full
+        let count = if end > len {
+            let granted = node.usage.reserve(end - len);
+            node.data.resize(len + granted, 0);
+            node.data.len().saturating_sub(self.offset)
+        } else {
+            buf.len()
+        };
+
+        if count == 0 && !buf.is_empty() {
+            return Err(FsError::WriteZero);
+        }
+
+        let offset = self.offset;
+        node.data[offset..offset + count].copy_from_slice(&buf[..count]);
+        node.modified = now();
+        self.offset += count;
+
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for TmpFile {
+    fn seek(&mut self, pos: SeekFrom) -> Result<usize> {
+        let offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.offset.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.file.read().data.len().checked_add_signed(offset),
+        };
+
+        self.offset = offset.ok_or(FsError::InvalidOffset)?;
+        Ok(self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_write() {
+        let fs = TmpFs::new(1024);
+
+        fs.create_dir("/dir").unwrap();
+        let mut file = fs.create_file("/dir/hello.txt").unwrap();
+        file.write_all(b"hello, ").unwrap();
+        drop(file);
+
+        let mut file = fs.append_file("/dir/hello.txt").unwrap();
+        file.write_all(b"world").unwrap();
+
+        let mut buf = Vec::new();
+        fs.open_file("/dir/hello.txt")
+            .unwrap()
+            .read_all(&mut buf)
+            .unwrap();
+        assert_eq!(buf, b"hello, world");
+        assert_eq!(fs.metadata("/dir/hello.txt").unwrap().len, 12);
+        assert_eq!(fs.used(), 12);
+
+        let names: Vec<String> = fs.read_dir("/dir").unwrap().map(|m| m.name).collect();
+        assert_eq!(names, ["hello.txt"]);
+
+        assert_eq!(fs.remove_dir("/dir"), Err(FsError::InvalidOperation));
+        fs.remove_file("/dir/hello.txt").unwrap();
+        fs.remove_dir("/dir").unwrap();
+        assert!(!fs.exists("/dir").unwrap());
+
+        // the removed file is still open
+        assert_eq!(fs.used(), 12);
+        drop(file);
+        assert_eq!(fs.used(), 0);
+    }
+
+    #[test]
+    fn size_limit() {
+        let fs = TmpFs::new(10);
+
+        let mut file = fs.create_file("/a").unwrap();
+        assert_eq!(file.write(b"0123456789abc").unwrap(), 10);
+        assert_eq!(file.write(b"d"), Err(FsError::WriteZero));
+
+        // truncating gives the space back
+        drop(file);
+        let mut file = fs.create_file("/a").unwrap();
+        assert_eq!(fs.used(), 0);
+        file.seek(SeekFrom::Start(4)).unwrap();
+        assert_eq!(file.write(b"xy").unwrap(), 2);
+
+        let mut buf = Vec::new();
+        fs.open_file("/a").unwrap().read_all(&mut buf).unwrap();
+        assert_eq!(buf, b"\0\0\0\0xy");
+    }
+}
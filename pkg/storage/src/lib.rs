@@ -32,10 +32,16 @@ use alloc::vec::Vec;
 //
 //     [ MBR | Partitions ] [ Partition 1 ] [ Partition 2 ] [ Partition 3 ] [ Partition 4 ]
 //
-// 2. The partition structure (in Fat16)
+//     A GPT disk keeps a protective MBR in the first sector, followed by the
+//     GPT header and the partition entry array, with a backup copy of both
+//     at the end of the disk.
+//
+//     [ Protective MBR ] [ GPT Header ] [ Entries ] [ Partitions... ] [ Entries ] [ GPT Header ]
+//
+// 2. The partition structure (in FAT)
 //
 //    - The partition is a collection of clusters.
 //     BPB (Boot Parameter Block) is the first sector of the partition.
 //     The BPB contains information about the filesystem.
 //
-//     [ Fat16 BPB ] [ Data ]
+//     [ FAT BPB ] [ Data ]
@@ -0,0 +1,106 @@
+//! GPT Partition Entry
+//!
+//! This struct represents an entry of the GPT partition entry array.
+
+use super::*;
+
+#[derive(Clone, Copy)]
+pub struct GptPartition {
+    data: [u8; 128],
+}
+
+impl GptPartition {
+    /// Parse the first 128 bytes of a partition entry, newer revisions
+    /// may append fields we do not know about.
+    pub fn parse(data: &[u8]) -> GptPartition {
+        GptPartition {
+            data: data[..128].try_into().unwrap(),
+        }
+    }
+
+    pub fn type_guid(&self) -> Guid {
+        Guid::from_bytes(self.type_guid_raw())
+    }
+
+    pub fn unique_guid(&self) -> Guid {
+        Guid::from_bytes(self.unique_guid_raw())
+    }
+
+    /// Entries with a zero type GUID are unused
+    pub fn is_used(&self) -> bool {
+        self.type_guid() != Guid::UNUSED
+    }
+
+    /// Number of sectors covered, `last_lba` is inclusive
+    pub fn total_lba(&self) -> u64 {
+        self.last_lba()
+            .saturating_sub(self.first_lba())
+            .saturating_add(1)
+    }
+
+    /// The partition name, stored as UTF-16LE and padded with zeros
+    pub fn name(&self) -> String {
+        let units = self
+            .name_raw()
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .map(|c| u16::from_le_bytes(*c))
+            .take_while(|&c| c != 0);
+
+        char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+
+    define_field!([u8; 16], 0x00, type_guid_raw);
+    define_field!([u8; 16], 0x10, unique_guid_raw);
+    define_field!(u64, 0x20, first_lba);
+    define_field!(u64, 0x28, last_lba);
+    define_field!(u64, 0x30, attributes);
+    define_field!([u8; 72], 0x38, name_raw);
+}
+
+impl core::fmt::Debug for GptPartition {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("GPT Partition")
+            .field("Name", &self.name())
+            .field("Type", &self.type_guid().type_name().unwrap_or("Unknown"))
+            .field("Type GUID", &self.type_guid())
+            .field("Unique GUID", &self.unique_guid())
+            .field("First LBA", &format!("0x{:016x}", self.first_lba()))
+            .field("Last LBA", &format!("0x{:016x}", self.last_lba()))
+            .field("Attributes", &format!("0x{:016x}", self.attributes()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partition_test() {
+        let mut data = [0u8; 128];
+        data[..16].copy_from_slice(Guid::EFI_SYSTEM.as_bytes());
+        data[0x20..0x28].copy_from_slice(&2048u64.to_le_bytes());
+        data[0x28..0x30].copy_from_slice(&206847u64.to_le_bytes());
+        for (i, c) in "EFI system partition".encode_utf16().enumerate() {
+            data[0x38 + i * 2..0x3a + i * 2].copy_from_slice(&c.to_le_bytes());
+        }
+
+        let part = GptPartition::parse(&data);
+
+        println!("{:#?}", part);
+
+        assert!(part.is_used());
+        assert_eq!(part.type_guid().type_name(), Some("EFI System"));
+        assert_eq!(
+            format!("{}", part.type_guid()),
+            "C12A7328-F81F-11D2-BA4B-00A0C93EC93B"
+        );
+        assert_eq!(part.first_lba(), 2048);
+        assert_eq!(part.total_lba(), 204800);
+        assert_eq!(part.name(), "EFI system partition");
+    }
+}
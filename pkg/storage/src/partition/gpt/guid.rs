@@ -0,0 +1,104 @@
+//! GUID
+//!
+//! GUIDs are stored in mixed endian: the first three groups are little
+//! endian, the last two are stored as bytes.
+
+use core::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct Guid([u8; 16]);
+
+impl Guid {
+    pub const UNUSED: Guid = Guid([0; 16]);
+
+    pub const EFI_SYSTEM: Guid = Guid::new(
+        0xC12A7328,
+        0xF81F,
+        0x11D2,
+        [0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9, 0x3B],
+    );
+    pub const BIOS_BOOT: Guid = Guid::new(
+        0x21686148,
+        0x6449,
+        0x6E6F,
+        [0x74, 0x4E, 0x65, 0x65, 0x64, 0x45, 0x46, 0x49],
+    );
+    pub const BASIC_DATA: Guid = Guid::new(
+        0xEBD0A0A2,
+        0xB9E5,
+        0x4433,
+        [0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26, 0x99, 0xC7],
+    );
+    pub const LINUX_FILESYSTEM: Guid = Guid::new(
+        0x0FC63DAF,
+        0x8483,
+        0x4772,
+        [0x8E, 0x79, 0x3D, 0x69, 0xD8, 0x47, 0x7D, 0xE4],
+    );
+    pub const LINUX_SWAP: Guid = Guid::new(
+        0x0657FD6D,
+        0xA4AB,
+        0x43C4,
+        [0x84, 0xE5, 0x09, 0x33, 0xC8, 0x4B, 0x4F, 0x4F],
+    );
+
+    /// Build a GUID from the groups of its textual form
+    pub const fn new(a: u32, b: u16, c: u16, d: [u8; 8]) -> Guid {
+        let a = a.to_le_bytes();
+        let b = b.to_le_bytes();
+        let c = c.to_le_bytes();
+        Guid([
+            a[0], a[1], a[2], a[3], b[0], b[1], c[0], c[1], d[0], d[1], d[2], d[3], d[4], d[5],
+            d[6], d[7],
+        ])
+    }
+
+    /// Read a GUID as it is stored on disk
+    pub fn from_bytes(data: &[u8; 16]) -> Guid {
+        Guid(*data)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    /// Human readable name of a well known partition type
+    pub fn type_name(&self) -> Option<&'static str> {
+        match *self {
+            Guid::UNUSED => Some("Unused"),
+            Guid::EFI_SYSTEM => Some("EFI System"),
+            Guid::BIOS_BOOT => Some("BIOS Boot"),
+            Guid::BASIC_DATA => Some("Basic Data"),
+            Guid::LINUX_FILESYSTEM => Some("Linux Filesystem"),
+            Guid::LINUX_SWAP => Some("Linux Swap"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let d = &self.0;
+        write!(
+            f,
+            "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-",
+            u32::from_le_bytes([d[0], d[1], d[2], d[3]]),
+            u16::from_le_bytes([d[4], d[5]]),
+            u16::from_le_bytes([d[6], d[7]]),
+            d[8],
+            d[9]
+        )?;
+
+        for byte in &d[10..] {
+            write!(f, "{:02X}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,131 @@
+//! GPT Header
+//!
+//! reference: <https://uefi.org/specs/UEFI/2.10/05_GUID_Partition_Table_Format.html>
+
+use super::*;
+
+pub struct GptHeader {
+    data: [u8; 512],
+}
+
+impl GptHeader {
+    pub const SIGNATURE: &'static [u8; 8] = b"EFI PART";
+
+    /// Smallest valid header, the fields below are all it holds
+    const MIN_SIZE: usize = 92;
+
+    /// Largest partition entry array we read, four times the 16 KiB the
+    /// spec reserves for it. A corrupted count must not use up the heap.
+    pub const MAX_ENTRIES_SIZE: usize = 64 * 1024;
+
+    /// Attempt to parse and validate a GPT header from the block read at
+    /// `lba`, of which the header takes the first 512 bytes.
+    pub fn parse(data: &[u8], lba: u64) -> Result<GptHeader> {
+        let block_size = data.len() as u64;
+        let data: [u8; 512] = data
+            .get(..512)
+            .and_then(|data| data.try_into().ok())
+            .ok_or(FsError::InvalidPartitionTable)?;
+        let header = GptHeader { data };
+
+        if header.signature() != Self::SIGNATURE {
+            return Err(FsError::InvalidPartitionTable);
+        }
+
+        let size = header.header_size() as usize;
+        if !(Self::MIN_SIZE..=512).contains(&size) {
+            return Err(FsError::InvalidPartitionTable);
+        }
+
+        // the checksum is computed with its own field zeroed
+        let mut raw = header.data;
+        raw[0x10..0x14].fill(0);
+        if crc32(&raw[..size]) != header.header_crc32() {
+            warn!("GPT header at LBA {} has a bad checksum", lba);
+            return Err(FsError::InvalidPartitionTable);
+        }
+
+        if header.current_lba() != lba
+            || header.entry_size() < 128
+            || !header.entry_size().is_multiple_of(128)
+        {
+            return Err(FsError::InvalidPartitionTable);
+        }
+
+        let entries_size = header
+            .entries_size()
+            .filter(|&size| size <= Self::MAX_ENTRIES_SIZE)
+            .ok_or_else(|| {
+                warn!(
+                    "GPT header at LBA {} has {} entries of {} bytes, too many to read",
+                    lba,
+                    header.entry_count(),
+                    header.entry_size()
+                );
+                FsError::InvalidPartitionTable
+            })?;
+
+        // the entry array lies outside the usable space, before it for the
+        // primary header and after it for the backup
+        let entries_start = header.entries_lba();
+        let entries_end = entries_start.saturating_add((entries_size as u64).div_ceil(block_size));
+        let (first_usable, last_usable) = (header.first_usable_lba(), header.last_usable_lba());
+
+        if first_usable > last_usable
+            || (entries_start..entries_end).contains(&lba)
+            || (entries_end > first_usable && entries_start <= last_usable)
+        {
+            warn!("GPT header at LBA {} has overlapping regions", lba);
+            return Err(FsError::InvalidPartitionTable);
+        }
+
+        Ok(header)
+    }
+
+    /// Size in bytes of the partition entry array, None if it overflows
+    pub fn entries_size(&self) -> Option<usize> {
+        (self.entry_count() as usize).checked_mul(self.entry_size() as usize)
+    }
+
+    /// Whether `entry` lies within the space usable by partitions
+    pub fn is_usable(&self, entry: &GptPartition) -> bool {
+        let usable = self.first_usable_lba()..=self.last_usable_lba();
+        entry.first_lba() <= entry.last_lba()
+            && usable.contains(&entry.first_lba())
+            && usable.contains(&entry.last_lba())
+    }
+
+    pub fn disk_guid(&self) -> Guid {
+        Guid::from_bytes(self.disk_guid_raw())
+    }
+
+    define_field!([u8; 8], 0x00, signature);
+    define_field!(u32, 0x08, revision);
+    define_field!(u32, 0x0c, header_size);
+    define_field!(u32, 0x10, header_crc32);
+    define_field!(u64, 0x18, current_lba);
+    define_field!(u64, 0x20, backup_lba);
+    define_field!(u64, 0x28, first_usable_lba);
+    define_field!(u64, 0x30, last_usable_lba);
+    define_field!([u8; 16], 0x38, disk_guid_raw);
+    define_field!(u64, 0x48, entries_lba);
+    define_field!(u32, 0x50, entry_count);
+    define_field!(u32, 0x54, entry_size);
+    define_field!(u32, 0x58, entries_crc32);
+}
+
+impl core::fmt::Debug for GptHeader {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("GPT Header")
+            .field("Revision", &format!("0x{:08x}", self.revision()))
+            .field("Current LBA", &self.current_lba())
+            .field("Backup LBA", &self.backup_lba())
+            .field("First Usable LBA", &self.first_usable_lba())
+            .field("Last Usable LBA", &self.last_usable_lba())
+            .field("Disk GUID", &self.disk_guid())
+            .field("Entries LBA", &self.entries_lba())
+            .field("Entry Count", &self.entry_count())
+            .field("Entry Size", &self.entry_size())
+            .finish()
+    }
+}
@@ -0,0 +1,374 @@
+//! GptTable
+//!
+//! reference: <https://uefi.org/specs/UEFI/2.10/05_GUID_Partition_Table_Format.html>
+//! reference: <https://wiki.osdev.org/GPT>
+
+mod entry;
+mod guid;
+mod header;
+
+use core::marker::PhantomData;
+
+use crate::mbr::MbrPartition;
+use crate::*;
+pub use entry::*;
+pub use guid::*;
+pub use header::*;
+
+pub struct GptTable<T, B>
+where
+    T: BlockDevice<B> + Clone,
+    B: BlockTrait,
+{
+    inner: T,
+    header: GptHeader,
+    entries: Vec<GptPartition>,
+    _block: PhantomData<B>,
+}
+
+impl<T, B> GptTable<T, B>
+where
+    T: BlockDevice<B> + Clone,
+    B: BlockTrait,
+{
+    /// Check for the protective MBR a GPT disk starts with
+    pub fn is_gpt(inner: &T) -> Result<bool> {
+        let mut block = B::default();
+        inner.read_block(0, &mut block)?;
+
+        let buffer = block.as_ref();
+        if buffer[0x1fe..0x200] != [0x55, 0xaa] {
+            return Ok(false);
+        }
+
+        Ok((0..4).any(|i| {
+            let entry = &buffer[0x1be + (i * 16)..0x1be + (i * 16) + 16];
+            MbrPartition::parse(entry.try_into().unwrap()).is_protective()
+        }))
+    }
+
+    /// The header of the disk, primary or backup
+    pub fn header(&self) -> &GptHeader {
+        &self.header
+    }
+
+    /// Every used entry of the partition entry array
+    pub fn entries(&self) -> &[GptPartition] {
+        &self.entries
+    }
+
+    /// Read and validate a header and its partition entry array
+    fn load(inner: &T, lba: u64) -> Result<(GptHeader, Vec<GptPartition>)> {
+        let mut block = B::default();
+        inner.read_block(lba as usize, &mut block)?;
+
+        let header = GptHeader::parse(block.as_ref(), lba)?;
+        trace!("{:#?}", header);
+
+        let entry_size = header.entry_size() as usize;
+        // bounded by `GptHeader::parse`, it cannot be too large to read
+        let length = header
+            .entries_size()
+            .ok_or(FsError::InvalidPartitionTable)?;
+        let blocks = length.div_ceil(B::size());
+
+        let mut array = Vec::with_capacity(blocks * B::size());
+        for i in 0..blocks {
+            inner.read_block(header.entries_lba() as usize + i, &mut block)?;
+            array.extend_from_slice(block.as_ref());
+        }
+
+        if crc32(&array[..length]) != header.entries_crc32() {
+            warn!(
+                "GPT partition entry array at LBA {} has a bad checksum",
+                lba
+            );
+            return Err(FsError::InvalidPartitionTable);
+        }
+
+        let entries = array[..length]
+            .chunks_exact(entry_size)
+            .map(GptPartition::parse)
+            .filter(|entry| entry.is_used())
+            .filter(|entry| {
+                let usable = header.is_usable(entry);
+                if !usable {
+                    warn!(
+                        "Skipping GPT partition outside the usable space: {:#?}",
+                        entry
+                    );
+                }
+                usable
+            })
+            .collect();
+
+        Ok((header, entries))
+    }
+}
+
+impl<T, B> PartitionTable<T, B> for GptTable<T, B>
+where
+    T: BlockDevice<B> + Clone,
+    B: BlockTrait,
+{
+    fn parse(inner: T) -> Result<Self> {
+        if !Self::is_gpt(&inner)? {
+            return Err(FsError::InvalidPartitionTable);
+        }
+
+        // fall back to the backup header at the end of the disk
+        let (header, entries) = match Self::load(&inner, 1) {
+            Ok(table) => table,
+            Err(_) => {
+                let last = inner.block_count()?.saturating_sub(1) as u64;
+                warn!(
+                    "Primary GPT header is corrupted, trying backup at LBA {}",
+                    last
+                );
+                Self::load(&inner, last)?
+            }
+        };
+
+        for (i, entry) in entries.iter().enumerate() {
+            trace!("Partition {}: {:#?}", i, entry);
+        }
+
+        Ok(Self {
+            inner,
+            header,
+            entries,
+            _block: PhantomData,
+        })
+    }
+
+    fn partitions(&self) -> Result<Vec<Partition<T, B>>> {
+        Ok(self
+            .entries
+            .iter()
+            .map(|entry| {
+                Partition::new(
+                    self.inner.clone(),
+                    entry.first_lba() as usize,
+                    entry.total_lba() as usize,
+                )
+            })
+            .collect())
+    }
+}
+
+/// CRC-32 (IEEE 802.3) as used by the GPT header and entry array
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECTORS: usize = 128;
+    const ENTRIES_SECTORS: u64 = 32;
+    const FIRST_USABLE: u64 = 2 + ENTRIES_SECTORS;
+    const LAST_USABLE: u64 = SECTORS as u64 - 2 - ENTRIES_SECTORS;
+
+    type Table = GptTable<Arc<MemoryDisk>, Block512>;
+
+    fn sector(disk: &mut [u8], lba: u64) -> &mut [u8] {
+        &mut disk[lba as usize * 512..][..512]
+    }
+
+    /// A header at `lba` for the entry array at `entries_lba`
+    fn header(lba: u64, backup: u64, entries_lba: u64, entries: &[u8]) -> [u8; 512] {
+        let mut data = [0u8; 512];
+        data[..8].copy_from_slice(GptHeader::SIGNATURE);
+        data[0x08..0x0c].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+        data[0x0c..0x10].copy_from_slice(&92u32.to_le_bytes());
+        data[0x18..0x20].copy_from_slice(&lba.to_le_bytes());
+        data[0x20..0x28].copy_from_slice(&backup.to_le_bytes());
+        data[0x28..0x30].copy_from_slice(&FIRST_USABLE.to_le_bytes());
+        data[0x30..0x38].copy_from_slice(&LAST_USABLE.to_le_bytes());
+        data[0x38..0x48].copy_from_slice(&[0x42; 16]);
+        data[0x48..0x50].copy_from_slice(&entries_lba.to_le_bytes());
+        data[0x50..0x54].copy_from_slice(&128u32.to_le_bytes());
+        data[0x54..0x58].copy_from_slice(&128u32.to_le_bytes());
+        data[0x58..0x5c].copy_from_slice(&crc32(entries).to_le_bytes());
+
+        seal(&mut data);
+        data
+    }
+
+    /// Compute the checksum of a header again after changing it
+    fn seal(header: &mut [u8]) {
+        header[0x10..0x14].fill(0);
+        let crc = crc32(&header[..92]);
+        header[0x10..0x14].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    /// A disk with a protective MBR and the partitions `parts`, given by
+    /// their first and last LBA, in both copies of the table
+    fn disk(parts: &[(u64, u64)]) -> Vec<u8> {
+        let mut disk = vec![0u8; SECTORS * 512];
+
+        let mbr = sector(&mut disk, 0);
+        mbr[0x1be..0x1ce]
+            .copy_from_slice(&[0, 0, 2, 0, 0xee, 0xff, 0xff, 0xff, 1, 0, 0, 0, 0, 0, 0, 0]);
+        mbr[0x1ca..0x1ce].copy_from_slice(&(SECTORS as u32 - 1).to_le_bytes());
+        mbr[0x1fe..].copy_from_slice(&[0x55, 0xaa]);
+
+        let mut entries = vec![0u8; 128 * 128];
+        for (entry, &(first, last)) in entries.as_chunks_mut::<128>().0.iter_mut().zip(parts) {
+            entry[..16].copy_from_slice(Guid::BASIC_DATA.as_bytes());
+            entry[0x10..0x20].copy_from_slice(&[0x24; 16]);
+            entry[0x20..0x28].copy_from_slice(&first.to_le_bytes());
+            entry[0x28..0x30].copy_from_slice(&last.to_le_bytes());
+        }
+
+        let last = SECTORS as u64 - 1;
+        let backup_entries = LAST_USABLE + 1;
+        disk[2 * 512..][..entries.len()].copy_from_slice(&entries);
+        disk[backup_entries as usize * 512..][..entries.len()].copy_from_slice(&entries);
+        sector(&mut disk, 1).copy_from_slice(&header(1, last, 2, &entries));
+        sector(&mut disk, last).copy_from_slice(&header(last, 1, backup_entries, &entries));
+
+        disk
+    }
+
+    fn parse(disk: Vec<u8>) -> Result<Table> {
+        Table::parse(Arc::new(MemoryDisk::new(disk)))
+    }
+
+    #[test]
+    fn crc32_test() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn header_test() {
+        let mut disk = disk(&[(40, 89)]);
+        let header = GptHeader::parse(sector(&mut disk, 1), 1).unwrap();
+
+        println!("{:#?}", header);
+
+        assert_eq!(header.current_lba(), 1);
+        assert_eq!(header.backup_lba(), SECTORS as u64 - 1);
+        assert_eq!(header.first_usable_lba(), FIRST_USABLE);
+        assert_eq!(header.last_usable_lba(), LAST_USABLE);
+        assert_eq!(header.entries_lba(), 2);
+        assert_eq!(header.entries_size(), Some(128 * 128));
+
+        // read at another LBA than it says it is at
+        assert!(GptHeader::parse(sector(&mut disk, 1), 2).is_err());
+
+        let table = parse(disk).unwrap();
+        assert_eq!(table.entries().len(), 1);
+        assert_eq!(table.entries()[0].first_lba(), 40);
+        assert_eq!(table.entries()[0].total_lba(), 50);
+        assert_eq!(table.partitions().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn bad_header_checksum() {
+        let mut disk = disk(&[(40, 89)]);
+        sector(&mut disk, 1)[0x28] += 1;
+        assert!(GptHeader::parse(sector(&mut disk, 1), 1).is_err());
+
+        // the backup header is used instead
+        let table = parse(disk.clone()).unwrap();
+        assert_eq!(table.header().current_lba(), SECTORS as u64 - 1);
+        assert_eq!(table.entries().len(), 1);
+
+        sector(&mut disk, SECTORS as u64 - 1)[0x28] += 1;
+        assert!(matches!(parse(disk), Err(FsError::InvalidPartitionTable)));
+    }
+
+    #[test]
+    fn bad_entries_checksum() {
+        let mut disk = disk(&[(40, 89)]);
+        sector(&mut disk, 2)[0x20] += 1;
+
+        let device = Arc::new(MemoryDisk::new(disk.clone()));
+        assert!(GptHeader::parse(sector(&mut disk, 1), 1).is_ok());
+        assert!(Table::load(&device, 1).is_err());
+        assert_eq!(Table::parse(device).unwrap().header().current_lba(), 127);
+
+        sector(&mut disk, LAST_USABLE + 1)[0x20] += 1;
+        assert!(matches!(parse(disk), Err(FsError::InvalidPartitionTable)));
+    }
+
+    #[test]
+    fn bad_entries_bounds() {
+        let mut disk = disk(&[(40, 89)]);
+
+        // far more entries than can be read
+        let header = sector(&mut disk, 1);
+        header[0x50..0x54].copy_from_slice(&u32::MAX.to_le_bytes());
+        seal(header);
+        assert!(GptHeader::parse(header, 1).is_err());
+
+        // an entry array running into the usable space
+        let header = sector(&mut disk, 1);
+        header[0x50..0x54].copy_from_slice(&256u32.to_le_bytes());
+        seal(header);
+        assert!(GptHeader::parse(header, 1).is_err());
+
+        // partitions beyond the usable space, or ending before they start
+        let table = parse(self::disk(&[
+            (40, 89),
+            (1, 50),
+            (60, LAST_USABLE + 1),
+            (70, 60),
+            (80, u64::MAX),
+        ]))
+        .unwrap();
+        assert_eq!(table.entries().len(), 1);
+        assert_eq!(table.entries()[0].first_lba(), 40);
+    }
+
+    #[test]
+    fn protective_mbr() {
+        let mut disk = disk(&[(40, 89)]);
+        let device = Arc::new(MemoryDisk::new(disk.clone()));
+        assert!(Table::is_gpt(&device).unwrap());
+
+        // a plain MBR partition instead
+        sector(&mut disk, 0)[0x1c2] = 0x0b;
+        let device = Arc::new(MemoryDisk::new(disk.clone()));
+        assert!(!Table::is_gpt(&device).unwrap());
+        assert!(matches!(
+            Table::parse(device),
+            Err(FsError::InvalidPartitionTable)
+        ));
+
+        // no boot signature at all
+        sector(&mut disk, 0)[0x1c2] = 0xee;
+        sector(&mut disk, 0)[0x1fe] = 0;
+        let device = Arc::new(MemoryDisk::new(disk));
+        assert!(!Table::is_gpt(&device).unwrap());
+    }
+
+    #[test]
+    fn disk_table() {
+        let mut disk = disk(&[(40, 89), (90, LAST_USABLE)]);
+
+        let table = DiskTable::<_, Block512>::parse(Arc::new(MemoryDisk::new(disk.clone())));
+        assert!(matches!(table, Ok(DiskTable::Gpt(_))));
+        assert_eq!(table.unwrap().partitions().unwrap().len(), 2);
+
+        // the same disk with an MBR partition takes the MBR
+        sector(&mut disk, 0)[0x1be] = 0x80;
+        sector(&mut disk, 0)[0x1c2] = 0x0b;
+        let table = DiskTable::<_, Block512>::parse(Arc::new(MemoryDisk::new(disk)));
+        assert!(matches!(table, Ok(DiskTable::Mbr(_))));
+        assert_eq!(table.unwrap().partitions().unwrap().len(), 1);
+    }
+}
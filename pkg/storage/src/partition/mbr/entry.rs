@@ -36,6 +36,11 @@ impl MbrPartition {
         self.filesystem_flag() == 0x05
     }
 
+    /// The single entry of a protective MBR, covering a GPT disk
+    pub fn is_protective(&self) -> bool {
+        self.filesystem_flag() == 0xee
+    }
+
     pub fn begin_sector(&self) -> u8 {
         self.data[2] & 0x3f
     }
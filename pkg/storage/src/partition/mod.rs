@@ -2,8 +2,12 @@ use core::marker::PhantomData;
 
 use crate::*;
 
+pub mod gpt;
 pub mod mbr;
 
+use gpt::GptTable;
+use mbr::MbrTable;
+
 /// Partition table trait
 pub trait PartitionTable<T, B>
 where
@@ -18,6 +22,38 @@ where
     fn partitions(&self) -> Result<Vec<Partition<T, B>>>;
 }
 
+/// The partition table of a disk, GPT when the disk has a protective MBR
+/// and MBR otherwise.
+pub enum DiskTable<T, B>
+where
+    T: BlockDevice<B> + Clone,
+    B: BlockTrait,
+{
+    Gpt(Box<GptTable<T, B>>),
+    Mbr(MbrTable<T, B>),
+}
+
+impl<T, B> PartitionTable<T, B> for DiskTable<T, B>
+where
+    T: BlockDevice<B> + Clone,
+    B: BlockTrait,
+{
+    fn parse(inner: T) -> Result<Self> {
+        if GptTable::is_gpt(&inner)? {
+            Ok(Self::Gpt(Box::new(GptTable::parse(inner)?)))
+        } else {
+            Ok(Self::Mbr(MbrTable::parse(inner)?))
+        }
+    }
+
+    fn partitions(&self) -> Result<Vec<Partition<T, B>>> {
+        match self {
+            Self::Gpt(table) => table.partitions(),
+            Self::Mbr(table) => table.partitions(),
+        }
+    }
+}
+
 /// Identifies a partition on the disk.
 pub struct Partition<T, B>
 where
@@ -64,7 +100,7 @@ where
     B: BlockTrait,
 {
     fn block_count(&self) -> Result<usize> {
-        self.inner.block_count()
+        Ok(self.size)
     }
 
     fn read_block(&self, offset: usize, block: &mut B) -> Result<()> {
@@ -84,4 +120,8 @@ where
         let offset = offset + self.offset;
         self.inner.write_block(offset, block)
     }
+
+    fn flush(&self) -> Result<()> {
+        self.inner.flush()
+    }
 }
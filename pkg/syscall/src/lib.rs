@@ -1,8 +1,11 @@
 #![no_std]
 
-use num_enum::FromPrimitive;
+use num_enum::{FromPrimitive, TryFromPrimitive};
 
 pub mod macros;
+pub mod signal;
+
+pub use signal::*;
 
 #[repr(u16)]
 #[derive(Clone, Debug, FromPrimitive)]
@@ -11,22 +14,65 @@ pub enum Syscall {
     Write = 1,
     Open = 2,
     Close = 3,
+    Stat = 4,
+    FStat = 5,
+
+    Seek = 8,
 
     Brk = 12,
+    SigAction = 13,
+    SigProcMask = 14,
+    SigReturn = 15,
+
+    Pipe = 22,
+
+    SchedYield = 24,
+
+    Dup = 32,
+    Dup2 = 33,
+
+    NanoSleep = 35,
 
     GetPid = 39,
 
-    VFork = 58,
+    ThreadCreate = 56,
+    Fork = 57,
     Spawn = 59,
     Exit = 60,
     WaitPid = 61,
     Kill = 62,
 
     Sem = 66,
+    ThreadExit = 67,
+    ThreadJoin = 68,
+
+    Fcntl = 72,
+
+    GetDents = 78,
+    GetCwd = 79,
+    ChDir = 80,
+
+    MkDir = 83,
+    RmDir = 84,
+
+    Unlink = 87,
+
+    SetPgid = 109,
+    GetPpid = 110,
+    GetPgid = 121,
+
+    GetPriority = 140,
+    SetPriority = 141,
+
+    Mount = 165,
+    Umount = 166,
     Time = 201,
+    ClockGetTime = 228,
+
+    Exec = 322,
 
-    Stat = 65530,
-    ListDir = 65531,
+    SetForeground = 65529,
+    ListProcess = 65530,
     Draw = 65532,
     Allocate = 65533,
     Deallocate = 65534,
@@ -34,3 +80,189 @@ pub enum Syscall {
     #[num_enum(default)]
     None = 65535,
 }
+
+/// A file descriptor
+pub type Fd = u32;
+
+/// Errors of the syscalls, which return them negated
+#[repr(u16)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, TryFromPrimitive)]
+pub enum Errno {
+    /// Operation not permitted
+    EPERM = 1,
+    /// No such file or directory
+    ENOENT = 2,
+    /// No such process or thread
+    ESRCH = 3,
+    /// I/O error
+    EIO = 5,
+    /// Bad file descriptor
+    EBADF = 9,
+    /// A pointer outside the memory of the process
+    EFAULT = 14,
+    /// Would block, the call is retried
+    EAGAIN = 11,
+    /// The mount point or device is in use
+    EBUSY = 16,
+    /// File exists
+    EEXIST = 17,
+    /// Not a directory
+    ENOTDIR = 20,
+    /// Is a directory
+    EISDIR = 21,
+    /// Invalid argument
+    EINVAL = 22,
+    /// Too many open files
+    EMFILE = 24,
+    /// Not a seekable file
+    ESPIPE = 29,
+    /// Read-only file system
+    EROFS = 30,
+    /// The pipe has no readers left
+    EPIPE = 32,
+    /// Directory not empty
+    ENOTEMPTY = 39,
+    /// The filesystem cannot do that
+    ENOTSUP = 95,
+}
+
+impl Errno {
+    /// The value a syscall returns for the error
+    pub const fn as_ret(self) -> usize {
+        -(self as isize) as usize
+    }
+
+    /// The error a syscall returned, None if it succeeded
+    pub fn from_ret(ret: usize) -> Option<Self> {
+        match ret as isize {
+            ret if ret < 0 => Some(Self::try_from(-ret as u16).unwrap_or(Self::EINVAL)),
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Display for Errno {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::EPERM => "Operation not permitted",
+            Self::ENOENT => "No such file or directory",
+            Self::ESRCH => "No such process",
+            Self::EIO => "Input/output error",
+            Self::EBADF => "Bad file descriptor",
+            Self::EFAULT => "Bad address",
+            Self::EAGAIN => "Resource temporarily unavailable",
+            Self::EBUSY => "Device or resource busy",
+            Self::EEXIST => "File exists",
+            Self::ENOTDIR => "Not a directory",
+            Self::EISDIR => "Is a directory",
+            Self::EINVAL => "Invalid argument",
+            Self::EMFILE => "Too many open files",
+            Self::ESPIPE => "Illegal seek",
+            Self::EROFS => "Read-only file system",
+            Self::EPIPE => "Broken pipe",
+            Self::ENOTEMPTY => "Directory not empty",
+            Self::ENOTSUP => "Operation not supported",
+        })
+    }
+}
+
+/// `open` flag, the fd is closed when the process runs another program
+pub const O_CLOEXEC: usize = 0o2000000;
+
+/// `fcntl` commands
+pub const F_GETFD: usize = 1;
+pub const F_SETFD: usize = 2;
+/// The fd flag of `F_GETFD` and `F_SETFD`
+pub const FD_CLOEXEC: usize = 1;
+
+/// `waitpid` returns at once if no child has exited
+pub const WNOHANG: usize = 1;
+/// `waitpid` also returns for children that stopped
+pub const WUNTRACED: usize = 2;
+
+/// What `waitpid` writes through its status pointer
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChildStatus {
+    /// Exit code of a child that exited
+    pub code: isize,
+    /// Signal that stopped the child, 0 if it exited
+    pub stopped: u8,
+}
+
+/// What a path or an fd refers to
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, TryFromPrimitive)]
+pub enum FileKind {
+    File = 0,
+    Directory = 1,
+    Pipe = 2,
+    Console = 3,
+}
+
+/// What `stat` and `fstat` write through their pointer, and what each
+/// entry of `getdents` starts with
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStat {
+    pub kind: FileKind,
+    /// Length in bytes, 0 for directories and streams
+    pub len: u64,
+    /// Times in milliseconds since the Unix epoch, 0 when the filesystem
+    /// keeps none
+    pub created: i64,
+    pub modified: i64,
+    pub accessed: i64,
+}
+
+impl FileStat {
+    pub fn is_dir(&self) -> bool {
+        self.kind == FileKind::Directory
+    }
+}
+
+/// An entry of `getdents`, followed by its name and padded so the next
+/// entry is aligned
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirEntry {
+    pub stat: FileStat,
+    /// Length of the name in bytes
+    pub name_len: u16,
+}
+
+impl DirEntry {
+    /// Bytes the entry with a name of `name_len` bytes takes in the buffer
+    pub const fn record_len(name_len: usize) -> usize {
+        let len = size_of::<DirEntry>() + name_len;
+        len.next_multiple_of(align_of::<DirEntry>())
+    }
+}
+
+/// Clocks of `clock_gettime`
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, TryFromPrimitive)]
+pub enum ClockId {
+    /// Wall-clock time since the Unix epoch
+    Realtime = 0,
+    /// Time since boot, it never goes back
+    Monotonic = 1,
+}
+
+/// The different ways we can open a file.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, TryFromPrimitive)]
+pub enum FileMode {
+    /// Open a file for reading, if it exists.
+    ReadOnly = 0,
+    /// Open a file for appending (writing to the end of the existing file), if it exists.
+    ReadWriteAppend = 1,
+    /// Open a file and remove all contents, before writing to the start of the existing file, if it exists.
+    ReadWriteTruncate = 2,
+    /// Create a new empty file. Fail if it exists.
+    ReadWriteCreate = 3,
+    /// Create a new empty file, or truncate an existing file.
+    ReadWriteCreateOrTruncate = 4,
+    /// Create a new empty file, or append to an existing file.
+    ReadWriteCreateOrAppend = 5,
+}
@@ -58,6 +58,66 @@ pub fn syscall3(n: Syscall, arg0: usize, arg1: usize, arg2: usize) -> usize {
     ret
 }
 
+#[doc(hidden)]
+#[inline(always)]
+pub fn syscall4(n: Syscall, arg0: usize, arg1: usize, arg2: usize, arg3: usize) -> usize {
+    let ret: usize;
+    unsafe {
+        asm!(
+            "int 0x80", in("rax") n as usize,
+            in("rdi") arg0, in("rsi") arg1, in("rdx") arg2,
+            in("r10") arg3,
+            lateout("rax") ret
+        );
+    }
+    ret
+}
+
+#[doc(hidden)]
+#[inline(always)]
+pub fn syscall5(
+    n: Syscall,
+    arg0: usize,
+    arg1: usize,
+    arg2: usize,
+    arg3: usize,
+    arg4: usize,
+) -> usize {
+    let ret: usize;
+    unsafe {
+        asm!(
+            "int 0x80", in("rax") n as usize,
+            in("rdi") arg0, in("rsi") arg1, in("rdx") arg2,
+            in("r10") arg3, in("r8") arg4,
+            lateout("rax") ret
+        );
+    }
+    ret
+}
+
+#[doc(hidden)]
+#[inline(always)]
+pub fn syscall6(
+    n: Syscall,
+    arg0: usize,
+    arg1: usize,
+    arg2: usize,
+    arg3: usize,
+    arg4: usize,
+    arg5: usize,
+) -> usize {
+    let ret: usize;
+    unsafe {
+        asm!(
+            "int 0x80", in("rax") n as usize,
+            in("rdi") arg0, in("rsi") arg1, in("rdx") arg2,
+            in("r10") arg3, in("r8") arg4, in("r9") arg5,
+            lateout("rax") ret
+        );
+    }
+    ret
+}
+
 #[macro_export]
 macro_rules! syscall {
     ($n:expr) => {
@@ -72,4 +132,28 @@ macro_rules! syscall {
     ($n:expr, $a1:expr, $a2:expr, $a3:expr) => {
         $crate::macros::syscall3($n, $a1 as usize, $a2 as usize, $a3 as usize)
     };
+    ($n:expr, $a1:expr, $a2:expr, $a3:expr, $a4:expr) => {
+        $crate::macros::syscall4($n, $a1 as usize, $a2 as usize, $a3 as usize, $a4 as usize)
+    };
+    ($n:expr, $a1:expr, $a2:expr, $a3:expr, $a4:expr, $a5:expr) => {
+        $crate::macros::syscall5(
+            $n,
+            $a1 as usize,
+            $a2 as usize,
+            $a3 as usize,
+            $a4 as usize,
+            $a5 as usize,
+        )
+    };
+    ($n:expr, $a1:expr, $a2:expr, $a3:expr, $a4:expr, $a5:expr, $a6:expr) => {
+        $crate::macros::syscall6(
+            $n,
+            $a1 as usize,
+            $a2 as usize,
+            $a3 as usize,
+            $a4 as usize,
+            $a5 as usize,
+            $a6 as usize,
+        )
+    };
 }
@@ -0,0 +1,65 @@
+use num_enum::TryFromPrimitive;
+
+/// Signals, numbered as on Linux
+#[allow(clippy::upper_case_acronyms, non_camel_case_types)]
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, TryFromPrimitive)]
+pub enum Signal {
+    SIGHUP = 1,
+    SIGINT = 2,
+    SIGQUIT = 3,
+    SIGILL = 4,
+    SIGTRAP = 5,
+    SIGABRT = 6,
+    SIGBUS = 7,
+    SIGFPE = 8,
+    /// Cannot be caught, blocked or ignored
+    SIGKILL = 9,
+    SIGUSR1 = 10,
+    SIGSEGV = 11,
+    SIGUSR2 = 12,
+    SIGPIPE = 13,
+    SIGALRM = 14,
+    SIGTERM = 15,
+    /// Sent to the parent when a child exits or stops, ignored by default
+    SIGCHLD = 17,
+    /// Resumes a stopped process, ignored by default
+    SIGCONT = 18,
+    /// Stops the process, cannot be caught, blocked or ignored
+    SIGSTOP = 19,
+    /// Stops the process, sent by Ctrl-Z
+    SIGTSTP = 20,
+}
+
+impl Signal {
+    /// The bit of the signal in a mask
+    pub const fn bit(self) -> u64 {
+        1 << self as u8
+    }
+}
+
+/// Take the default action of the signal
+pub const SIG_DFL: usize = 0;
+/// Discard the signal
+pub const SIG_IGN: usize = 1;
+
+/// What to do when a signal arrives
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SigAction {
+    /// `SIG_DFL`, `SIG_IGN` or an `extern "C" fn(signal: usize)`
+    pub handler: usize,
+    /// Signals blocked while the handler runs, besides the signal itself
+    pub mask: u64,
+    /// Where the handler returns to, it has to call `sigreturn`
+    pub restorer: usize,
+}
+
+/// How `sigprocmask` changes the mask
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, TryFromPrimitive)]
+pub enum SigHow {
+    Block = 0,
+    Unblock = 1,
+    SetMask = 2,
+}